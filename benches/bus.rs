@@ -0,0 +1,125 @@
+//! Throughput benchmarks for the hot bus paths.
+//!
+//! Run with `cargo bench`. Each benchmark prints the mean time per operation; compare the
+//! numbers before and after a change to `bus.rs` to catch regressions. The harness is a plain
+//! timing loop so the crate keeps its dependency-free build.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use embedded_can::{Frame as _, StandardId};
+use embedded_can_mock::{BusHandle, MockFrame};
+
+/// Run `op` `iterations` times after a short warm-up and report the mean time per call.
+fn bench(name: &str, iterations: u32, mut op: impl FnMut()) {
+    for _ in 0..iterations / 10 {
+        op();
+    }
+    let start = Instant::now();
+    for _ in 0..iterations {
+        op();
+    }
+    let per_op = start.elapsed() / iterations;
+    println!("{name:<40} {per_op:>12?}/op");
+}
+
+fn frame(id: u16) -> MockFrame {
+    MockFrame::new(StandardId::new(id).unwrap(), &[0xAA; 8]).unwrap()
+}
+
+fn transmit_and_drain(nodes: usize) {
+    let bus = BusHandle::new();
+    let interfaces: Vec<_> = (0..nodes)
+        .map(|_| bus.add_interface(vec![]).unwrap())
+        .collect();
+    let frame = frame(0x123);
+    bench(&format!("transmit + drain, {nodes} nodes"), 20_000, || {
+        interfaces[0].transmit(frame.clone()).unwrap();
+        for iface in &interfaces {
+            black_box(iface.pop_frame());
+        }
+    });
+}
+
+fn transmit_and_inspect(nodes: usize) {
+    let bus = BusHandle::new();
+    let interfaces: Vec<_> = (0..nodes)
+        .map(|_| bus.add_interface(vec![]).unwrap())
+        .collect();
+    let frame = frame(0x123);
+    bench(
+        &format!("transmit + inspect in place, {nodes} nodes"),
+        20_000,
+        || {
+            interfaces[0].transmit(frame.clone()).unwrap();
+            for iface in &interfaces {
+                black_box(iface.with_received_frames(|frames| frames.count()));
+                iface.clear_rx();
+            }
+        },
+    );
+}
+
+fn blocking_ping_pong() {
+    let bus = BusHandle::new();
+    let ping = bus.add_interface(vec![]).unwrap();
+    let pong = bus.add_interface(vec![]).unwrap();
+    ping.set_self_reception(false);
+    pong.set_self_reception(false);
+    let rounds = 5_000;
+    let echo = thread::spawn(move || {
+        for _ in 0..rounds + rounds / 10 {
+            let frame = pong.recv_frame(Some(Duration::from_secs(5))).unwrap();
+            pong.transmit(frame).unwrap();
+        }
+    });
+    let frame = frame(0x456);
+    bench("blocking ping-pong round trip", rounds, || {
+        ping.transmit(frame.clone()).unwrap();
+        black_box(ping.recv_frame(Some(Duration::from_secs(5))).unwrap());
+    });
+    echo.join().unwrap();
+}
+
+fn contended_receivers() {
+    let bus = BusHandle::new();
+    let tx = bus.add_interface(vec![]).unwrap();
+    let rx = bus.add_interface(vec![]).unwrap();
+    let frames = 20_000;
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let rx = rx.clone();
+            thread::spawn(move || {
+                let mut received = 0;
+                while rx.recv_frame(Some(Duration::from_millis(50))).is_some() {
+                    received += 1;
+                }
+                received
+            })
+        })
+        .collect();
+    let frame = frame(0x789);
+    let start = Instant::now();
+    for _ in 0..frames {
+        tx.transmit(frame.clone()).unwrap();
+    }
+    let received: u32 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(received, frames);
+    println!(
+        "{:<40} {:>12?}/frame",
+        "4 receivers sharing one queue",
+        (start.elapsed() - Duration::from_millis(50)) / frames
+    );
+}
+
+fn main() {
+    for nodes in [2, 16, 64] {
+        transmit_and_drain(nodes);
+    }
+    transmit_and_inspect(64);
+    blocking_ping_pong();
+    contended_receivers();
+}
@@ -0,0 +1,47 @@
+//! Workload-driven benchmarks: transmit, deliver and receive under synthetic bus shapes.
+//!
+//! Run with `cargo bench --features bench --bench workload`. Each line reports wall-clock time
+//! per transmitted frame and per delivery for one second of virtual traffic.
+
+use std::time::Duration;
+
+use embedded_can_mock::workload::{RunStats, Workload};
+
+fn report(name: &str, stats: RunStats) {
+    let per_frame = stats.elapsed / stats.frames.max(1) as u32;
+    let per_delivery = match stats.deliveries {
+        0 => "-".to_string(),
+        n => format!("{:?}", stats.elapsed / n as u32),
+    };
+    println!(
+        "{name:<44} {per_frame:>10?}/frame {per_delivery:>10}/delivery ({} deliveries)",
+        stats.deliveries
+    );
+}
+
+fn main() {
+    for nodes in [2, 16, 64] {
+        for density in [1.0, 0.25, 0.0] {
+            let mut rig = Workload {
+                nodes,
+                frames_per_second: 20_000,
+                filter_density: density,
+                ..Default::default()
+            }
+            .build();
+            let stats = rig.run(Duration::from_secs(1));
+            report(&format!("{nodes} nodes, filter density {density}"), stats);
+        }
+    }
+    let mut rig = Workload {
+        nodes: 16,
+        frames_per_second: 20_000,
+        payload_len: 64,
+        ..Default::default()
+    }
+    .build();
+    report(
+        "16 nodes, 64-byte payloads",
+        rig.run(Duration::from_secs(1)),
+    );
+}
@@ -0,0 +1,234 @@
+//! Derive macros for [`embedded-can-mock`](https://docs.rs/embedded-can-mock).
+//!
+//! Use them through the `derive` feature of `embedded-can-mock`, which re-exports them next to
+//! the traits they implement; the attribute syntax is documented there, in the `message` module.
+//!
+//! The macros only use the compiler's `proc_macro` API, so the crate has no dependencies.
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Derive `embedded_can_mock::message::CanMessage` for a struct with named fields.
+#[proc_macro_derive(CanMessage, attributes(can))]
+pub fn derive_can_message(input: TokenStream) -> TokenStream {
+    let code = expand(input).unwrap_or_else(|message| format!("compile_error!({message:?});"));
+    code.parse().expect("generated code is valid Rust")
+}
+
+/// `key` or `key = value` inside `#[can(...)]`.
+struct Arg {
+    key: String,
+    value: Option<String>,
+}
+
+struct Field {
+    name: String,
+    ty: String,
+    args: Vec<Arg>,
+}
+
+fn expand(input: TokenStream) -> Result<String, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+    let args = attributes(&tokens, &mut i)?;
+    skip_visibility(&tokens, &mut i);
+    match tokens.get(i) {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => i += 1,
+        _ => return Err("CanMessage can only be derived for structs".into()),
+    }
+    let Some(TokenTree::Ident(name)) = tokens.get(i) else {
+        return Err("expected a struct name".into());
+    };
+    let fields = match tokens.get(i + 1) {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => {
+            fields(body.stream())?
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+            return Err("CanMessage does not support generic structs".into());
+        }
+        _ => return Err("CanMessage needs a struct with named fields".into()),
+    };
+
+    let (mut id, mut extended, mut dlc) = (None, false, None);
+    for arg in args {
+        match (arg.key.as_str(), arg.value) {
+            ("id", Some(value)) => id = Some(value),
+            ("extended", None) => extended = true,
+            ("dlc", Some(value)) => dlc = Some(value),
+            (key, _) => return Err(format!("unknown or malformed struct attribute `{key}`")),
+        }
+    }
+    let id = id.ok_or("missing `#[can(id = ...)]` on the struct")?;
+    let id_max = if extended { "0x1FFF_FFFF" } else { "0x7FF" };
+
+    let mut code = String::from(
+        "const _: () = {\n\
+         use ::embedded_can_mock::message::{CanMessage, Layout, Signal, read_signal, write_signal};\n",
+    );
+    let mut ends = Vec::new();
+    let (mut encode, mut decode) = (String::new(), String::new());
+    for (n, field) in fields.iter().enumerate() {
+        let (mut start, mut len, mut scale, mut offset, mut signed) =
+            (None, None, None, None, false);
+        for arg in &field.args {
+            match (arg.key.as_str(), &arg.value) {
+                ("start", Some(value)) => start = Some(value.clone()),
+                ("len", Some(value)) => len = Some(value.clone()),
+                ("scale", Some(value)) => scale = Some(value.clone()),
+                ("offset", Some(value)) => offset = Some(value.clone()),
+                ("signed", None) => signed = true,
+                (key, _) => {
+                    return Err(format!(
+                        "unknown or malformed attribute `{key}` on field `{}`",
+                        field.name
+                    ));
+                }
+            }
+        }
+        let start = start.unwrap_or_else(|| {
+            if n == 0 {
+                "0".into()
+            } else {
+                format!("S{} + N{}", n - 1, n - 1)
+            }
+        });
+        let len = len.unwrap_or_else(|| format!("<{} as Signal>::LEN", field.ty));
+        let (name, ty) = (&field.name, &field.ty);
+        code += &format!(
+            "const S{n}: u32 = {start};\n\
+             const N{n}: u32 = {len};\n\
+             const _: () = assert!(N{n} >= 1 && N{n} <= 64, \"field `{name}` needs a `len` between 1 and 64\");\n\
+             const L{n}: Layout = Layout {{ start: S{n}, len: N{n}, signed: {signed}, scale: ({}) as f64, offset: ({}) as f64 }};\n",
+            scale.as_deref().unwrap_or("1.0"),
+            offset.as_deref().unwrap_or("0.0"),
+        );
+        ends.push(format!("S{n} + N{n}"));
+        encode += &format!("write_signal::<{ty}>(data, &L{n}, &self.{name});\n");
+        decode += &format!("{name}: read_signal::<{ty}>(data, &L{n}),\n");
+    }
+    let end = ends
+        .iter()
+        .fold("0".to_string(), |acc, end| format!("max({acc}, {end})"));
+    code += &format!(
+        "const fn max(a: u32, b: u32) -> u32 {{ if a > b {{ a }} else {{ b }} }}\n\
+         const END: u32 = {end};\n\
+         impl CanMessage for {name} {{\n\
+         const ID: u32 = {id};\n\
+         const EXTENDED: bool = {extended};\n\
+         const DLC: usize = {};\n\
+         fn encode_payload(&self, data: &mut [u8]) {{\n{encode}}}\n\
+         fn decode_payload(data: &[u8]) -> Self {{\nSelf {{\n{decode}}}\n}}\n\
+         }}\n\
+         const _: () = assert!(<{name} as CanMessage>::ID <= {id_max}, \"CAN ID out of range\");\n\
+         const _: () = assert!(END as usize <= <{name} as CanMessage>::DLC * 8, \"signals extend past the payload\");\n\
+         const _: () = assert!(<{name} as CanMessage>::DLC <= 64, \"payload longer than 64 bytes\");\n\
+         }};\n",
+        dlc.unwrap_or_else(|| "END.div_ceil(8) as usize".into()),
+    );
+    Ok(code)
+}
+
+/// Collect the `#[can(...)]` arguments of the attributes starting at `tokens[*i]`, skipping
+/// other attributes.
+fn attributes(tokens: &[TokenTree], i: &mut usize) -> Result<Vec<Arg>, String> {
+    let mut args = Vec::new();
+    while let (Some(TokenTree::Punct(hash)), Some(TokenTree::Group(attr))) =
+        (tokens.get(*i), tokens.get(*i + 1))
+    {
+        if hash.as_char() != '#' || attr.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        *i += 2;
+        let inner: Vec<TokenTree> = attr.stream().into_iter().collect();
+        match inner.as_slice() {
+            [TokenTree::Ident(ident), TokenTree::Group(list)]
+                if ident.to_string() == "can" && list.delimiter() == Delimiter::Parenthesis =>
+            {
+                for part in split_commas(list.stream()) {
+                    args.push(arg(&part)?);
+                }
+            }
+            [TokenTree::Ident(ident), ..] if ident.to_string() == "can" => {
+                return Err("expected `#[can(key = value, ...)]`".into());
+            }
+            _ => {}
+        }
+    }
+    Ok(args)
+}
+
+fn arg(tokens: &[TokenTree]) -> Result<Arg, String> {
+    match tokens {
+        [TokenTree::Ident(key)] => Ok(Arg {
+            key: key.to_string(),
+            value: None,
+        }),
+        [TokenTree::Ident(key), TokenTree::Punct(eq), value @ ..]
+            if eq.as_char() == '=' && !value.is_empty() =>
+        {
+            Ok(Arg {
+                key: key.to_string(),
+                value: Some(value.iter().cloned().collect::<TokenStream>().to_string()),
+            })
+        }
+        _ => Err("expected `key` or `key = value` in `#[can(...)]`".into()),
+    }
+}
+
+fn skip_visibility(tokens: &[TokenTree], i: &mut usize) {
+    if matches!(tokens.get(*i), Some(TokenTree::Ident(ident)) if ident.to_string() == "pub") {
+        *i += 1;
+        if matches!(tokens.get(*i), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis)
+        {
+            *i += 1;
+        }
+    }
+}
+
+fn fields(body: TokenStream) -> Result<Vec<Field>, String> {
+    split_commas(body)
+        .into_iter()
+        .map(|tokens| {
+            let mut i = 0;
+            let args = attributes(&tokens, &mut i)?;
+            skip_visibility(&tokens, &mut i);
+            match (tokens.get(i), tokens.get(i + 1)) {
+                (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(colon)))
+                    if colon.as_char() == ':' =>
+                {
+                    Ok(Field {
+                        name: name.to_string(),
+                        ty: tokens[i + 2..]
+                            .iter()
+                            .cloned()
+                            .collect::<TokenStream>()
+                            .to_string(),
+                        args,
+                    })
+                }
+                _ => Err("expected a named field".into()),
+            }
+        })
+        .collect()
+}
+
+/// Split `stream` at top-level commas (outside `<...>`), dropping empty pieces.
+fn split_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0i32;
+    for token in stream {
+        if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        parts.last_mut().expect("never empty").push(token);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
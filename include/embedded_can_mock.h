@@ -0,0 +1,154 @@
+#ifndef EMBEDDED_CAN_MOCK_H
+#define EMBEDDED_CAN_MOCK_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// Result of a C API call.
+typedef enum CanMockStatus {
+  // Success.
+  CAN_MOCK_STATUS_OK = 0,
+  // No frame arrived (immediately, or within the timeout).
+  CAN_MOCK_STATUS_EMPTY = 1,
+  // A null pointer, an out-of-range ID, or an invalid length or filter.
+  CAN_MOCK_STATUS_INVALID_ARGUMENT = -1,
+  // The bus refused the transmission (listen-only, overloaded, rejected by a policy, ...).
+  CAN_MOCK_STATUS_TRANSMIT_FAILED = -2,
+  // The interface or bus was closed.
+  CAN_MOCK_STATUS_CLOSED = -3,
+  // The received frame does not fit a [`CanMockFrame`] (a CAN XL payload over 64 bytes); it
+  // was removed from the queue.
+  CAN_MOCK_STATUS_FRAME_TOO_LARGE = -4,
+} CanMockStatus;
+
+// Opaque handle to a bus.
+typedef struct CanMockBus CanMockBus;
+
+// Opaque handle to an interface on a bus.
+typedef struct CanMockInterface CanMockInterface;
+
+// A classic or CAN FD frame.
+typedef struct CanMockFrame {
+  // Raw 11-bit or 29-bit ID.
+  uint32_t id;
+  // `id` is a 29-bit extended ID.
+  bool extended;
+  // Remote frame: `len` is the requested DLC and `data` is unused.
+  bool remote;
+  // CAN FD frame.
+  bool fd;
+  // Payload length, up to 8 (64 for CAN FD frames).
+  uint8_t len;
+  // Payload; bytes past `len` are ignored on send and zeroed on receive.
+  uint8_t data[64];
+} CanMockFrame;
+
+// An acceptance filter: a frame is accepted when `frame.id & mask == id & mask`.
+typedef struct CanMockFilter {
+  // Raw ID to match.
+  uint32_t id;
+  // Bits of the ID that must match.
+  uint32_t mask;
+  // Match extended (29-bit) rather than standard frames.
+  bool extended;
+} CanMockFilter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a bus.
+CanMockBus *can_mock_bus_new(void);
+
+// Open the bus registered under `name` (see [`BusHandle::named`]), or null if `name` is null
+// or not UTF-8.
+//
+// # Safety
+//
+// `name` must be null or a valid NUL-terminated string.
+CanMockBus *can_mock_bus_named(const char *name);
+
+// Release a bus handle. Interfaces stay attached until they are freed themselves.
+//
+// # Safety
+//
+// `bus` must be null or a handle returned by this API that was not freed yet.
+void can_mock_bus_free(CanMockBus *bus);
+
+// Virtual time in microseconds (0 for a null bus).
+//
+// # Safety
+//
+// `bus` must be null or a live handle.
+uint64_t can_mock_bus_now_us(const CanMockBus *bus);
+
+// Advance virtual time by `us` microseconds, delivering frames that become due.
+//
+// # Safety
+//
+// `bus` must be null or a live handle.
+CanMockStatus can_mock_bus_advance_us(const CanMockBus *bus, uint64_t us);
+
+// Attach a new interface that accepts every frame, or return null if `bus` is null or full.
+//
+// # Safety
+//
+// `bus` must be null or a live handle.
+CanMockInterface *can_mock_interface_new(const CanMockBus *bus);
+
+// Detach and release an interface.
+//
+// # Safety
+//
+// `iface` must be null or a handle returned by this API that was not freed yet.
+void can_mock_interface_free(CanMockInterface *iface);
+
+// Process-wide unique ID of an interface (`u64::MAX` for a null interface).
+//
+// # Safety
+//
+// `iface` must be null or a live handle.
+uint64_t can_mock_interface_id(const CanMockInterface *iface);
+
+// Replace the interface's acceptance filters with the `count` filters at `filters`; an empty
+// list accepts every frame.
+//
+// # Safety
+//
+// `iface` must be null or a live handle, and `filters` must point to `count` filters (it may
+// be null when `count` is 0).
+CanMockStatus can_mock_interface_set_filters(const CanMockInterface *iface,
+                                             const CanMockFilter *filters,
+                                             size_t count);
+
+// Transmit `frame`.
+//
+// # Safety
+//
+// `iface` and `frame` must each be null or valid.
+CanMockStatus can_mock_send(const CanMockInterface *iface, const CanMockFrame *frame);
+
+// Receive the oldest queued frame into `out`, waiting up to `timeout_us` microseconds for one
+// to arrive: 0 polls, and a negative timeout waits forever. A frame too large for
+// [`CanMockFrame`] is dropped and reported as [`CanMockStatus::FrameTooLarge`].
+//
+// # Safety
+//
+// `iface` and `out` must each be null or valid.
+CanMockStatus can_mock_recv(const CanMockInterface *iface, CanMockFrame *out, int64_t timeout_us);
+
+// Number of frames queued for receive (0 for a null interface).
+//
+// # Safety
+//
+// `iface` must be null or a live handle.
+size_t can_mock_pending(const CanMockInterface *iface);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* EMBEDDED_CAN_MOCK_H */
@@ -0,0 +1,289 @@
+//! Python bindings for `embedded-can-mock`.
+//!
+//! Exposes the in-memory bus to Python as the `can_mock` module, so pytest harnesses can inject
+//! and inspect frames on the same bus a Rust firmware simulation runs on:
+//!
+//! ```python
+//! import can_mock
+//!
+//! bus = can_mock.Bus.named("sim")      # the bus a Rust test opened with BusHandle::named("sim")
+//! tester = bus.add_interface()
+//! tester.transmit(can_mock.MockFrame(0x7E0, b"\x02\x10\x03"))
+//! reply = tester.recv(timeout=1.0)
+//! assert reply.id == 0x7E8
+//! ```
+//!
+//! Blocking waits release the GIL, so Python threads keep running while a test waits for a
+//! frame.
+
+use core::time::Duration;
+
+use embedded_can::{ExtendedId, Frame as _, Id, StandardId};
+use embedded_can_interface::{IdMask, IdMaskFilter};
+use embedded_can_mock::{BusHandle, InterfaceHandle, MockFrame};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+create_exception!(can_mock, TransmitError, PyException, "The bus refused a transmission.");
+create_exception!(can_mock, BusError, PyException, "A bus or interface operation failed.");
+
+fn make_id(id: u32, extended: bool) -> PyResult<Id> {
+    let id = if extended {
+        ExtendedId::new(id).map(Id::Extended)
+    } else {
+        u16::try_from(id)
+            .ok()
+            .and_then(StandardId::new)
+            .map(Id::Standard)
+    };
+    id.ok_or_else(|| PyValueError::new_err("CAN ID out of range"))
+}
+
+fn timeout(seconds: Option<f64>) -> PyResult<Option<Duration>> {
+    seconds
+        .map(|s| Duration::try_from_secs_f64(s).map_err(|err| PyValueError::new_err(err.to_string())))
+        .transpose()
+}
+
+/// A CAN frame: `MockFrame(id, data=b"", extended=False)`.
+#[pyclass(name = "MockFrame", module = "can_mock", frozen, eq)]
+#[derive(Clone, PartialEq)]
+struct PyMockFrame(MockFrame);
+
+#[pymethods]
+impl PyMockFrame {
+    #[new]
+    #[pyo3(signature = (id, data = Vec::new(), extended = false))]
+    fn new(id: u32, data: Vec<u8>, extended: bool) -> PyResult<Self> {
+        MockFrame::new(make_id(id, extended)?, &data)
+            .map(Self)
+            .ok_or_else(|| PyValueError::new_err("invalid frame"))
+    }
+
+    /// A remote frame requesting `dlc` bytes.
+    #[staticmethod]
+    #[pyo3(signature = (id, dlc, extended = false))]
+    fn remote(id: u32, dlc: usize, extended: bool) -> PyResult<Self> {
+        MockFrame::new_remote(make_id(id, extended)?, dlc)
+            .map(Self)
+            .ok_or_else(|| PyValueError::new_err("invalid remote frame"))
+    }
+
+    /// Parse `candump` / `cansend` notation such as `"123#AABB"`.
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Self> {
+        MockFrame::parse_candump(text)
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(format!("{err:?}")))
+    }
+
+    #[getter]
+    fn id(&self) -> u32 {
+        match self.0.id() {
+            Id::Standard(id) => u32::from(id.as_raw()),
+            Id::Extended(id) => id.as_raw(),
+        }
+    }
+
+    #[getter]
+    fn extended(&self) -> bool {
+        self.0.is_extended()
+    }
+
+    #[getter]
+    fn is_remote(&self) -> bool {
+        self.0.is_remote_frame()
+    }
+
+    #[getter]
+    fn dlc(&self) -> usize {
+        self.0.dlc()
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, self.0.data())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.fmt_candump()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MockFrame.parse('{}')", self.0.fmt_candump())
+    }
+}
+
+/// A node on a bus.
+#[pyclass(name = "Interface", module = "can_mock", frozen)]
+struct PyInterface(InterfaceHandle);
+
+#[pymethods]
+impl PyInterface {
+    /// Process-wide unique interface ID.
+    #[getter]
+    fn id(&self) -> u64 {
+        self.0.id().as_raw()
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.0.name()
+    }
+
+    #[setter]
+    fn set_name(&self, name: String) {
+        self.0.set_name(name);
+    }
+
+    /// Transmit `frame`; returns the transmission's sequence number.
+    fn transmit(&self, frame: &PyMockFrame) -> PyResult<u64> {
+        self.0
+            .transmit(frame.0.clone())
+            .map(|token| token.seq())
+            .map_err(|err| TransmitError::new_err(format!("{err:?}")))
+    }
+
+    /// Remove and return the oldest received frame, or `None`.
+    fn pop_frame(&self) -> Option<PyMockFrame> {
+        self.0.pop_frame().map(PyMockFrame)
+    }
+
+    /// Wait up to `timeout` seconds (forever if `None`) for a frame and return it, or `None`.
+    #[pyo3(signature = (timeout = None))]
+    fn recv(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<PyMockFrame>> {
+        let timeout = self::timeout(timeout)?;
+        let ready = py.allow_threads(|| self.0.wait_for_frame(timeout));
+        Ok(ready.then(|| self.0.pop_frame()).flatten().map(PyMockFrame))
+    }
+
+    /// Remove and return every received frame.
+    fn drain_frames(&self) -> Vec<PyMockFrame> {
+        self.0.drain_frames().into_iter().map(PyMockFrame).collect()
+    }
+
+    /// Copy of the received frames, leaving them queued.
+    fn received_frames(&self) -> Vec<PyMockFrame> {
+        self.0.received_frames().into_iter().map(PyMockFrame).collect()
+    }
+
+    /// Accept only frames matching one of `filters`, given as `(id, mask, extended)` tuples; an
+    /// empty list accepts everything.
+    fn set_filters(&self, filters: Vec<(u32, u32, bool)>) -> PyResult<()> {
+        let filters = filters
+            .into_iter()
+            .map(|(id, mask, extended)| filter(id, mask, extended))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.0
+            .set_filters(filters)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn set_self_reception(&self, on: bool) {
+        self.0.set_self_reception(on);
+    }
+
+    fn set_listen_only(&self, on: bool) {
+        self.0.set_listen_only(on);
+    }
+
+    /// Close the interface, waking blocked receivers.
+    fn close(&self) {
+        self.0.close();
+    }
+}
+
+fn filter(id: u32, mask: u32, extended: bool) -> PyResult<IdMaskFilter> {
+    let (id, mask) = match make_id(id, extended)? {
+        Id::Standard(id) => (
+            embedded_can_interface::Id::Standard(id),
+            IdMask::Standard(
+                u16::try_from(mask)
+                    .map_err(|_| PyValueError::new_err("standard filter mask out of range"))?,
+            ),
+        ),
+        Id::Extended(id) => (
+            embedded_can_interface::Id::Extended(id),
+            IdMask::Extended(mask),
+        ),
+    };
+    Ok(IdMaskFilter { id, mask })
+}
+
+/// An in-memory CAN bus.
+#[pyclass(name = "Bus", module = "can_mock", frozen)]
+struct PyBus(BusHandle);
+
+#[pymethods]
+impl PyBus {
+    #[new]
+    fn new() -> Self {
+        Self(BusHandle::new())
+    }
+
+    /// The bus shared by every handle opened with this name (see `BusHandle::named`), including
+    /// those opened from Rust in the same process.
+    #[staticmethod]
+    fn named(name: &str) -> Self {
+        Self(BusHandle::named(name))
+    }
+
+    /// Attach a new interface, optionally with `(id, mask, extended)` acceptance filters.
+    #[pyo3(signature = (filters = Vec::new()))]
+    fn add_interface(&self, filters: Vec<(u32, u32, bool)>) -> PyResult<PyInterface> {
+        let filters = filters
+            .into_iter()
+            .map(|(id, mask, extended)| filter(id, mask, extended))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.0
+            .add_interface(filters)
+            .map(PyInterface)
+            .map_err(|err| BusError::new_err(format!("{err:?}")))
+    }
+
+    /// A receive-only interface that sees every frame.
+    fn tap(&self) -> PyInterface {
+        PyInterface(self.0.tap())
+    }
+
+    /// Virtual time in seconds.
+    #[getter]
+    fn now(&self) -> f64 {
+        self.0.now().as_secs_f64()
+    }
+
+    /// Advance virtual time by `seconds`, delivering frames that become due.
+    fn advance(&self, seconds: f64) -> PyResult<()> {
+        self.0.advance(self::timeout(Some(seconds))?.unwrap_or_default());
+        Ok(())
+    }
+
+    #[getter]
+    fn interface_count(&self) -> usize {
+        self.0.interface_count()
+    }
+
+    /// Clear queues and state, keeping interfaces attached.
+    fn reset(&self) {
+        self.0.reset();
+    }
+
+    /// Close every interface, waking blocked receivers.
+    fn close(&self) {
+        self.0.close();
+    }
+}
+
+#[pymodule]
+fn can_mock(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMockFrame>()?;
+    m.add_class::<PyInterface>()?;
+    m.add_class::<PyBus>()?;
+    m.add("TransmitError", m.py().get_type_bound::<TransmitError>())?;
+    m.add("BusError", m.py().get_type_bound::<BusError>())?;
+    Ok(())
+}
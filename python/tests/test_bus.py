@@ -0,0 +1,52 @@
+import threading
+
+import pytest
+
+import can_mock
+
+
+def test_frames_round_trip_between_interfaces():
+    bus = can_mock.Bus()
+    a = bus.add_interface()
+    b = bus.add_interface(filters=[(0x7E8, 0x7FF, False)])
+    a.set_self_reception(False)
+
+    a.transmit(can_mock.MockFrame(0x7E0, b"\x02\x10\x03"))
+    a.transmit(can_mock.MockFrame.parse("7E8#065003"))
+
+    frame = b.recv(timeout=1.0)
+    assert (frame.id, frame.data, frame.extended) == (0x7E8, b"\x06\x50\x03", False)
+    assert str(frame) == "7E8#065003"
+    assert b.pop_frame() is None
+    assert a.drain_frames() == []
+
+
+def test_named_bus_is_shared_and_close_wakes_receivers():
+    tester = can_mock.Bus.named("pytest").add_interface()
+    ecu = can_mock.Bus.named("pytest").add_interface()
+    assert can_mock.Bus.named("pytest").interface_count == 2
+
+    received = []
+    waiter = threading.Thread(target=lambda: received.append(ecu.recv()))
+    waiter.start()
+    tester.transmit(can_mock.MockFrame(0x18DAF110, b"\x01", extended=True))
+    waiter.join(timeout=5)
+    assert received[0].extended and received[0].id == 0x18DAF110
+
+    ecu.close()
+    assert ecu.recv() is None
+
+
+def test_invalid_input_raises():
+    with pytest.raises(ValueError):
+        can_mock.MockFrame(0x800)
+    with pytest.raises(ValueError):
+        can_mock.MockFrame.parse("not a frame")
+    tap = can_mock.Bus().tap()
+    with pytest.raises(can_mock.TransmitError):
+        tap.transmit(can_mock.MockFrame(0x1))
+    node = can_mock.Bus().add_interface()
+    with pytest.raises(ValueError):
+        node.set_filters([(0x7E8, 0x107FF, False)])
+    with pytest.raises(ValueError):
+        node.set_filters([(0x7E8, 0xFFFF, False)])
@@ -0,0 +1,160 @@
+//! Constructors for property-based testing.
+//!
+//! Property-testing frameworks generate raw integers and byte vectors; the functions here map
+//! *any* such input onto a valid CAN value (IDs are truncated to 11 or 29 bits, payloads to 8
+//! bytes, filter masks to the ID width). This keeps valid-ID generation in one place without
+//! tying the crate to a particular framework.
+//!
+//! With the `proptest` feature, [`MockFrame`] implements `proptest::arbitrary::Arbitrary`, and
+//! [`ids`] and [`filters`] are strategies for the ID and filter types of `embedded-can` and
+//! `embedded-can-interface` (which this crate cannot implement `Arbitrary` for):
+//!
+//! ```text
+//! use embedded_can_mock::{MockFrame, arbitrary};
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn driver_accepts_any_frame(frame: MockFrame, filter in arbitrary::filters()) {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! [`frames`] produces a seeded sequence directly, for quick randomized tests without a
+//! framework.
+
+use alloc::vec::Vec;
+
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+use embedded_can_interface::{IdMask, IdMaskFilter};
+
+use crate::{frame::MockFrame, rng::Rng};
+
+/// Largest payload produced by [`frame`] (classic CAN).
+pub const MAX_DATA_LEN: usize = 8;
+
+/// A valid ID built from the low 11 (standard) or 29 (extended) bits of `raw`.
+pub fn id(raw: u32, extended: bool) -> Id {
+    if extended {
+        Id::Extended(ExtendedId::new(raw & 0x1FFF_FFFF).unwrap())
+    } else {
+        Id::Standard(StandardId::new((raw & 0x7FF) as u16).unwrap())
+    }
+}
+
+/// A valid classic frame: `data` is truncated to [`MAX_DATA_LEN`] bytes. A remote frame
+/// requests `data.len()` (truncated) bytes.
+pub fn frame(id: Id, data: &[u8], remote: bool) -> MockFrame {
+    let data = &data[..data.len().min(MAX_DATA_LEN)];
+    if remote {
+        MockFrame::new_remote(id, data.len()).unwrap()
+    } else {
+        MockFrame::new(id, data).unwrap()
+    }
+}
+
+/// A valid filter for `id`, with `mask` truncated to the ID’s width.
+pub fn filter(id: Id, mask: u32) -> IdMaskFilter {
+    let (id, mask) = match id {
+        Id::Standard(id) => (
+            embedded_can_interface::Id::Standard(id),
+            IdMask::Standard((mask & 0x7FF) as u16),
+        ),
+        Id::Extended(id) => (
+            embedded_can_interface::Id::Extended(id),
+            IdMask::Extended(mask & 0x1FFF_FFFF),
+        ),
+    };
+    IdMaskFilter { id, mask }
+}
+
+/// Strategy producing valid standard and extended IDs.
+#[cfg(feature = "proptest")]
+pub fn ids() -> impl proptest::strategy::Strategy<Value = Id> {
+    use proptest::prelude::*;
+    (any::<u32>(), any::<bool>()).prop_map(|(raw, extended)| id(raw, extended))
+}
+
+/// Strategy producing valid filters for IDs from [`ids`].
+#[cfg(feature = "proptest")]
+pub fn filters() -> impl proptest::strategy::Strategy<Value = IdMaskFilter> {
+    use proptest::prelude::*;
+    (ids(), any::<u32>()).prop_map(|(id, mask)| filter(id, mask))
+}
+
+/// Valid classic frames from [`frame`]: standard and extended, data and remote.
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for MockFrame {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        use proptest::prelude::*;
+        let data = prop::collection::vec(any::<u8>(), 0..=MAX_DATA_LEN);
+        (ids(), data, any::<bool>())
+            .prop_map(|(id, data, remote)| frame(id, &data, remote))
+            .boxed()
+    }
+}
+
+/// `count` random valid frames (standard and extended, data and remote) derived from `seed`.
+pub fn frames(seed: u64, count: usize) -> Vec<MockFrame> {
+    let mut rng = Rng::new(seed);
+    (0..count)
+        .map(|_| {
+            let bits = rng.next_u64();
+            let len = rng.range(0, MAX_DATA_LEN as u64) as usize;
+            let data: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+            frame(
+                id(bits as u32, bits & (1 << 32) != 0),
+                &data,
+                bits & (1 << 33) != 0,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{matches, validate_filter};
+
+    #[test]
+    fn any_input_maps_to_valid_values() {
+        for raw in [0, 0x7FF, 0x800, u32::MAX] {
+            for extended in [false, true] {
+                let id = id(raw, extended);
+                let filter = filter(id, u32::MAX);
+                assert!(validate_filter(&filter).is_ok());
+                assert!(matches(&filter, id));
+            }
+        }
+        let long = frame(id(1, false), &[0; 64], false);
+        assert_eq!(long.dlc(), MAX_DATA_LEN);
+        assert!(frame(id(1, true), &[0; 3], true).is_remote_frame());
+    }
+
+    #[test]
+    fn seeded_frames_are_reproducible() {
+        let a = frames(9, 50);
+        assert_eq!(a, frames(9, 50));
+        assert!(a.iter().all(|f| f.dlc() <= MAX_DATA_LEN));
+        assert!(a.iter().any(Frame::is_extended));
+        assert!(a.iter().any(|f| !f.is_extended()));
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn strategies_produce_valid_values(frame: MockFrame, filter in filters()) {
+            proptest::prop_assert!(frame.dlc() <= MAX_DATA_LEN);
+            proptest::prop_assert!(validate_filter(&filter).is_ok());
+            let id = match filter.id {
+                embedded_can_interface::Id::Standard(id) => Id::Standard(id),
+                embedded_can_interface::Id::Extended(id) => Id::Extended(id),
+            };
+            proptest::prop_assert!(matches(&filter, id));
+        }
+    }
+}
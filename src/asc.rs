@@ -0,0 +1,393 @@
+//! Vector ASC trace import and export.
+//!
+//! ASC is the text log format of Vector CANoe/CANalyzer. [`Recorder::to_asc`] writes a
+//! recording as an ASC log (absolute hex timestamps on channel 1, every frame logged as `Rx`,
+//! corrupted deliveries as `ErrorFrame`s), and [`parse`] reads classic CAN, CAN FD and error
+//! frame events back from a log, so traffic captured on a real vehicle can be
+//! [replayed](schedule) on a mock bus. Other events (statistics, comments, symbolic names in
+//! the header) are skipped. Vector's binary BLF format is not supported; CANoe converts BLF
+//! logs to ASC.
+//!
+//! [`Recorder::to_asc`]: crate::recorder::Recorder::to_asc
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame, asc};
+//!
+//! let log = "\
+//! date Thu Jan 1 12:00:00.000 am 1970
+//! base hex  timestamps absolute
+//! Begin Triggerblock Thu Jan 1 12:00:00.000 am 1970
+//!    0.000000 Start of measurement
+//!    0.010000 1  7E0             Tx   d 3 02 10 03
+//!    0.012500 1  7E8             Rx   d 3 06 50 03
+//! End TriggerBlock
+//! ";
+//! let entries = asc::parse(log).unwrap();
+//! assert_eq!(entries[1].at, Duration::from_micros(12_500));
+//!
+//! let bus = BusHandle::new();
+//! let node = bus.add_interface(vec![]).unwrap();
+//! asc::schedule(&bus, &entries);
+//! bus.advance(Duration::from_millis(20));
+//! assert_eq!(node.drain_frames().len(), 2);
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, fmt::Write as _, time::Duration};
+
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+
+use crate::{bus::BusHandle, frame::MockFrame, recorder::TraceRecord};
+
+/// Header date written to exported logs: recordings run on virtual time, which starts at the
+/// Unix epoch.
+const EPOCH: &str = "Thu Jan 1 12:00:00.000 am 1970";
+
+/// CAN FD flag bits in the `Flags` field of `CANFD` events.
+const FLAG_EDL: u32 = 0x1000;
+const FLAG_BRS: u32 = 0x2000;
+const FLAG_ESI: u32 = 0x4000;
+
+/// Direction of a logged frame, from the point of view of the logging node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received.
+    Rx,
+    /// Transmitted.
+    Tx,
+}
+
+/// What an ASC log line recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AscEvent {
+    /// A data or remote frame (CAN FD frames carry [`fd`](crate::FrameMeta::fd) and friends).
+    Frame(MockFrame),
+    /// An error frame.
+    ErrorFrame,
+}
+
+/// One frame event read from an ASC log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AscEntry {
+    /// Time since the start of the measurement.
+    pub at: Duration,
+    /// 1-based CAN channel.
+    pub channel: u8,
+    /// `Rx` or `Tx`; `None` for error frames.
+    pub direction: Option<Direction>,
+    /// The frame or error frame.
+    pub event: AscEvent,
+}
+
+/// A malformed ASC log line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AscError {
+    /// 1-based line number.
+    pub line: usize,
+    /// What is wrong with it.
+    pub reason: String,
+}
+
+impl fmt::Display for AscError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ASC line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Write `records` as an ASC log.
+pub fn write<F: Frame + 'static>(records: &[TraceRecord<F>]) -> String {
+    let mut out = format!(
+        "date {EPOCH}\nbase hex  timestamps absolute\ninternal events logged\n\
+         Begin Triggerblock {EPOCH}\n   0.000000 Start of measurement\n"
+    );
+    for record in records {
+        let at = record.at;
+        let time = format!("{:>4}.{:06}", at.as_secs(), at.subsec_micros());
+        if record.error.is_some() {
+            let _ = writeln!(out, "{time} 1  ErrorFrame");
+            continue;
+        }
+        let frame = MockFrame::from_any_frame(&record.frame);
+        let id = id_text(frame.id());
+        let data: String = frame.data().iter().map(|b| format!(" {b:02X}")).collect();
+        let meta = frame.meta();
+        if meta.fd || frame.data().len() > 8 {
+            let flags = [(meta.brs, FLAG_BRS), (meta.esi, FLAG_ESI)]
+                .into_iter()
+                .filter(|(on, _)| *on)
+                .fold(FLAG_EDL, |flags, (_, flag)| flags | flag);
+            let _ = writeln!(
+                out,
+                "{time} CANFD   1 Rx {id:>10} {} {} {:X} {}{data} 0 0 {flags:X} 0 0 0 0 0",
+                u8::from(meta.brs),
+                u8::from(meta.esi),
+                fd_dlc(frame.data().len()),
+                frame.data().len(),
+            );
+        } else if frame.is_remote_frame() {
+            let _ = writeln!(out, "{time} 1  {id:<15} Rx   r {:X}", frame.dlc());
+        } else {
+            let _ = writeln!(
+                out,
+                "{time} 1  {id:<15} Rx   d {:X}{data}",
+                frame.data().len()
+            );
+        }
+    }
+    out.push_str("End TriggerBlock\n");
+    out
+}
+
+/// Read the frame events of an ASC log, in file order.
+///
+/// Both `base hex` and `base dec`, and absolute and relative timestamps, are understood.
+pub fn parse(text: &str) -> Result<Vec<AscEntry>, AscError> {
+    let mut radix = 16;
+    let mut relative = false;
+    let mut last = Duration::ZERO;
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["base", base, "timestamps", stamps, ..] => {
+                radix = if *base == "dec" { 10 } else { 16 };
+                relative = *stamps == "relative";
+                continue;
+            }
+            [time, rest @ ..] if !rest.is_empty() => {
+                let Some(at) = parse_time(time) else {
+                    continue;
+                };
+                let Some(event) = parse_event(rest, radix).map_err(|reason| AscError {
+                    line: n + 1,
+                    reason,
+                })?
+                else {
+                    continue;
+                };
+                let at = if relative { last + at } else { at };
+                last = at;
+                entries.push(AscEntry { at, ..event });
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Schedule every frame in `entries` on `bus`, `entry.at` after the bus's current time (see
+/// [`BusHandle::schedule_transmit`]). Error frames are skipped.
+pub fn schedule(bus: &BusHandle, entries: &[AscEntry]) {
+    let start = bus.now();
+    for entry in entries {
+        if let AscEvent::Frame(frame) = &entry.event {
+            bus.schedule_transmit(start + entry.at, frame.clone());
+        }
+    }
+}
+
+fn id_text(id: Id) -> String {
+    match id {
+        Id::Standard(id) => format!("{:X}", id.as_raw()),
+        Id::Extended(id) => format!("{:X}x", id.as_raw()),
+    }
+}
+
+/// DLC code of a CAN FD payload length.
+fn fd_dlc(len: usize) -> usize {
+    match len {
+        0..=8 => len,
+        9..=12 => 9,
+        13..=16 => 10,
+        17..=20 => 11,
+        21..=24 => 12,
+        25..=32 => 13,
+        33..=48 => 14,
+        _ => 15,
+    }
+}
+
+fn parse_time(text: &str) -> Option<Duration> {
+    let (secs, fraction) = text.split_once('.')?;
+    if fraction.is_empty() || fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = format!("{fraction:0<9}").parse().ok()?;
+    Some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+/// Parse the words after the timestamp: `Ok(None)` for events other than frames.
+fn parse_event(words: &[&str], radix: u32) -> Result<Option<AscEntry>, String> {
+    let entry = |channel, direction, event| AscEntry {
+        at: Duration::ZERO,
+        channel,
+        direction,
+        event,
+    };
+    match words {
+        ["CANFD", channel, direction, id, rest @ ..] => {
+            let channel = parse_channel(channel)?;
+            let direction = parse_direction(direction)?;
+            let id = parse_id(id, radix)?;
+            // An optional symbolic message name may follow the ID.
+            let rest = match rest {
+                [name, rest @ ..] if name.parse::<u8>().is_err() => rest,
+                _ => rest,
+            };
+            let [brs, esi, _dlc, len, rest @ ..] = rest else {
+                return Err("truncated CANFD event".to_string());
+            };
+            let len: usize = len.parse().map_err(|_| format!("invalid length `{len}`"))?;
+            let data = parse_bytes(rest.get(..len).ok_or("truncated CANFD data")?, radix)?;
+            let mut frame = MockFrame::new(id, &data).ok_or("invalid CANFD frame")?;
+            let meta = frame.meta_mut();
+            meta.fd = true;
+            meta.brs = *brs == "1";
+            meta.esi = *esi == "1";
+            Ok(Some(entry(
+                channel,
+                Some(direction),
+                AscEvent::Frame(frame),
+            )))
+        }
+        [channel, "ErrorFrame", ..] => Ok(Some(entry(
+            parse_channel(channel)?,
+            None,
+            AscEvent::ErrorFrame,
+        ))),
+        [channel, id, direction, kind, rest @ ..]
+            if channel.parse::<u8>().is_ok() && matches!(*kind, "d" | "r") =>
+        {
+            let channel = parse_channel(channel)?;
+            let direction = parse_direction(direction)?;
+            let id = parse_id(id, radix)?;
+            let dlc = match rest.first() {
+                Some(dlc) => {
+                    usize::from_str_radix(dlc, 16).map_err(|_| format!("invalid DLC `{dlc}`"))?
+                }
+                None if *kind == "r" => 0,
+                None => return Err("missing DLC".to_string()),
+            };
+            let frame = if *kind == "r" {
+                MockFrame::new_remote(id, dlc).ok_or("invalid remote frame")?
+            } else {
+                let bytes = rest.get(1..1 + dlc).ok_or("truncated data")?;
+                MockFrame::new(id, &parse_bytes(bytes, radix)?).ok_or("invalid frame")?
+            };
+            Ok(Some(entry(
+                channel,
+                Some(direction),
+                AscEvent::Frame(frame),
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_channel(text: &str) -> Result<u8, String> {
+    text.parse()
+        .map_err(|_| format!("invalid channel `{text}`"))
+}
+
+fn parse_direction(text: &str) -> Result<Direction, String> {
+    match text {
+        "Rx" => Ok(Direction::Rx),
+        "Tx" | "TxRq" => Ok(Direction::Tx),
+        _ => Err(format!("invalid direction `{text}`")),
+    }
+}
+
+fn parse_id(text: &str, radix: u32) -> Result<Id, String> {
+    let invalid = || format!("invalid ID `{text}`");
+    let (raw, extended) = match text.strip_suffix('x') {
+        Some(raw) => (raw, true),
+        None => (text, false),
+    };
+    let raw = u32::from_str_radix(raw, radix).map_err(|_| invalid())?;
+    if extended {
+        ExtendedId::new(raw).map(Id::Extended).ok_or_else(invalid)
+    } else {
+        u16::try_from(raw)
+            .ok()
+            .and_then(StandardId::new)
+            .map(Id::Standard)
+            .ok_or_else(invalid)
+    }
+}
+
+fn parse_bytes(words: &[&str], radix: u32) -> Result<Vec<u8>, String> {
+    words
+        .iter()
+        .map(|b| u8::from_str_radix(b, radix).map_err(|_| format!("invalid data byte `{b}`")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event::BusEvent, recorder::Recorder};
+
+    #[test]
+    fn exported_logs_parse_back_to_the_same_traffic() {
+        let bus = BusHandle::new();
+        let recorder = Recorder::attach(&bus);
+        let node = bus.add_interface(Vec::new()).unwrap();
+        node.set_auto_retransmit(false);
+        let classic = MockFrame::new(StandardId::new(0x123).unwrap(), &[0xAA, 0x01]).unwrap();
+        let remote = MockFrame::new_remote(ExtendedId::new(0x1ABC_DE01).unwrap(), 4).unwrap();
+        let mut fd = MockFrame::new(StandardId::new(0x456).unwrap(), &[9; 12]).unwrap();
+        fd.meta_mut().fd = true;
+        fd.meta_mut().brs = true;
+
+        node.transmit(classic.clone()).unwrap();
+        bus.advance(Duration::from_micros(1500));
+        node.transmit(remote.clone()).unwrap();
+        node.transmit(fd.clone()).unwrap();
+        bus.corrupt_next(BusEvent::CrcError);
+        node.transmit(classic.clone()).unwrap();
+
+        let log = recorder.to_asc();
+        assert!(
+            log.contains("   0.000000 1  123             Rx   d 2 AA 01\n"),
+            "{log}"
+        );
+        assert!(
+            log.contains("   0.001500 1  1ABCDE01x       Rx   r 4\n"),
+            "{log}"
+        );
+        let entries = parse(&log).unwrap();
+        let events: Vec<_> = entries.iter().map(|e| e.event.clone()).collect();
+        assert_eq!(
+            events,
+            [
+                AscEvent::Frame(classic),
+                AscEvent::Frame(remote),
+                AscEvent::Frame(fd),
+                AscEvent::ErrorFrame
+            ]
+        );
+        assert_eq!(entries[3].at, Duration::from_micros(1500));
+        assert_eq!(entries[0].direction, Some(Direction::Rx));
+
+        let relative =
+            "base dec  timestamps relative\n 0.5 2 100 Tx d 1 255\n 0.25 2 100 Tx d 1 7\n";
+        let entries = parse(relative).unwrap();
+        assert_eq!(
+            (entries[1].at, entries[1].channel),
+            (Duration::from_millis(750), 2)
+        );
+        assert_eq!(
+            parse("base hex timestamps absolute\n 1.0 1 123 Rx d 2 AA\n"),
+            Err(AscError {
+                line: 2,
+                reason: "truncated data".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Backend selection from the environment.
+//!
+//! Integration-test binaries often need to run against the mock in CI and against real hardware
+//! on a developer's bench. [`selected`] reads the [`CAN_BACKEND`](BACKEND_VAR) environment variable,
+//! formatted as `kind:target`, and tells the application which backend to open:
+//!
+//! - `mock`, `mock:bus0` or a full `mock://bus0?listen_only=true` [configuration string](crate::uri)
+//!   select this crate; [`Backend::open_mock`] opens the configured [`MockCan`].
+//! - Anything else, such as `socketcan:can0`, is returned as [`Backend::Other`] for the
+//!   application to hand to the matching driver.
+//!
+//! ```
+//! use embedded_can_mock::backend::{Backend, BackendError};
+//!
+//! // What an application does with `CAN_BACKEND` set to each of these:
+//! let can = Backend::parse("mock:bus0?self_reception=false").unwrap().open_mock().unwrap();
+//! assert!(!can.interface().is_self_reception());
+//!
+//! match Backend::parse("socketcan:can0").unwrap() {
+//!     Backend::Other { kind, target } => assert_eq!((kind.as_str(), target.as_str()), ("socketcan", "can0")),
+//!     Backend::Mock(_) => unreachable!(),
+//! }
+//! assert!(matches!(
+//!     Backend::parse("socketcan:can0").unwrap().open_mock(),
+//!     Err(BackendError::OtherBackend { .. })
+//! ));
+//! ```
+
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::{
+    MockCan, MockError,
+    uri::{MockUri, SCHEME, UriError},
+};
+
+/// Environment variable read by [`selected`] and [`from_env`].
+pub const BACKEND_VAR: &str = "CAN_BACKEND";
+
+/// Backend named by a `kind:target` selection string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// This crate's mock, configured as described.
+    Mock(MockUri),
+    /// Some other backend, left to the application.
+    Other {
+        /// Backend kind, the part before the first `:` (such as `socketcan`).
+        kind: String,
+        /// Everything after the `:` (such as `can0`); empty if there was no `:`.
+        target: String,
+    },
+}
+
+/// Errors from selecting or opening a backend.
+#[derive(Debug)]
+pub enum BackendError {
+    /// The environment variable is not set.
+    Unset {
+        /// Name of the variable.
+        var: String,
+    },
+    /// The environment variable is not valid Unicode.
+    NotUnicode {
+        /// Name of the variable.
+        var: String,
+    },
+    /// The selection names the mock, but its configuration is malformed.
+    InvalidUri(UriError),
+    /// A mock was requested from a selection naming another backend.
+    OtherBackend {
+        /// Backend kind, such as `socketcan`.
+        kind: String,
+        /// Its target, such as `can0`.
+        target: String,
+    },
+    /// Opening the mock interface failed.
+    Open(MockError),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Unset { var } => {
+                write!(
+                    f,
+                    "{var} is not set; set it to `mock` or e.g. `socketcan:can0`"
+                )
+            }
+            BackendError::NotUnicode { var } => write!(f, "{var} is not valid Unicode"),
+            BackendError::InvalidUri(err) => write!(f, "invalid mock configuration: {err}"),
+            BackendError::OtherBackend { kind, target } => write!(
+                f,
+                "backend `{kind}:{target}` is not provided by embedded-can-mock; open it with \
+                 its own driver"
+            ),
+            BackendError::Open(err) => write!(f, "opening the mock failed: {err:?}"),
+        }
+    }
+}
+
+impl Backend {
+    /// Parse a `kind:target` selection string (see the [module documentation](self)).
+    pub fn parse(spec: &str) -> Result<Self, BackendError> {
+        let spec = spec.trim();
+        let (kind, target) = spec.split_once(':').unwrap_or((spec, ""));
+        if kind != "mock" {
+            return Ok(Backend::Other {
+                kind: kind.to_string(),
+                target: target.to_string(),
+            });
+        }
+        // `mock:bus0?...` is shorthand for `mock://bus0?...`.
+        let target = target.strip_prefix("//").unwrap_or(target);
+        MockUri::parse(&alloc::format!("{SCHEME}{target}"))
+            .map(Backend::Mock)
+            .map_err(BackendError::InvalidUri)
+    }
+
+    /// Open the selected mock interface, or report that another backend was selected.
+    pub fn open_mock(&self) -> Result<MockCan, BackendError> {
+        match self {
+            Backend::Mock(uri) => uri.builder().build().map_err(BackendError::Open),
+            Backend::Other { kind, target } => Err(BackendError::OtherBackend {
+                kind: kind.clone(),
+                target: target.clone(),
+            }),
+        }
+    }
+}
+
+/// Backend selected by the environment variable `var`.
+pub fn selected_by(var: &str) -> Result<Backend, BackendError> {
+    select(var, std::env::var(var))
+}
+
+/// Backend selected by `value`, the result of reading the environment variable `var`.
+fn select(var: &str, value: Result<String, std::env::VarError>) -> Result<Backend, BackendError> {
+    match value {
+        Ok(spec) => Backend::parse(&spec),
+        Err(std::env::VarError::NotPresent) => Err(BackendError::Unset {
+            var: var.to_string(),
+        }),
+        Err(std::env::VarError::NotUnicode(_)) => Err(BackendError::NotUnicode {
+            var: var.to_string(),
+        }),
+    }
+}
+
+/// Backend selected by [`CAN_BACKEND`](BACKEND_VAR).
+pub fn selected() -> Result<Backend, BackendError> {
+    selected_by(BACKEND_VAR)
+}
+
+/// Open the mock interface selected by [`CAN_BACKEND`](BACKEND_VAR).
+///
+/// Fails with [`BackendError::OtherBackend`] if the variable names another backend, so a test
+/// binary can fall back to real hardware; use [`selected`] to dispatch on the backend instead.
+pub fn from_env() -> Result<MockCan, BackendError> {
+    selected()?.open_mock()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_selects_mock_or_reports_other_backend() {
+        use std::env::VarError;
+
+        let var = "EMBEDDED_CAN_MOCK_TEST_BACKEND";
+        assert!(matches!(selected_by(var), Err(BackendError::Unset { .. })));
+        assert!(matches!(
+            select(var, Err(VarError::NotUnicode("\u{FFFD}".into()))),
+            Err(BackendError::NotUnicode { .. })
+        ));
+
+        let spec = |value: &str| select(var, Ok(value.to_string()));
+        let can = spec("mock:backend-test?listen_only=true")
+            .unwrap()
+            .open_mock()
+            .unwrap();
+        assert!(can.interface().is_listen_only());
+        assert_eq!(crate::BusHandle::named("backend-test").interface_count(), 1);
+
+        assert!(matches!(spec("mock"), Ok(Backend::Mock(uri)) if uri.bus.is_empty()));
+        assert!(matches!(
+            spec("mock:bus?bitrate=1"),
+            Err(BackendError::InvalidUri(_))
+        ));
+
+        let Err(err) = spec("pcan:PCAN_USBBUS1").unwrap().open_mock() else {
+            panic!("opened a mock for another backend");
+        };
+        assert_eq!(
+            err.to_string(),
+            "backend `pcan:PCAN_USBBUS1` is not provided by embedded-can-mock; open it with its \
+             own driver"
+        );
+    }
+}
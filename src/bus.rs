@@ -5,32 +5,304 @@
 //! and has its own receive queue and acceptance filter list.
 //!
 //! The bus is intentionally simple:
-//! - Transmit is immediate and synchronous.
-//! - Frames are broadcast to every attached interface (including the transmitter).
-//! - Receive queues are unbounded (in-memory).
+//! - Transmit is immediate and synchronous, unless timing ([`BusHandle::set_timing`]) or stepped
+//!   mode ([`BusHandle::set_stepped`]) is enabled.
+//! - Frames are broadcast to every attached interface (including the transmitter, unless
+//!   self-reception is disabled), unless a custom [medium](crate::medium) is installed.
+//! - Receive queues are unbounded (in-memory) unless a capacity is set.
+//! - Every frame is delivered to the receiving interfaces in the order they were attached, so
+//!   wakeups, subscriber feeds and trace events for one frame follow attach order.
+//!
+//! # Frame types
+//!
+//! Buses and interfaces are generic over the frame type they carry: any
+//! `F: embedded_can::Frame + Clone`, with [`MockFrame`] as the default. A project with its own
+//! frame type (carrying FD flags or timestamps, say) can route it through the mock unchanged:
+//!
+//! ```
+//! use embedded_can::{Frame, Id, StandardId};
+//! use embedded_can_mock::BusHandle;
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! struct MyFrame {
+//!     id: Id,
+//!     data: Vec<u8>,
+//!     timestamp_us: u64,
+//! }
+//!
+//! impl Frame for MyFrame {
+//!     fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+//!         Some(Self { id: id.into(), data: data.to_vec(), timestamp_us: 0 })
+//!     }
+//!     fn new_remote(_id: impl Into<Id>, _dlc: usize) -> Option<Self> {
+//!         None
+//!     }
+//!     fn is_extended(&self) -> bool {
+//!         matches!(self.id, Id::Extended(_))
+//!     }
+//!     fn is_remote_frame(&self) -> bool {
+//!         false
+//!     }
+//!     fn id(&self) -> Id {
+//!         self.id
+//!     }
+//!     fn dlc(&self) -> usize {
+//!         self.data.len()
+//!     }
+//!     fn data(&self) -> &[u8] {
+//!         &self.data
+//!     }
+//! }
+//!
+//! let bus = BusHandle::<MyFrame>::default();
+//! let node = bus.add_interface(vec![]).unwrap();
+//! let mut frame = MyFrame::new(StandardId::new(0x10).unwrap(), &[1]).unwrap();
+//! frame.timestamp_us = 42;
+//! node.transmit(frame.clone()).unwrap();
+//! assert_eq!(node.pop_frame(), Some(frame));
+//! ```
+//!
+//! # Locking
+//!
+//! All interface state (queues, filters, modes) lives inside the bus it is attached to, behind a
+//! single mutex. A broadcast therefore takes exactly one lock regardless of the number of
+//! attached nodes, and receivers are woken through a per-interface condition variable; threads
+//! blocked on the same interface are served in the order they started waiting. An
+//! interface that is not attached to any bus lives on a private, single-node bus until
+//! [`InterfaceHandle::attach_to_bus`] moves it.
 
-use std::{
-    collections::VecDeque,
-    sync::{Arc, Condvar, Mutex, Weak},
+use alloc::{
+    boxed::Box,
+    collections::{VecDeque, vec_deque},
+    string::String,
+    sync::{Arc, Weak},
     vec::Vec,
 };
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
 
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+use crate::sync::{MutexGuard, wait, wait_timeout};
 use crate::{
-    filter::{FilterError, matches as filter_matches, validate_filters},
-    frame::MockFrame,
+    chaos::ChaosConfig,
+    event::{BusEvent, ErrorCounters, ErrorState},
+    filter::{
+        EmptyListPolicy, FilterError, FilterSemantics, FilterSet, FilterStats, MatchMode,
+        MatchReport, accept_range, accept_range_limited, explain, validate_filters,
+    },
+    frame::{CandumpParseError, MockFrame},
+    matcher::FrameMatcher,
+    medium::{Broadcast, BusMedium},
+    memory::{MemoryLimits, MemoryUsage, trim},
+    middleware::{self, Middleware, MiddlewareAction},
+    policy::{PolicyDecision, PolicyRecord, TransmitPolicy},
+    recorder::{TraceRecord, TraceSink},
+    rng::Rng,
+    sync::{Condvar, Mutex, lock},
+    timing::{BusLoad, BusTiming, LoadLimit, PaceState, Pacing, Starvation, arbitration_key},
+    trace::{TraceEvent, Tracer, dispatch, dispatch_node_left},
+    ttcan::{OutOfWindow, TtSchedule, WindowEvent},
 };
-use embedded_can::Frame;
+use embedded_can::{Frame, Id};
 use embedded_can_interface::IdMaskFilter;
 
-pub(crate) struct MockBus {
-    interfaces: Vec<Arc<Mutex<MockInterface>>>,
+/// State of one bus: every interface living on it, in attach order.
+pub(crate) struct MockBus<F> {
+    /// Attached interfaces in attach order, which is also delivery order.
+    interfaces: Vec<MockInterface<F>>,
+    /// Attach limit set by [`BusHandle::with_capacity`]; taps are exempt.
+    max_interfaces: Option<usize>,
+    /// Virtual clock, advanced explicitly via [`BusHandle::advance`].
+    now: Duration,
+    timing: Option<BusTiming>,
+    /// Virtual time at which the wire becomes free again.
+    busy_until: Duration,
+    /// Frame currently occupying the wire.
+    on_wire: Option<InFlight<F>>,
+    /// Frames waiting for the wire, in request order; arbitration picks among them.
+    waiting: Vec<InFlight<F>>,
+    load_limit: Option<LoadLimit>,
+    starvation: Option<Starvation>,
+    /// Virtual time at which the last unstarved frame left the wire.
+    unstarved_until: Duration,
+    /// Total wire time of completed frames, for occupancy.
+    busy_time: Duration,
+    overloaded: bool,
+    overload_rejections: u64,
+    /// Errors to inject instead of delivering the next frames, one per frame.
+    corruptions: VecDeque<BusEvent>,
+    /// Attached [`Recorder`](crate::recorder::Recorder)s; dropped ones are pruned lazily.
+    recorders: Vec<Weak<TraceSink<F>>>,
+    tracer: Option<Tracer<F>>,
+    /// Stepped mode: transmitted frames are held until [`BusHandle::step`] releases them.
+    stepped: bool,
+    held: VecDeque<InFlight<F>>,
+    /// Decides which interfaces each delivered frame reaches.
+    medium: Box<dyn BusMedium<F>>,
+    /// Isolated groups set by [`BusHandle::partition`]; frames only reach their own group.
+    partition: Option<Vec<Vec<InterfaceId>>>,
+    policy: Option<TransmitPolicy<F>>,
+    /// Frames the policy dropped, rejected or flagged for logging, oldest first.
+    policy_log: Vec<PolicyRecord<F>>,
+    /// Deliveries refused by receivers' acceptance filters, while logging is enabled.
+    rejections: Option<Vec<Rejection<F>>>,
+    /// Frames matching the breakpoint pause the bus (see [`BusHandle::pause_on`]).
+    breakpoint: Option<Box<dyn FrameMatcher<F> + Send + Sync>>,
+    /// Paused by the breakpoint: frames are held as in stepped mode until resumed.
+    paused: bool,
+    /// Stuck-dominant fault: the wire never goes idle, so nothing is transmitted.
+    stuck_dominant: bool,
+    /// Babbling-idiot fault: this interface requests the wire with this frame again as soon as
+    /// its previous copy is sent, starting no earlier than the given time.
+    babbler: Option<(InterfaceId, F, Duration)>,
+    /// Caps on histories and the queue watermark.
+    limits: MemoryLimits,
+    /// Recently completed frames, oldest first, for interfaces attaching with [`CatchUp`].
+    replay: VecDeque<Arrival<F>>,
+    replay_capacity: Option<usize>,
+    /// Report [`BusEvent::NodeLeft`] to every node, not just taps.
+    announce_departures: bool,
+    /// Bus-wide fault injection and the generator driving it.
+    chaos: Option<(ChaosConfig, Rng)>,
+    /// Time-triggered schedule constraining when each interface may transmit.
+    schedule: Option<TtSchedule>,
+    window_events: Vec<WindowEvent>,
+    /// Earliest window start not yet logged in `window_events`.
+    window_cursor: Duration,
+    /// Frames programmed with [`BusHandle::schedule_transmit`], sorted by release time.
+    scheduled: Vec<(Duration, F)>,
+    /// Source identity and next sequence number for frames injected by the bus itself.
+    injector: Option<(InterfaceId, u64)>,
+    /// Number of frames delivered so far; the next delivery's sequence number.
+    deliveries: u64,
+}
+
+/// One delivery of a frame. Every receiver queuing it shares the same allocation, so a
+/// broadcast costs one frame copy however many nodes receive it.
+#[derive(Clone)]
+struct Arrival<F> {
+    frame: Arc<F>,
+    token: TxToken,
+    /// Virtual time and bus-wide sequence number of the delivery.
+    at: Duration,
+    seq: u64,
+}
+
+/// A frame queued on a receiver, tagged with the transmission it came from.
+struct Received<F> {
+    frame: Arc<F>,
+    token: TxToken,
+    /// Mailbox the frame was routed to (always 0 outside mailbox mode).
+    mailbox: usize,
+    /// Virtual time and bus-wide sequence number of the delivery.
+    at: Duration,
+    seq: u64,
+}
+
+impl<F: Clone> Received<F> {
+    /// Take the frame out, copying it only if another receiver still shares it.
+    fn into_frame(self) -> F {
+        Arc::try_unwrap(self.frame).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+/// A received frame together with where and when it came from, as returned by
+/// [`InterfaceHandle::pop_delivery`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivered<F = MockFrame> {
+    /// The frame itself.
+    pub frame: F,
+    /// Interface that transmitted the frame (or [`BusHandle::injector_id`] for frames the bus
+    /// injected itself).
+    pub source: InterfaceId,
+    /// Virtual time at which the frame was delivered (see [`BusHandle::now`]).
+    pub timestamp: Duration,
+    /// Bus-wide delivery sequence number, starting at 0. Every receiver of one frame sees the
+    /// same number, so it orders deliveries across nodes.
+    pub seq: u64,
+    /// `true` if the receiving interface transmitted the frame itself (self-reception or
+    /// loopback), `false` if it came from another node.
+    pub echo: bool,
+}
+
+/// A delivery an interface’s acceptance filters refused, as returned by
+/// [`BusHandle::take_rejections`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection<F = MockFrame> {
+    /// Interface that did not receive the frame.
+    pub receiver: InterfaceId,
+    /// Transmission the frame belonged to.
+    pub token: TxToken,
+    /// The frame itself.
+    pub frame: F,
+    /// Why each of the receiver’s filters rejected it.
+    pub report: MatchReport,
+}
+
+/// Borrowing iterator over a receive queue, passed to
+/// [`InterfaceHandle::with_received_frames`].
+#[derive(Clone)]
+pub struct ReceivedFrames<'a, F = MockFrame> {
+    inner: vec_deque::Iter<'a, Received<F>>,
+}
+
+impl<'a, F> Iterator for ReceivedFrames<'a, F> {
+    type Item = &'a F;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|r| &*r.frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<F> DoubleEndedIterator for ReceivedFrames<'_, F> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|r| &*r.frame)
+    }
+}
+
+impl<F> ExactSizeIterator for ReceivedFrames<'_, F> {}
+
+/// A frame waiting for, or occupying, the wire.
+struct InFlight<F> {
+    /// Request time while waiting; completion time once on the wire.
+    at: Duration,
+    /// Time the frame was handed to the bus, before any pacing or retransmission delay.
+    requested: Duration,
+    source: InterfaceId,
+    frame: F,
+    token: TxToken,
+    /// Queue a confirmation on the transmitter once the frame completes.
+    confirm: bool,
+    /// Group the frame was addressed to with [`InterfaceHandle::transmit_to_group`]; only its
+    /// members, taps and the transmitter receive it.
+    group: Option<String>,
 }
 
 /// Errors returned when transmitting a frame via an [`InterfaceHandle`].
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TransmitError {
     /// The interface is not attached to any bus.
     BusNotAttached,
+    /// The bus [load limit](crate::timing::LoadLimit) rejected the frame.
+    BusOverloaded,
+    /// The interface is in [listen-only](InterfaceHandle::set_listen_only) mode, or is a
+    /// [tap](BusHandle::tap).
+    ListenOnly,
+    /// The bus [transmit policy](BusHandle::set_transmit_policy) rejected the frame.
+    PolicyRejected,
+    /// The bus [schedule](BusHandle::set_schedule) does not let the interface transmit now.
+    OutsideWindow,
+    /// The interface’s [pacing](InterfaceHandle::set_pacing) does not allow another frame yet.
+    RateLimited,
 }
 
 /// Errors returned by bus / interface attachment operations.
@@ -42,14 +314,278 @@ pub enum MockInterfaceError {
     BusNotAttached,
     /// One or more acceptance filters failed validation.
     InvalidFilters,
+    /// The bus already holds as many interfaces as [`BusHandle::with_capacity`] allows.
+    BusFull,
+}
+
+/// Number of transmitted frames each interface keeps in its TX history by default (see
+/// [`InterfaceHandle::sent_frames`]).
+pub const DEFAULT_TX_HISTORY: usize = 1024;
+
+/// Number of completed frames a bus keeps for late joiners to [catch up](CatchUp) on by default
+/// (see [`BusHandle::set_replay_capacity`]).
+pub const DEFAULT_REPLAY_HISTORY: usize = 1024;
+
+/// Earlier traffic replayed to an interface when it attaches to a bus mid-traffic.
+///
+/// Replayed frames are taken from the frames the bus has most recently completed (up to its
+/// [replay capacity](BusHandle::set_replay_capacity)), pass through the new interface's
+/// filters, and keep their original timestamps and sequence numbers. Frames sent to a
+/// [group](InterfaceHandle::transmit_to_group) are never replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Only receive traffic completing after the attach, like a real node powering up.
+    #[default]
+    Nothing,
+    /// Replay the last `n` frames that completed on the bus.
+    Last(usize),
+    /// Replay every retained frame that completed at or after this virtual time.
+    Since(Duration),
+}
+
+/// Stable identifier of an interface, unique within the process.
+///
+/// Returned by [`InterfaceHandle::id`]; used wherever the bus reports which node something
+/// belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InterfaceId(u64);
+
+impl InterfaceId {
+    fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed) as u64)
+    }
+
+    /// Raw numeric value of the identifier.
+    pub fn as_raw(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Token identifying one transmitted frame, returned by [`InterfaceHandle::transmit`] and
+/// [`InterfaceHandle::transmit_confirmed`].
+///
+/// Every receiver of the frame sees the same token (see
+/// [`InterfaceHandle::pop_frame_with_token`]), so tests can correlate “the third frame I sent”
+/// with what was received even when payloads are identical. Tokens are ordered by transmit order
+/// within an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxToken {
+    source: InterfaceId,
+    seq: u64,
+}
+
+impl TxToken {
+    /// Interface that transmitted the frame.
+    pub fn source(&self) -> InterfaceId {
+        self.source
+    }
+
+    /// Per-interface sequence number of the frame, starting at 0.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+/// Point-in-time copy of a bus’s receive queues, as returned by [`BusHandle::snapshot`] and
+/// put back with [`BusHandle::restore`].
+///
+/// Snapshots are plain data (IDs and frames), so they can be compared or stored in fixtures.
+/// Snapshots of [`MockFrame`]s also have a stable text form: their
+/// [`Display`](fmt::Display) output, read back with [`parse`](Self::parse). Each interface
+/// starts with an `interface <id>` line, followed by its queued frames in candump notation:
+///
+/// ```text
+/// interface 3
+/// 123#DEADBEEF
+/// 1ABCDE01#R4
+/// interface 4
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusSnapshot<F = MockFrame> {
+    /// One entry per attached interface, in attach order.
+    pub interfaces: Vec<InterfaceSnapshot<F>>,
+}
+
+impl BusSnapshot {
+    /// Parse the text form of a snapshot. Blank lines are ignored.
+    pub fn parse(text: &str) -> Result<Self, SnapshotParseError> {
+        let mut interfaces: Vec<InterfaceSnapshot> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(id) = line.strip_prefix("interface") {
+                let id = id
+                    .trim()
+                    .parse()
+                    .map_err(|_| SnapshotParseError::InvalidInterface { line: line_no })?;
+                interfaces.push(InterfaceSnapshot {
+                    id: InterfaceId(id),
+                    frames: Vec::new(),
+                });
+                continue;
+            }
+            let Some(current) = interfaces.last_mut() else {
+                return Err(SnapshotParseError::FrameBeforeInterface { line: line_no });
+            };
+            let frame = MockFrame::parse_candump(line).map_err(|error| {
+                SnapshotParseError::InvalidFrame {
+                    line: line_no,
+                    error,
+                }
+            })?;
+            current.frames.push(frame);
+        }
+        Ok(Self { interfaces })
+    }
+}
+
+impl fmt::Display for BusSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for interface in &self.interfaces {
+            writeln!(f, "interface {}", interface.id.as_raw())?;
+            for frame in &interface.frames {
+                writeln!(f, "{frame}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned by [`BusSnapshot::parse`]; lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotParseError {
+    /// An `interface` line does not carry a numeric interface ID.
+    InvalidInterface {
+        /// The offending line.
+        line: usize,
+    },
+    /// A frame appears before the first `interface` line.
+    FrameBeforeInterface {
+        /// The offending line.
+        line: usize,
+    },
+    /// A frame line is not valid candump notation.
+    InvalidFrame {
+        /// The offending line.
+        line: usize,
+        /// Why the frame did not parse.
+        error: CandumpParseError,
+    },
+}
+
+impl fmt::Display for SnapshotParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotParseError::InvalidInterface { line } => {
+                write!(f, "snapshot line {line}: invalid interface ID")
+            }
+            SnapshotParseError::FrameBeforeInterface { line } => {
+                write!(f, "snapshot line {line}: frame before the first interface")
+            }
+            SnapshotParseError::InvalidFrame { line, error } => {
+                write!(f, "snapshot line {line}: invalid frame ({error:?})")
+            }
+        }
+    }
+}
+
+/// Queued frames of a single interface within a [`BusSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceSnapshot<F = MockFrame> {
+    /// Interface the frames are queued on.
+    pub id: InterfaceId,
+    /// Queued received frames, oldest first.
+    pub frames: Vec<F>,
 }
 
-pub(crate) struct MockInterface {
+/// Per-interface state, owned by the bus the interface lives on.
+pub(crate) struct MockInterface<F> {
+    id: InterfaceId,
     pub(crate) filters: Vec<IdMaskFilter>,
-    me: Weak<Mutex<MockInterface>>,
-    bus: Weak<Mutex<MockBus>>, // TODO remove arc<mutex> spam
-    received_frames: VecDeque<MockFrame>,
+    filter_semantics: FilterSemantics,
+    /// Frames accepted by each filter since the filters were installed.
+    filter_hits: Vec<u64>,
+    /// Exact IDs the filters were meant to accept, when installed from a range.
+    filter_intent: Option<FilterSet>,
+    false_accepts: u64,
+    attached: bool,
+    received_frames: VecDeque<Received<F>>,
     condvar: Arc<Condvar>,
+    nonblocking: bool,
+    /// Probability and RNG for silently dropping accepted frames.
+    rx_drop: Option<(f64, Rng)>,
+    rx_dropped: u64,
+    /// Reordering window and RNG: an arriving frame may overtake up to that many queued frames.
+    rx_reorder: Option<(usize, Rng)>,
+    events: VecDeque<BusEvent>,
+    counters: ErrorCounters,
+    /// `Some(lose_wake_frame)` while asleep.
+    asleep: Option<bool>,
+    /// Internal loopback: disconnected from the bus, transmissions only come back to itself.
+    loopback: bool,
+    /// Sequence number of the next transmitted frame.
+    next_seq: u64,
+    /// Tokens of confirmed transmissions that completed, oldest first.
+    tx_confirmations: VecDeque<TxToken>,
+    pacing: Option<Pacing>,
+    pace: PaceState,
+    /// Frames this interface transmitted, oldest first, bounded by `tx_history_capacity`.
+    tx_history: VecDeque<F>,
+    tx_history_capacity: Option<usize>,
+    name: Option<String>,
+    /// Groups joined with [`InterfaceHandle::join_group`].
+    groups: Vec<String>,
+    /// Maximum receive queue length; frames arriving at a full queue are lost.
+    rx_capacity: Option<usize>,
+    rx_overflows: u64,
+    /// Maximum virtual time a frame may sit in the receive queue before it is discarded.
+    rx_ttl: Option<Duration>,
+    rx_expired: u64,
+    /// Retry transmissions that fail with a bus error, like a controller's automatic
+    /// retransmission; `false` is one-shot mode.
+    auto_retransmit: bool,
+    retransmissions: u64,
+    /// Whether the interface receives its own transmissions.
+    self_reception: bool,
+    listen_only: bool,
+    /// Route frames into per-filter mailboxes.
+    mailboxes: bool,
+    /// Mailbox assigned to each filter; filters past the end use their own index.
+    filter_mailboxes: Vec<usize>,
+    /// Promiscuous, receive-only observer created by [`BusHandle::tap`].
+    tap: bool,
+    /// Receive-only cursor created by [`InterfaceHandle::subscribe`] on this interface.
+    subscribed_to: Option<InterfaceId>,
+    /// Async receivers to wake when a frame is queued.
+    rx_wakers: Vec<Waker>,
+    /// Threads blocked in a wait on this interface, in arrival order.
+    #[cfg_attr(any(not(feature = "std"), target_family = "wasm"), allow(dead_code))]
+    waiters: VecDeque<Waiter>,
+    #[cfg_attr(any(not(feature = "std"), target_family = "wasm"), allow(dead_code))]
+    next_ticket: u64,
+    /// Incremented on every state change waiters may be interested in.
+    generation: u64,
+    /// Closed by [`InterfaceHandle::close`]: waits no longer block.
+    closed: bool,
+    /// Middleware run on transmitted frames, in registration order.
+    tx_middleware: Vec<Middleware<F>>,
+    /// Middleware run on accepted frames before they are queued.
+    rx_middleware: Vec<Middleware<F>>,
+    /// Frames delayed by RX middleware, with the time they are queued at, in arrival order.
+    rx_delayed: Vec<(Duration, Received<F>)>,
+}
+
+/// A thread blocked on an interface, with the last state generation it examined.
+#[cfg_attr(any(not(feature = "std"), target_family = "wasm"), allow(dead_code))]
+struct Waiter {
+    ticket: u64,
+    seen: Option<u64>,
 }
 
 /// Handle to a shared in-memory bus.
@@ -68,7 +604,7 @@ pub(crate) struct MockInterface {
 /// assert_eq!(iface.pop_frame().unwrap(), frame);
 /// ```
 #[derive(Clone)]
-pub struct BusHandle(Arc<Mutex<MockBus>>);
+pub struct BusHandle<F = MockFrame>(Arc<Mutex<MockBus<F>>>);
 
 /// Handle to a single mock CAN interface (node).
 ///
@@ -78,189 +614,3990 @@ pub struct BusHandle(Arc<Mutex<MockBus>>);
 ///
 /// Use [`InterfaceHandle::transmit`] to send a frame onto the bus, and
 /// [`InterfaceHandle::pop_frame`] / [`InterfaceHandle::wait_for_frame`] to receive.
+///
+/// Handles are cheap clones of the same interface; dropping the last one takes the interface off
+/// its bus.
 #[derive(Clone)]
-pub struct InterfaceHandle(Arc<Mutex<MockInterface>>);
-
-impl MockInterface {
-    fn new(filters: Vec<IdMaskFilter>) -> Arc<Mutex<Self>> {
-        Arc::<Mutex<Self>>::new_cyclic(|me| {
-            Mutex::new(Self {
-                filters,
-                me: me.clone(),
-                bus: Weak::new(),
-                received_frames: VecDeque::new(),
-                condvar: Arc::new(Condvar::new()),
-            })
-        })
+pub struct InterfaceHandle<F = MockFrame>(Arc<InterfaceShared<F>>);
+
+/// Part of an interface shared by all of its handles.
+struct InterfaceShared<F> {
+    id: InterfaceId,
+    #[cfg_attr(any(not(feature = "std"), target_family = "wasm"), allow(dead_code))]
+    condvar: Arc<Condvar>,
+    /// Bus currently holding this interface’s state (a private bus while unattached).
+    home: Mutex<Arc<Mutex<MockBus<F>>>>,
+}
+
+impl<F> MockInterface<F> {
+    fn push_event(&mut self, event: BusEvent) {
+        self.events.push_back(event);
+        self.notify();
     }
 
-    fn attach_to_bus(&mut self, bus: Arc<Mutex<MockBus>>) -> Result<(), MockInterfaceError> {
-        match self.bus.upgrade() {
-            Some(_) => Err(MockInterfaceError::BusAlreadyAttached),
-            None => {
-                bus.lock()
-                    .unwrap()
-                    .interfaces
-                    .push(self.me.upgrade().unwrap());
-                self.bus = Arc::downgrade(&bus);
-                Ok(())
-            }
+    /// Wake blocked waiters after a state change. Without waiters this is just a counter bump,
+    /// which keeps the hot delivery path free of condition-variable traffic.
+    fn notify(&mut self) {
+        self.generation += 1;
+        if !self.waiters.is_empty() {
+            self.condvar.notify_all();
         }
     }
+}
 
-    /// Transmit without requiring the caller to hold a `MutexGuard`, preventing re-entrant locking.
-    fn transmit_arc(me: &Arc<Mutex<Self>>, frame: MockFrame) -> Result<(), TransmitError> {
-        // Grab the bus while holding the interface lock, then drop the lock before transmitting.
-        let bus = {
-            let me_locked = me.lock().unwrap();
-            me_locked.bus.upgrade()
-        };
-
-        match bus {
-            Some(bus) => {
-                bus.lock().unwrap().transmit(frame);
-                Ok(())
-            }
-            None => Err(TransmitError::BusNotAttached),
+impl<F> Drop for InterfaceShared<F> {
+    /// The last handle is gone: take the interface off its bus.
+    fn drop(&mut self) {
+        let home = lock(&self.home).clone();
+        let mut bus = lock(&home);
+        if bus.interface_attached(self.id) {
+            bus.depart(self.id);
         }
     }
 }
 
-impl MockBus {
-    pub(crate) fn new() -> Self {
+impl<F: Frame + Clone> MockInterface<F> {
+    fn new(id: InterfaceId, filters: Vec<IdMaskFilter>, condvar: Arc<Condvar>) -> Self {
         Self {
-            interfaces: Vec::new(),
+            id,
+            filters,
+            filter_semantics: FilterSemantics::default(),
+            filter_hits: Vec::new(),
+            filter_intent: None,
+            false_accepts: 0,
+            attached: false,
+            received_frames: VecDeque::new(),
+            condvar,
+            nonblocking: false,
+            rx_drop: None,
+            rx_dropped: 0,
+            rx_reorder: None,
+            events: VecDeque::new(),
+            counters: ErrorCounters::default(),
+            asleep: None,
+            loopback: false,
+            next_seq: 0,
+            tx_confirmations: VecDeque::new(),
+            pacing: None,
+            pace: PaceState::default(),
+            tx_history: VecDeque::new(),
+            tx_history_capacity: Some(DEFAULT_TX_HISTORY),
+            name: None,
+            groups: Vec::new(),
+            rx_capacity: None,
+            rx_overflows: 0,
+            rx_ttl: None,
+            rx_expired: 0,
+            auto_retransmit: true,
+            retransmissions: 0,
+            self_reception: true,
+            listen_only: false,
+            tap: false,
+            subscribed_to: None,
+            rx_wakers: Vec::new(),
+            waiters: VecDeque::new(),
+            next_ticket: 0,
+            closed: false,
+            generation: 0,
+            mailboxes: false,
+            filter_mailboxes: Vec::new(),
+            tx_middleware: Vec::new(),
+            rx_middleware: Vec::new(),
+            rx_delayed: Vec::new(),
         }
     }
 
-    fn transmit(&self, frame: MockFrame) {
-        for interface in &self.interfaces {
-            let mut int = interface.lock().unwrap();
-            let should_receive = if int.filters.is_empty() {
-                true
-            } else {
-                int.filters
-                    .iter()
-                    .any(|filter| filter_matches(filter, frame.id()))
-            };
+    /// Discard in-flight state and fault injection, keeping filters and modes.
+    fn reset(&mut self) {
+        self.received_frames.clear();
+        self.rx_drop = None;
+        self.rx_dropped = 0;
+        self.rx_reorder = None;
+        self.events.clear();
+        self.counters = ErrorCounters::default();
+        self.asleep = None;
+        self.tx_confirmations.clear();
+        self.tx_history.clear();
+        self.pace = PaceState::default();
+        self.rx_overflows = 0;
+        self.rx_expired = 0;
+        self.retransmissions = 0;
+        self.filter_hits.clear();
+        self.false_accepts = 0;
+        self.rx_delayed.clear();
+        self.closed = false;
+    }
+
+    /// Replace the filter list, restarting its acceptance counters.
+    fn install_filters(&mut self, filters: Vec<IdMaskFilter>, intent: Option<FilterSet>) {
+        self.filters = filters;
+        self.filter_hits.clear();
+        self.filter_intent = intent;
+        self.false_accepts = 0;
+    }
+
+    fn filter_stats(&self) -> FilterStats {
+        let mut hits = self.filter_hits.clone();
+        hits.resize(self.filters.len(), 0);
+        FilterStats {
+            hits,
+            false_accepts: self.false_accepts,
+        }
+    }
+
+    /// Count an accepted frame with `id`, decided by filter `index`.
+    fn count_accept(&mut self, index: usize, id: Id) {
+        if self.filters.is_empty() {
+            return;
+        }
+        self.filter_hits.resize(self.filters.len(), 0);
+        match self.filter_semantics.mode {
+            MatchMode::MatchAny => self.filter_hits[index] += 1,
+            MatchMode::MatchAll => self.filter_hits.iter_mut().for_each(|hits| *hits += 1),
+        }
+        if self
+            .filter_intent
+            .as_ref()
+            .is_some_and(|intent| !intent.contains_id(id))
+        {
+            self.false_accepts += 1;
+        }
+    }
+
+    /// Stop blocking: wake every waiting thread and async receiver.
+    fn close(&mut self) {
+        self.closed = true;
+        self.notify();
+        for waker in self.rx_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Append a transmitted frame to the TX history, evicting the oldest beyond its capacity or
+    /// the bus-wide history `limit`, whichever is smaller.
+    fn record_sent(&mut self, frame: &F, limit: Option<usize>) {
+        let capacity = match (self.tx_history_capacity, limit) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if capacity == Some(0) {
+            return;
+        }
+        while capacity.is_some_and(|capacity| self.tx_history.len() >= capacity) {
+            self.tx_history.pop_front();
+        }
+        self.tx_history.push_back(frame.clone());
+    }
+
+    /// Move the newest queued frame in front of the one before it.
+    fn overtake(&mut self) {
+        let len = self.received_frames.len();
+        if len >= 2 {
+            self.received_frames.swap(len - 1, len - 2);
+        }
+    }
+
+    /// Discard queued frames that have been waiting longer than the receive TTL at `now`.
+    fn expire(&mut self, now: Duration) {
+        let Some(ttl) = self.rx_ttl else {
+            return;
+        };
+        let before = self.received_frames.len();
+        self.received_frames
+            .retain(|r| now.saturating_sub(r.at) <= ttl);
+        self.rx_expired += (before - self.received_frames.len()) as u64;
+    }
 
-            if should_receive {
-                int.received_frames.push_back(frame.clone());
-                int.condvar.notify_all();
+    /// Queue `flight`’s frame after RX middleware; returns `false` (and raises
+    /// [`BusEvent::RxOverflow`]) if the receive queue is full. Frames the middleware dropped or
+    /// delayed count as taken.
+    fn enqueue(&mut self, arrival: &Arrival<F>, mailbox: usize) -> bool {
+        let mut received = Received {
+            frame: arrival.frame.clone(),
+            token: arrival.token,
+            mailbox,
+            at: arrival.at,
+            seq: arrival.seq,
+        };
+        if !self.rx_middleware.is_empty() {
+            let mut events = Vec::new();
+            let passed =
+                middleware::apply(&self.rx_middleware, (*arrival.frame).clone(), &mut events);
+            for event in events {
+                self.push_event(event);
+            }
+            let Some((frame, delay)) = passed else {
+                return true;
+            };
+            received.frame = Arc::new(frame);
+            if !delay.is_zero() && !self.loopback {
+                received.at += delay;
+                self.rx_delayed.push((received.at, received));
+                return true;
             }
         }
+        self.push_received(received)
     }
-}
 
-impl BusHandle {
-    /// Create a new, empty bus.
-    pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(MockBus::new())))
+    /// Queue frames whose RX middleware delay has passed by `now`.
+    fn release_delayed(&mut self, now: Duration) {
+        while let Some(index) = self.rx_delayed.iter().position(|(at, _)| *at <= now) {
+            let (_, received) = self.rx_delayed.remove(index);
+            self.push_received(received);
+        }
     }
 
-    /// Attach a new interface to this bus.
+    fn push_received(&mut self, received: Received<F>) -> bool {
+        if self
+            .rx_capacity
+            .is_some_and(|capacity| self.received_frames.len() >= capacity)
+        {
+            self.rx_overflows += 1;
+            self.push_event(BusEvent::RxOverflow);
+            return false;
+        }
+        let len = self.received_frames.len();
+        let overtaken = match &mut self.rx_reorder {
+            Some((window, rng)) => rng.range(0, (*window).min(len) as u64) as usize,
+            None => 0,
+        };
+        self.received_frames.insert(len - overtaken, received);
+        self.notify();
+        for waker in self.rx_wakers.drain(..) {
+            waker.wake();
+        }
+        true
+    }
+
+    fn confirm(&mut self, flight: &InFlight<F>) {
+        if flight.confirm {
+            self.tx_confirmations.push_back(flight.token);
+            self.notify();
+        }
+    }
+
+    /// Mailbox `frame` is routed to, or `None` if the filters reject it.
     ///
-    /// If `filters` is empty, the interface receives all frames. Otherwise it only receives frames
-    /// matching at least one filter.
-    pub fn add_interface(
-        &self,
-        filters: Vec<IdMaskFilter>,
-    ) -> Result<InterfaceHandle, MockInterfaceError> {
-        validate_filters(&filters).map_err(|_| MockInterfaceError::InvalidFilters)?;
-        let interface = MockInterface::new(filters);
-        interface.lock().unwrap().attach_to_bus(self.0.clone())?;
-        Ok(InterfaceHandle(interface))
+    /// In mailbox mode this is the mailbox assigned to the first matching filter (0 with no
+    /// filters); otherwise every accepted frame goes to mailbox 0.
+    fn route(&mut self, frame: &F) -> Option<usize> {
+        if self.tap {
+            return Some(0);
+        }
+        let index = self.filter_semantics.route(&self.filters, frame.id())?;
+        self.count_accept(index, frame.id());
+        if !self.mailboxes || self.filters.is_empty() {
+            return Some(0);
+        }
+        Some(self.filter_mailboxes.get(index).copied().unwrap_or(index))
     }
 
-    /// Number of interfaces currently attached to the bus.
-    pub fn interface_count(&self) -> usize {
-        self.0.lock().unwrap().interfaces.len()
+    /// Remove the oldest frame in `mailbox`.
+    fn pop_mailbox(&mut self, mailbox: usize) -> Option<F> {
+        let index = self
+            .received_frames
+            .iter()
+            .position(|r| r.mailbox == mailbox)?;
+        self.received_frames.remove(index).map(Received::into_frame)
     }
 }
 
-impl Default for BusHandle {
-    fn default() -> Self {
-        Self::new()
+impl<F> MockBus<F> {
+    /// Whether interface `id`'s state lives on this bus.
+    fn holds(&self, id: InterfaceId) -> bool {
+        self.interfaces.iter().any(|int| int.id == id)
+    }
+
+    fn interface_attached(&self, id: InterfaceId) -> bool {
+        self.interfaces
+            .iter()
+            .any(|int| int.id == id && int.attached)
+    }
+
+    /// Remove interface `id` from the bus, if it is here, and announce that it left.
+    fn depart(&mut self, id: InterfaceId) -> Option<MockInterface<F>> {
+        let index = self.interfaces.iter().position(|int| int.id == id)?;
+        let int = self.interfaces.remove(index);
+        dispatch_node_left(self.tracer.as_ref(), id);
+        for other in &mut self.interfaces {
+            if other.tap || (self.announce_departures && other.subscribed_to.is_none()) {
+                other.push_event(BusEvent::NodeLeft(id));
+            }
+        }
+        Some(int)
     }
 }
 
-impl InterfaceHandle {
-    /// Create a new interface that is not attached to any bus yet.
-    ///
-    /// Use [`InterfaceHandle::attach_to_bus`] to connect it to a [`BusHandle`].
-    pub fn new_unattached(filters: Vec<IdMaskFilter>) -> Self {
-        Self(MockInterface::new(filters))
+impl<F: Frame + Clone + 'static> MockBus<F> {
+    pub(crate) fn new() -> Self {
+        Self {
+            interfaces: Vec::new(),
+            max_interfaces: None,
+            now: Duration::ZERO,
+            timing: None,
+            busy_until: Duration::ZERO,
+            on_wire: None,
+            waiting: Vec::new(),
+            load_limit: None,
+            starvation: None,
+            unstarved_until: Duration::ZERO,
+            busy_time: Duration::ZERO,
+            overloaded: false,
+            overload_rejections: 0,
+            corruptions: VecDeque::new(),
+            recorders: Vec::new(),
+            tracer: None,
+            stepped: false,
+            held: VecDeque::new(),
+            medium: Box::new(Broadcast),
+            partition: None,
+            policy: None,
+            policy_log: Vec::new(),
+            rejections: None,
+            breakpoint: None,
+            paused: false,
+            stuck_dominant: false,
+            babbler: None,
+            limits: MemoryLimits::default(),
+            announce_departures: false,
+            replay: VecDeque::new(),
+            replay_capacity: Some(DEFAULT_REPLAY_HISTORY),
+            chaos: None,
+            schedule: None,
+            window_events: Vec::new(),
+            window_cursor: Duration::ZERO,
+            scheduled: Vec::new(),
+            injector: None,
+            deliveries: 0,
+        }
     }
 
-    /// Attach this interface to `bus`.
-    ///
-    /// Returns [`MockInterfaceError::BusAlreadyAttached`] if the interface is already attached.
-    pub fn attach_to_bus(&self, bus: &BusHandle) -> Result<(), MockInterfaceError> {
-        self.0.lock().unwrap().attach_to_bus(bus.0.clone())
+    fn injector_id(&mut self) -> InterfaceId {
+        self.injector
+            .get_or_insert_with(|| (InterfaceId::next(), 0))
+            .0
     }
 
-    /// Transmit `frame` onto the bus.
-    ///
-    /// Frames are broadcast to all attached interfaces (including this interface) subject to the
-    /// receivers’ acceptance filters.
-    pub fn transmit(&self, frame: MockFrame) -> Result<(), TransmitError> {
-        MockInterface::transmit_arc(&self.0, frame)
+    /// Put a frame injected by the bus itself (not by any interface) on the wire.
+    fn inject(&mut self, frame: F) {
+        let source = self.injector_id();
+        let (_, seq) = self.injector.as_mut().unwrap();
+        let token = TxToken { source, seq: *seq };
+        *seq += 1;
+        let flight = InFlight {
+            at: self.now,
+            requested: self.now,
+            source,
+            frame,
+            token,
+            confirm: false,
+            group: None,
+        };
+        dispatch(
+            self.tracer.as_ref(),
+            TraceEvent::Transmit {
+                token,
+                frame: &flight.frame,
+            },
+        );
+        if self.is_holding() {
+            self.held.push_back(flight);
+        } else {
+            let _ = self.offer(flight);
+        }
     }
 
-    /// Return a snapshot of all currently queued received frames.
-    ///
-    /// This does not remove frames from the receive queue; use [`pop_frame`](Self::pop_frame) to
-    /// consume frames.
-    pub fn received_frames(&self) -> Vec<MockFrame> {
-        self.0
-            .lock()
-            .unwrap()
-            .received_frames
+    fn interface(&self, id: InterfaceId) -> &MockInterface<F> {
+        self.interfaces
             .iter()
-            .cloned()
-            .collect()
+            .find(|int| int.id == id)
+            .expect("interface state lives on its home bus")
     }
 
-    /// Replace this interface’s acceptance filter list.
-    ///
-    /// If `filters` is empty, the interface receives all frames. Otherwise it only receives frames
-    /// matching at least one filter.
-    pub fn set_filters(&self, filters: Vec<IdMaskFilter>) -> Result<(), FilterError> {
-        validate_filters(&filters)?;
-        let mut int = self.0.lock().unwrap();
-        int.filters = filters;
+    fn interface_mut(&mut self, id: InterfaceId) -> &mut MockInterface<F> {
+        self.interfaces
+            .iter_mut()
+            .find(|int| int.id == id)
+            .expect("interface state lives on its home bus")
+    }
+
+    fn transmit(
+        &mut self,
+        source: InterfaceId,
+        frame: F,
+        confirm: bool,
+        group: Option<String>,
+    ) -> Result<TxToken, TransmitError> {
+        let now = self.now;
+        let int = self.interface_mut(source);
+        if !int.attached {
+            return Err(TransmitError::BusNotAttached);
+        }
+        if int.listen_only || int.tap || int.subscribed_to.is_some() {
+            return Err(TransmitError::ListenOnly);
+        }
+        let loopback = int.loopback;
+        let mut events = Vec::new();
+        let passed = middleware::apply(&int.tx_middleware, frame, &mut events);
+        for event in events {
+            int.push_event(event);
+        }
+        let Some((frame, delay)) = passed else {
+            let token = TxToken {
+                source,
+                seq: int.next_seq,
+            };
+            int.next_seq += 1;
+            return Ok(token);
+        };
+        let decision = match &self.policy {
+            Some(policy) if !loopback => policy(&frame, source),
+            _ => PolicyDecision::Allow,
+        };
+        if decision != PolicyDecision::Allow {
+            self.policy_log.push(PolicyRecord {
+                source,
+                frame: frame.clone(),
+                decision,
+            });
+            trim(&mut self.policy_log, self.limits.history);
+        }
+        if decision == PolicyDecision::Reject {
+            return Err(TransmitError::PolicyRejected);
+        }
+        // Time at which the frame requests the bus, after middleware, pacing and the schedule.
+        let mut at = if loopback { now } else { now + delay };
+        let pacing = self.interface(source).pacing.filter(|_| !loopback);
+        if let Some(pacing) = pacing {
+            let paced = pacing.release(&self.interface(source).pace, at);
+            if paced > at && pacing.reject {
+                return Err(TransmitError::RateLimited);
+            }
+            at = paced;
+        }
+        if let Some((config, rng)) = &mut self.chaos
+            && !loopback
+            && !config.delay_jitter.is_zero()
+        {
+            at += Duration::from_nanos(rng.range(0, config.delay_jitter.as_nanos() as u64));
+        }
+        if let Some(schedule) = &self.schedule
+            && !loopback
+        {
+            match schedule.next_slot(source, at) {
+                Some(slot) if slot == at => {}
+                Some(slot) if schedule.out_of_window() == OutOfWindow::Delay => at = slot,
+                _ => return Err(TransmitError::OutsideWindow),
+            }
+        }
+        let release = (at > now).then_some(at);
+        // An overloaded bus refuses the frame before it counts as sent.
+        if !loopback
+            && decision != PolicyDecision::Drop
+            && (release.is_some() || (!self.is_holding() && !self.delivers_immediately()))
+        {
+            self.check_load(&frame)?;
+        }
+        let history = self.limits.history;
+        let int = self.interface_mut(source);
+        if let Some(pacing) = pacing {
+            pacing.commit(&mut int.pace, at);
+        }
+        int.record_sent(&frame, history);
+        // Transmitting is a local wake-up request.
+        int.asleep = None;
+        let token = TxToken {
+            source,
+            seq: int.next_seq,
+        };
+        int.next_seq += 1;
+        let mut flight = InFlight {
+            at: now,
+            requested: now,
+            source,
+            frame,
+            token,
+            confirm,
+            group,
+        };
+        dispatch(
+            self.tracer.as_ref(),
+            TraceEvent::Transmit {
+                token,
+                frame: &flight.frame,
+            },
+        );
+        if decision == PolicyDecision::Drop {
+            return Ok(token);
+        }
+        if loopback {
+            let arrival = self.arrival(&flight);
+            let int = self.interface_mut(source);
+            if let Some(mailbox) = int.route(&flight.frame)
+                && int.enqueue(&arrival, mailbox)
+            {
+                self.feed_subscribers(&arrival, &[(source, mailbox)]);
+            }
+            self.interface_mut(source).confirm(&flight);
+            return Ok(token);
+        }
+        if let Some(at) = release {
+            // Request the bus once pacing and the sender's window allow it.
+            flight.at = at;
+            self.waiting.push(flight);
+            self.advance_to(self.now);
+            return Ok(token);
+        }
+        if self.is_holding() {
+            self.held.push_back(flight);
+            self.check_growth();
+            return Ok(token);
+        }
+        self.queue(flight);
+        Ok(token)
+    }
+
+    /// Whether transmitted frames are held back instead of reaching the bus.
+    fn is_holding(&self) -> bool {
+        self.stepped || self.paused || self.stuck_dominant
+    }
+
+    /// Offer held frames to the bus, oldest first, until none are left or the bus starts
+    /// holding again (a released frame can hit the breakpoint).
+    fn release_held(&mut self) {
+        while !self.is_holding()
+            && let Some(flight) = self.held.pop_front()
+        {
+            let _ = self.offer(flight);
+        }
+    }
+
+    /// Put a frame on the bus: deliver it right away, or queue it for arbitration when timing is
+    /// enabled or the wire is busy.
+    fn offer(&mut self, flight: InFlight<F>) -> Result<(), TransmitError> {
+        if !self.delivers_immediately() {
+            self.check_load(&flight.frame)?;
+        }
+        self.queue(flight);
         Ok(())
     }
 
-    /// Remove and return the oldest received frame, if any.
-    pub fn pop_frame(&self) -> Option<MockFrame> {
-        self.0.lock().unwrap().received_frames.pop_front()
+    /// Deliver `flight` right away on an idle untimed bus, or queue it for arbitration, without
+    /// applying the load limit.
+    fn queue(&mut self, mut flight: InFlight<F>) {
+        if self.delivers_immediately() {
+            self.deliver(flight);
+            return;
+        }
+        flight.at = self.now;
+        self.waiting.push(flight);
+        self.advance_to(self.now);
     }
 
-    /// Returns `true` if any frames are currently queued for receive.
-    pub fn has_frames(&self) -> bool {
-        !self.0.lock().unwrap().received_frames.is_empty()
+    /// Whether an offered frame skips arbitration: the bus is untimed and the wire idle.
+    fn delivers_immediately(&self) -> bool {
+        self.timing.is_none() && self.waiting.is_empty() && self.on_wire.is_none()
     }
 
-    /// Wait until at least one frame is available to receive.
-    ///
-    /// - `timeout: None` blocks indefinitely.
-    /// - `timeout: Some(d)` waits up to `d` and returns whether a frame became available.
-    pub fn wait_for_frame(&self, timeout: Option<std::time::Duration>) -> bool {
-        let mut guard = self.0.lock().unwrap();
-        if let Some(timeout) = timeout {
-            let condvar = guard.condvar.clone();
-            let (new_guard, _) = condvar
-                .wait_timeout_while(guard, timeout, |int| int.received_frames.is_empty())
-                .unwrap();
-            guard = new_guard;
-            !guard.received_frames.is_empty()
-        } else {
-            while guard.received_frames.is_empty() {
-                let condvar = guard.condvar.clone();
-                guard = condvar.wait(guard).unwrap();
+    fn wire_time(&self, frame: &F) -> Duration {
+        self.timing
+            .map_or(Duration::ZERO, |timing| timing.wire_time(frame))
+    }
+
+    /// Time needed to drain everything waiting for or occupying the wire.
+    fn backlog(&self) -> Duration {
+        let current = self
+            .on_wire
+            .as_ref()
+            .map_or(Duration::ZERO, |f| f.at.saturating_sub(self.now));
+        self.waiting
+            .iter()
+            .fold(current, |acc, f| acc + self.wire_time(&f.frame))
+    }
+
+    /// Apply the load limit to a newly offered `frame`.
+    fn check_load(&mut self, frame: &F) -> Result<(), TransmitError> {
+        let Some(limit) = self.load_limit else {
+            return Ok(());
+        };
+        if self.backlog() + self.wire_time(frame) <= limit.max_backlog {
+            return Ok(());
+        }
+        self.overloaded = true;
+        if !limit.reject {
+            return Ok(());
+        }
+        self.overload_rejections += 1;
+        let key = arbitration_key(frame);
+        let lowest = self
+            .waiting
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, f)| arbitration_key(&f.frame))
+            .filter(|(_, f)| arbitration_key(&f.frame) > key)
+            .map(|(index, _)| index);
+        match lowest {
+            Some(index) => {
+                self.waiting.remove(index);
+                Ok(())
             }
-            true
+            None => Err(TransmitError::BusOverloaded),
+        }
+    }
+
+    /// Move the virtual clock to `now`, releasing scheduled frames, arbitrating waiting frames
+    /// onto the wire and delivering every frame that finished by then. Events are processed in
+    /// time order, with the clock stepping to each one.
+    fn advance_to(&mut self, now: Duration) {
+        let target = self.now.max(now);
+        loop {
+            self.request_babble();
+            let release = self.scheduled.first().map(|(at, _)| *at);
+            let wire = match &self.on_wire {
+                // While paused or stuck the wire is frozen; scheduled frames are still released
+                // (and held).
+                _ if self.paused || self.stuck_dominant => None,
+                Some(current) => Some(current.at),
+                // The wire is idle: the next arbitration round starts once it is free and at
+                // least one frame is waiting; every frame requested by then competes. It never
+                // starts in the past, even for frames whose starvation was only just lifted.
+                None => self
+                    .waiting
+                    .iter()
+                    .map(|f| self.eligible_at(f))
+                    .min()
+                    .map(|earliest| earliest.max(self.busy_until).max(self.now)),
+            };
+            if self.on_wire.is_none() && self.waiting.is_empty() && !self.is_holding() {
+                self.overloaded = false;
+            }
+            // A frame released at the same instant as an arbitration round takes part in it.
+            let next = match (release, wire) {
+                (Some(release), Some(wire)) if release <= wire => release,
+                (_, Some(wire)) => wire,
+                (Some(release), None) => release,
+                (None, None) => break,
+            };
+            if next > target {
+                break;
+            }
+            self.now = self.now.max(next);
+
+            if release == Some(next) {
+                let (_, frame) = self.scheduled.remove(0);
+                self.inject(frame);
+                continue;
+            }
+            if self.on_wire.is_some() {
+                let done = self.on_wire.take().unwrap();
+                self.busy_time += self.wire_time(&done.frame);
+                self.deliver(done);
+                continue;
+            }
+            let start = next;
+            let winner = self
+                .waiting
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| self.eligible_at(f) <= start)
+                .min_by_key(|(_, f)| (self.is_starved(f), arbitration_key(&f.frame)))
+                .map(|(index, _)| index)
+                .unwrap();
+            let mut frame = self.waiting.remove(winner);
+            frame.at = start + self.wire_time(&frame.frame);
+            self.busy_until = frame.at;
+            if !self.is_starved(&frame) {
+                self.unstarved_until = frame.at;
+            }
+            self.on_wire = Some(frame);
+        }
+        self.now = target;
+        for int in &mut self.interfaces {
+            int.release_delayed(target);
+            int.expire(target);
+        }
+        if let Some(schedule) = &self.schedule {
+            self.window_events
+                .extend(schedule.events(self.window_cursor, target));
+            trim(&mut self.window_events, self.limits.history);
+            self.window_cursor = target + Duration::from_nanos(1);
+        }
+        self.check_growth();
+    }
+
+    fn is_starved(&self, frame: &InFlight<F>) -> bool {
+        self.starvation
+            .as_ref()
+            .is_some_and(|starvation| starvation.starves(frame.source, &frame.frame))
+    }
+
+    /// Earliest time `frame` may take part in arbitration.
+    fn eligible_at(&self, frame: &InFlight<F>) -> Duration {
+        match &self.starvation {
+            Some(starvation) if starvation.starves(frame.source, &frame.frame) => {
+                frame.at.max(self.unstarved_until + starvation.quiet)
+            }
+            _ => frame.at,
+        }
+    }
+
+    /// Queue the babbling idiot's next frame for arbitration unless one is already waiting.
+    fn request_babble(&mut self) {
+        let Some((source, frame, from)) = &self.babbler else {
+            return;
+        };
+        let (source, at) = (*source, self.busy_until.max(*from));
+        if self.timing.is_none() || self.waiting.iter().any(|f| f.source == source) {
+            return;
+        }
+        let frame = frame.clone();
+        let Some(int) = self.interfaces.iter_mut().find(|int| int.id == source) else {
+            // The babbler was detached from this bus.
+            self.babbler = None;
+            return;
+        };
+        let token = TxToken {
+            source,
+            seq: int.next_seq,
+        };
+        int.next_seq += 1;
+        self.waiting.push(InFlight {
+            at,
+            requested: at,
+            source,
+            frame,
+            token,
+            confirm: false,
+            group: None,
+        });
+    }
+
+    fn deliver(&mut self, mut flight: InFlight<F>) {
+        while let Some(error) = self.next_corruption() {
+            // A corrupted frame is never delivered; an immediate retry goes round this loop.
+            match self.fail(flight, error) {
+                Some(retry) => flight = retry,
+                None => return,
+            }
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "deliver",
+            source = flight.source.as_raw(),
+            seq = flight.token.seq()
+        )
+        .entered();
+        let source = flight.source;
+        for int in &mut self.interfaces {
+            if int.id == source {
+                int.counters.transmit_ok();
+                int.confirm(&flight);
+            } else {
+                int.counters.receive_ok();
+            }
+        }
+        let mut receivers = Vec::new();
+        let mut queued = Vec::new();
+        let token = flight.token;
+        let arrival = self.arrival(&flight);
+        if flight.group.is_none() {
+            self.retain_for_replay(&arrival);
+        }
+        let attached: Vec<_> = self.interfaces.iter().map(|int| int.id).collect();
+        let mut reached = self.medium.deliver(&flight.frame, source, &attached);
+        if let Some(groups) = &self.partition {
+            let group = groups.iter().find(|group| group.contains(&source));
+            reached.retain(|id| *id == source || group.is_some_and(|group| group.contains(id)));
+        }
+        let trace = |event: TraceEvent<'_, F>| dispatch(self.tracer.as_ref(), event);
+        for int in &mut self.interfaces {
+            if int.loopback
+                || int.subscribed_to.is_some()
+                || (int.id == source && !int.self_reception)
+                || !reached.contains(&int.id)
+                || flight.group.as_ref().is_some_and(|group| {
+                    int.id != source && !int.tap && !int.groups.contains(group)
+                })
+            {
+                continue;
+            }
+            let Some(mailbox) = int.route(&flight.frame) else {
+                trace(TraceEvent::FilterReject {
+                    token,
+                    receiver: int.id,
+                });
+                if let Some(rejections) = &mut self.rejections {
+                    rejections.push(Rejection {
+                        receiver: int.id,
+                        token,
+                        frame: flight.frame.clone(),
+                        report: explain(&int.filters, flight.frame.id()),
+                    });
+                    trim(rejections, self.limits.history);
+                }
+                continue;
+            };
+            if let Some(lose_wake_frame) = int.asleep.take() {
+                int.push_event(BusEvent::WakeUp);
+                if lose_wake_frame {
+                    continue;
+                }
+            }
+            if let Some((probability, rng)) = &mut int.rx_drop
+                && rng.chance(*probability)
+            {
+                int.rx_dropped += 1;
+                trace(TraceEvent::Drop {
+                    token,
+                    receiver: int.id,
+                });
+                continue;
+            }
+            let (mut lost, mut duplicated, mut reordered) = (false, false, false);
+            if let Some((config, rng)) = &mut self.chaos {
+                lost = rng.chance(config.drop);
+                duplicated = rng.chance(config.dup);
+                reordered = rng.chance(config.reorder);
+            }
+            if lost {
+                int.rx_dropped += 1;
+                trace(TraceEvent::Drop {
+                    token,
+                    receiver: int.id,
+                });
+                continue;
+            }
+            if duplicated && !int.enqueue(&arrival, mailbox) {
+                trace(TraceEvent::QueueOverflow {
+                    token,
+                    receiver: int.id,
+                });
+            }
+            if !int.enqueue(&arrival, mailbox) {
+                trace(TraceEvent::QueueOverflow {
+                    token,
+                    receiver: int.id,
+                });
+                continue;
+            }
+            if reordered {
+                int.overtake();
+            }
+            receivers.push(int.id);
+            queued.push((int.id, mailbox));
+            trace(TraceEvent::Deliver {
+                token,
+                receiver: int.id,
+                frame: &flight.frame,
+            });
+        }
+        self.feed_subscribers(&arrival, &queued);
+        self.record(&flight, receivers, None);
+        if self
+            .breakpoint
+            .as_ref()
+            .is_some_and(|breakpoint| breakpoint.matches(&flight.frame))
+        {
+            self.paused = true;
+        }
+        self.check_growth();
+    }
+
+    /// Enforce the queue watermark of the memory limits (debug builds only).
+    fn check_growth(&self) {
+        let Some(watermark) = self
+            .limits
+            .queue_watermark
+            .filter(|_| cfg!(debug_assertions))
+        else {
+            return;
+        };
+        let bus_queues = [
+            ("frames waiting for the wire", self.waiting.len()),
+            ("held frames", self.held.len()),
+            ("scheduled frames", self.scheduled.len()),
+        ];
+        for (queue, len) in bus_queues {
+            assert!(
+                len <= watermark,
+                "unbounded queue growth: {len} {queue} on the bus exceed the watermark of {watermark}"
+            );
+        }
+        for int in &self.interfaces {
+            let queues = [
+                ("received frames", int.received_frames.len()),
+                ("pending events", int.events.len()),
+                ("TX confirmations", int.tx_confirmations.len()),
+            ];
+            for (queue, len) in queues {
+                assert!(
+                    len <= watermark,
+                    "unbounded queue growth: {len} {queue} on interface {} exceed the watermark of \
+                     {watermark}",
+                    int.id.as_raw()
+                );
+            }
+        }
+    }
+
+    /// Entries and approximate bytes held by the bus's queues and histories.
+    fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        usage.add_queue::<InFlight<F>>(self.waiting.len() + self.held.len());
+        usage.add_queue::<(Duration, F)>(self.scheduled.len());
+        for int in &self.interfaces {
+            usage.add_queue::<Received<F>>(int.received_frames.len() + int.rx_delayed.len());
+            usage.bytes += int.received_frames.len() * size_of::<F>();
+            usage.add_queue::<BusEvent>(int.events.len());
+            usage.add_queue::<TxToken>(int.tx_confirmations.len());
+            usage.add_history::<F>(int.tx_history.len());
+        }
+        usage.add_history::<PolicyRecord<F>>(self.policy_log.len());
+        usage.add_history::<Rejection<F>>(self.rejections.as_ref().map_or(0, Vec::len));
+        usage.add_history::<WindowEvent>(self.window_events.len());
+        usage.add_history::<Arrival<F>>(self.replay.len());
+        usage.bytes += self.replay.len() * size_of::<F>();
+        for sink in self.recorders.iter().filter_map(Weak::upgrade) {
+            usage.add_history::<TraceRecord<F>>(lock(&sink).len());
+        }
+        usage
+    }
+
+    /// Keep a completed frame for late joiners, evicting the oldest beyond the replay capacity
+    /// or the bus-wide history limit.
+    fn retain_for_replay(&mut self, arrival: &Arrival<F>) {
+        let capacity = match (self.replay_capacity, self.limits.history) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if capacity == Some(0) {
+            return;
+        }
+        while capacity.is_some_and(|capacity| self.replay.len() >= capacity) {
+            self.replay.pop_front();
+        }
+        self.replay.push_back(arrival.clone());
+    }
+
+    /// Queue retained frames selected by `catch_up` on interface `id`, as its filters allow.
+    fn catch_up(&mut self, id: InterfaceId, catch_up: CatchUp) {
+        let skip = match catch_up {
+            CatchUp::Nothing => self.replay.len(),
+            CatchUp::Last(n) => self.replay.len().saturating_sub(n),
+            CatchUp::Since(at) => self.replay.partition_point(|arrival| arrival.at < at),
+        };
+        let replay: Vec<_> = self.replay.iter().skip(skip).cloned().collect();
+        let int = self.interface_mut(id);
+        for arrival in &replay {
+            if let Some(mailbox) = int.route(&arrival.frame) {
+                int.enqueue(arrival, mailbox);
+            }
+        }
+    }
+
+    /// The error corrupting the next frame to complete, if any: queued by
+    /// [`BusHandle::corrupt_next`] or drawn from the chaos configuration.
+    fn next_corruption(&mut self) -> Option<BusEvent> {
+        let chaos_error = match &mut self.chaos {
+            Some((config, rng)) => rng.chance(config.corrupt).then_some(BusEvent::CrcError),
+            None => None,
+        };
+        self.corruptions.pop_front().or(chaos_error)
+    }
+
+    /// Report `error` on the nodes that detect it and charge the transmitter for the failed
+    /// attempt. Returns the frame if it is to be delivered again immediately (on an untimed
+    /// bus); on a timed bus a retry rejoins arbitration instead.
+    fn fail(&mut self, mut flight: InFlight<F>, error: BusEvent) -> Option<InFlight<F>> {
+        let source = flight.source;
+        let to_transmitter = error.is_transmitter_error();
+        for int in &mut self.interfaces {
+            if int.id == source {
+                // Every failed attempt raises the transmit error counter, whoever detected it,
+                // so endless retries end in bus-off.
+                int.counters.transmit_error();
+                if to_transmitter {
+                    int.push_event(error.clone());
+                }
+            } else if !to_transmitter {
+                int.counters.receive_error();
+                int.push_event(error.clone());
+            }
+        }
+        self.record(&flight, Vec::new(), Some(error));
+        let int = self
+            .interfaces
+            .iter_mut()
+            .find(|int| int.id == source)
+            .filter(|int| int.auto_retransmit && int.counters.state() != ErrorState::BusOff)?;
+        int.retransmissions += 1;
+        if self.timing.is_none() {
+            return Some(flight);
+        }
+        flight.at = self.now;
+        self.waiting.push(flight);
+        None
+    }
+
+    /// Copy a delivery into the subscribers of every interface in `queued` (which lists each
+    /// interface that queued the frame and the mailbox it went to).
+    fn feed_subscribers(&mut self, arrival: &Arrival<F>, queued: &[(InterfaceId, usize)]) {
+        for int in &mut self.interfaces {
+            let Some(parent) = int.subscribed_to else {
+                continue;
+            };
+            if let Some((_, mailbox)) = queued.iter().find(|(id, _)| *id == parent) {
+                int.enqueue(arrival, *mailbox);
+            }
+        }
+    }
+
+    /// Start delivering `flight` now, taking the next bus-wide sequence number.
+    fn arrival(&mut self, flight: &InFlight<F>) -> Arrival<F> {
+        self.deliveries += 1;
+        Arrival {
+            frame: Arc::new(flight.frame.clone()),
+            token: flight.token,
+            at: self.now,
+            seq: self.deliveries - 1,
+        }
+    }
+
+    fn record(
+        &mut self,
+        flight: &InFlight<F>,
+        receivers: Vec<InterfaceId>,
+        error: Option<BusEvent>,
+    ) {
+        self.recorders.retain(|sink| sink.strong_count() > 0);
+        if self.recorders.is_empty() {
+            return;
+        }
+        let record = TraceRecord {
+            requested: flight.requested,
+            started: self.now.saturating_sub(self.wire_time(&flight.frame)),
+            at: self.now,
+            token: flight.token,
+            frame: flight.frame.clone(),
+            receivers,
+            error,
+        };
+        for sink in self.recorders.iter().filter_map(Weak::upgrade) {
+            let mut records = lock(&sink);
+            records.push(record.clone());
+            trim(&mut records, self.limits.history);
+        }
+    }
+}
+
+impl BusHandle {
+    /// Create a new, empty bus carrying [`MockFrame`]s.
+    ///
+    /// Buses for other frame types are created with [`BusHandle::default`], e.g.
+    /// `BusHandle::<MyFrame>::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bus registered under `name`, created on first use.
+    ///
+    /// Every call with the same name returns the same bus for as long as any handle to it (or
+    /// any interface on it) is alive; afterwards the name refers to a fresh bus. This is the bus
+    /// [`MockCan::open`](crate::MockCan) attaches to for `mock://name` configuration strings
+    /// (see [`uri`](crate::uri)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::BusHandle;
+    ///
+    /// let bus = BusHandle::named("powertrain");
+    /// let _node = bus.add_interface(vec![]).unwrap();
+    /// assert_eq!(BusHandle::named("powertrain").interface_count(), 1);
+    /// assert_eq!(BusHandle::named("body").interface_count(), 0);
+    /// ```
+    pub fn named(name: &str) -> Self {
+        type Registry = Vec<(String, Weak<Mutex<MockBus<MockFrame>>>)>;
+        static NAMED: Mutex<Registry> = Mutex::new(Vec::new());
+        let mut named = lock(&NAMED);
+        named.retain(|(_, bus)| bus.strong_count() > 0);
+        if let Some(bus) = named
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, bus)| bus.upgrade())
+        {
+            return Self(bus);
+        }
+        let bus = Self::new();
+        named.push((name.into(), Arc::downgrade(&bus.0)));
+        bus
+    }
+}
+
+impl<F: Frame + Clone + 'static> BusHandle<F> {
+    /// Create a bus with room for `max_interfaces` interfaces, allocated up front.
+    ///
+    /// Attaching beyond that fails with [`MockInterfaceError::BusFull`] until an interface
+    /// leaves. [Taps](Self::tap) are observers rather than nodes and may always be added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::{BusHandle, MockInterfaceError};
+    ///
+    /// let bus = BusHandle::<embedded_can_mock::MockFrame>::with_capacity(2);
+    /// let a = bus.add_interface(vec![]).unwrap();
+    /// let _b = bus.add_interface(vec![]).unwrap();
+    /// assert!(matches!(
+    ///     bus.add_interface(vec![]),
+    ///     Err(MockInterfaceError::BusFull)
+    /// ));
+    /// drop(a);
+    /// bus.add_interface(vec![]).unwrap();
+    /// ```
+    pub fn with_capacity(max_interfaces: usize) -> Self {
+        let mut bus = MockBus::new();
+        bus.interfaces.reserve_exact(max_interfaces);
+        bus.max_interfaces = Some(max_interfaces);
+        Self(Arc::new(Mutex::new(bus)))
+    }
+}
+
+impl<F: Frame + Clone + 'static> BusHandle<F> {
+    /// Attach a new interface to this bus.
+    ///
+    /// If `filters` is empty, the interface receives all frames. Otherwise it only receives frames
+    /// matching at least one filter.
+    pub fn add_interface(
+        &self,
+        filters: Vec<IdMaskFilter>,
+    ) -> Result<InterfaceHandle<F>, MockInterfaceError> {
+        self.add_interface_with(filters, CatchUp::Nothing)
+    }
+
+    /// Attach a new interface to this bus, replaying the earlier traffic selected by `catch_up`
+    /// to it (see [`InterfaceHandle::attach_with`]).
+    pub fn add_interface_with(
+        &self,
+        filters: Vec<IdMaskFilter>,
+        catch_up: CatchUp,
+    ) -> Result<InterfaceHandle<F>, MockInterfaceError> {
+        validate_filters(&filters).map_err(|_| MockInterfaceError::InvalidFilters)?;
+        let interface = InterfaceHandle::new_unattached(filters);
+        interface.attach_with(self, catch_up)?;
+        Ok(interface)
+    }
+
+    /// Keep up to `capacity` recently completed frames for late joiners to [catch up](CatchUp)
+    /// on; `None` keeps every frame and `Some(0)` disables retention. The default is
+    /// [`DEFAULT_REPLAY_HISTORY`]. Shrinking the capacity discards the oldest retained frames.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, CatchUp, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_replay_capacity(Some(3));
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    /// for id in 1..=5 {
+    ///     node.transmit(frame(id)).unwrap();
+    ///     bus.advance(Duration::from_millis(10));
+    /// }
+    ///
+    /// let all = bus.add_interface_with(vec![], CatchUp::Last(10)).unwrap();
+    /// assert_eq!(all.drain_frames(), vec![frame(3), frame(4), frame(5)]);
+    /// let recent = bus.add_interface_with(vec![], CatchUp::Since(Duration::from_millis(35))).unwrap();
+    /// assert_eq!(recent.drain_frames(), vec![frame(5)]);
+    /// ```
+    pub fn set_replay_capacity(&self, capacity: Option<usize>) {
+        let mut bus = lock(&self.0);
+        bus.replay_capacity = capacity;
+        if let Some(capacity) = capacity {
+            let excess = bus.replay.len().saturating_sub(capacity);
+            bus.replay.drain(..excess);
+        }
+    }
+
+    /// Number of completed frames retained for catch-up, or `None` if unbounded.
+    pub fn replay_capacity(&self) -> Option<usize> {
+        lock(&self.0).replay_capacity
+    }
+
+    /// Attach a promiscuous, receive-only observer to the bus.
+    ///
+    /// The tap queues every frame that completes on the bus, ignoring its own filters and
+    /// regardless of the senders' self-reception settings, and cannot transmit (transmitting
+    /// fails with [`TransmitError::ListenOnly`]). It is meant for test assertions and recorders
+    /// that must not change how the nodes under test are configured. A tap counts towards
+    /// [`interface_count`](Self::interface_count) like any other interface.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_interface::{Id as IfaceId, IdMask, IdMaskFilter};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![IdMaskFilter {
+    ///     id: IfaceId::Standard(StandardId::new(0x100).unwrap()),
+    ///     mask: IdMask::Standard(0x7FF),
+    /// }]).unwrap();
+    /// node.set_self_reception(false);
+    /// let tap = bus.tap();
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x200).unwrap(), &[]).unwrap();
+    /// node.transmit(frame.clone()).unwrap();
+    /// assert!(!node.has_frames());
+    /// assert_eq!(tap.pop_frame(), Some(frame.clone()));
+    /// assert!(tap.transmit(frame).is_err());
+    /// ```
+    pub fn tap(&self) -> InterfaceHandle<F> {
+        let tap = InterfaceHandle::new_unattached(Vec::new());
+        tap.with(|int| int.tap = true);
+        tap.attach_to_bus(self)
+            .expect("a new interface is never attached");
+        tap
+    }
+
+    /// Returns `true` if both handles refer to the same bus.
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Number of interfaces currently attached to the bus.
+    pub fn interface_count(&self) -> usize {
+        lock(&self.0).interfaces.len()
+    }
+
+    /// Clear every attached interface’s receive queue, drop frames still on the wire, remove
+    /// fault injection and rewind the virtual clock to zero.
+    ///
+    /// Interfaces stay attached and keep their filters and modes, and the bus keeps its timing
+    /// configuration; only in-flight state, counters and injected faults are discarded, so one
+    /// bus can be shared by several table-driven test cases.
+    pub fn reset(&self) {
+        let mut bus = lock(&self.0);
+        for int in &mut bus.interfaces {
+            int.reset();
+        }
+        bus.on_wire = None;
+        bus.waiting.clear();
+        bus.held.clear();
+        bus.paused = false;
+        bus.stuck_dominant = false;
+        bus.babbler = None;
+        bus.partition = None;
+        bus.policy_log.clear();
+        if let Some(rejections) = &mut bus.rejections {
+            rejections.clear();
+        }
+        bus.scheduled.clear();
+        bus.window_events.clear();
+        bus.window_cursor = Duration::ZERO;
+        bus.replay.clear();
+        bus.chaos = None;
+        bus.deliveries = 0;
+        bus.now = Duration::ZERO;
+        bus.busy_until = Duration::ZERO;
+        bus.unstarved_until = Duration::ZERO;
+        bus.busy_time = Duration::ZERO;
+        bus.overloaded = false;
+        bus.overload_rejections = 0;
+        bus.corruptions.clear();
+    }
+
+    /// Corrupt the next frame to complete on the bus: instead of being delivered, it produces
+    /// `error` on the nodes that would detect it.
+    ///
+    /// Receiver-side errors ([`CrcError`](BusEvent::CrcError),
+    /// [`StuffError`](BusEvent::StuffError), [`FormError`](BusEvent::FormError)) are reported to
+    /// every attached interface except the transmitter, regardless of filters; transmitter-side
+    /// errors ([`BitError`](BusEvent::BitError), [`AckError`](BusEvent::AckError)) only to the
+    /// transmitter. Calling this repeatedly corrupts consecutive frames (including
+    /// retransmissions).
+    ///
+    /// Either way the transmitter's transmit error counter rises by 8. The failed transmission
+    /// is retried if the transmitter has
+    /// [automatic retransmission](InterfaceHandle::set_auto_retransmit) enabled, as it is by
+    /// default, until it goes bus-off; in one-shot mode the frame is lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tx = bus.add_interface(vec![]).unwrap();
+    /// let rx = bus.add_interface(vec![]).unwrap();
+    /// tx.set_auto_retransmit(false);
+    ///
+    /// bus.corrupt_next(BusEvent::CrcError);
+    /// tx.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(!rx.has_frames());
+    /// assert_eq!(rx.pop_event(), Some(BusEvent::CrcError));
+    /// assert_eq!(tx.pop_event(), None);
+    /// ```
+    pub fn corrupt_next(&self, error: BusEvent) {
+        lock(&self.0).corruptions.push_back(error);
+    }
+
+    /// Install (or with `None`, remove) a [`Tracer`] called for every transmit, delivery,
+    /// filter rejection, drop and queue overflow on this bus. See [`trace`](crate::trace).
+    pub fn set_tracer(&self, tracer: Option<Tracer<F>>) {
+        // Drop the old tracer outside the lock: it may own the last handle of an interface, and
+        // dropping that takes the lock.
+        let old = core::mem::replace(&mut lock(&self.0).tracer, tracer);
+        drop(old);
+    }
+
+    pub(crate) fn add_recorder(&self, sink: Weak<TraceSink<F>>) {
+        lock(&self.0).recorders.push(sink);
+    }
+
+    /// Broadcast an error frame: every attached interface receives [`BusEvent::ErrorFrame`] and
+    /// has its receive error counter incremented.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::{BusEvent, BusHandle};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    ///
+    /// bus.inject_error_frame();
+    /// assert_eq!(node.pop_event(), Some(BusEvent::ErrorFrame));
+    /// assert_eq!(node.error_counters().rec, 1);
+    /// ```
+    pub fn inject_error_frame(&self) {
+        let mut bus = lock(&self.0);
+        for int in &mut bus.interfaces {
+            int.counters.receive_error();
+            int.push_event(BusEvent::ErrorFrame);
+        }
+    }
+
+    /// Enable (or with `None`, disable) bitrate-accurate timing.
+    ///
+    /// With timing enabled, a transmitted frame occupies the wire for its
+    /// [`wire_time`](BusTiming::wire_time), and is only delivered once
+    /// [`advance`](Self::advance) moves the virtual clock past its end. Frames offered while the
+    /// wire is busy wait and are arbitrated like on a real bus: when the wire frees up, the
+    /// waiting frame with the lowest [`arbitration_key`] goes next.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame, timing::BusTiming};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(125_000)));
+    /// let iface = bus.add_interface(vec![]).unwrap();
+    ///
+    /// iface.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[0; 8]).unwrap()).unwrap();
+    /// assert!(!iface.has_frames());
+    ///
+    /// bus.advance(Duration::from_millis(2));
+    /// assert!(iface.has_frames());
+    /// ```
+    pub fn set_timing(&self, timing: Option<BusTiming>) {
+        lock(&self.0).timing = timing;
+    }
+
+    /// Current timing configuration, if any.
+    pub fn timing(&self) -> Option<BusTiming> {
+        lock(&self.0).timing
+    }
+
+    /// Current virtual time.
+    pub fn now(&self) -> Duration {
+        lock(&self.0).now
+    }
+
+    /// Advance the virtual clock by `by`, delivering every frame whose transmission completes in
+    /// that window.
+    pub fn advance(&self, by: Duration) {
+        let mut bus = lock(&self.0);
+        let now = bus.now + by;
+        bus.advance_to(now);
+    }
+
+    /// Program `frame` to be transmitted when the virtual clock reaches `at`.
+    ///
+    /// `at` is an absolute virtual time; frames due at or before [`now`](Self::now) go out
+    /// immediately. Scheduled frames are released in time order (frames due at the same time in
+    /// the order they were scheduled) as [`advance`](Self::advance) moves the clock, and then
+    /// behave like any other transmission: with timing enabled they arbitrate for the wire. They
+    /// are sent by the bus itself, from the source [`injector_id`](Self::injector_id), which
+    /// belongs to no interface.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    /// bus.schedule_transmit(Duration::from_millis(20), frame(0x2));
+    /// bus.schedule_transmit(Duration::from_millis(10), frame(0x1));
+    ///
+    /// bus.advance(Duration::from_millis(15));
+    /// assert_eq!(node.drain_frames(), vec![frame(0x1)]);
+    /// bus.advance(Duration::from_millis(15));
+    /// assert_eq!(node.drain_frames(), vec![frame(0x2)]);
+    /// ```
+    pub fn schedule_transmit(&self, at: Duration, frame: F) {
+        let mut bus = lock(&self.0);
+        let index = bus.scheduled.partition_point(|(t, _)| *t <= at);
+        bus.scheduled.insert(index, (at, frame));
+        let now = bus.now;
+        bus.advance_to(now);
+    }
+
+    /// Number of scheduled frames not yet released.
+    pub fn scheduled_count(&self) -> usize {
+        lock(&self.0).scheduled.len()
+    }
+
+    /// Source identity used for frames the bus injects itself (see
+    /// [`schedule_transmit`](Self::schedule_transmit)).
+    pub fn injector_id(&self) -> InterfaceId {
+        lock(&self.0).injector_id()
+    }
+
+    /// Number of frames transmitted but not yet delivered.
+    pub fn in_flight_count(&self) -> usize {
+        let bus = lock(&self.0);
+        bus.held.len() + bus.waiting.len() + bus.on_wire.is_some() as usize
+    }
+
+    /// Switch stepped mode on or off.
+    ///
+    /// In stepped mode nothing reaches the bus on its own: transmitted frames are held, in
+    /// transmit order, until [`step`](Self::step) or [`run_until_idle`](Self::run_until_idle)
+    /// releases them. This gives a test full control over when each frame arrives, e.g. to let a
+    /// receiver time out before the response shows up. Switching stepped mode off releases every
+    /// held frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_stepped(true);
+    /// let node = bus.add_interface(vec![]).unwrap();
+    ///
+    /// node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// node.transmit(MockFrame::new(StandardId::new(0x2).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(!node.has_frames());
+    ///
+    /// assert!(bus.step());
+    /// assert_eq!(node.pop_frames(usize::MAX).len(), 1);
+    /// assert_eq!(bus.run_until_idle(), 1);
+    /// assert!(!bus.step());
+    /// ```
+    pub fn set_stepped(&self, on: bool) {
+        let mut bus = lock(&self.0);
+        bus.stepped = on;
+        bus.release_held();
+    }
+
+    /// Returns `true` if the bus is in stepped mode.
+    pub fn is_stepped(&self) -> bool {
+        lock(&self.0).stepped
+    }
+
+    /// Replace the bus medium, which decides which interfaces each frame reaches (see
+    /// [`medium`](crate::medium)). The default is [`Broadcast`].
+    pub fn set_medium(&self, medium: impl BusMedium<F> + 'static) {
+        let old = core::mem::replace(&mut lock(&self.0).medium, Box::new(medium));
+        drop(old);
+    }
+
+    /// Simulate a stuck-dominant wire (a shorted transceiver holding the bus low), or clear it.
+    ///
+    /// While stuck, the bus never goes idle: nothing is delivered, transmissions stay pending
+    /// (as in [stepped mode](Self::set_stepped)) and, with [timing](Self::set_timing), the frame
+    /// on the wire never completes. Every attached interface sees the endless dominant level as a
+    /// stuff error once, when the fault starts. Clearing the fault releases the pending frames.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// bus.set_stuck_dominant(true);
+    /// assert_eq!(node.pop_event(), Some(BusEvent::StuffError));
+    ///
+    /// node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(!node.has_frames());
+    /// assert!(node.is_transmit_pending());
+    ///
+    /// bus.set_stuck_dominant(false);
+    /// assert!(node.has_frames());
+    /// ```
+    pub fn set_stuck_dominant(&self, on: bool) {
+        let mut bus = lock(&self.0);
+        if on && !bus.stuck_dominant {
+            for int in &mut bus.interfaces {
+                int.counters.receive_error();
+                int.push_event(BusEvent::StuffError);
+            }
+        }
+        bus.stuck_dominant = on;
+        bus.release_held();
+        let now = bus.now;
+        bus.advance_to(now);
+    }
+
+    /// Returns `true` while the bus is [stuck dominant](Self::set_stuck_dominant).
+    pub fn is_stuck_dominant(&self) -> bool {
+        lock(&self.0).stuck_dominant
+    }
+
+    /// Install (or with `None`, remove) a [`TransmitPolicy`] deciding whether each transmitted
+    /// frame is allowed, dropped, rejected or logged. See [`policy`](crate::policy).
+    pub fn set_transmit_policy(&self, policy: Option<TransmitPolicy<F>>) {
+        let old = core::mem::replace(&mut lock(&self.0).policy, policy);
+        drop(old);
+    }
+
+    /// Whether every node, not only taps, receives [`BusEvent::NodeLeft`] when an interface
+    /// leaves the bus. Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::{BusEvent, BusHandle};
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_announce_departures(true);
+    /// let monitor = bus.add_interface(vec![]).unwrap();
+    /// let peer = bus.add_interface(vec![]).unwrap();
+    /// let peer_id = peer.id();
+    ///
+    /// drop(peer);
+    /// assert_eq!(monitor.pop_event(), Some(BusEvent::NodeLeft(peer_id)));
+    /// assert_eq!(bus.interface_count(), 1);
+    /// ```
+    pub fn set_announce_departures(&self, on: bool) {
+        lock(&self.0).announce_departures = on;
+    }
+
+    /// Whether departures are announced to every node.
+    pub fn announces_departures(&self) -> bool {
+        lock(&self.0).announce_departures
+    }
+
+    /// Cap the bus's histories and watch its queues for unbounded growth. See
+    /// [`memory`](crate::memory).
+    ///
+    /// Histories already longer than the new cap shrink as soon as they are next appended to.
+    pub fn set_memory_limits(&self, limits: MemoryLimits) {
+        lock(&self.0).limits = limits;
+    }
+
+    /// Current memory limits.
+    pub fn memory_limits(&self) -> MemoryLimits {
+        lock(&self.0).limits
+    }
+
+    /// Estimate of the entries and bytes currently held in the bus's queues and histories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tx = bus.add_interface(vec![]).unwrap();
+    /// let rx = bus.add_interface(vec![]).unwrap();
+    /// tx.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    ///
+    /// let usage = bus.memory_usage();
+    /// assert_eq!(usage.queued, 2); // in both receive queues, as tx hears itself
+    /// assert_eq!(usage.history, 2); // in tx's TX history and the bus's replay buffer
+    /// assert!(usage.bytes > 0);
+    /// tx.drain_frames();
+    /// rx.drain_frames();
+    /// tx.clear_sent_frames();
+    /// bus.set_replay_capacity(Some(0));
+    /// assert_eq!(bus.memory_usage().bytes, 0);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        lock(&self.0).memory_usage()
+    }
+
+    /// [Close](InterfaceHandle::close) every interface attached to the bus, waking all threads
+    /// blocked waiting on any of them.
+    pub fn close(&self) {
+        for int in &mut lock(&self.0).interfaces {
+            int.close();
+        }
+    }
+
+    /// Enable (or with `None`, disable) bus-wide chaos mode. See [`chaos`](crate::chaos).
+    ///
+    /// The fault generator is seeded from `chaos`, so enabling the same configuration again,
+    /// for example after a [`reset`](Self::reset) (which disables chaos mode), replays the same
+    /// faults.
+    pub fn set_chaos(&self, chaos: Option<ChaosConfig>) {
+        lock(&self.0).chaos = chaos.map(|config| (config, Rng::new(config.seed)));
+    }
+
+    /// Current chaos configuration, if enabled.
+    pub fn chaos(&self) -> Option<ChaosConfig> {
+        lock(&self.0).chaos.as_ref().map(|(config, _)| *config)
+    }
+
+    /// Install (or with `None`, remove) a time-triggered schedule restricting when each
+    /// interface may transmit. See [`ttcan`](crate::ttcan).
+    ///
+    /// Window events are logged from the current virtual time on.
+    pub fn set_schedule(&self, schedule: Option<TtSchedule>) {
+        let mut bus = lock(&self.0);
+        bus.schedule = schedule;
+        bus.window_events.clear();
+        bus.window_cursor = bus.now;
+    }
+
+    /// Remove and return the window starts the virtual clock has passed since the last call,
+    /// oldest first.
+    pub fn take_window_events(&self) -> Vec<WindowEvent> {
+        core::mem::take(&mut lock(&self.0).window_events)
+    }
+
+    /// Remove and return the frames the transmit policy did not simply allow, oldest first.
+    pub fn take_policy_log(&self) -> Vec<PolicyRecord<F>> {
+        core::mem::take(&mut lock(&self.0).policy_log)
+    }
+
+    /// Record every delivery refused by a receiver’s acceptance filters, with a
+    /// [`MatchReport`] explaining the refusal, for [`take_rejections`](Self::take_rejections).
+    /// Disabled by default; disabling it discards the log.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame, arbitrary};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tx = bus.add_interface(vec![]).unwrap();
+    /// let rx = bus
+    ///     .add_interface(vec![arbitrary::filter(arbitrary::id(0x100, false), 0x700)])
+    ///     .unwrap();
+    /// bus.set_log_rejections(true);
+    ///
+    /// tx.transmit(MockFrame::new(StandardId::new(0x234).unwrap(), &[]).unwrap()).unwrap();
+    /// let rejections = bus.take_rejections();
+    /// assert_eq!(rejections[0].receiver, rx.id());
+    /// assert_eq!(rejections[0].report.to_string(),
+    ///     "ID 234 rejected by all 1 filters\n  #0: masked bits 300 differ\n");
+    /// ```
+    pub fn set_log_rejections(&self, on: bool) {
+        let mut bus = lock(&self.0);
+        match (on, bus.rejections.is_some()) {
+            (true, false) => bus.rejections = Some(Vec::new()),
+            (false, true) => bus.rejections = None,
+            _ => {}
+        }
+    }
+
+    /// Remove and return the logged filter rejections, oldest first.
+    pub fn take_rejections(&self) -> Vec<Rejection<F>> {
+        lock(&self.0)
+            .rejections
+            .as_mut()
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Sever the bus into isolated segments: a frame only reaches interfaces in the same group
+    /// as its transmitter. Interfaces not listed in any group are cut off from every other node.
+    ///
+    /// Each segment keeps working on its own, like a physically split network, until
+    /// [`heal`](Self::heal) rejoins them; frames sent meanwhile never cross. Calling this again
+    /// replaces the previous split. The partition applies on top of the bus
+    /// [medium](Self::set_medium).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let (a, b, c) = (
+    ///     bus.add_interface(vec![]).unwrap(),
+    ///     bus.add_interface(vec![]).unwrap(),
+    ///     bus.add_interface(vec![]).unwrap(),
+    /// );
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    ///
+    /// bus.partition(&[&[a.id(), b.id()], &[c.id()]]);
+    /// a.transmit(frame.clone()).unwrap();
+    /// assert!(b.has_frames());
+    /// assert!(!c.has_frames());
+    ///
+    /// bus.heal();
+    /// a.transmit(frame).unwrap();
+    /// assert!(c.has_frames());
+    /// ```
+    pub fn partition(&self, groups: &[&[InterfaceId]]) {
+        let groups = groups.iter().map(|group| group.to_vec()).collect();
+        lock(&self.0).partition = Some(groups);
+    }
+
+    /// Rejoin the segments created by [`partition`](Self::partition).
+    pub fn heal(&self) {
+        lock(&self.0).partition = None;
+    }
+
+    /// Returns `true` while the bus is [partitioned](Self::partition).
+    pub fn is_partitioned(&self) -> bool {
+        lock(&self.0).partition.is_some()
+    }
+
+    /// Pause the bus whenever a frame matching `matcher` is delivered, replacing any previous
+    /// breakpoint.
+    ///
+    /// The matching frame itself is delivered as usual; from then on the bus holds every further
+    /// transmission, and with [timing](Self::set_timing) freezes the wire, until
+    /// [`resume`](Self::resume) is called. A test can therefore stop a conversation right after
+    /// a given frame and inspect both sides deterministically. While paused,
+    /// [`step`](Self::step) releases held frames one at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, Id, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    /// bus.pause_on(Id::Standard(StandardId::new(0x2).unwrap()));
+    ///
+    /// node.transmit_all(&[frame(0x1), frame(0x2), frame(0x3)]).unwrap();
+    /// assert!(bus.is_paused());
+    /// assert_eq!(node.drain_frames(), vec![frame(0x1), frame(0x2)]);
+    ///
+    /// bus.resume();
+    /// assert_eq!(node.drain_frames(), vec![frame(0x3)]);
+    /// ```
+    pub fn pause_on(&self, matcher: impl FrameMatcher<F> + Send + Sync + 'static) {
+        let old = lock(&self.0).breakpoint.replace(Box::new(matcher));
+        drop(old);
+    }
+
+    /// Remove the breakpoint set by [`pause_on`](Self::pause_on). A paused bus stays paused
+    /// until [`resume`](Self::resume).
+    pub fn clear_breakpoint(&self) {
+        let old = lock(&self.0).breakpoint.take();
+        drop(old);
+    }
+
+    /// Returns `true` if the bus is paused at a breakpoint.
+    pub fn is_paused(&self) -> bool {
+        lock(&self.0).paused
+    }
+
+    /// Continue after a breakpoint: release held frames (unless in stepped mode) and let the
+    /// wire run again. The breakpoint stays armed.
+    pub fn resume(&self) {
+        let mut bus = lock(&self.0);
+        bus.paused = false;
+        bus.release_held();
+        let now = bus.now;
+        bus.advance_to(now);
+    }
+
+    /// Release the oldest held frame onto the bus. Returns `false` if nothing was held (or the
+    /// bus is [stuck dominant](Self::set_stuck_dominant)).
+    ///
+    /// Without [timing](Self::set_timing) the frame is delivered immediately; with timing it
+    /// enters arbitration and completes as the virtual clock advances. A frame rejected by the
+    /// [load limit](Self::set_load_limit) is discarded.
+    pub fn step(&self) -> bool {
+        let mut bus = lock(&self.0);
+        if bus.stuck_dominant {
+            return false;
+        }
+        match bus.held.pop_front() {
+            Some(flight) => {
+                let _ = bus.offer(flight);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// [`step`](Self::step) until no frames are held, including frames transmitted while
+    /// stepping. Returns the number of frames released.
+    pub fn run_until_idle(&self) -> usize {
+        let mut released = 0;
+        while self.step() {
+            released += 1;
+        }
+        released
+    }
+
+    /// Limit the offered load of a timed bus (see [`LoadLimit`]); `None` removes the limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame, TransmitError};
+    /// use embedded_can_mock::timing::{BusTiming, LoadLimit};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(125_000)));
+    /// bus.set_load_limit(Some(LoadLimit { max_backlog: Duration::from_millis(2), reject: true }));
+    /// let node = bus.add_interface(vec![]).unwrap();
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x700).unwrap(), &[0; 8]).unwrap();
+    /// node.transmit(frame.clone()).unwrap();
+    /// assert!(matches!(node.transmit(frame.clone()), Err(TransmitError::BusOverloaded)));
+    /// assert!(bus.load().overloaded);
+    /// ```
+    pub fn set_load_limit(&self, limit: Option<LoadLimit>) {
+        lock(&self.0).load_limit = limit;
+    }
+
+    /// Starve selected frames on a timed bus (see [`Starvation`]); `None` restores fair
+    /// arbitration.
+    ///
+    /// An untimed bus delivers every frame immediately, so starvation needs
+    /// [timing](Self::set_timing).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use embedded_can_mock::timing::{BusTiming, Starvation};
+    /// use std::time::Duration;
+    ///
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[0; 8]).unwrap();
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(500_000)));
+    /// bus.set_starvation(Some(Starvation {
+    ///     below: Some(StandardId::new(0x400).unwrap().into()),
+    ///     quiet: Duration::from_millis(1),
+    ///     ..Starvation::default()
+    /// }));
+    /// let ecu = bus.add_interface(vec![]).unwrap();
+    /// let tester = bus.add_interface(vec![]).unwrap();
+    ///
+    /// // 0x500 waits while 0x100 traffic keeps flowing every 500 µs ...
+    /// ecu.transmit(frame(0x500)).unwrap();
+    /// for _ in 0..10 {
+    ///     ecu.transmit(frame(0x100)).unwrap();
+    ///     bus.advance(Duration::from_micros(500));
+    /// }
+    /// assert!(!tester.received_frames().contains(&frame(0x500)));
+    /// // ... and goes once the bus has been quiet for 1 ms.
+    /// bus.advance(Duration::from_millis(2));
+    /// assert_eq!(tester.received_frames().last(), Some(&frame(0x500)));
+    /// ```
+    pub fn set_starvation(&self, starvation: Option<Starvation>) {
+        let mut bus = lock(&self.0);
+        bus.starvation = starvation;
+        let now = bus.now;
+        bus.advance_to(now);
+    }
+
+    /// Current occupancy and overload metrics.
+    pub fn load(&self) -> BusLoad {
+        let bus = lock(&self.0);
+        let occupancy = if bus.now.is_zero() {
+            0.0
+        } else {
+            (bus.busy_time.as_secs_f64() / bus.now.as_secs_f64()).min(1.0)
+        };
+        BusLoad {
+            occupancy,
+            backlog: bus.backlog(),
+            waiting: bus.waiting.len(),
+            overloaded: bus.overloaded,
+            rejected: bus.overload_rejections,
+        }
+    }
+
+    /// Copy the receive queues of all attached interfaces.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let iface = bus.add_interface(vec![]).unwrap();
+    /// iface.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    ///
+    /// let snapshot = bus.snapshot();
+    /// assert_eq!(snapshot.interfaces[0].id, iface.id());
+    /// assert_eq!(snapshot.interfaces[0].frames.len(), 1);
+    ///
+    /// bus.reset();
+    /// assert!(bus.snapshot().interfaces[0].frames.is_empty());
+    /// ```
+    pub fn snapshot(&self) -> BusSnapshot<F> {
+        let bus = lock(&self.0);
+        let interfaces = bus
+            .interfaces
+            .iter()
+            .map(|int| InterfaceSnapshot {
+                id: int.id,
+                frames: int
+                    .received_frames
+                    .iter()
+                    .map(|r| (*r.frame).clone())
+                    .collect(),
+            })
+            .collect();
+        BusSnapshot { interfaces }
+    }
+
+    /// Replace the receive queue of every interface in `snapshot` with the snapshot's frames,
+    /// for example to start each case of a table-driven test from the same queued traffic.
+    ///
+    /// Interfaces no longer on this bus are skipped, and interfaces missing from the snapshot
+    /// keep their queues. Restored frames count as delivered now by the bus itself (see
+    /// [`injector_id`](Self::injector_id)), to the interface's first mailbox.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, BusSnapshot, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let iface = bus.add_interface(vec![]).unwrap();
+    /// iface.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[0xAB]).unwrap()).unwrap();
+    /// let saved = bus.snapshot().to_string();
+    ///
+    /// bus.reset();
+    /// bus.restore(&BusSnapshot::parse(&saved).unwrap());
+    /// assert_eq!(iface.pop_frame().unwrap().data(), [0xAB]);
+    /// ```
+    pub fn restore(&self, snapshot: &BusSnapshot<F>) {
+        let mut bus = lock(&self.0);
+        let source = bus.injector_id();
+        let mut replaced = Vec::new();
+        for saved in &snapshot.interfaces {
+            if !bus.holds(saved.id) {
+                continue;
+            }
+            let mut queue = VecDeque::new();
+            for frame in &saved.frames {
+                let (_, seq) = bus.injector.as_mut().unwrap();
+                let token = TxToken { source, seq: *seq };
+                *seq += 1;
+                bus.deliveries += 1;
+                queue.push_back(Received {
+                    frame: Arc::new(frame.clone()),
+                    token,
+                    mailbox: 0,
+                    at: bus.now,
+                    seq: bus.deliveries - 1,
+                });
+            }
+            let int = bus.interface_mut(saved.id);
+            replaced.push(core::mem::replace(&mut int.received_frames, queue));
+            int.notify();
+            for waker in int.rx_wakers.drain(..) {
+                waker.wake();
+            }
+        }
+        drop(bus);
+        drop(replaced);
+    }
+
+    /// ID, name, accepted IDs and filter counters of every attached interface, in attach order.
+    #[cfg(feature = "cli")]
+    pub(crate) fn interface_filters(
+        &self,
+    ) -> Vec<(InterfaceId, Option<String>, FilterSet, FilterStats)> {
+        let bus = lock(&self.0);
+        bus.interfaces
+            .iter()
+            .map(|int| {
+                let set = FilterSet::from_filters(&int.filters, int.filter_semantics)
+                    .expect("installed filters are valid");
+                (int.id, int.name.clone(), set, int.filter_stats())
+            })
+            .collect()
+    }
+}
+
+impl<F: Frame + Clone + 'static> Default for BusHandle<F> {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(MockBus::new())))
+    }
+}
+
+impl<F: Frame + Clone + 'static> InterfaceHandle<F> {
+    /// Create a new interface that is not attached to any bus yet.
+    ///
+    /// Use [`InterfaceHandle::attach_to_bus`] to connect it to a [`BusHandle`].
+    pub fn new_unattached(filters: Vec<IdMaskFilter>) -> Self {
+        let id = InterfaceId::next();
+        let condvar = Arc::new(Condvar::new());
+        let mut private = MockBus::new();
+        private
+            .interfaces
+            .push(MockInterface::new(id, filters, condvar.clone()));
+        Self(Arc::new(InterfaceShared {
+            id,
+            condvar,
+            home: Mutex::new(Arc::new(Mutex::new(private))),
+        }))
+    }
+
+    /// Identifier of this interface.
+    pub fn id(&self) -> InterfaceId {
+        self.0.id
+    }
+
+    /// Attach this interface to `bus`.
+    ///
+    /// Returns [`MockInterfaceError::BusAlreadyAttached`] if the interface is already attached.
+    pub fn attach_to_bus(&self, bus: &BusHandle<F>) -> Result<(), MockInterfaceError> {
+        self.attach_with(bus, CatchUp::Nothing)
+    }
+
+    /// Attach this interface to `bus`, first queuing the earlier traffic selected by `catch_up`.
+    ///
+    /// Attaching and replaying happen atomically, so no live frame can slip in between the
+    /// replayed ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, CatchUp, InterfaceHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    /// for id in 1..=5 {
+    ///     node.transmit(frame(id)).unwrap();
+    /// }
+    ///
+    /// let late = InterfaceHandle::new_unattached(vec![]);
+    /// late.attach_with(&bus, CatchUp::Last(2)).unwrap();
+    /// node.transmit(frame(6)).unwrap();
+    /// assert_eq!(late.drain_frames(), vec![frame(4), frame(5), frame(6)]);
+    /// ```
+    pub fn attach_with(
+        &self,
+        bus: &BusHandle<F>,
+        catch_up: CatchUp,
+    ) -> Result<(), MockInterfaceError> {
+        let mut home = lock(&self.0.home);
+        let int = {
+            let mut private = lock(&home);
+            if private.interface(self.0.id).attached {
+                return Err(MockInterfaceError::BusAlreadyAttached);
+            }
+            let index = private
+                .interfaces
+                .iter()
+                .position(|int| int.id == self.0.id)
+                .expect("interface state lives on its home bus");
+            private.interfaces.remove(index)
+        };
+        let mut target = lock(&bus.0);
+        if !int.tap
+            && target
+                .max_interfaces
+                .is_some_and(|max| target.interfaces.len() >= max)
+        {
+            drop(target);
+            lock(&home).interfaces.push(int);
+            return Err(MockInterfaceError::BusFull);
+        }
+        target.interfaces.push(MockInterface {
+            attached: true,
+            ..int
+        });
+        target.catch_up(self.0.id, catch_up);
+        drop(target);
+        *home = bus.0.clone();
+        // Blocked waiters still sleep on the old bus.
+        self.0.condvar.notify_all();
+        Ok(())
+    }
+
+    /// Detach this interface from its bus. It keeps its queues and settings and can be
+    /// [attached](Self::attach_to_bus) again later; until then, transmitting fails with
+    /// [`TransmitError::BusNotAttached`].
+    ///
+    /// The bus reports [`BusEvent::NodeLeft`] to its taps (and, if it
+    /// [announces departures](BusHandle::set_announce_departures), to every node). The same
+    /// happens when the last handle of an attached interface is dropped.
+    ///
+    /// Returns [`MockInterfaceError::BusNotAttached`] if the interface is not attached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::{BusEvent, BusHandle};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tap = bus.tap();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    ///
+    /// node.detach().unwrap();
+    /// assert_eq!(tap.pop_event(), Some(BusEvent::NodeLeft(node.id())));
+    /// assert!(node.detach().is_err());
+    /// node.attach_to_bus(&bus).unwrap();
+    /// ```
+    pub fn detach(&self) -> Result<(), MockInterfaceError> {
+        let mut home = lock(&self.0.home);
+        let int = {
+            let mut bus = lock(&home);
+            if !bus.interface(self.0.id).attached {
+                return Err(MockInterfaceError::BusNotAttached);
+            }
+            bus.depart(self.0.id)
+                .expect("interface state lives on its home bus")
+        };
+        let mut private = MockBus::new();
+        private.interfaces.push(MockInterface {
+            attached: false,
+            ..int
+        });
+        *home = Arc::new(Mutex::new(private));
+        // Blocked waiters still sleep on the bus the interface left.
+        self.0.condvar.notify_all();
+        Ok(())
+    }
+
+    /// Bus currently holding this interface’s state.
+    fn home(&self) -> Arc<Mutex<MockBus<F>>> {
+        lock(&self.0.home).clone()
+    }
+
+    /// Run `f` on this interface’s state while holding its bus lock.
+    fn with<R>(&self, f: impl FnOnce(&mut MockInterface<F>) -> R) -> R {
+        loop {
+            let home = self.home();
+            let mut bus = lock(&home);
+            // The state moved between resolving the home and locking it: follow it.
+            if bus.holds(self.0.id) {
+                return f(bus.interface_mut(self.0.id));
+            }
+        }
+    }
+
+    /// Block on this interface’s condition variable until `done` returns `true` or `timeout`
+    /// elapses. Returns the final result of `done`.
+    ///
+    /// Waiters are served in arrival order: after a state change, a waiter only examines it once
+    /// every waiter that arrived earlier has, so the longest-waiting thread gets the first chance
+    /// to consume a new frame and later threads cannot steal it.
+    ///
+    /// Attaching or detaching moves the interface’s state, waiter queue included, to another
+    /// bus while a waiter sleeps; the mover wakes it and the waiter follows the state there.
+    #[cfg(all(feature = "std", not(target_family = "wasm")))]
+    fn wait_until(
+        &self,
+        timeout: Option<Duration>,
+        mut done: impl FnMut(&mut MockInterface<F>) -> bool,
+    ) -> bool {
+        let deadline = timeout.map(|t| std::time::Instant::now() + t);
+        let mut home = self.home();
+        let mut bus: MutexGuard<'_, MockBus<F>> = lock(&home);
+        while !bus.holds(self.0.id) {
+            drop(bus);
+            home = self.home();
+            bus = lock(&home);
+        }
+        let int = bus.interface_mut(self.0.id);
+        if int.waiters.is_empty() && done(int) {
+            return true;
+        }
+        let ticket = int.next_ticket;
+        int.next_ticket += 1;
+        int.waiters.push_back(Waiter { ticket, seen: None });
+        loop {
+            if !bus.holds(self.0.id) {
+                drop(bus);
+                home = self.home();
+                bus = lock(&home);
+                continue;
+            }
+            let int = bus.interface_mut(self.0.id);
+            let generation = int.generation;
+            let position = int
+                .waiters
+                .iter()
+                .position(|w| w.ticket == ticket)
+                .expect("waiter stays queued until it leaves");
+            let my_turn = int
+                .waiters
+                .iter()
+                .take(position)
+                .all(|w| w.seen == Some(generation));
+            if my_turn && int.waiters[position].seen != Some(generation) {
+                let satisfied = done(int);
+                if satisfied {
+                    int.waiters.remove(position);
+                } else {
+                    int.waiters[position].seen = Some(generation);
+                }
+                // Let the next waiter in line examine the state.
+                if int.waiters.len() > usize::from(!satisfied) {
+                    self.0.condvar.notify_all();
+                }
+                if satisfied {
+                    return true;
+                }
+                if int.closed {
+                    int.waiters.retain(|w| w.ticket != ticket);
+                    return false;
+                }
+            }
+            let now = std::time::Instant::now();
+            match deadline {
+                Some(deadline) if now >= deadline => {
+                    let int = bus.interface_mut(self.0.id);
+                    int.waiters.retain(|w| w.ticket != ticket);
+                    if !int.waiters.is_empty() {
+                        self.0.condvar.notify_all();
+                    }
+                    return false;
+                }
+                Some(deadline) => {
+                    bus = wait_timeout(&self.0.condvar, bus, deadline - now);
+                }
+                None => bus = wait(&self.0.condvar, bus),
+            }
+        }
+    }
+
+    /// Without `std` there is no clock or thread parking: an unbounded wait spins, and any
+    /// bounded wait checks `done` exactly once. On `wasm` targets nothing else can run while
+    /// this one spins, so every wait checks once.
+    #[cfg(any(not(feature = "std"), target_family = "wasm"))]
+    fn wait_until(
+        &self,
+        timeout: Option<Duration>,
+        mut done: impl FnMut(&mut MockInterface<F>) -> bool,
+    ) -> bool {
+        loop {
+            let (satisfied, closed) = self.with(|int| (done(int), int.closed));
+            if satisfied {
+                return true;
+            }
+            if closed || timeout.is_some() || cfg!(target_family = "wasm") {
+                return false;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Transmit `frame` onto the bus.
+    ///
+    /// Frames are broadcast to all attached interfaces (including this interface) subject to the
+    /// receivers’ acceptance filters. The returned token carries the frame’s per-interface
+    /// sequence number.
+    pub fn transmit(&self, frame: F) -> Result<TxToken, TransmitError> {
+        let home = self.home();
+        let mut bus = lock(&home);
+        bus.transmit(self.0.id, frame, false, None)
+    }
+
+    /// Transmit `frame` and request a TX-complete confirmation for it.
+    ///
+    /// Once the frame has completed on the bus (immediately without [timing](BusHandle::set_timing),
+    /// otherwise when the virtual clock passes its end of frame), the returned token is queued on
+    /// this interface, like a TX-complete interrupt. Frames that are corrupted or rejected are
+    /// never confirmed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use embedded_can_mock::timing::BusTiming;
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(500_000)));
+    /// let node = bus.add_interface(vec![]).unwrap();
+    ///
+    /// let token = node
+    ///     .transmit_confirmed(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap())
+    ///     .unwrap();
+    /// assert_eq!(node.pop_tx_confirmation(), None);
+    ///
+    /// bus.advance(Duration::from_millis(1));
+    /// assert_eq!(node.pop_tx_confirmation(), Some(token));
+    /// ```
+    pub fn transmit_confirmed(&self, frame: F) -> Result<TxToken, TransmitError> {
+        let home = self.home();
+        let mut bus = lock(&home);
+        bus.transmit(self.0.id, frame, true, None)
+    }
+
+    /// Remove and return the oldest pending TX confirmation, if any.
+    pub fn pop_tx_confirmation(&self) -> Option<TxToken> {
+        self.with(|int| int.tx_confirmations.pop_front())
+    }
+
+    /// Wait until the transmission identified by `token` is confirmed, removing its
+    /// confirmation. Returns `false` on timeout; `timeout` behaves like in
+    /// [`wait_for_frame`](Self::wait_for_frame).
+    pub fn wait_tx_confirmation(&self, token: TxToken, timeout: Option<Duration>) -> bool {
+        self.wait_until(timeout, |int| {
+            let position = int.tx_confirmations.iter().position(|t| *t == token);
+            position
+                .and_then(|index| int.tx_confirmations.remove(index))
+                .is_some()
+        })
+    }
+
+    /// Transmit every frame in `frames`, in order, under a single bus lock acquisition.
+    ///
+    /// Equivalent to calling [`transmit`](Self::transmit) for each frame, but no other
+    /// transmitter can interleave frames into the batch.
+    pub fn transmit_all(&self, frames: &[F]) -> Result<(), TransmitError> {
+        let home = self.home();
+        let mut bus = lock(&home);
+        if !bus.interface(self.0.id).attached {
+            return Err(TransmitError::BusNotAttached);
+        }
+        for frame in frames {
+            bus.transmit(self.0.id, frame.clone(), false, None)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a frame transmitted by this interface is still waiting for or
+    /// occupying the wire.
+    ///
+    /// Always `false` unless the bus has [timing](BusHandle::set_timing) enabled.
+    pub fn is_transmit_pending(&self) -> bool {
+        let home = self.home();
+        let bus = lock(&home);
+        bus.on_wire
+            .iter()
+            .chain(&bus.waiting)
+            .chain(&bus.held)
+            .any(|f| f.source == self.0.id)
+    }
+
+    /// Turn this interface into a babbling idiot that floods the bus with `frame`.
+    ///
+    /// From the current virtual time on, the interface requests the wire with another copy of
+    /// `frame` as soon as the previous one has been sent, so with a high-priority ID it wins
+    /// every arbitration and starves the rest of the bus, which is what bus guardians and
+    /// watchdogs are meant to catch. Only one interface per bus babbles at a time; this replaces
+    /// any previous babbler.
+    ///
+    /// Flooding needs a [timed](BusHandle::set_timing) bus, where each copy occupies the wire for
+    /// its transmission time; on an untimed bus the babbler stays silent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use embedded_can_mock::timing::BusTiming;
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(500_000)));
+    /// let idiot = bus.add_interface(vec![]).unwrap();
+    /// let victim = bus.add_interface(vec![]).unwrap();
+    /// victim.set_self_reception(false);
+    ///
+    /// idiot.babble(MockFrame::new(StandardId::new(0x000).unwrap(), &[0xFF; 8]).unwrap());
+    /// victim.transmit(MockFrame::new(StandardId::new(0x100).unwrap(), &[]).unwrap()).unwrap();
+    /// bus.advance(Duration::from_millis(10));
+    /// assert!(victim.queue_len() > 10);
+    /// assert!(victim.is_transmit_pending());
+    ///
+    /// idiot.stop_babbling();
+    /// bus.advance(Duration::from_millis(1));
+    /// assert!(!victim.is_transmit_pending());
+    /// ```
+    pub fn babble(&self, frame: F) {
+        let home = self.home();
+        let mut bus = lock(&home);
+        let now = bus.now;
+        bus.babbler = Some((self.0.id, frame, now));
+        bus.advance_to(now);
+    }
+
+    /// Stop babbling (see [`babble`](Self::babble)); a copy already waiting for the wire is
+    /// still sent.
+    pub fn stop_babbling(&self) {
+        let home = self.home();
+        let mut bus = lock(&home);
+        if bus
+            .babbler
+            .as_ref()
+            .is_some_and(|(id, _, _)| *id == self.0.id)
+        {
+            bus.babbler = None;
+        }
+    }
+
+    /// Cancel every transmission of this interface that has not reached the wire yet, returning
+    /// the cancelled frames with their tokens in request order.
+    ///
+    /// Frames held in [stepped mode](BusHandle::set_stepped) or waiting for arbitration on a
+    /// [timed](BusHandle::set_timing) bus (including pending retransmissions) are removed; a
+    /// frame already on the wire completes, as on real hardware. Cancelled frames are never
+    /// delivered or confirmed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use embedded_can_mock::timing::BusTiming;
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// bus.set_timing(Some(BusTiming::new(500_000)));
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    ///
+    /// node.transmit(frame(0x1)).unwrap(); // goes straight onto the idle wire
+    /// let second = node.transmit(frame(0x2)).unwrap();
+    /// let third = node.transmit(frame(0x3)).unwrap();
+    ///
+    /// assert_eq!(node.abort_pending_tx(), vec![(second, frame(0x2)), (third, frame(0x3))]);
+    /// bus.advance(Duration::from_millis(1));
+    /// assert_eq!(node.drain_frames(), vec![frame(0x1)]);
+    /// ```
+    pub fn abort_pending_tx(&self) -> Vec<(TxToken, F)> {
+        self.abort_where(|_| true)
+    }
+
+    /// Cancel the single transmission identified by `token`, like aborting one TX mailbox.
+    ///
+    /// Returns the cancelled frame, or `None` if the transmission already reached the wire or
+    /// is unknown. See [`abort_pending_tx`](Self::abort_pending_tx).
+    pub fn abort_tx(&self, token: TxToken) -> Option<F> {
+        self.abort_where(|t| t == token)
+            .pop()
+            .map(|(_, frame)| frame)
+    }
+
+    fn abort_where(&self, cancel: impl Fn(TxToken) -> bool) -> Vec<(TxToken, F)> {
+        let home = self.home();
+        let mut bus = lock(&home);
+        let id = self.0.id;
+        let pending = |f: &InFlight<F>| f.source == id && cancel(f.token);
+        let (held, kept): (VecDeque<_>, _) = core::mem::take(&mut bus.held)
+            .into_iter()
+            .partition(|f| pending(f));
+        bus.held = kept;
+        let (waiting, kept): (Vec<_>, _) = core::mem::take(&mut bus.waiting)
+            .into_iter()
+            .partition(|f| pending(f));
+        bus.waiting = kept;
+        let mut aborted: Vec<_> = held.into_iter().chain(waiting).collect();
+        aborted.sort_by_key(|f| f.token);
+        aborted.into_iter().map(|f| (f.token, f.frame)).collect()
+    }
+
+    /// Return a snapshot of all currently queued received frames.
+    ///
+    /// This does not remove frames from the receive queue; use [`pop_frame`](Self::pop_frame) to
+    /// consume frames.
+    pub fn received_frames(&self) -> Vec<F> {
+        self.with(|int| {
+            int.received_frames
+                .iter()
+                .map(|r| (*r.frame).clone())
+                .collect()
+        })
+    }
+
+    /// Inspect the receive queue in place, without cloning it.
+    ///
+    /// `f` gets an iterator over the queued frames, oldest first, and runs while the bus is
+    /// locked: it must not call back into the bus or any of its interfaces.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// for len in 0..4 {
+    ///     node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[0; 8][..len]).unwrap()).unwrap();
+    /// }
+    ///
+    /// let bytes = node.with_received_frames(|frames| frames.map(|f| f.dlc()).sum::<usize>());
+    /// assert_eq!(bytes, 6);
+    /// assert_eq!(node.queue_len(), 4);
+    /// ```
+    pub fn with_received_frames<R>(&self, f: impl FnOnce(ReceivedFrames<'_, F>) -> R) -> R {
+        self.with(|int| {
+            f(ReceivedFrames {
+                inner: int.received_frames.iter(),
+            })
+        })
+    }
+
+    /// Replace this interface’s acceptance filter list.
+    ///
+    /// If `filters` is empty, the interface receives all frames. Otherwise it only receives frames
+    /// matching at least one filter. [`set_filter_semantics`](Self::set_filter_semantics) changes
+    /// both rules.
+    ///
+    /// Any mailbox assignment from
+    /// [`set_filters_with_mailboxes`](Self::set_filters_with_mailboxes) is cleared.
+    pub fn set_filters(&self, filters: Vec<IdMaskFilter>) -> Result<(), FilterError> {
+        validate_filters(&filters)?;
+        self.with(|int| {
+            int.install_filters(filters, None);
+            int.filter_mailboxes.clear();
+        });
+        Ok(())
+    }
+
+    /// Choose how this interface interprets an empty filter list and combines several filters.
+    ///
+    /// Hardware differs here: some controllers accept everything until a filter is configured
+    /// while others accept nothing, and some require a frame to pass every enabled filter. The
+    /// default is [accept-all-when-empty](crate::filter::EmptyListPolicy::AcceptAllWhenEmpty)
+    /// and [match-any](crate::filter::MatchMode::MatchAny). Under match-all, matched frames go to the first
+    /// filter’s mailbox.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::filter::{EmptyListPolicy, FilterSemantics, MatchMode, accept_exact};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_filter_semantics(FilterSemantics {
+    ///     empty: EmptyListPolicy::RejectAllWhenEmpty,
+    ///     mode: MatchMode::MatchAny,
+    /// });
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x42).unwrap(), &[]).unwrap();
+    /// node.transmit(frame.clone()).unwrap();
+    /// assert!(!node.has_frames());
+    ///
+    /// node.set_filters(vec![accept_exact(StandardId::new(0x42).unwrap())]).unwrap();
+    /// node.transmit(frame).unwrap();
+    /// assert!(node.has_frames());
+    /// ```
+    pub fn set_filter_semantics(&self, semantics: FilterSemantics) {
+        self.with(|int| int.filter_semantics = semantics);
+    }
+
+    /// Current filter semantics.
+    pub fn filter_semantics(&self) -> FilterSemantics {
+        self.with(|int| int.filter_semantics)
+    }
+
+    /// The set of IDs this interface currently accepts, taking its
+    /// [filter semantics](Self::set_filter_semantics) into account.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::StandardId;
+    /// use embedded_can_mock::BusHandle;
+    /// use embedded_can_mock::filter::{FilterSet, accept_range};
+    ///
+    /// let id = |raw| StandardId::new(raw).unwrap();
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// assert_eq!(node.filter_set(), FilterSet::all());
+    ///
+    /// node.set_filter_set(&FilterSet::any_of(accept_range(id(0x100), id(0x17F))).unwrap());
+    /// let accepted = node.filter_set();
+    /// assert!([0x100, 0x142, 0x17F].into_iter().all(|raw| accepted.contains_id(id(raw))));
+    /// assert!(!accepted.contains_id(id(0x180)));
+    /// ```
+    pub fn filter_set(&self) -> FilterSet {
+        self.with(|int| FilterSet::from_filters(&int.filters, int.filter_semantics))
+            .expect("installed filters are valid")
+    }
+
+    /// Accept exactly the IDs in `set`: installs its filters with match-any semantics and an
+    /// empty list rejecting everything, and clears any mailbox assignment.
+    pub fn set_filter_set(&self, set: &FilterSet) {
+        let filters = set.filters().to_vec();
+        self.with(|int| {
+            int.install_filters(filters, None);
+            int.filter_mailboxes.clear();
+            int.filter_semantics = FilterSemantics {
+                empty: EmptyListPolicy::RejectAllWhenEmpty,
+                mode: MatchMode::MatchAny,
+            };
+        });
+    }
+
+    /// Accept `lo..=hi` with at most `max_filters` filters, as firmware with few filter banks
+    /// would configure them (see [`accept_range_limited`]), and count frames the filters accept
+    /// outside the range as [false accepts](FilterStats::false_accepts).
+    ///
+    /// Filter semantics are set as by [`set_filter_set`](Self::set_filter_set). An empty range
+    /// installs no filters and so accepts nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo` and `hi` are of different kinds (standard vs extended).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let id = |raw| StandardId::new(raw).unwrap();
+    /// let bus = BusHandle::new();
+    /// let ecu = bus.add_interface(vec![]).unwrap();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_filter_range(id(0x101), id(0x10E), 2);
+    ///
+    /// for raw in [0x100, 0x101, 0x10E, 0x10F, 0x110] {
+    ///     ecu.transmit(MockFrame::new(id(raw), &[]).unwrap()).unwrap();
+    /// }
+    /// let stats = node.filter_stats();
+    /// assert_eq!(stats.hits, [2, 2]);
+    /// assert_eq!(stats.false_accepts, 2); // 0x100 and 0x10F
+    /// ```
+    pub fn set_filter_range(&self, lo: impl Into<Id>, hi: impl Into<Id>, max_filters: usize) {
+        let (lo, hi) = (lo.into(), hi.into());
+        let intent = FilterSet::any_of(accept_range(lo, hi)).expect("range filters are valid");
+        let filters = accept_range_limited(lo, hi, max_filters);
+        self.with(|int| {
+            int.install_filters(filters, Some(intent));
+            int.filter_mailboxes.clear();
+            int.filter_semantics = FilterSemantics {
+                empty: EmptyListPolicy::RejectAllWhenEmpty,
+                mode: MatchMode::MatchAny,
+            };
+        });
+    }
+
+    /// Per-filter hit counts and false accepts since the filters were last replaced.
+    pub fn filter_stats(&self) -> FilterStats {
+        self.with(|int| int.filter_stats())
+    }
+
+    /// Replace the filter list, assigning each filter the mailbox its matches are routed to, and
+    /// switch on [mailbox mode](Self::set_mailbox_mode).
+    ///
+    /// This models controllers whose filter banks each target a FIFO (bxCAN / FDCAN FIFO0 and
+    /// FIFO1) or a dedicated receive buffer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_interface::{Id, IdMask, IdMaskFilter};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let filter = |id| IdMaskFilter {
+    ///     id: Id::Standard(StandardId::new(id).unwrap()),
+    ///     mask: IdMask::Standard(0x7FF),
+    /// };
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_filters_with_mailboxes(vec![(filter(0x1), 1), (filter(0x2), 0), (filter(0x3), 1)])
+    ///     .unwrap();
+    ///
+    /// for id in [0x1, 0x2, 0x3] {
+    ///     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()).unwrap();
+    /// }
+    /// assert_eq!(node.mailbox_len(0), 1);
+    /// assert_eq!(node.mailbox_len(1), 2);
+    /// ```
+    pub fn set_filters_with_mailboxes(
+        &self,
+        filters: Vec<(IdMaskFilter, usize)>,
+    ) -> Result<(), FilterError> {
+        let (filters, mailboxes): (Vec<_>, Vec<_>) = filters.into_iter().unzip();
+        validate_filters(&filters)?;
+        self.with(|int| {
+            int.install_filters(filters, None);
+            int.filter_mailboxes = mailboxes;
+            int.mailboxes = true;
+        });
+        Ok(())
+    }
+
+    /// Switch this interface between blocking and non-blocking mode.
+    ///
+    /// The mode is stored on the interface, so it is shared by every handle (and every
+    /// [`MockCan`](crate::MockCan) / [`MockRx`](crate::MockRx)) referring to it. In non-blocking
+    /// mode, high-level receive operations return immediately instead of waiting for a frame.
+    pub fn set_nonblocking(&self, on: bool) {
+        self.with(|int| int.nonblocking = on);
+    }
+
+    /// Returns `true` if this interface is in non-blocking mode.
+    pub fn is_nonblocking(&self) -> bool {
+        self.with(|int| int.nonblocking)
+    }
+
+    /// Switch internal loopback (self-test) mode on or off.
+    ///
+    /// In loopback mode the interface is disconnected from the bus: its transmissions are
+    /// delivered only to itself (still subject to its filters), immediately and without timing or
+    /// fault injection, and it receives nothing from other nodes. Like other modes, this survives
+    /// [`BusHandle::reset`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let other = bus.add_interface(vec![]).unwrap();
+    /// node.set_loopback(true);
+    ///
+    /// node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(node.has_frames());
+    /// assert!(!other.has_frames());
+    /// ```
+    pub fn set_loopback(&self, on: bool) {
+        self.with(|int| int.loopback = on);
+    }
+
+    /// Returns `true` if this interface is in internal loopback mode.
+    pub fn is_loopback(&self) -> bool {
+        self.with(|int| int.loopback)
+    }
+
+    /// Give this interface a human-readable name for diagnostics.
+    pub fn set_name(&self, name: impl Into<String>) {
+        let name = name.into();
+        self.with(|int| int.name = Some(name));
+    }
+
+    /// The interface’s name, if one was set.
+    pub fn name(&self) -> Option<String> {
+        self.with(|int| int.name.clone())
+    }
+
+    /// Join the named group, e.g. a controller channel such as `"CAN1"`, so that frames sent
+    /// to it with [`transmit_to_group`](Self::transmit_to_group) reach this interface.
+    ///
+    /// Group membership does not affect ordinary broadcasts. Joining a group twice has no
+    /// further effect.
+    pub fn join_group(&self, group: impl Into<String>) {
+        let group = group.into();
+        self.with(|int| {
+            if !int.groups.contains(&group) {
+                int.groups.push(group);
+            }
+        });
+    }
+
+    /// Leave the named group. Returns `false` if this interface was not a member.
+    pub fn leave_group(&self, group: &str) -> bool {
+        self.with(|int| {
+            let before = int.groups.len();
+            int.groups.retain(|joined| joined != group);
+            int.groups.len() != before
+        })
+    }
+
+    /// The groups this interface has joined, in join order.
+    pub fn groups(&self) -> Vec<String> {
+        self.with(|int| int.groups.clone())
+    }
+
+    /// Transmit `frame` to the members of `group` only.
+    ///
+    /// The frame arbitrates for and occupies the shared wire like any other, but only members
+    /// of the group, [taps](BusHandle::tap) and the transmitter itself (subject to
+    /// self-reception) receive it. This models several controller channels multiplexed onto one
+    /// [`BusHandle`]. The transmitter does not need to be a member.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let gateway = bus.add_interface(vec![]).unwrap();
+    /// let can1 = bus.add_interface(vec![]).unwrap();
+    /// let can2 = bus.add_interface(vec![]).unwrap();
+    /// can1.join_group("CAN1");
+    /// can2.join_group("CAN2");
+    /// gateway.set_self_reception(false);
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x10).unwrap(), &[1]).unwrap();
+    /// gateway.transmit_to_group("CAN1", frame.clone()).unwrap();
+    /// assert_eq!(can1.pop_frame(), Some(frame));
+    /// assert!(!can2.has_frames());
+    /// ```
+    pub fn transmit_to_group(&self, group: &str, frame: F) -> Result<TxToken, TransmitError> {
+        let home = self.home();
+        let mut bus = lock(&home);
+        bus.transmit(self.0.id, frame, false, Some(group.into()))
+    }
+
+    /// Bound the receive queue to `capacity` frames (`None` for unbounded, the default).
+    ///
+    /// A frame arriving at a full queue is lost, counted in
+    /// [`overflow_count`](Self::overflow_count) and reported as [`BusEvent::RxOverflow`], like a
+    /// hardware FIFO overrun. Frames already queued are kept even if they exceed a new, smaller
+    /// capacity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_rx_capacity(Some(1));
+    ///
+    /// for id in [0x1, 0x2] {
+    ///     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()).unwrap();
+    /// }
+    /// assert_eq!(node.received_frames().len(), 1);
+    /// assert_eq!(node.overflow_count(), 1);
+    /// assert_eq!(node.pop_event(), Some(BusEvent::RxOverflow));
+    /// ```
+    pub fn set_rx_capacity(&self, capacity: Option<usize>) {
+        self.with(|int| int.rx_capacity = capacity);
+    }
+
+    /// Current receive queue bound, if any.
+    pub fn rx_capacity(&self) -> Option<usize> {
+        self.with(|int| int.rx_capacity)
+    }
+
+    /// Number of frames lost to a full receive queue.
+    pub fn overflow_count(&self) -> u64 {
+        self.with(|int| int.rx_overflows)
+    }
+
+    /// Pace this interface’s transmissions (`None`, the default, sends every frame as soon as it
+    /// is transmitted). Replacing the pacing keeps the interface’s pacing history.
+    ///
+    /// Delayed frames still count as transmitted right away: the call succeeds and the frame
+    /// requests the bus once it conforms. Internal loopback traffic is not paced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::timing::{Pacing, RateLimit};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let tap = bus.tap();
+    /// // At most 100 frames per second, bursts of 2, and 1 ms between frames.
+    /// node.set_pacing(Some(Pacing {
+    ///     min_gap: Duration::from_millis(1),
+    ///     rate_limit: Some(RateLimit { frames_per_second: 100, burst: 2 }),
+    ///     reject: false,
+    /// }));
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    /// for _ in 0..3 {
+    ///     node.transmit(frame.clone()).unwrap();
+    /// }
+    /// bus.advance(Duration::from_millis(100));
+    /// let times: Vec<_> = std::iter::from_fn(|| tap.pop_delivery())
+    ///     .map(|delivery| delivery.timestamp.as_millis())
+    ///     .collect();
+    /// assert_eq!(times, [0, 1, 10]);
+    /// ```
+    pub fn set_pacing(&self, pacing: Option<Pacing>) {
+        self.with(|int| int.pacing = pacing);
+    }
+
+    /// Current pacing configuration, if any.
+    pub fn pacing(&self) -> Option<Pacing> {
+        self.with(|int| int.pacing)
+    }
+
+    /// Frames this interface has transmitted, oldest first.
+    ///
+    /// Every frame accepted by a transmit call is recorded once, whether or not it has completed
+    /// on the bus yet, so tests can assert on what the system under test sent without attaching
+    /// an observer. Frames rejected by the transmit call itself are not recorded, and
+    /// retransmissions are not recorded again. Only the most recent
+    /// [`DEFAULT_TX_HISTORY`] frames are kept unless changed with
+    /// [`set_tx_history_capacity`](Self::set_tx_history_capacity).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_tx_history_capacity(Some(2));
+    /// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+    /// for id in 1..=3 {
+    ///     node.transmit(frame(id)).unwrap();
+    /// }
+    /// assert_eq!(node.sent_frames(), vec![frame(2), frame(3)]);
+    /// node.clear_sent_frames();
+    /// assert!(node.sent_frames().is_empty());
+    /// ```
+    pub fn sent_frames(&self) -> Vec<F> {
+        self.with(|int| int.tx_history.iter().cloned().collect())
+    }
+
+    /// Forget the TX history.
+    pub fn clear_sent_frames(&self) {
+        self.with(|int| int.tx_history.clear());
+    }
+
+    /// Bound the TX history to the most recent `capacity` frames (`None` keeps every frame,
+    /// `Some(0)` disables recording). Shrinking it discards the oldest frames immediately.
+    pub fn set_tx_history_capacity(&self, capacity: Option<usize>) {
+        self.with(|int| {
+            int.tx_history_capacity = capacity;
+            if let Some(capacity) = capacity {
+                let excess = int.tx_history.len().saturating_sub(capacity);
+                int.tx_history.drain(..excess);
+            }
+        });
+    }
+
+    /// Current TX history bound, if any.
+    pub fn tx_history_capacity(&self) -> Option<usize> {
+        self.with(|int| int.tx_history_capacity)
+    }
+
+    /// Discard received frames once they have been queued for longer than `ttl` of virtual time,
+    /// or keep them indefinitely with `None` (the default).
+    ///
+    /// Frames are aged against [`BusHandle::now`] and expire as the clock advances, modelling a
+    /// driver that drops stale messages; the number discarded is reported by
+    /// [`expired_count`](Self::expired_count). Setting a TTL applies it to frames already queued.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_rx_ttl(Some(Duration::from_millis(10)));
+    ///
+    /// node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// bus.advance(Duration::from_millis(10));
+    /// assert_eq!(node.queue_len(), 1);
+    /// bus.advance(Duration::from_millis(1));
+    /// assert_eq!(node.queue_len(), 0);
+    /// assert_eq!(node.expired_count(), 1);
+    /// ```
+    pub fn set_rx_ttl(&self, ttl: Option<Duration>) {
+        let home = self.home();
+        let mut bus = lock(&home);
+        let now = bus.now;
+        let int = bus.interface_mut(self.0.id);
+        int.rx_ttl = ttl;
+        int.expire(now);
+    }
+
+    /// Current receive queue TTL, if any.
+    pub fn rx_ttl(&self) -> Option<Duration> {
+        self.with(|int| int.rx_ttl)
+    }
+
+    /// Number of received frames discarded because they outlived the receive TTL.
+    pub fn expired_count(&self) -> u64 {
+        self.with(|int| int.rx_expired)
+    }
+
+    /// Choose whether transmissions that fail with a bus error are retried automatically (the
+    /// default, like a real controller) or dropped after one attempt (one-shot mode).
+    ///
+    /// Retries happen right away on an untimed bus and rejoin arbitration on a
+    /// [timed](BusHandle::set_timing) one. They stop once the interface is bus-off. Each retry
+    /// is counted in [`retransmit_count`](Self::retransmit_count).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tx = bus.add_interface(vec![]).unwrap();
+    /// let rx = bus.add_interface(vec![]).unwrap();
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    ///
+    /// bus.corrupt_next(BusEvent::AckError);
+    /// tx.transmit(frame.clone()).unwrap();
+    /// assert_eq!(tx.pop_event(), Some(BusEvent::AckError));
+    /// assert_eq!(rx.pop_frame(), Some(frame));
+    /// assert_eq!(tx.retransmit_count(), 1);
+    /// ```
+    pub fn set_auto_retransmit(&self, on: bool) {
+        self.with(|int| int.auto_retransmit = on);
+    }
+
+    /// Returns `true` if failed transmissions are retried automatically.
+    pub fn is_auto_retransmit(&self) -> bool {
+        self.with(|int| int.auto_retransmit)
+    }
+
+    /// Number of automatic retransmissions after failed attempts.
+    pub fn retransmit_count(&self) -> u64 {
+        self.with(|int| int.retransmissions)
+    }
+
+    /// Choose whether this interface receives its own transmissions (the default).
+    pub fn set_self_reception(&self, on: bool) {
+        self.with(|int| int.self_reception = on);
+    }
+
+    /// Returns `true` if this interface receives its own transmissions.
+    pub fn is_self_reception(&self) -> bool {
+        self.with(|int| int.self_reception)
+    }
+
+    /// Switch listen-only (bus monitoring) mode on or off.
+    ///
+    /// A listen-only interface keeps receiving, but [`transmit`](Self::transmit) fails with
+    /// [`TransmitError::ListenOnly`].
+    pub fn set_listen_only(&self, on: bool) {
+        self.with(|int| int.listen_only = on);
+    }
+
+    /// Returns `true` if this interface is in listen-only mode (taps and subscribers always
+    /// are).
+    pub fn is_listen_only(&self) -> bool {
+        self.with(|int| int.listen_only || int.tap || int.subscribed_to.is_some())
+    }
+
+    /// Run every frame this interface transmits through `middleware` before the bus sees it,
+    /// after any middleware registered earlier. See [`middleware`](crate::middleware).
+    ///
+    /// The middleware returns a [`MiddlewareAction`] or an `Option` (`None` drops the frame);
+    /// the transmit call succeeds either way.
+    pub fn on_tx<R>(&self, middleware: impl Fn(F) -> R + Send + Sync + 'static)
+    where
+        R: Into<MiddlewareAction<F>>,
+    {
+        let middleware: Middleware<F> = Arc::new(move |frame| middleware(frame).into());
+        self.with(|int| int.tx_middleware.push(middleware));
+    }
+
+    /// Run every frame this interface's filters accept through `middleware` before it is
+    /// queued, after any middleware registered earlier. See [`middleware`](crate::middleware).
+    pub fn on_rx<R>(&self, middleware: impl Fn(F) -> R + Send + Sync + 'static)
+    where
+        R: Into<MiddlewareAction<F>>,
+    {
+        let middleware: Middleware<F> = Arc::new(move |frame| middleware(frame).into());
+        self.with(|int| int.rx_middleware.push(middleware));
+    }
+
+    /// Remove all TX and RX middleware. Frames already delayed by RX middleware are still
+    /// queued when their time comes.
+    pub fn clear_middleware(&self) {
+        let (tx, rx) = self.with(|int| {
+            (
+                core::mem::take(&mut int.tx_middleware),
+                core::mem::take(&mut int.rx_middleware),
+            )
+        });
+        drop((tx, rx));
+    }
+
+    /// Create an independent receive cursor over this interface's deliveries.
+    ///
+    /// Cloning an `InterfaceHandle` shares one receive queue, so clones compete for frames. A
+    /// subscriber instead gets its own queue that receives a copy of every frame this interface
+    /// queues (after its filters, drop simulation and capacity), so a logger and the system
+    /// under test can both consume all frames. Subscribers are receive-only (transmitting fails
+    /// with [`TransmitError::ListenOnly`]), and have their own capacity, mailbox and
+    /// non-blocking settings. The interface should be attached to its bus first: a subscriber
+    /// stays on the bus the interface was on when it subscribed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let logger = node.subscribe();
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    /// node.transmit(frame.clone()).unwrap();
+    /// assert_eq!(node.pop_frame(), Some(frame.clone()));
+    /// assert_eq!(logger.pop_frame(), Some(frame));
+    /// ```
+    pub fn subscribe(&self) -> InterfaceHandle<F> {
+        let subscriber = InterfaceHandle::new_unattached(Vec::new());
+        subscriber.with(|int| int.subscribed_to = Some(self.0.id));
+        subscriber
+            .attach_to_bus(&BusHandle(self.home()))
+            .expect("a new interface is never attached");
+        subscriber
+    }
+
+    /// The interface this handle is a [subscriber](Self::subscribe) of, if any.
+    pub fn subscribed_to(&self) -> Option<InterfaceId> {
+        self.with(|int| int.subscribed_to)
+    }
+
+    /// Returns `true` if this handle is a [tap](BusHandle::tap).
+    pub fn is_tap(&self) -> bool {
+        self.with(|int| int.tap)
+    }
+
+    /// Silently drop each frame accepted by this interface with probability `probability`,
+    /// simulating a flaky transceiver.
+    ///
+    /// Drops are decided by a PRNG seeded with `seed`, so a run can be replayed exactly. A
+    /// probability of `0.0` disables dropping.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let flaky = bus.add_interface(vec![]).unwrap();
+    /// flaky.set_drop_probability(1.0, 0);
+    ///
+    /// flaky.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(!flaky.has_frames());
+    /// assert_eq!(flaky.dropped_count(), 1);
+    /// ```
+    pub fn set_drop_probability(&self, probability: f64, seed: u64) {
+        let rx_drop = (probability > 0.0).then(|| (probability.min(1.0), Rng::new(seed)));
+        self.with(|int| int.rx_drop = rx_drop);
+    }
+
+    /// Deliver frames to this interface slightly out of order: each arriving frame is queued
+    /// ahead of up to `window` of the most recently queued, still unread frames.
+    ///
+    /// How far each frame jumps ahead is chosen by a PRNG seeded with `seed`, so a failing run
+    /// can be replayed exactly. A window of 0 restores in-order delivery. Only frames still in
+    /// the queue are reordered, so a consumer that reads every frame as soon as it arrives sees
+    /// no difference; the fault catches consumers that assume a global order across a backlog.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// node.set_reordering(2, 7);
+    ///
+    /// let frames: Vec<_> = (1..=6)
+    ///     .map(|id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap())
+    ///     .collect();
+    /// node.transmit_all(&frames).unwrap();
+    ///
+    /// let received = node.drain_frames();
+    /// assert_eq!(received.len(), frames.len());
+    /// assert!(frames.iter().all(|f| received.contains(f)));
+    /// ```
+    pub fn set_reordering(&self, window: usize, seed: u64) {
+        let reorder = (window > 0).then(|| (window, Rng::new(seed)));
+        self.with(|int| int.rx_reorder = reorder);
+    }
+
+    /// Number of frames dropped by [`set_drop_probability`](Self::set_drop_probability) or by
+    /// [chaos mode](crate::chaos).
+    pub fn dropped_count(&self) -> u64 {
+        self.with(|int| int.rx_dropped)
+    }
+
+    /// Put this interface to sleep.
+    ///
+    /// The next frame accepted by its filters wakes it up and raises [`BusEvent::WakeUp`]. If
+    /// `lose_wake_frame` is `true` that frame is consumed by the wake-up and never queued, like a
+    /// transceiver that only starts listening after the wake-up pattern. Transmitting, calling
+    /// [`wake`](Self::wake), or [`BusHandle::reset`] also wakes the interface, without an event.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let sender = bus.add_interface(vec![]).unwrap();
+    /// let sleeper = bus.add_interface(vec![]).unwrap();
+    /// sleeper.sleep(true);
+    ///
+    /// sender.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+    /// assert!(!sleeper.is_asleep());
+    /// assert_eq!(sleeper.pop_event(), Some(BusEvent::WakeUp));
+    /// assert!(!sleeper.has_frames());
+    /// ```
+    pub fn sleep(&self, lose_wake_frame: bool) {
+        self.with(|int| int.asleep = Some(lose_wake_frame));
+    }
+
+    /// Wake this interface locally (no [`BusEvent::WakeUp`] is raised).
+    pub fn wake(&self) {
+        self.with(|int| int.asleep = None);
+    }
+
+    /// Returns `true` while the interface is asleep.
+    pub fn is_asleep(&self) -> bool {
+        self.with(|int| int.asleep.is_some())
+    }
+
+    /// Current transmit / receive error counters.
+    ///
+    /// Counters move with injected errors ([`BusHandle::corrupt_next`],
+    /// [`BusHandle::inject_error_frame`]) and recover with successful traffic.
+    pub fn error_counters(&self) -> ErrorCounters {
+        self.with(|int| int.counters)
+    }
+
+    /// Queue `event` on this interface, as if its controller had reported it.
+    pub fn inject_event(&self, event: BusEvent) {
+        self.with(|int| int.push_event(event));
+    }
+
+    /// Remove and return the oldest pending event, if any.
+    pub fn pop_event(&self) -> Option<BusEvent> {
+        self.with(|int| int.events.pop_front())
+    }
+
+    /// Copy of all pending events, oldest first.
+    pub fn pending_events(&self) -> Vec<BusEvent> {
+        self.with(|int| int.events.iter().cloned().collect())
+    }
+
+    /// Wait for and remove the next event. `timeout` behaves like in
+    /// [`wait_for_frame`](Self::wait_for_frame).
+    pub fn wait_for_event(&self, timeout: Option<Duration>) -> Option<BusEvent> {
+        let mut event = None;
+        self.wait_until(timeout, |int| {
+            event = int.events.pop_front();
+            event.is_some()
+        });
+        event
+    }
+
+    /// Remove and return the oldest received frame, if any.
+    pub fn pop_frame(&self) -> Option<F> {
+        self.with(|int| int.received_frames.pop_front().map(Received::into_frame))
+    }
+
+    /// Remove and return the oldest received frame together with the token of the transmission
+    /// it came from.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let tx = bus.add_interface(vec![]).unwrap();
+    /// let rx = bus.add_interface(vec![]).unwrap();
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    ///
+    /// let first = tx.transmit(frame.clone()).unwrap();
+    /// let second = tx.transmit(frame).unwrap();
+    /// assert_eq!(second.seq(), first.seq() + 1);
+    /// assert_eq!(rx.pop_frame_with_token().unwrap().0, first);
+    /// assert_eq!(rx.pop_frame_with_token().unwrap().0, second);
+    /// ```
+    pub fn pop_frame_with_token(&self) -> Option<(TxToken, F)> {
+        self.with(|int| {
+            int.received_frames
+                .pop_front()
+                .map(|r| (r.token, r.into_frame()))
+        })
+    }
+
+    /// Remove and return the oldest received frame with its delivery metadata: the transmitting
+    /// interface, the virtual delivery time and the bus-wide delivery sequence number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let a = bus.add_interface(vec![]).unwrap();
+    /// let b = bus.add_interface(vec![]).unwrap();
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    /// a.transmit(frame.clone()).unwrap();
+    ///
+    /// let delivered = b.pop_delivery().unwrap();
+    /// assert_eq!(delivered.frame, frame);
+    /// assert_eq!(delivered.source, a.id());
+    /// assert!(!delivered.echo);
+    /// assert!(a.pop_delivery().unwrap().echo);
+    /// ```
+    pub fn pop_delivery(&self) -> Option<Delivered<F>> {
+        self.with(|int| {
+            let id = int.id;
+            int.received_frames.pop_front().map(|r| Delivered {
+                source: r.token.source,
+                timestamp: r.at,
+                seq: r.seq,
+                echo: r.token.source == id,
+                frame: r.into_frame(),
+            })
+        })
+    }
+
+    /// Remove and return up to `max` of the oldest received frames, oldest first.
+    pub fn pop_frames(&self, max: usize) -> Vec<F> {
+        self.with(|int| {
+            let count = max.min(int.received_frames.len());
+            int.received_frames
+                .drain(..count)
+                .map(Received::into_frame)
+                .collect()
+        })
+    }
+
+    /// Remove and return every queued frame, oldest first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// for id in [0x1, 0x2] {
+    ///     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()).unwrap();
+    /// }
+    ///
+    /// assert_eq!(node.queue_len(), 2);
+    /// assert_eq!(node.peek_frame().unwrap().to_string(), "001#");
+    /// assert_eq!(node.drain_frames().len(), 2);
+    /// assert_eq!(node.queue_len(), 0);
+    /// ```
+    pub fn drain_frames(&self) -> Vec<F> {
+        self.with(|int| {
+            int.received_frames
+                .drain(..)
+                .map(Received::into_frame)
+                .collect()
+        })
+    }
+
+    /// Copy of the oldest received frame, without removing it.
+    pub fn peek_frame(&self) -> Option<F> {
+        self.with(|int| int.received_frames.front().map(|r| (*r.frame).clone()))
+    }
+
+    /// Number of frames currently queued for receive.
+    pub fn queue_len(&self) -> usize {
+        self.with(|int| int.received_frames.len())
+    }
+
+    /// Discard every queued frame; returns how many were discarded.
+    pub fn clear_rx(&self) -> usize {
+        self.with(|int| {
+            let count = int.received_frames.len();
+            int.received_frames.clear();
+            count
+        })
+    }
+
+    /// Switch mailbox mode on or off.
+    ///
+    /// In mailbox mode every accepted frame is tagged with the index of the first filter it
+    /// matched, like dedicated RX buffers or filter-bank FIFOs, and can be taken per mailbox with
+    /// [`pop_mailbox`](Self::pop_mailbox). The ordinary receive functions keep working and see
+    /// all mailboxes in arrival order. With no filters, everything lands in mailbox 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_interface::{Id, IdMask, IdMaskFilter};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let filter = |id| IdMaskFilter {
+    ///     id: Id::Standard(StandardId::new(id).unwrap()),
+    ///     mask: IdMask::Standard(0x7FF),
+    /// };
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![filter(0x100), filter(0x200)]).unwrap();
+    /// node.set_mailbox_mode(true);
+    ///
+    /// for id in [0x100, 0x200, 0x100] {
+    ///     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()).unwrap();
+    /// }
+    /// assert_eq!(node.mailbox_len(0), 2);
+    /// assert_eq!(node.pop_mailbox(1).unwrap().to_string(), "200#");
+    /// assert_eq!(node.pop_mailbox(1), None);
+    /// ```
+    pub fn set_mailbox_mode(&self, on: bool) {
+        self.with(|int| int.mailboxes = on);
+    }
+
+    /// Returns `true` if this interface is in mailbox mode.
+    pub fn is_mailbox_mode(&self) -> bool {
+        self.with(|int| int.mailboxes)
+    }
+
+    /// Remove and return the oldest frame routed to `mailbox`, if any.
+    pub fn pop_mailbox(&self, mailbox: usize) -> Option<F> {
+        self.with(|int| int.pop_mailbox(mailbox))
+    }
+
+    /// Number of frames queued in `mailbox`.
+    pub fn mailbox_len(&self, mailbox: usize) -> usize {
+        self.with(|int| {
+            int.received_frames
+                .iter()
+                .filter(|r| r.mailbox == mailbox)
+                .count()
+        })
+    }
+
+    /// Wait for and remove the oldest frame routed to `mailbox`. `timeout` behaves like in
+    /// [`wait_for_frame`](Self::wait_for_frame).
+    pub fn wait_for_mailbox(&self, mailbox: usize, timeout: Option<Duration>) -> Option<F> {
+        let mut frame = None;
+        self.wait_until(timeout, |int| {
+            frame = int.pop_mailbox(mailbox);
+            frame.is_some()
+        });
+        frame
+    }
+
+    /// Wait for and remove the oldest received frame. `timeout` behaves like in
+    /// [`wait_for_frame`](Self::wait_for_frame).
+    ///
+    /// When several threads receive on the same interface, frames are handed out in the order
+    /// the threads started waiting.
+    pub fn recv_frame(&self, timeout: Option<Duration>) -> Option<F> {
+        let mut frame = None;
+        self.wait_until(timeout, |int| {
+            frame = int.received_frames.pop_front().map(Received::into_frame);
+            frame.is_some()
+        });
+        frame
+    }
+
+    /// Close this interface for receiving: every thread blocked in a wait on it (such as
+    /// [`wait_for_frame`](Self::wait_for_frame) or [`recv_frame`](Self::recv_frame)) wakes up
+    /// and gives up as if it had timed out, and later waits return at once instead of blocking.
+    /// Frames already queued can still be received, and the interface keeps transmitting and
+    /// receiving as before. Blocking receives through [`MockCan`](crate::MockCan) report
+    /// [`MockError::Closed`](crate::MockError::Closed).
+    ///
+    /// Use this in test teardown so a consumer thread waiting for a frame that will never
+    /// arrive does not hang the test. [`BusHandle::reset`] reopens the interface.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::BusHandle;
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let consumer = {
+    ///     let node = node.clone();
+    ///     std::thread::spawn(move || node.recv_frame(None))
+    /// };
+    /// while node.waiter_count() == 0 {
+    ///     std::thread::yield_now();
+    /// }
+    /// node.close();
+    /// assert_eq!(consumer.join().unwrap(), None);
+    /// assert!(node.is_closed());
+    /// assert!(!node.wait_for_frame(None));
+    /// ```
+    pub fn close(&self) {
+        self.with(MockInterface::close);
+    }
+
+    /// Whether [`close`](Self::close) (or [`BusHandle::close`]) closed this interface.
+    pub fn is_closed(&self) -> bool {
+        self.with(|int| int.closed)
+    }
+
+    /// Number of threads currently blocked waiting on this interface.
+    pub fn waiter_count(&self) -> usize {
+        self.with(|int| int.waiters.len())
+    }
+
+    /// Returns `true` if any frames are currently queued for receive.
+    pub fn has_frames(&self) -> bool {
+        self.with(|int| !int.received_frames.is_empty())
+    }
+
+    /// Wait until at least one frame is available to receive.
+    ///
+    /// - `timeout: None` blocks indefinitely.
+    /// - `timeout: Some(d)` waits up to `d` and returns whether a frame became available.
+    ///
+    /// Without the `std` feature, `None` busy-waits and `Some(_)` only checks once. On `wasm`
+    /// targets both only check once; await [`poll_has_frame`](Self::poll_has_frame) instead.
+    pub fn wait_for_frame(&self, timeout: Option<Duration>) -> bool {
+        self.wait_until(timeout, |int| !int.received_frames.is_empty())
+    }
+
+    /// Whether a frame is queued, registering `cx`'s waker to be woken when one arrives if not.
+    ///
+    /// Like [`wait_for_frame`](Self::wait_for_frame) without blocking: it resolves to `true` once
+    /// a frame is queued, or `false` if the interface is [closed](Self::close) while empty. The
+    /// frame stays queued.
+    pub fn poll_has_frame(&self, cx: &mut Context<'_>) -> Poll<bool> {
+        self.with(|int| {
+            if !int.received_frames.is_empty() {
+                Poll::Ready(true)
+            } else if int.closed {
+                Poll::Ready(false)
+            } else {
+                if !int.rx_wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    int.rx_wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Remove the oldest received frame, or register `cx`'s waker to be woken when one arrives.
+    ///
+    /// This is the building block for async receive: it never blocks, so it can be polled from
+    /// any executor, and adapters such as `futures::stream::poll_fn` turn it into a stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::task::{Context, Poll, Waker};
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let mut cx = Context::from_waker(Waker::noop());
+    /// assert!(node.poll_frame(&mut cx).is_pending());
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    /// node.transmit(frame.clone()).unwrap();
+    /// assert_eq!(node.poll_frame(&mut cx), Poll::Ready(frame));
+    /// ```
+    pub fn poll_frame(&self, cx: &mut Context<'_>) -> Poll<F> {
+        self.with(|int| match int.received_frames.pop_front() {
+            Some(received) => Poll::Ready(received.into_frame()),
+            None => {
+                if !int.rx_wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    int.rx_wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Wait for and remove the first queued frame satisfying `matcher`.
+    ///
+    /// Frames that do not match are left in the receive queue, in order. `timeout` behaves like
+    /// in [`wait_for_frame`](Self::wait_for_frame); `None` is returned if no matching frame
+    /// arrived in time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, Id, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let iface = bus.add_interface(vec![]).unwrap();
+    ///
+    /// let noise = MockFrame::new(StandardId::new(0x100).unwrap(), &[]).unwrap();
+    /// let wanted = MockFrame::new(StandardId::new(0x200).unwrap(), &[0x01]).unwrap();
+    /// iface.transmit(noise.clone()).unwrap();
+    /// iface.transmit(wanted.clone()).unwrap();
+    ///
+    /// let id = Id::Standard(StandardId::new(0x200).unwrap());
+    /// let got = iface.recv_matching(id, Some(Duration::from_millis(10)));
+    /// assert_eq!(got, Some(wanted));
+    /// assert_eq!(iface.received_frames(), vec![noise]);
+    /// ```
+    pub fn recv_matching<M: FrameMatcher<F>>(
+        &self,
+        matcher: M,
+        timeout: Option<Duration>,
+    ) -> Option<F> {
+        self.recv_matching_inner(&matcher, timeout, false)
+    }
+
+    /// Like [`recv_matching`](Self::recv_matching), but discards every non-matching frame it
+    /// examines while waiting.
+    pub fn recv_matching_discard<M: FrameMatcher<F>>(
+        &self,
+        matcher: M,
+        timeout: Option<Duration>,
+    ) -> Option<F> {
+        self.recv_matching_inner(&matcher, timeout, true)
+    }
+
+    fn recv_matching_inner(
+        &self,
+        matcher: &dyn FrameMatcher<F>,
+        timeout: Option<Duration>,
+        discard: bool,
+    ) -> Option<F> {
+        let mut found = None;
+        self.wait_until(timeout, |int| {
+            let position = int
+                .received_frames
+                .iter()
+                .position(|r| matcher.matches(&*r.frame));
+            match position {
+                Some(index) if discard => {
+                    int.received_frames.drain(..index);
+                    found = int.received_frames.pop_front().map(Received::into_frame);
+                }
+                Some(index) => found = int.received_frames.remove(index).map(Received::into_frame),
+                None if discard => int.received_frames.clear(),
+                None => {}
+            }
+            found.is_some()
+        });
+        found
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: Frame + Clone + Send + Sync + 'static> InterfaceHandle<F> {
+    /// Turn this interface into channels, for code that prefers channel-style plumbing.
+    ///
+    /// Frames sent into the returned [`Sender`](std::sync::mpsc::Sender) are transmitted on the
+    /// bus, and frames received by the interface come out of the returned [`FrameReceiver`].
+    /// Frames the bus refuses come back out of the third channel with the reason. Two background
+    /// threads shuttle the frames: the transmit pump stops once the sender is dropped, the
+    /// receive pump once the receiver is dropped or the interface is [closed](Self::close).
+    /// With self-reception on, the interface's own transmissions come back out of the receiver.
+    ///
+    /// The channels are `std::sync::mpsc`; with the `tokio` feature,
+    /// [`into_tokio_channels`](Self::into_tokio_channels) bridges to tokio channels instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame, TransmitError};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let peer = bus.add_interface(vec![]).unwrap();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let listener = bus.add_interface(vec![]).unwrap();
+    /// let (tx, rx, errors) = node.into_channels();
+    ///
+    /// let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    /// peer.transmit(frame.clone()).unwrap();
+    /// assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), frame);
+    ///
+    /// tx.send(frame.clone()).unwrap();
+    /// assert!(peer.wait_for_frame(Some(Duration::from_secs(5))));
+    ///
+    /// listener.set_listen_only(true);
+    /// let (tx, _rx, errors) = listener.into_channels();
+    /// tx.send(frame.clone()).unwrap();
+    /// let (refused, error) = errors.recv_timeout(Duration::from_secs(5)).unwrap();
+    /// assert_eq!(refused, frame);
+    /// assert!(matches!(error, TransmitError::ListenOnly));
+    /// ```
+    pub fn into_channels(
+        self,
+    ) -> (
+        std::sync::mpsc::Sender<F>,
+        FrameReceiver<F>,
+        std::sync::mpsc::Receiver<(F, TransmitError)>,
+    ) {
+        use std::sync::{atomic::AtomicBool, mpsc};
+        // How often the receive pump checks whether the receiver is gone.
+        const POLL: Duration = Duration::from_millis(10);
+
+        let (to_bus, from_user) = mpsc::channel::<F>();
+        let (to_user, from_bus) = mpsc::channel::<F>();
+        let (to_errors, errors) = mpsc::channel();
+        let dropped = Arc::new(AtomicBool::new(false));
+
+        let tx_iface = self.clone();
+        std::thread::spawn(move || {
+            for frame in from_user {
+                if let Err(error) = tx_iface.transmit(frame.clone()) {
+                    let _ = to_errors.send((frame, error));
+                }
+            }
+        });
+        let receiver = FrameReceiver {
+            rx: from_bus,
+            dropped: dropped.clone(),
+            condvar: self.0.condvar.clone(),
+        };
+        std::thread::spawn(move || {
+            while !dropped.load(Ordering::Acquire) {
+                if !self.wait_for_frame(Some(POLL)) {
+                    if self.is_closed() {
+                        return;
+                    }
+                    continue;
+                }
+                for frame in self.drain_frames() {
+                    if to_user.send(frame).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        (to_bus, receiver, errors)
+    }
+
+    /// Turn this interface into tokio channels, for async code that prefers channel-style
+    /// plumbing.
+    ///
+    /// Like [`into_channels`](Self::into_channels), but the channels are `tokio::sync::mpsc`
+    /// channels buffering up to `capacity` frames each way, and the frames are shuttled by two
+    /// tasks spawned on the current tokio runtime. The transmit task stops once the sender is
+    /// dropped; the receive task awaits the interface's wakers and stops as soon as the receiver
+    /// is dropped or the interface is [closed](Self::close), which ends the receiver's stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime, or if `capacity` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// runtime.block_on(async {
+    ///     let bus = BusHandle::new();
+    ///     let (a_tx, _a_rx, _) = bus.add_interface(vec![]).unwrap().into_tokio_channels(8);
+    ///     let b = bus.add_interface(vec![]).unwrap();
+    ///     let (_b_tx, mut b_rx, _) = b.into_tokio_channels(8);
+    ///
+    ///     let frame = MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap();
+    ///     a_tx.send(frame.clone()).await.unwrap();
+    ///     assert_eq!(b_rx.recv().await, Some(frame));
+    ///
+    ///     bus.close();
+    ///     assert_eq!(b_rx.recv().await, None);
+    /// });
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn into_tokio_channels(
+        self,
+        capacity: usize,
+    ) -> (
+        tokio::sync::mpsc::Sender<F>,
+        tokio::sync::mpsc::Receiver<F>,
+        tokio::sync::mpsc::UnboundedReceiver<(F, TransmitError)>,
+    ) {
+        use tokio::sync::mpsc;
+
+        let (to_bus, mut from_user) = mpsc::channel::<F>(capacity);
+        let (to_user, from_bus) = mpsc::channel::<F>(capacity);
+        let (to_errors, errors) = mpsc::unbounded_channel();
+
+        let tx_iface = self.clone();
+        tokio::spawn(async move {
+            while let Some(frame) = from_user.recv().await {
+                if let Err(error) = tx_iface.transmit(frame.clone()) {
+                    let _ = to_errors.send((frame, error));
+                }
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                let queued = tokio::select! {
+                    () = to_user.closed() => return,
+                    queued = core::future::poll_fn(|cx| self.poll_has_frame(cx)) => queued,
+                };
+                if !queued {
+                    return;
+                }
+                for frame in self.drain_frames() {
+                    if to_user.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        (to_bus, from_bus, errors)
+    }
+
+    /// Call `callback` with every frame this interface receives that satisfies `matcher`, on a
+    /// background worker thread, until the returned [`FrameCallback`] is dropped.
+    ///
+    /// The worker reads from its own [subscription](Self::subscribe), so it sees frames without
+    /// taking them from this interface's queue, and the callback may transmit through a clone
+    /// of this handle to build a reactive test double. Frames arriving before the call are not
+    /// seen. The interface should be attached to its bus.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, Id, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use std::time::Duration;
+    ///
+    /// let bus = BusHandle::new();
+    /// let tester = bus.add_interface(vec![]).unwrap();
+    /// let ecu = bus.add_interface(vec![]).unwrap();
+    /// let request = Id::Standard(StandardId::new(0x7E0).unwrap());
+    /// // Answer every request with a positive response echoing its first byte.
+    /// let responder = ecu.on_frame(request, {
+    ///     let ecu = ecu.clone();
+    ///     move |frame: MockFrame| {
+    ///         let response = MockFrame::new(StandardId::new(0x7E8).unwrap(), &[frame.data()[0] + 0x40]);
+    ///         ecu.transmit(response.unwrap()).unwrap();
+    ///     }
+    /// });
+    ///
+    /// tester.transmit(MockFrame::new(request, &[0x10]).unwrap()).unwrap();
+    /// let response = Id::Standard(StandardId::new(0x7E8).unwrap());
+    /// let answer = tester.recv_matching(response, Some(Duration::from_secs(5))).unwrap();
+    /// assert_eq!(answer.data(), [0x50]);
+    ///
+    /// drop(responder);
+    /// tester.transmit(MockFrame::new(request, &[0x10]).unwrap()).unwrap();
+    /// assert_eq!(tester.recv_matching(response, Some(Duration::from_millis(50))), None);
+    /// ```
+    pub fn on_frame<M, C>(&self, matcher: M, mut callback: C) -> FrameCallback<F>
+    where
+        M: FrameMatcher<F> + Send + 'static,
+        C: FnMut(F) + Send + 'static,
+    {
+        let cursor = self.subscribe();
+        let worker = cursor.clone();
+        let thread = std::thread::Builder::new()
+            .name(alloc::format!("can-on-frame {}", self.0.id.as_raw()))
+            .spawn(move || {
+                while let Some(frame) = worker.recv_matching_inner(&matcher, None, true) {
+                    callback(frame);
+                }
+            })
+            .expect("failed to spawn the frame callback worker");
+        FrameCallback {
+            cursor,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Receiving end of [`InterfaceHandle::into_channels`].
+///
+/// Dereferences to the underlying [`Receiver`](std::sync::mpsc::Receiver); dropping it stops
+/// the receive pump.
+#[cfg(feature = "std")]
+pub struct FrameReceiver<F> {
+    rx: std::sync::mpsc::Receiver<F>,
+    dropped: Arc<std::sync::atomic::AtomicBool>,
+    condvar: Arc<Condvar>,
+}
+
+#[cfg(feature = "std")]
+impl<F> core::ops::Deref for FrameReceiver<F> {
+    type Target = std::sync::mpsc::Receiver<F>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rx
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F> Drop for FrameReceiver<F> {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::Release);
+        self.condvar.notify_all();
+    }
+}
+
+/// A frame callback worker started by [`InterfaceHandle::on_frame`].
+///
+/// Dropping it stops the worker once the callback in progress, if any, returns, and takes the
+/// worker's subscription off the bus. A panic in the callback is raised again on drop.
+#[cfg(feature = "std")]
+pub struct FrameCallback<F: Frame + Clone + 'static = MockFrame> {
+    cursor: InterfaceHandle<F>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "std")]
+impl<F: Frame + Clone + 'static> FrameCallback<F> {
+    /// Returns `true` while the worker is running, `false` once the callback panicked.
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: Frame + Clone + 'static> Drop for FrameCallback<F> {
+    fn drop(&mut self) {
+        self.cursor.close();
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        // Dropped from inside its own callback: the worker exits once the callback returns.
+        if thread.thread().id() == std::thread::current().id() {
+            return;
+        }
+        if let Err(panic) = thread.join()
+            && !std::thread::panicking()
+        {
+            std::panic::resume_unwind(panic);
         }
     }
 }
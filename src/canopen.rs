@@ -0,0 +1,670 @@
+//! CANopen node simulation helpers.
+//!
+//! A [`HeartbeatProducer`] plays a CANopen node publishing NMT heartbeats (COB-ID `0x700 +
+//! node-id`, one state byte) with a fixed period, and can be told to change state or fall silent.
+//! A [`HeartbeatConsumer`] watches heartbeats on the bus and reports which nodes timed out. Both
+//! are driven by the virtual time passed to `poll`, typically [`BusHandle::now`], so
+//! heartbeat-timeout handling can be tested deterministically:
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::canopen::{HeartbeatConsumer, HeartbeatProducer, HeartbeatStatus, NmtState};
+//!
+//! let bus = BusHandle::new();
+//! let mut node = HeartbeatProducer::new(bus.add_interface(vec![]).unwrap(), 5, Duration::from_millis(100));
+//! let mut master = HeartbeatConsumer::new(bus.add_interface(vec![]).unwrap());
+//! master.watch(5, Duration::from_millis(150));
+//!
+//! node.boot(bus.now()).unwrap();
+//! node.set_state(NmtState::Operational);
+//! for _ in 0..3 {
+//!     bus.advance(Duration::from_millis(100));
+//!     node.poll(bus.now()).unwrap();
+//!     master.poll(bus.now());
+//! }
+//! assert_eq!(master.status(5), HeartbeatStatus::Alive(NmtState::Operational));
+//!
+//! node.set_silent(true);
+//! bus.advance(Duration::from_millis(200));
+//! node.poll(bus.now()).unwrap();
+//! master.poll(bus.now());
+//! assert_eq!(master.status(5), HeartbeatStatus::TimedOut);
+//! ```
+//!
+//! An [`SdoServer`] answers expedited and segmented SDO reads and writes from a scripted object
+//! dictionary, so an SDO client can be tested end-to-end in memory.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+use embedded_can_interface::{Id as IfaceId, IdMask, IdMaskFilter};
+
+use crate::{
+    bus::{InterfaceHandle, TransmitError},
+    frame::MockFrame,
+};
+
+/// Base COB-ID of NMT heartbeat (error control) messages.
+pub const HEARTBEAT_BASE: u16 = 0x700;
+
+/// NMT state carried in a heartbeat message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NmtState {
+    /// Boot-up message, sent once when the node starts.
+    BootUp,
+    /// Stopped.
+    Stopped,
+    /// Operational.
+    Operational,
+    /// Pre-operational.
+    PreOperational,
+}
+
+impl NmtState {
+    /// The state byte sent on the wire.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            NmtState::BootUp => 0x00,
+            NmtState::Stopped => 0x04,
+            NmtState::Operational => 0x05,
+            NmtState::PreOperational => 0x7F,
+        }
+    }
+
+    /// Decode a state byte (the toggle bit is ignored).
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte & 0x7F {
+            0x00 => Some(NmtState::BootUp),
+            0x04 => Some(NmtState::Stopped),
+            0x05 => Some(NmtState::Operational),
+            0x7F => Some(NmtState::PreOperational),
+            _ => None,
+        }
+    }
+}
+
+/// Build the heartbeat frame node `node_id` sends in `state`.
+///
+/// # Panics
+///
+/// Panics if `node_id` is not a valid CANopen node-id (1..=127).
+pub fn heartbeat_frame(node_id: u8, state: NmtState) -> MockFrame {
+    assert!(
+        (1..=127).contains(&node_id),
+        "invalid CANopen node-id {node_id}"
+    );
+    let id = StandardId::new(HEARTBEAT_BASE + u16::from(node_id)).unwrap();
+    MockFrame::new(id, &[state.to_byte()]).unwrap()
+}
+
+/// Decode a heartbeat frame into its node-id and state.
+pub fn parse_heartbeat<F: Frame>(frame: &F) -> Option<(u8, NmtState)> {
+    let Id::Standard(id) = frame.id() else {
+        return None;
+    };
+    let node_id = id.as_raw().checked_sub(HEARTBEAT_BASE)?;
+    if frame.is_remote_frame() || !(1..=127).contains(&node_id) {
+        return None;
+    }
+    let [state] = frame.data() else {
+        return None;
+    };
+    Some((node_id as u8, NmtState::from_byte(*state)?))
+}
+
+/// Simulated CANopen node producing heartbeats.
+pub struct HeartbeatProducer {
+    interface: InterfaceHandle,
+    node_id: u8,
+    period: Duration,
+    state: NmtState,
+    next_due: Option<Duration>,
+    silent: bool,
+}
+
+impl HeartbeatProducer {
+    /// Create a producer for `node_id` sending every `period` on `interface`.
+    ///
+    /// Nothing is sent until [`boot`](Self::boot) is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node_id` is not a valid CANopen node-id (1..=127).
+    pub fn new(interface: InterfaceHandle, node_id: u8, period: Duration) -> Self {
+        assert!(
+            (1..=127).contains(&node_id),
+            "invalid CANopen node-id {node_id}"
+        );
+        Self {
+            interface,
+            node_id,
+            period,
+            state: NmtState::PreOperational,
+            next_due: None,
+            silent: false,
+        }
+    }
+
+    /// The node-id heartbeats are sent for.
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    /// The interface heartbeats are sent from.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.interface
+    }
+
+    /// Current NMT state.
+    pub fn state(&self) -> NmtState {
+        self.state
+    }
+
+    /// Change the NMT state reported by subsequent heartbeats.
+    pub fn set_state(&mut self, state: NmtState) {
+        self.state = state;
+    }
+
+    /// Change the heartbeat period. Takes effect after the next heartbeat.
+    pub fn set_period(&mut self, period: Duration) {
+        self.period = period;
+    }
+
+    /// Stop (`true`) or resume (`false`) sending heartbeats, simulating a node that died or
+    /// lost its bus connection. Resuming sends the next heartbeat on the following poll.
+    pub fn set_silent(&mut self, silent: bool) {
+        if self.silent && !silent && self.next_due.is_some() {
+            self.next_due = Some(Duration::ZERO);
+        }
+        self.silent = silent;
+    }
+
+    /// Send the boot-up message and enter pre-operational; heartbeats follow every period.
+    pub fn boot(&mut self, now: Duration) -> Result<(), TransmitError> {
+        self.interface
+            .transmit(heartbeat_frame(self.node_id, NmtState::BootUp))?;
+        self.state = NmtState::PreOperational;
+        self.next_due = Some(now + self.period);
+        Ok(())
+    }
+
+    /// Send the heartbeats due by `now`, returning how many were sent.
+    ///
+    /// Missed periods are not caught up: a producer polled late sends one heartbeat and
+    /// schedules the next one a period later, like a node whose timer fired late.
+    pub fn poll(&mut self, now: Duration) -> Result<usize, TransmitError> {
+        match self.next_due {
+            Some(due) if due <= now && !self.silent => {
+                self.interface
+                    .transmit(heartbeat_frame(self.node_id, self.state))?;
+                self.next_due = Some(now + self.period);
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+/// Liveness of a node watched by a [`HeartbeatConsumer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatStatus {
+    /// No heartbeat seen yet (or the node is not watched).
+    Unknown,
+    /// The last heartbeat arrived in time and reported this state.
+    Alive(NmtState),
+    /// No heartbeat arrived within the consumer timeout.
+    TimedOut,
+}
+
+struct Watched {
+    timeout: Duration,
+    last: Option<(Duration, NmtState)>,
+    timed_out: bool,
+}
+
+/// Heartbeat consumer tracking the liveness of watched nodes.
+pub struct HeartbeatConsumer {
+    interface: InterfaceHandle,
+    nodes: BTreeMap<u8, Watched>,
+}
+
+impl HeartbeatConsumer {
+    /// Create a consumer receiving on `interface`.
+    ///
+    /// The interface's filters are replaced with one accepting only heartbeat COB-IDs.
+    pub fn new(interface: InterfaceHandle) -> Self {
+        let filter = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(HEARTBEAT_BASE).unwrap()),
+            mask: IdMask::Standard(0x780),
+        };
+        interface.set_filters(vec![filter]).unwrap();
+        Self {
+            interface,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Expect a heartbeat from `node_id` at least every `timeout`.
+    pub fn watch(&mut self, node_id: u8, timeout: Duration) {
+        self.nodes.insert(
+            node_id,
+            Watched {
+                timeout,
+                last: None,
+                timed_out: false,
+            },
+        );
+    }
+
+    /// Process the heartbeats received so far and check timeouts against `now`.
+    ///
+    /// Heartbeats received in the same poll are treated as arriving at `now`.
+    pub fn poll(&mut self, now: Duration) {
+        for frame in self.interface.drain_frames() {
+            if let Some((node_id, state)) = parse_heartbeat(&frame)
+                && let Some(node) = self.nodes.get_mut(&node_id)
+            {
+                node.last = Some((now, state));
+                node.timed_out = false;
+            }
+        }
+        for node in self.nodes.values_mut() {
+            if let Some((seen, _)) = node.last
+                && now > seen + node.timeout
+            {
+                node.timed_out = true;
+            }
+        }
+    }
+
+    /// Liveness of `node_id` as of the last [`poll`](Self::poll).
+    pub fn status(&self, node_id: u8) -> HeartbeatStatus {
+        match self.nodes.get(&node_id) {
+            Some(Watched {
+                timed_out: true, ..
+            }) => HeartbeatStatus::TimedOut,
+            Some(Watched {
+                last: Some((_, state)),
+                ..
+            }) => HeartbeatStatus::Alive(*state),
+            _ => HeartbeatStatus::Unknown,
+        }
+    }
+}
+
+/// Base COB-ID of SDO requests (client to server).
+pub const SDO_RX_BASE: u16 = 0x600;
+
+/// Base COB-ID of SDO responses (server to client).
+pub const SDO_TX_BASE: u16 = 0x580;
+
+/// SDO abort code: object does not exist in the object dictionary.
+pub const ABORT_NO_OBJECT: u32 = 0x0602_0000;
+
+/// SDO abort code: toggle bit not alternated.
+pub const ABORT_TOGGLE: u32 = 0x0503_0000;
+
+/// SDO abort code: client/server command specifier not valid or unknown.
+pub const ABORT_COMMAND: u32 = 0x0504_0001;
+
+/// Transfer in progress on an [`SdoServer`].
+enum SdoTransfer {
+    Upload {
+        data: Vec<u8>,
+        offset: usize,
+        toggle: u8,
+    },
+    Download {
+        key: (u16, u8),
+        data: Vec<u8>,
+        toggle: u8,
+    },
+}
+
+/// Scriptable SDO server answering expedited and segmented reads and writes.
+///
+/// The object dictionary maps index/subindex pairs to raw bytes. Requests arrive on COB-ID
+/// `0x600 + node-id` and responses go out on `0x580 + node-id`; requests for objects that are
+/// missing, or that were scripted to fail with [`abort_on`](Self::abort_on), are answered with an
+/// abort transfer.
+///
+/// ```
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{BusHandle, MockFrame};
+/// use embedded_can_mock::canopen::SdoServer;
+///
+/// let bus = BusHandle::new();
+/// let mut server = SdoServer::new(bus.add_interface(vec![]).unwrap(), 0x22);
+/// server.insert(0x1018, 0x01, 0x0000_1234u32.to_le_bytes().to_vec());
+/// let client = bus.add_interface(vec![]).unwrap();
+/// client.set_self_reception(false);
+///
+/// // Expedited upload of 0x1018:01.
+/// let request = [0x40, 0x18, 0x10, 0x01, 0, 0, 0, 0];
+/// client.transmit(MockFrame::new(StandardId::new(0x622).unwrap(), &request).unwrap()).unwrap();
+/// assert_eq!(server.poll().unwrap(), 1);
+/// let response = client.pop_frame().unwrap();
+/// assert_eq!(response.data(), [0x43, 0x18, 0x10, 0x01, 0x34, 0x12, 0x00, 0x00]);
+/// ```
+pub struct SdoServer {
+    interface: InterfaceHandle,
+    node_id: u8,
+    dictionary: BTreeMap<(u16, u8), Vec<u8>>,
+    aborts: BTreeMap<(u16, u8), u32>,
+    transfer: Option<SdoTransfer>,
+}
+
+impl SdoServer {
+    /// Create a server for `node_id` answering on `interface`.
+    ///
+    /// The interface's filters are replaced with one accepting only this node's SDO requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node_id` is not a valid CANopen node-id (1..=127).
+    pub fn new(interface: InterfaceHandle, node_id: u8) -> Self {
+        assert!(
+            (1..=127).contains(&node_id),
+            "invalid CANopen node-id {node_id}"
+        );
+        let filter = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(SDO_RX_BASE + u16::from(node_id)).unwrap()),
+            mask: IdMask::Standard(0x7FF),
+        };
+        interface.set_filters(vec![filter]).unwrap();
+        Self {
+            interface,
+            node_id,
+            dictionary: BTreeMap::new(),
+            aborts: BTreeMap::new(),
+            transfer: None,
+        }
+    }
+
+    /// The node-id this server answers for.
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    /// The interface the server answers on.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.interface
+    }
+
+    /// Add or replace object `index:subindex` in the dictionary.
+    pub fn insert(&mut self, index: u16, subindex: u8, value: Vec<u8>) {
+        self.dictionary.insert((index, subindex), value);
+    }
+
+    /// Current value of object `index:subindex`, including values written by clients.
+    pub fn get(&self, index: u16, subindex: u8) -> Option<&[u8]> {
+        self.dictionary.get(&(index, subindex)).map(Vec::as_slice)
+    }
+
+    /// Answer every access to `index:subindex` with an abort carrying `code`.
+    pub fn abort_on(&mut self, index: u16, subindex: u8, code: u32) {
+        self.aborts.insert((index, subindex), code);
+    }
+
+    /// Answer the requests received so far, returning how many were processed.
+    pub fn poll(&mut self) -> Result<usize, TransmitError> {
+        let requests = self.interface.drain_frames();
+        for request in &requests {
+            let mut data = [0; 8];
+            let len = request.data().len().min(8);
+            data[..len].copy_from_slice(&request.data()[..len]);
+            let Some(response) = self.handle(&data) else {
+                continue;
+            };
+            let id = StandardId::new(SDO_TX_BASE + u16::from(self.node_id)).unwrap();
+            self.interface
+                .transmit(MockFrame::new(id, &response).unwrap())?;
+        }
+        Ok(requests.len())
+    }
+
+    fn handle(&mut self, request: &[u8; 8]) -> Option<[u8; 8]> {
+        let key = (u16::from_le_bytes([request[1], request[2]]), request[3]);
+        match request[0] >> 5 {
+            // Initiate upload.
+            2 => {
+                self.transfer = None;
+                if let Some(code) = self.check(key) {
+                    return Some(abort(key, code));
+                }
+                let value = self.dictionary[&key].clone();
+                let mut response = initiate_response(0x40, key);
+                // An empty value has no expedited encoding, so it goes segmented with size 0.
+                if !value.is_empty() && value.len() <= 4 {
+                    response[0] |= 0x03 | ((4 - value.len() as u8) << 2);
+                    response[4..4 + value.len()].copy_from_slice(&value);
+                } else {
+                    response[0] |= 0x01;
+                    response[4..].copy_from_slice(&(value.len() as u32).to_le_bytes());
+                    self.transfer = Some(SdoTransfer::Upload {
+                        data: value,
+                        offset: 0,
+                        toggle: 0,
+                    });
+                }
+                Some(response)
+            }
+            // Upload segment.
+            3 => {
+                let toggle = (request[0] >> 4) & 1;
+                let Some(SdoTransfer::Upload {
+                    data,
+                    offset,
+                    toggle: expected,
+                }) = &mut self.transfer
+                else {
+                    return Some(abort(key, ABORT_COMMAND));
+                };
+                if toggle != *expected {
+                    self.transfer = None;
+                    return Some(abort(key, ABORT_TOGGLE));
+                }
+                let n = (data.len() - *offset).min(7);
+                let mut response = [0; 8];
+                response[0] = (toggle << 4) | ((7 - n as u8) << 1);
+                response[1..1 + n].copy_from_slice(&data[*offset..*offset + n]);
+                *offset += n;
+                *expected ^= 1;
+                if *offset == data.len() {
+                    response[0] |= 0x01;
+                    self.transfer = None;
+                }
+                Some(response)
+            }
+            // Initiate download.
+            1 => {
+                self.transfer = None;
+                if let Some(code) = self.check(key) {
+                    return Some(abort(key, code));
+                }
+                if request[0] & 0x02 != 0 {
+                    let len = if request[0] & 0x01 != 0 {
+                        4 - usize::from((request[0] >> 2) & 0x03)
+                    } else {
+                        4
+                    };
+                    self.dictionary.insert(key, request[4..4 + len].to_vec());
+                } else {
+                    self.transfer = Some(SdoTransfer::Download {
+                        key,
+                        data: Vec::new(),
+                        toggle: 0,
+                    });
+                }
+                Some(initiate_response(0x60, key))
+            }
+            // Download segment.
+            0 => {
+                let toggle = (request[0] >> 4) & 1;
+                let Some(SdoTransfer::Download {
+                    key,
+                    data,
+                    toggle: expected,
+                }) = &mut self.transfer
+                else {
+                    return Some(abort(key, ABORT_COMMAND));
+                };
+                if toggle != *expected {
+                    let key = *key;
+                    self.transfer = None;
+                    return Some(abort(key, ABORT_TOGGLE));
+                }
+                let n = 7 - usize::from((request[0] >> 1) & 0x07);
+                data.extend_from_slice(&request[1..1 + n]);
+                *expected ^= 1;
+                if request[0] & 0x01 != 0 {
+                    let (key, data) = (*key, core::mem::take(data));
+                    self.dictionary.insert(key, data);
+                    self.transfer = None;
+                }
+                Some([0x20 | (toggle << 4), 0, 0, 0, 0, 0, 0, 0])
+            }
+            // Abort from the client: aborts are unconfirmed, so nothing is sent back.
+            4 => {
+                self.transfer = None;
+                None
+            }
+            _ => Some(abort(key, ABORT_COMMAND)),
+        }
+    }
+
+    fn check(&self, key: (u16, u8)) -> Option<u32> {
+        if let Some(code) = self.aborts.get(&key) {
+            return Some(*code);
+        }
+        (!self.dictionary.contains_key(&key)).then_some(ABORT_NO_OBJECT)
+    }
+}
+
+fn initiate_response(command: u8, (index, subindex): (u16, u8)) -> [u8; 8] {
+    let [lo, hi] = index.to_le_bytes();
+    [command, lo, hi, subindex, 0, 0, 0, 0]
+}
+
+fn abort(key: (u16, u8), code: u32) -> [u8; 8] {
+    let mut response = initiate_response(0x80, key);
+    response[4..].copy_from_slice(&code.to_le_bytes());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+
+    #[test]
+    fn producer_sends_boot_up_then_periodic_state() {
+        let bus = BusHandle::new();
+        let mut node = HeartbeatProducer::new(
+            bus.add_interface(vec![]).unwrap(),
+            0x10,
+            Duration::from_millis(50),
+        );
+        let monitor = bus.add_interface(vec![]).unwrap();
+
+        assert_eq!(node.poll(bus.now()).unwrap(), 0);
+        node.boot(bus.now()).unwrap();
+        bus.advance(Duration::from_millis(49));
+        assert_eq!(node.poll(bus.now()).unwrap(), 0);
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(node.poll(bus.now()).unwrap(), 1);
+
+        let states: Vec<_> = monitor
+            .drain_frames()
+            .iter()
+            .map(|f| parse_heartbeat(f).unwrap())
+            .collect();
+        assert_eq!(
+            states,
+            vec![(0x10, NmtState::BootUp), (0x10, NmtState::PreOperational)]
+        );
+        assert_eq!(heartbeat_frame(0x10, NmtState::Stopped).data(), [0x04]);
+    }
+
+    fn sdo_exchange(server: &mut SdoServer, client: &InterfaceHandle, request: [u8; 8]) -> Vec<u8> {
+        let id = StandardId::new(SDO_RX_BASE + u16::from(server.node_id())).unwrap();
+        client
+            .transmit(MockFrame::new(id, &request).unwrap())
+            .unwrap();
+        assert_eq!(server.poll().unwrap(), 1);
+        client.pop_frame().unwrap().data().to_vec()
+    }
+
+    #[test]
+    fn sdo_server_handles_segmented_transfers_and_aborts() {
+        let bus = BusHandle::new();
+        let mut server = SdoServer::new(bus.add_interface(vec![]).unwrap(), 0x05);
+        let client = bus.add_interface(vec![]).unwrap();
+        client.set_self_reception(false);
+        server.insert(0x1008, 0x00, b"mock-device".to_vec());
+        server.insert(0x2000, 0x01, vec![0; 4]);
+        server.abort_on(0x2000, 0x02, 0x0601_0002);
+
+        // Segmented upload: size first, then 7-byte segments with alternating toggle.
+        let response = sdo_exchange(&mut server, &client, [0x40, 0x08, 0x10, 0, 0, 0, 0, 0]);
+        assert_eq!(response, [0x41, 0x08, 0x10, 0x00, 11, 0, 0, 0]);
+        let response = sdo_exchange(&mut server, &client, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, [0x00, b'm', b'o', b'c', b'k', b'-', b'd', b'e']);
+        let response = sdo_exchange(&mut server, &client, [0x70, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, [0x17, b'v', b'i', b'c', b'e', 0, 0, 0]);
+
+        // Segmented download replaces the object once the last segment arrives.
+        let response = sdo_exchange(&mut server, &client, [0x21, 0x00, 0x20, 0x01, 9, 0, 0, 0]);
+        assert_eq!(response, [0x60, 0x00, 0x20, 0x01, 0, 0, 0, 0]);
+        let response = sdo_exchange(&mut server, &client, [0x00, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(response[0], 0x20);
+        assert_eq!(server.get(0x2000, 0x01), Some(&[0; 4][..]));
+        let response = sdo_exchange(&mut server, &client, [0x1B, 8, 9, 0, 0, 0, 0, 0]);
+        assert_eq!(response[0], 0x30);
+        assert_eq!(
+            server.get(0x2000, 0x01),
+            Some(&[1, 2, 3, 4, 5, 6, 7, 8, 9][..])
+        );
+
+        // Expedited download of two bytes, then scripted and missing-object aborts.
+        sdo_exchange(
+            &mut server,
+            &client,
+            [0x2B, 0x00, 0x20, 0x01, 0xAA, 0xBB, 0, 0],
+        );
+        assert_eq!(server.get(0x2000, 0x01), Some(&[0xAA, 0xBB][..]));
+        let response = sdo_exchange(&mut server, &client, [0x40, 0x00, 0x20, 0x02, 0, 0, 0, 0]);
+        assert_eq!(response, [0x80, 0x00, 0x20, 0x02, 0x02, 0x00, 0x01, 0x06]);
+        let response = sdo_exchange(&mut server, &client, [0x40, 0x00, 0x30, 0x00, 0, 0, 0, 0]);
+        assert_eq!(&response[4..], ABORT_NO_OBJECT.to_le_bytes());
+    }
+
+    #[test]
+    fn sdo_server_uploads_empty_values_and_stays_silent_on_client_aborts() {
+        let bus = BusHandle::new();
+        let mut server = SdoServer::new(bus.add_interface(vec![]).unwrap(), 0x05);
+        let client = bus.add_interface(vec![]).unwrap();
+        client.set_self_reception(false);
+        server.insert(0x1008, 0x00, Vec::new());
+
+        // An empty value is a segmented upload of size 0 with a single, final segment.
+        let response = sdo_exchange(&mut server, &client, [0x40, 0x08, 0x10, 0, 0, 0, 0, 0]);
+        assert_eq!(response, [0x41, 0x08, 0x10, 0x00, 0, 0, 0, 0]);
+        let response = sdo_exchange(&mut server, &client, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, [0x0F, 0, 0, 0, 0, 0, 0, 0]);
+
+        // A client abort ends the transfer without a reply.
+        sdo_exchange(&mut server, &client, [0x40, 0x08, 0x10, 0, 0, 0, 0, 0]);
+        let id = StandardId::new(SDO_RX_BASE + 0x05).unwrap();
+        let abort = [0x80, 0x08, 0x10, 0x00, 0x00, 0x00, 0x04, 0x05];
+        client
+            .transmit(MockFrame::new(id, &abort).unwrap())
+            .unwrap();
+        assert_eq!(server.poll().unwrap(), 1);
+        assert!(!client.has_frames());
+        let response = sdo_exchange(&mut server, &client, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&response[4..], ABORT_COMMAND.to_le_bytes());
+    }
+}
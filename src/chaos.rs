@@ -0,0 +1,69 @@
+//! Reproducible, bus-wide fault injection.
+//!
+//! A [`ChaosConfig`] installed with [`BusHandle::set_chaos`](crate::BusHandle::set_chaos)
+//! randomly drops, duplicates, reorders, delays and corrupts traffic. Every random choice comes
+//! from one generator seeded with [`ChaosConfig::seed`] and is made at a point fixed by the
+//! sequence of bus operations and the virtual clock, never by wall-clock time or thread timing,
+//! so running the same test with the same seed reproduces exactly the same faults. Print the
+//! configuration (its [`Display`](core::fmt::Display) output includes the seed) when a chaos test
+//! fails, and replay it by pasting the seed back in.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::chaos::ChaosConfig;
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//!
+//! let run = |seed| {
+//!     let bus = BusHandle::new();
+//!     let tx = bus.add_interface(vec![]).unwrap();
+//!     let rx = bus.add_interface(vec![]).unwrap();
+//!     let chaos = ChaosConfig {
+//!         seed,
+//!         drop: 0.1,
+//!         dup: 0.1,
+//!         reorder: 0.2,
+//!         delay_jitter: Duration::from_micros(500),
+//!         corrupt: 0.05,
+//!     };
+//!     bus.set_chaos(Some(chaos));
+//!     for i in 0..100u8 {
+//!         tx.transmit(MockFrame::new(StandardId::new(0x100).unwrap(), &[i]).unwrap()).unwrap();
+//!         bus.advance(Duration::from_millis(1));
+//!     }
+//!     rx.drain_frames()
+//! };
+//! assert_eq!(run(7), run(7));
+//! assert_ne!(run(7), run(8));
+//! ```
+
+use core::{fmt, time::Duration};
+
+/// Probabilities and bounds of the faults injected by chaos mode. All default to zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChaosConfig {
+    /// Seed of the fault generator.
+    pub seed: u64,
+    /// Probability that a receiver loses a frame it would have accepted.
+    pub drop: f64,
+    /// Probability that a receiver queues a frame twice.
+    pub dup: f64,
+    /// Probability that a frame overtakes the frame queued just before it on a receiver.
+    pub reorder: f64,
+    /// Upper bound of a uniformly random delay added to each transmit's bus request.
+    pub delay_jitter: Duration,
+    /// Probability that a frame is corrupted on the wire; receivers report a
+    /// [`CrcError`](crate::BusEvent::CrcError) and the transmitter may retransmit.
+    pub corrupt: f64,
+}
+
+impl fmt::Display for ChaosConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chaos seed={} drop={} dup={} reorder={} delay_jitter={:?} corrupt={}",
+            self.seed, self.drop, self.dup, self.reorder, self.delay_jitter, self.corrupt
+        )
+    }
+}
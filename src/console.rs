@@ -0,0 +1,236 @@
+//! Interactive debug console.
+//!
+//! A [`Console`] attaches to a bus and answers one-line commands, so a developer can poke at a
+//! running integration test: inject frames, watch traffic, and inspect filters and load. Serve
+//! it on the terminal with [`run_stdio`](Console::run_stdio), or on a TCP port with
+//! [`serve_tcp`](Console::serve_tcp) and connect with `nc localhost <port>`.
+//!
+//! | command | effect |
+//! |---------|--------|
+//! | `send 123#AABB` | transmit a frame, in `cansend` notation |
+//! | `dump` | list the frames completed since the previous `dump` |
+//! | `filters` | list every interface with the IDs it accepts and per-filter hit counts |
+//! | `stats` | virtual time, interface count, load and memory use |
+//! | `help` | list the commands |
+//! | `quit` | end the session |
+//!
+//! ```
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::console::Console;
+//!
+//! let bus = BusHandle::new();
+//! let node = bus.add_interface(vec![]).unwrap();
+//! let console = Console::attach(&bus);
+//!
+//! assert_eq!(console.execute("send 123#AABB"), "sent 123#AABB");
+//! assert_eq!(node.pop_frame().unwrap().to_string(), "123#AABB");
+//! assert!(console.execute("dump").ends_with("123#AABB"));
+//! assert_eq!(console.execute("dump"), "no traffic");
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    thread,
+};
+
+use crate::{
+    bus::{BusHandle, InterfaceHandle},
+    frame::MockFrame,
+};
+
+const HELP: &str = "\
+send <id>#<data>  transmit a frame (cansend notation, e.g. send 123#AABB)
+dump              frames completed since the previous dump
+filters           interfaces, the IDs they accept and filter hits
+stats             time, interfaces, load and memory use
+quit              end the session";
+
+/// A command console attached to one bus.
+///
+/// The console owns two interfaces on the bus, named `console` (which transmits `send`
+/// commands without hearing itself) and `console tap` (a [tap](BusHandle::tap) collecting the
+/// traffic `dump` shows).
+pub struct Console {
+    bus: BusHandle,
+    sender: InterfaceHandle,
+    tap: InterfaceHandle,
+}
+
+impl Console {
+    /// Attach a console to `bus`; traffic is collected from now on.
+    pub fn attach(bus: &BusHandle) -> Self {
+        let sender = bus
+            .add_interface(Vec::new())
+            .expect("the console interface has no filters to reject");
+        sender.set_name("console");
+        sender.set_self_reception(false);
+        let tap = bus.tap();
+        tap.set_name("console tap");
+        Self {
+            bus: bus.clone(),
+            sender,
+            tap,
+        }
+    }
+
+    /// Run one command and return its output (without a trailing newline).
+    pub fn execute(&self, line: &str) -> String {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (None, ..) => String::new(),
+            (Some("help"), None, _) => HELP.into(),
+            (Some("send"), Some(frame), None) => self.send(frame),
+            (Some("dump"), None, _) => self.dump(),
+            (Some("filters"), None, _) => self.filters(),
+            (Some("stats"), None, _) => self.stats(),
+            (Some(command), ..) => format!("error: unknown command `{command}` (try `help`)"),
+        }
+    }
+
+    /// Serve commands read from `input`, writing a prompt and each reply to `output`, until
+    /// `quit` or the end of the input.
+    pub fn run(&self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "quit" | "exit") {
+                break;
+            }
+            let reply = self.execute(&line);
+            if !reply.is_empty() {
+                writeln!(output, "{reply}")?;
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Serve commands on the terminal.
+    pub fn run_stdio(&self) -> io::Result<()> {
+        self.run(io::stdin().lock(), io::stdout().lock())
+    }
+
+    /// Serve commands on a TCP port from a background thread, one connection at a time, and
+    /// return the bound address (bind port 0 to let the OS pick one).
+    ///
+    /// The thread runs until the process exits.
+    pub fn serve_tcp(self, addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+        thread::Builder::new()
+            .name(format!("can-console {local}"))
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Ok(reader) = stream.try_clone() {
+                        // A dropped connection only ends that session.
+                        let _ = self.run(BufReader::new(reader), stream);
+                    }
+                }
+            })?;
+        Ok(local)
+    }
+
+    fn send(&self, text: &str) -> String {
+        let frame = match MockFrame::parse_candump(text) {
+            Ok(frame) => frame,
+            Err(err) => return format!("error: invalid frame `{text}`: {err:?}"),
+        };
+        match self.sender.transmit(frame.clone()) {
+            Ok(_) => format!("sent {frame}"),
+            Err(err) => format!("error: transmit failed: {err:?}"),
+        }
+    }
+
+    fn dump(&self) -> String {
+        let lines: Vec<String> = core::iter::from_fn(|| self.tap.pop_delivery())
+            .map(|delivery| {
+                format!(
+                    "{:>12?}  node {:<3} {}",
+                    delivery.timestamp,
+                    delivery.source.as_raw(),
+                    delivery.frame
+                )
+            })
+            .collect();
+        if lines.is_empty() {
+            "no traffic".into()
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    fn filters(&self) -> String {
+        self.bus
+            .interface_filters()
+            .into_iter()
+            .map(|(id, name, set, stats)| {
+                let name = name.unwrap_or_else(|| "-".into());
+                let mut line = format!("node {:<3} {name:<12} {set}", id.as_raw());
+                if !stats.hits.is_empty() {
+                    line += &format!("  hits {:?}", stats.hits);
+                }
+                if stats.false_accepts > 0 {
+                    line += &format!(", {} false accepts", stats.false_accepts);
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn stats(&self) -> String {
+        let load = self.bus.load();
+        let memory = self.bus.memory_usage();
+        format!(
+            "time {:?}\ninterfaces {}\nin flight {}\nload {:.1}% (backlog {:?}, {} rejected)\n\
+             memory {} queued, {} history, ~{} bytes",
+            self.bus.now(),
+            self.bus.interface_count(),
+            self.bus.in_flight_count(),
+            load.occupancy * 100.0,
+            load.backlog,
+            load.rejected,
+            memory.queued,
+            memory.history,
+            memory.bytes,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+    use std::net::TcpStream;
+
+    use super::*;
+
+    #[test]
+    fn console_answers_commands_over_tcp() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(Vec::new()).unwrap();
+        node.set_name("ecu");
+        let console = Console::attach(&bus);
+        assert!(
+            console
+                .execute("send 12#ZZ")
+                .starts_with("error: invalid frame")
+        );
+        assert!(console.execute("frobnicate").contains("unknown command"));
+        assert!(console.execute("filters").contains("ecu"));
+        assert!(console.execute("stats").contains("interfaces 3"));
+
+        let addr = console.serve_tcp("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"send 7DF#0201\ndump\nquit\n").unwrap();
+        let mut transcript = String::new();
+        stream.read_to_string(&mut transcript).unwrap();
+
+        assert!(transcript.contains("sent 7DF#0201"), "{transcript}");
+        assert!(transcript.contains("7DF#0201\n> "), "{transcript}");
+        assert_eq!(node.pop_frame().unwrap().to_string(), "7DF#0201");
+    }
+}
@@ -0,0 +1,271 @@
+//! Pairing request frames with their responses.
+//!
+//! Request/response protocols (diagnostics, SDO, proprietary command sets) are tested by checking
+//! that every request got the right answer and nothing answered a request that was never made. A
+//! [`Conversation`] does the bookkeeping: it is fed the observed traffic and pairs requests with
+//! responses using [`CorrelationRule`]s, which say which response ID answers which request ID and
+//! which payload bytes must agree. Whatever cannot be paired is kept for assertions.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::MockFrame;
+//! use embedded_can_mock::conversation::{Conversation, CorrelationRule};
+//!
+//! let frame = |id, data: &[u8]| MockFrame::new(StandardId::new(id).unwrap(), data).unwrap();
+//! // UDS over ISO-TP single frames: the response SID is the request SID + 0x40.
+//! let mut conversation = Conversation::new()
+//!     .rule(CorrelationRule::new(StandardId::new(0x7E0).unwrap(), StandardId::new(0x7E8).unwrap())
+//!         .byte_plus(1, 1, 0x40));
+//!
+//! conversation.feed(&frame(0x7E0, &[0x02, 0x10, 0x03]));
+//! conversation.feed(&frame(0x7E0, &[0x02, 0x3E, 0x00]));
+//! conversation.feed(&frame(0x7E8, &[0x02, 0x7E, 0x00]));
+//! conversation.feed(&frame(0x7E8, &[0x03, 0x7F, 0x22, 0x31]));
+//!
+//! assert_eq!(conversation.exchanges().len(), 1);
+//! assert_eq!(conversation.exchanges()[0].request.data(), [0x02, 0x3E, 0x00]);
+//! assert_eq!(conversation.unmatched_requests().len(), 1);
+//! assert_eq!(conversation.unmatched_responses().len(), 1);
+//! assert!(!conversation.is_complete());
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, time::Duration};
+
+use embedded_can::{Frame, Id};
+
+use crate::{frame::MockFrame, recorder::TraceRecord};
+
+/// How a response is recognised as answering a request.
+///
+/// Without byte checks, any frame with the response ID answers the oldest outstanding request
+/// with the request ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationRule {
+    request: Id,
+    response: Id,
+    /// `(request byte, response byte, added value)`: the response byte must equal the request
+    /// byte plus the value (wrapping).
+    bytes: Vec<(usize, usize, u8)>,
+}
+
+impl CorrelationRule {
+    /// Responses with ID `response` answer requests with ID `request`.
+    pub fn new(request: impl Into<Id>, response: impl Into<Id>) -> Self {
+        Self {
+            request: request.into(),
+            response: response.into(),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Require response byte `response_index` to equal request byte `request_index`.
+    pub fn byte(self, request_index: usize, response_index: usize) -> Self {
+        self.byte_plus(request_index, response_index, 0)
+    }
+
+    /// Require response byte `response_index` to equal request byte `request_index` plus
+    /// `added` (wrapping), as in protocols that echo a command code with a flag set.
+    pub fn byte_plus(mut self, request_index: usize, response_index: usize, added: u8) -> Self {
+        self.bytes.push((request_index, response_index, added));
+        self
+    }
+
+    fn answers<F: Frame>(&self, request: &F, response: &F) -> bool {
+        request.id() == self.request
+            && response.id() == self.response
+            && self.bytes.iter().all(|&(req, resp, added)| {
+                match (request.data().get(req), response.data().get(resp)) {
+                    (Some(req), Some(resp)) => req.wrapping_add(added) == *resp,
+                    _ => false,
+                }
+            })
+    }
+}
+
+/// A request and the response that answered it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange<F = MockFrame> {
+    /// The request.
+    pub request: F,
+    /// The response.
+    pub response: F,
+    /// When the request completed, if fed with a time.
+    pub requested_at: Option<Duration>,
+    /// When the response completed, if fed with a time.
+    pub answered_at: Option<Duration>,
+}
+
+impl<F> Exchange<F> {
+    /// Time from request to response, if both were fed with a time.
+    pub fn latency(&self) -> Option<Duration> {
+        Some(self.answered_at?.saturating_sub(self.requested_at?))
+    }
+}
+
+/// Request/response bookkeeping over observed traffic (see the [module documentation](self)).
+#[derive(Debug, Clone)]
+pub struct Conversation<F = MockFrame> {
+    rules: Vec<CorrelationRule>,
+    outstanding: Vec<(F, Option<Duration>)>,
+    exchanges: Vec<Exchange<F>>,
+    unmatched_responses: Vec<F>,
+}
+
+impl<F: Frame + Clone> Default for Conversation<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Frame + Clone> Conversation<F> {
+    /// A conversation without rules; add them with [`rule`](Self::rule).
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            outstanding: Vec::new(),
+            exchanges: Vec::new(),
+            unmatched_responses: Vec::new(),
+        }
+    }
+
+    /// Add a correlation rule.
+    pub fn rule(mut self, rule: CorrelationRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Observe `frame` without a timestamp.
+    pub fn feed(&mut self, frame: &F) {
+        self.observe(frame, None);
+    }
+
+    /// Observe a recorded frame with its completion time; corrupted records are skipped.
+    pub fn feed_record(&mut self, record: &TraceRecord<F>) {
+        if record.error.is_none() {
+            self.observe(&record.frame, Some(record.at));
+        }
+    }
+
+    /// Observe every record of a [recording](crate::recorder::Recorder::records).
+    pub fn feed_records(&mut self, records: &[TraceRecord<F>]) {
+        records.iter().for_each(|record| self.feed_record(record));
+    }
+
+    /// A frame answering an outstanding request is paired with the oldest such request; a frame
+    /// with a rule's request ID becomes outstanding; a frame with only a response ID is an
+    /// unmatched response. Other frames are ignored.
+    fn observe(&mut self, frame: &F, at: Option<Duration>) {
+        let answered = self
+            .outstanding
+            .iter()
+            .position(|(request, _)| self.rules.iter().any(|rule| rule.answers(request, frame)));
+        if let Some(index) = answered {
+            let (request, requested_at) = self.outstanding.remove(index);
+            self.exchanges.push(Exchange {
+                request,
+                response: frame.clone(),
+                requested_at,
+                answered_at: at,
+            });
+        } else if self.rules.iter().any(|rule| rule.request == frame.id()) {
+            self.outstanding.push((frame.clone(), at));
+        } else if self.rules.iter().any(|rule| rule.response == frame.id()) {
+            self.unmatched_responses.push(frame.clone());
+        }
+    }
+
+    /// Requests paired with their responses, in response order.
+    pub fn exchanges(&self) -> &[Exchange<F>] {
+        &self.exchanges
+    }
+
+    /// Requests still waiting for a response, oldest first.
+    pub fn unmatched_requests(&self) -> Vec<&F> {
+        self.outstanding
+            .iter()
+            .map(|(request, _)| request)
+            .collect()
+    }
+
+    /// Frames with a response ID that answered no outstanding request.
+    pub fn unmatched_responses(&self) -> &[F] {
+        &self.unmatched_responses
+    }
+
+    /// Returns `true` if every request was answered and every response answered a request.
+    pub fn is_complete(&self) -> bool {
+        self.outstanding.is_empty() && self.unmatched_responses.is_empty()
+    }
+
+    /// Panic, listing what is unmatched, unless the conversation is
+    /// [complete](Self::is_complete).
+    #[track_caller]
+    pub fn assert_complete(&self)
+    where
+        F: fmt::Debug,
+    {
+        if !self.is_complete() {
+            let list = |frames: Vec<&F>| -> String {
+                frames
+                    .iter()
+                    .map(|frame| format!("\n  {frame:?}"))
+                    .collect()
+            };
+            panic!(
+                "conversation incomplete: {} exchange(s), {} unmatched request(s), {} unmatched \
+                 response(s)\nunmatched requests:{}\nunmatched responses:{}",
+                self.exchanges.len(),
+                self.outstanding.len(),
+                self.unmatched_responses.len(),
+                list(self.unmatched_requests()),
+                list(self.unmatched_responses.iter().collect()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusHandle, recorder::Recorder};
+    use embedded_can::{ExtendedId, StandardId};
+
+    #[test]
+    fn pairs_out_of_order_responses_by_payload_and_keeps_times() {
+        let bus = BusHandle::new();
+        let recorder = Recorder::attach(&bus);
+        let node = bus.add_interface(Vec::new()).unwrap();
+        let request: Id = ExtendedId::new(0x18DA_10F1).unwrap().into();
+        let response: Id = ExtendedId::new(0x18DA_F110).unwrap().into();
+        let frame = |id: Id, data: &[u8]| MockFrame::new(id, data).unwrap();
+        node.transmit(frame(request, &[1, 0xAA])).unwrap();
+        node.transmit(frame(request, &[2, 0xBB])).unwrap();
+        bus.advance(Duration::from_millis(3));
+        node.transmit(frame(response, &[2])).unwrap();
+        bus.advance(Duration::from_millis(2));
+        node.transmit(frame(response, &[1])).unwrap();
+        // Unrelated traffic is ignored.
+        node.transmit(frame(StandardId::new(0x1).unwrap().into(), &[1]))
+            .unwrap();
+
+        let mut conversation =
+            Conversation::new().rule(CorrelationRule::new(request, response).byte(0, 0));
+        conversation.feed_records(&recorder.records());
+        conversation.assert_complete();
+        let exchanges = conversation.exchanges();
+        assert_eq!(exchanges[0].request.data(), [2, 0xBB]);
+        assert_eq!(exchanges[0].latency(), Some(Duration::from_millis(3)));
+        assert_eq!(exchanges[1].request.data(), [1, 0xAA]);
+        assert_eq!(exchanges[1].latency(), Some(Duration::from_millis(5)));
+
+        conversation.feed(&frame(response, &[3]));
+        let message = std::panic::catch_unwind(|| conversation.assert_complete()).unwrap_err();
+        assert!(
+            message
+                .downcast_ref::<String>()
+                .unwrap()
+                .contains("2 exchange(s), 0 unmatched request(s), 1 unmatched response(s)")
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Devices with several CAN controllers, such as dual-CAN microcontrollers.
+//!
+//! A [`MockDevice`] owns one [`MockCan`] per channel. Channels may sit on separate buses or
+//! share one; either way the device keeps every bus it touches on a single virtual clock, so a
+//! driver coordinating several channels (a gateway, a redundant network) sees one consistent
+//! time base. The device also summarises its channels' fault-confinement states and merges
+//! their controller events, as a device-level status register would.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_interface::{BuilderBinding, RxFrameIo, TxFrameIo};
+//! use embedded_can_mock::device::MockDevice;
+//! use embedded_can_mock::timing::BusTiming;
+//! use embedded_can_mock::{BusHandle, MockCan, MockFrame};
+//!
+//! let (powertrain, body) = (BusHandle::new(), BusHandle::new());
+//! powertrain.set_timing(Some(BusTiming::new(500_000)));
+//! body.set_timing(Some(BusTiming::new(125_000)));
+//! let mut ecu = MockDevice::builder()
+//!     .channel(MockCan::builder().on_bus(&powertrain).name("can1"))
+//!     .channel(MockCan::builder().on_bus(&body).name("can2"))
+//!     .build()
+//!     .unwrap();
+//! let mut body_node = MockCan::new_with_bus(&body, vec![]).unwrap();
+//!
+//! let frame = MockFrame::new(StandardId::new(0x321).unwrap(), &[1, 2]).unwrap();
+//! ecu.channel_mut(1).send(&frame).unwrap();
+//! ecu.advance(Duration::from_millis(5));
+//! assert_eq!(powertrain.now(), body.now());
+//! assert_eq!(body_node.try_recv().unwrap(), frame);
+//! ```
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{BusEvent, BusHandle, ErrorState, MockBuilder, MockCan, MockError};
+
+/// A device owning several [`MockCan`] channels on a shared virtual clock.
+pub struct MockDevice {
+    channels: Vec<MockCan>,
+    /// Distinct buses the channels are attached to, in first-use order.
+    buses: Vec<BusHandle>,
+    /// Channel whose events [`poll_event`](Self::poll_event) looks at first.
+    next_event: usize,
+}
+
+/// Builder for a [`MockDevice`], created by [`MockDevice::builder`].
+#[derive(Default)]
+pub struct MockDeviceBuilder {
+    channels: Vec<MockBuilder>,
+}
+
+impl MockDeviceBuilder {
+    /// Add a channel built from `builder`; channels are numbered in the order they are added.
+    pub fn channel(mut self, builder: MockBuilder) -> Self {
+        self.channels.push(builder);
+        self
+    }
+
+    /// Build every channel and bring their buses to a common virtual time (the latest of their
+    /// clocks).
+    pub fn build(self) -> Result<MockDevice, MockError> {
+        let channels = self
+            .channels
+            .into_iter()
+            .map(MockBuilder::build)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut buses: Vec<BusHandle> = Vec::new();
+        for channel in &channels {
+            if !buses.iter().any(|bus| bus.ptr_eq(&channel.bus)) {
+                buses.push(channel.bus.clone());
+            }
+        }
+        let device = MockDevice {
+            channels,
+            buses,
+            next_event: 0,
+        };
+        device.advance_to(device.now());
+        Ok(device)
+    }
+}
+
+impl MockDevice {
+    /// Start describing a device.
+    pub fn builder() -> MockDeviceBuilder {
+        MockDeviceBuilder::default()
+    }
+
+    /// Number of channels.
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Channel `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn channel(&self, index: usize) -> &MockCan {
+        &self.channels[index]
+    }
+
+    /// Channel `index`, for sending and receiving.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn channel_mut(&mut self, index: usize) -> &mut MockCan {
+        &mut self.channels[index]
+    }
+
+    /// All channels, in the order they were added.
+    pub fn channels_mut(&mut self) -> &mut [MockCan] {
+        &mut self.channels
+    }
+
+    /// The distinct buses the channels are attached to.
+    pub fn buses(&self) -> &[BusHandle] {
+        &self.buses
+    }
+
+    /// The device's virtual time.
+    pub fn now(&self) -> Duration {
+        self.buses
+            .iter()
+            .map(BusHandle::now)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Advance every bus the device is attached to by `by`, keeping them in lockstep.
+    ///
+    /// Buses whose clock was moved independently are brought up to the device's time first.
+    pub fn advance(&self, by: Duration) {
+        self.advance_to(self.now() + by);
+    }
+
+    fn advance_to(&self, at: Duration) {
+        for bus in &self.buses {
+            bus.advance(at.saturating_sub(bus.now()));
+        }
+    }
+
+    /// Fault-confinement state of each channel.
+    pub fn error_states(&self) -> Vec<ErrorState> {
+        self.channels
+            .iter()
+            .map(|channel| channel.iface.error_counters().state())
+            .collect()
+    }
+
+    /// Device-level error state: the worst state of any channel.
+    pub fn error_state(&self) -> ErrorState {
+        let severity = |state: &ErrorState| match state {
+            ErrorState::Active => 0,
+            ErrorState::Passive => 1,
+            ErrorState::BusOff => 2,
+        };
+        self.error_states()
+            .into_iter()
+            .max_by_key(severity)
+            .unwrap_or(ErrorState::Active)
+    }
+
+    /// Take the next pending controller event of any channel, together with the channel index.
+    ///
+    /// Channels are polled round-robin, so a channel with a burst of events cannot hide the
+    /// others'.
+    pub fn poll_event(&mut self) -> Option<(usize, BusEvent)> {
+        let count = self.channels.len();
+        for offset in 0..count {
+            let index = (self.next_event + offset) % count;
+            if let Some(event) = self.channels[index].iface.pop_event() {
+                self.next_event = (index + 1) % count;
+                return Some((index, event));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockFrame, timing::BusTiming};
+    use embedded_can::{Frame as _, StandardId};
+    use embedded_can_interface::{BuilderBinding, RxFrameIo, TxFrameIo};
+
+    #[test]
+    fn channels_share_clock_and_report_combined_errors() {
+        let (a, b) = (BusHandle::new(), BusHandle::new());
+        a.set_timing(Some(BusTiming::new(500_000)));
+        b.advance(Duration::from_millis(3));
+        let mut device = MockDevice::builder()
+            .channel(MockCan::builder().on_bus(&a))
+            .channel(MockCan::builder().on_bus(&b))
+            .channel(MockCan::builder().on_bus(&b).self_reception(false))
+            .build()
+            .unwrap();
+        assert_eq!(device.channel_count(), 3);
+        assert_eq!(device.buses().len(), 2);
+        assert_eq!(a.now(), Duration::from_millis(3));
+
+        // Channels on the same bus talk to each other directly.
+        let frame = MockFrame::new(StandardId::new(0x55).unwrap(), &[9]).unwrap();
+        device.channel_mut(2).send(&frame).unwrap();
+        assert_eq!(device.channel_mut(1).try_recv().unwrap(), frame);
+        device.advance(Duration::from_millis(2));
+        assert_eq!(device.now(), Duration::from_millis(5));
+        assert_eq!(a.now(), b.now());
+
+        device.channel(0).interface().set_auto_retransmit(false);
+        for _ in 0..16 {
+            a.corrupt_next(BusEvent::BitError);
+            device.channel_mut(0).send(&frame).unwrap();
+            device.advance(Duration::from_millis(1));
+        }
+        assert_eq!(
+            device.error_states(),
+            vec![ErrorState::Passive, ErrorState::Active, ErrorState::Active]
+        );
+        assert_eq!(device.error_state(), ErrorState::Passive);
+
+        device
+            .channel(1)
+            .interface()
+            .inject_event(BusEvent::ErrorFrame);
+        device.channel(1).interface().inject_event(BusEvent::WakeUp);
+        let (first, _) = device.poll_event().unwrap();
+        let (second, _) = device.poll_event().unwrap();
+        assert_eq!((first, second), (0, 1));
+    }
+}
@@ -0,0 +1,422 @@
+//! End-to-end (E2E) protection with CRC8 checksums and alive counters.
+//!
+//! Safety-related messages often carry their own checksum and counter, in the style of the
+//! AUTOSAR E2E profiles, so that receivers can detect corrupted, repeated or lost data that the
+//! CAN CRC does not catch. Here a protected frame carries its payload followed by an alive
+//! counter byte and a CRC8 byte. The CRC covers the frame ID, the payload and the counter, so a
+//! frame copied onto another ID fails the check as well.
+//!
+//! [`E2eProfile::wrap`] protects an interface through [middleware](crate::middleware). Frames it
+//! transmits on a protected ID get the next counter value and the CRC appended. Protected frames
+//! it receives are checked and queued with the counter and CRC stripped. Failed checks are
+//! reported as [`BusEvent::E2e`] events on that interface.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::e2e::{E2eError, E2eProfile};
+//! use embedded_can_mock::{BusEvent, BusHandle, MockFrame};
+//!
+//! let (status, command) = (StandardId::new(0x0C9).unwrap(), StandardId::new(0x0CA).unwrap());
+//! let profile = E2eProfile::PROFILE_1;
+//! let bus = BusHandle::new();
+//! let ecu = bus.add_interface(vec![]).unwrap();
+//! let tester = bus.add_interface(vec![]).unwrap();
+//! profile.wrap(&tester, [status.into(), command.into()]);
+//!
+//! // The ECU's frames are verified on arrival. Its counter skips from 0 to 2.
+//! ecu.transmit(profile.protect(status, &[1], 0).unwrap()).unwrap();
+//! ecu.transmit(profile.protect(status, &[2], 2).unwrap()).unwrap();
+//! assert_eq!(tester.pop_frame(), Some(MockFrame::new(status, &[1]).unwrap()));
+//! assert_eq!(tester.pop_frame(), Some(MockFrame::new(status, &[2]).unwrap()));
+//! let Some(BusEvent::E2e(violation)) = tester.pop_event() else { panic!() };
+//! assert_eq!(violation.error, E2eError::Skipped { expected: 1, found: 2 });
+//!
+//! // The tester's own frames go out protected.
+//! tester.transmit(MockFrame::new(command, &[0x12, 0x34]).unwrap()).unwrap();
+//! let sent = ecu.received_frames().pop().unwrap();
+//! assert_eq!(profile.check(&sent), Ok((vec![0x12, 0x34], 0)));
+//! ```
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::fmt;
+
+use embedded_can::{Frame, Id};
+
+use crate::{
+    bus::InterfaceHandle,
+    event::BusEvent,
+    frame::MockFrame,
+    middleware::MiddlewareAction,
+    sync::{Mutex, lock},
+};
+
+/// CRC8 variant of an [`E2eProfile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc8 {
+    /// SAE J1850: polynomial 0x1D, initial value and final XOR 0xFF.
+    SaeJ1850,
+    /// AUTOSAR CRC8H2F: polynomial 0x2F, initial value and final XOR 0xFF.
+    H2f,
+}
+
+impl Crc8 {
+    /// Checksum of `data`.
+    ///
+    /// ```
+    /// use embedded_can_mock::e2e::Crc8;
+    ///
+    /// assert_eq!(Crc8::SaeJ1850.checksum(b"123456789"), 0x4B);
+    /// assert_eq!(Crc8::H2f.checksum(b"123456789"), 0xDF);
+    /// ```
+    pub fn checksum(self, data: &[u8]) -> u8 {
+        let poly = match self {
+            Crc8::SaeJ1850 => 0x1D,
+            Crc8::H2f => 0x2F,
+        };
+        let crc = data.iter().fold(0xFF_u8, |crc, byte| {
+            (0..8).fold(crc ^ byte, |crc, _| {
+                if crc & 0x80 != 0 {
+                    (crc << 1) ^ poly
+                } else {
+                    crc << 1
+                }
+            })
+        });
+        crc ^ 0xFF
+    }
+}
+
+/// Why a protected frame failed its E2E check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum E2eError {
+    /// The frame is too short to hold the counter and CRC.
+    Truncated,
+    /// The CRC does not match (corrupted frame, or one sent on the wrong ID).
+    CrcMismatch {
+        /// CRC computed over the received frame.
+        expected: u8,
+        /// CRC the frame carried.
+        found: u8,
+    },
+    /// The counter did not advance: the frame was repeated.
+    Repeated(u8),
+    /// The counter advanced by more than allowed (frames were lost) or is out of range.
+    Skipped {
+        /// Counter value following the last one accepted.
+        expected: u8,
+        /// Counter value the frame carried.
+        found: u8,
+    },
+}
+
+impl fmt::Display for E2eError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            E2eError::Truncated => f.write_str("frame too short for E2E counter and CRC"),
+            E2eError::CrcMismatch { expected, found } => {
+                write!(f, "E2E CRC {found:02X} does not match {expected:02X}")
+            }
+            E2eError::Repeated(counter) => write!(f, "E2E counter {counter} repeated"),
+            E2eError::Skipped { expected, found } => {
+                write!(f, "E2E counter jumped to {found}, expected {expected}")
+            }
+        }
+    }
+}
+
+/// A protected frame that failed its check, reported as [`BusEvent::E2e`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct E2eViolation {
+    /// ID of the offending frame.
+    pub id: Id,
+    /// Why the check failed.
+    pub error: E2eError,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for E2eViolation {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self.id {
+            Id::Standard(id) => defmt::write!(f, "{=u16:03X}: {}", id.as_raw(), self.error),
+            Id::Extended(id) => defmt::write!(f, "{=u32:08X}: {}", id.as_raw(), self.error),
+        }
+    }
+}
+
+/// Alive counter of one protected ID, for the sending or the receiving side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AliveCounter {
+    max: u8,
+    max_delta: u8,
+    last: Option<u8>,
+}
+
+impl AliveCounter {
+    /// A counter running from 0 to `max` and wrapping; receivers tolerate steps of up to
+    /// `max_delta` (at least 1).
+    pub fn new(max: u8, max_delta: u8) -> Self {
+        Self {
+            max,
+            max_delta: max_delta.max(1),
+            last: None,
+        }
+    }
+
+    /// The next value to send: 0 first, then one more than the previous value.
+    pub fn next_value(&mut self) -> u8 {
+        let value = self.last.map_or(0, |last| self.successor(last));
+        self.last = Some(value);
+        value
+    }
+
+    /// Accept the received value `counter`. The first value is always accepted; after a skip
+    /// the counter resynchronises to the received value.
+    pub fn check(&mut self, counter: u8) -> Result<(), E2eError> {
+        let expected = self.last.map_or(0, |last| self.successor(last));
+        if counter > self.max {
+            return Err(E2eError::Skipped {
+                expected,
+                found: counter,
+            });
+        }
+        let Some(last) = self.last else {
+            self.last = Some(counter);
+            return Ok(());
+        };
+        let span = u16::from(self.max) + 1;
+        let delta = (u16::from(counter) + span - u16::from(last)) % span;
+        if delta == 0 {
+            return Err(E2eError::Repeated(counter));
+        }
+        self.last = Some(counter);
+        if delta > u16::from(self.max_delta) {
+            return Err(E2eError::Skipped {
+                expected,
+                found: counter,
+            });
+        }
+        Ok(())
+    }
+
+    fn successor(&self, value: u8) -> u8 {
+        if value >= self.max { 0 } else { value + 1 }
+    }
+}
+
+/// E2E protection profile: CRC variant and alive counter behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct E2eProfile {
+    /// Checksum over the frame ID, payload and counter.
+    pub crc: Crc8,
+    /// Largest counter value; the counter wraps to 0 after it.
+    pub counter_max: u8,
+    /// Largest counter step a receiver accepts without reporting lost frames.
+    pub max_delta: u8,
+}
+
+impl E2eProfile {
+    /// CRC8 SAE J1850 with a counter from 0 to 14, like AUTOSAR E2E profile 1.
+    pub const PROFILE_1: Self = Self {
+        crc: Crc8::SaeJ1850,
+        counter_max: 14,
+        max_delta: 1,
+    };
+
+    /// CRC8H2F with a counter from 0 to 15, like AUTOSAR E2E profile 2.
+    pub const PROFILE_2: Self = Self {
+        crc: Crc8::H2f,
+        counter_max: 15,
+        max_delta: 1,
+    };
+
+    /// Number of bytes the counter and CRC add to each payload.
+    pub const OVERHEAD: usize = 2;
+
+    /// A fresh alive counter following this profile.
+    pub fn counter(&self) -> AliveCounter {
+        AliveCounter::new(self.counter_max, self.max_delta)
+    }
+
+    /// Build the protected frame for `payload` with counter value `counter`, or `None` if the
+    /// result exceeds the 64 bytes of a CAN FD frame.
+    pub fn protect(&self, id: impl Into<Id>, payload: &[u8], counter: u8) -> Option<MockFrame> {
+        if payload.len() + Self::OVERHEAD > 64 {
+            return None;
+        }
+        let id = id.into();
+        let mut data = payload.to_vec();
+        data.push(counter);
+        data.push(self.crc(id, &data));
+        MockFrame::new(id, &data)
+    }
+
+    /// Check the CRC of `frame`, returning its payload and counter value. Counter continuity is
+    /// up to an [`AliveCounter`].
+    pub fn check(&self, frame: &MockFrame) -> Result<(Vec<u8>, u8), E2eError> {
+        let data = frame.data();
+        let payload_len = data
+            .len()
+            .checked_sub(Self::OVERHEAD)
+            .ok_or(E2eError::Truncated)?;
+        let expected = self.crc(frame.id(), &data[..=payload_len]);
+        let found = data[payload_len + 1];
+        if expected != found {
+            return Err(E2eError::CrcMismatch { expected, found });
+        }
+        Ok((data[..payload_len].to_vec(), data[payload_len]))
+    }
+
+    /// Protect the frames `interface` sends and receives on the IDs in `protected`, with one
+    /// alive counter per ID and direction (see the [module docs](self)).
+    ///
+    /// Transmitted frames too long to [protect](Self::protect) are dropped.
+    ///
+    /// Received frames that fail a check are still queued (stripped, unless too short), so the
+    /// test sees what arrived next to the [`BusEvent::E2e`] event. A frame with a bad CRC does
+    /// not advance the receive counter.
+    pub fn wrap(&self, interface: &InterfaceHandle, protected: impl IntoIterator<Item = Id>) {
+        let profile = *self;
+        let ids: Vec<Id> = protected.into_iter().collect();
+        let counters = |ids: &[Id]| {
+            let counters: BTreeMap<Id, AliveCounter> =
+                ids.iter().map(|id| (*id, profile.counter())).collect();
+            Arc::new(Mutex::new(counters))
+        };
+
+        let tx = counters(&ids);
+        interface.on_tx(move |frame: MockFrame| {
+            let mut tx = lock(&tx);
+            let Some(counter) = tx.get_mut(&frame.id()) else {
+                return Some(frame);
+            };
+            profile.protect(frame.id(), frame.data(), counter.next_value())
+        });
+
+        let rx = counters(&ids);
+        interface.on_rx(move |frame: MockFrame| {
+            let mut rx = lock(&rx);
+            let Some(counter) = rx.get_mut(&frame.id()) else {
+                return MiddlewareAction::Pass(frame);
+            };
+            let id = frame.id();
+            let report = |frame, error| {
+                MiddlewareAction::Report(frame, BusEvent::E2e(E2eViolation { id, error }))
+            };
+            match profile.check(&frame) {
+                Ok((payload, value)) => {
+                    let stripped = MockFrame::new(id, &payload).unwrap();
+                    match counter.check(value) {
+                        Ok(()) => MiddlewareAction::Pass(stripped),
+                        Err(error) => report(stripped, error),
+                    }
+                }
+                Err(E2eError::Truncated) => report(frame, E2eError::Truncated),
+                Err(error) => {
+                    let payload_len = frame.data().len() - Self::OVERHEAD;
+                    report(
+                        MockFrame::new(id, &frame.data()[..payload_len]).unwrap(),
+                        error,
+                    )
+                }
+            }
+        });
+    }
+
+    fn crc(&self, id: Id, data: &[u8]) -> u8 {
+        let raw = match id {
+            Id::Standard(id) => u32::from(id.as_raw()),
+            Id::Extended(id) => id.as_raw(),
+        };
+        let mut input = raw.to_be_bytes().to_vec();
+        input.extend_from_slice(data);
+        self.crc.checksum(&input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+    use embedded_can::StandardId;
+
+    #[test]
+    fn counters_wrap_and_flag_repeats_skips_and_bad_crcs() {
+        let mut tx = E2eProfile::PROFILE_1.counter();
+        let sent: Vec<u8> = (0..17).map(|_| tx.next_value()).collect();
+        assert_eq!(sent[14..], [14, 0, 1]);
+
+        let mut rx = AliveCounter::new(15, 2);
+        assert_eq!(rx.check(15), Ok(()));
+        assert_eq!(rx.check(1), Ok(()));
+        assert_eq!(rx.check(1), Err(E2eError::Repeated(1)));
+        assert_eq!(
+            rx.check(5),
+            Err(E2eError::Skipped {
+                expected: 2,
+                found: 5
+            })
+        );
+        assert_eq!(rx.check(6), Ok(()));
+        assert_eq!(
+            rx.check(16),
+            Err(E2eError::Skipped {
+                expected: 7,
+                found: 16
+            })
+        );
+
+        let profile = E2eProfile::PROFILE_2;
+        let id = StandardId::new(0x10).unwrap();
+        let bus = BusHandle::new();
+        let ecu = bus.add_interface(vec![]).unwrap();
+        let tester = bus.add_interface(vec![]).unwrap();
+        profile.wrap(&tester, [id.into()]);
+
+        let good = profile.protect(id, &[0xAA], 3).unwrap();
+        let mut corrupted = good.data().to_vec();
+        corrupted[0] ^= 0x01;
+        ecu.transmit(MockFrame::new(id, &corrupted).unwrap())
+            .unwrap();
+        ecu.transmit(MockFrame::new(id, &[0]).unwrap()).unwrap();
+        ecu.transmit(good).unwrap();
+        let other = MockFrame::new(StandardId::new(0x11).unwrap(), &[1, 2, 3]).unwrap();
+        ecu.transmit(other.clone()).unwrap();
+
+        assert_eq!(
+            tester.received_frames(),
+            [
+                MockFrame::new(id, &[0xAB]).unwrap(),
+                MockFrame::new(id, &[0]).unwrap(),
+                MockFrame::new(id, &[0xAA]).unwrap(),
+                other,
+            ]
+        );
+        let errors: Vec<E2eError> = tester
+            .pending_events()
+            .into_iter()
+            .map(|event| match event {
+                BusEvent::E2e(violation) => violation.error,
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert!(matches!(
+            errors[..],
+            [E2eError::CrcMismatch { .. }, E2eError::Truncated]
+        ));
+
+        // Protection must still fit a CAN FD frame; longer transmits are dropped.
+        assert_eq!(profile.protect(id, &[0; 62], 0).unwrap().data().len(), 64);
+        assert_eq!(profile.protect(id, &[0; 63], 0), None);
+        ecu.drain_frames();
+        tester
+            .transmit(MockFrame::new(id, &[0; 63]).unwrap())
+            .unwrap();
+        tester
+            .transmit(MockFrame::new(id, &[0; 62]).unwrap())
+            .unwrap();
+        let sent = ecu.drain_frames();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].data().len(), 64);
+    }
+}
@@ -0,0 +1,125 @@
+//! Out-of-band bus events.
+//!
+//! Besides frames, each interface has an event queue carrying conditions a real controller would
+//! report through status registers or error interrupts: CRC and stuff errors, error frames, and
+//! so on, plus violations found by [E2E protection](crate::e2e). Events are queued by fault
+//! injection (for example
+//! [`BusHandle::corrupt_next`](crate::BusHandle::corrupt_next)) and consumed with
+//! [`InterfaceHandle::pop_event`](crate::InterfaceHandle::pop_event).
+
+use crate::{bus::InterfaceId, e2e::E2eViolation};
+
+/// Event reported to an interface alongside its frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum BusEvent {
+    /// A received frame failed its CRC check.
+    CrcError,
+    /// A received frame violated the bit-stuffing rule.
+    StuffError,
+    /// A received frame had a fixed-form field with an illegal value.
+    FormError,
+    /// The transmitter read back a different bit than it sent.
+    BitError,
+    /// The transmitter saw no acknowledgement.
+    AckError,
+    /// An error frame was observed on the bus.
+    ErrorFrame,
+    /// A sleeping interface was woken by bus traffic.
+    WakeUp,
+    /// A frame was lost because the receive queue was full.
+    RxOverflow,
+    /// An interface left the bus: it was [detached](crate::InterfaceHandle::detach) or its last
+    /// handle was dropped. Reported to taps, and to every other node if the bus
+    /// [announces departures](crate::BusHandle::set_announce_departures).
+    NodeLeft(InterfaceId),
+    /// A frame on an [E2E-protected](crate::e2e) ID failed its CRC or counter check.
+    E2e(E2eViolation),
+}
+
+impl BusEvent {
+    /// Returns `true` if the error is detected by the transmitter rather than the receivers.
+    pub fn is_transmitter_error(&self) -> bool {
+        matches!(self, BusEvent::BitError | BusEvent::AckError)
+    }
+}
+
+/// Transmit / receive error counters of an interface, following the CAN fault-confinement rules.
+///
+/// Receive errors increment `rec` by 1 and transmit errors increment `tec` by 8; every
+/// successfully received frame decrements `rec` and every successfully transmitted frame
+/// decrements `tec` by 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorCounters {
+    /// Transmit error counter.
+    pub tec: u16,
+    /// Receive error counter.
+    pub rec: u16,
+}
+
+/// Fault-confinement state derived from [`ErrorCounters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorState {
+    /// Both counters below 128.
+    Active,
+    /// Either counter at 128 or above (but `tec` below 256).
+    Passive,
+    /// `tec` reached 256.
+    BusOff,
+}
+
+impl ErrorCounters {
+    /// Fault-confinement state for these counters.
+    pub fn state(&self) -> ErrorState {
+        if self.tec >= 256 {
+            ErrorState::BusOff
+        } else if self.tec >= 128 || self.rec >= 128 {
+            ErrorState::Passive
+        } else {
+            ErrorState::Active
+        }
+    }
+
+    pub(crate) fn receive_error(&mut self) {
+        self.rec = self.rec.saturating_add(1);
+    }
+
+    pub(crate) fn transmit_error(&mut self) {
+        self.tec = self.tec.saturating_add(8);
+    }
+
+    pub(crate) fn receive_ok(&mut self) {
+        self.rec = self.rec.saturating_sub(1);
+    }
+
+    pub(crate) fn transmit_ok(&mut self) {
+        self.tec = self.tec.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_follow_fault_confinement_thresholds() {
+        let mut counters = ErrorCounters::default();
+        assert_eq!(counters.state(), ErrorState::Active);
+        for _ in 0..16 {
+            counters.transmit_error();
+        }
+        assert_eq!(counters.state(), ErrorState::Passive);
+        for _ in 0..16 {
+            counters.transmit_error();
+        }
+        assert_eq!(counters.state(), ErrorState::BusOff);
+
+        let mut counters = ErrorCounters { tec: 0, rec: 128 };
+        assert_eq!(counters.state(), ErrorState::Passive);
+        counters.receive_ok();
+        assert_eq!(counters.state(), ErrorState::Active);
+    }
+}
@@ -0,0 +1,434 @@
+//! C API for embedding the mock bus in C test harnesses.
+//!
+//! The functions below are exported unmangled, and `include/embedded_can_mock.h` declares them
+//! (regenerate it with `cbindgen --config cbindgen.toml --output include/embedded_can_mock.h`
+//! after changing this module). Build a library to link against with
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib   # or cdylib
+//! ```
+//!
+//! Buses and interfaces are opaque handles owned by the caller and released with
+//! [`can_mock_bus_free`] and [`can_mock_interface_free`]. A C test and a Rust test in the same
+//! process share a bus through its name ([`can_mock_bus_named`] and
+//! [`BusHandle::named`](crate::BusHandle::named)). Functions that can fail return a
+//! [`CanMockStatus`]; null handles are reported as [`CanMockStatus::InvalidArgument`] rather
+//! than dereferenced.
+//!
+//! ```c
+//! CanMockBus *bus = can_mock_bus_new();
+//! CanMockInterface *a = can_mock_interface_new(bus);
+//! CanMockInterface *b = can_mock_interface_new(bus);
+//! CanMockFrame frame = { .id = 0x123, .len = 2, .data = { 0xAA, 0xBB } };
+//! assert(can_mock_send(a, &frame) == CAN_MOCK_STATUS_OK);
+//! assert(can_mock_recv(b, &frame, 0) == CAN_MOCK_STATUS_OK);
+//! can_mock_interface_free(b);
+//! can_mock_interface_free(a);
+//! can_mock_bus_free(bus);
+//! ```
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{ffi::CStr, ffi::c_char, time::Duration};
+
+use embedded_can::{ExtendedId, Frame as _, Id, StandardId};
+use embedded_can_interface::{IdMask, IdMaskFilter};
+
+use crate::{bus::BusHandle, bus::InterfaceHandle, frame::MockFrame};
+
+/// Result of a C API call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanMockStatus {
+    /// Success.
+    Ok = 0,
+    /// No frame arrived (immediately, or within the timeout).
+    Empty = 1,
+    /// A null pointer, an out-of-range ID, or an invalid length or filter.
+    InvalidArgument = -1,
+    /// The bus refused the transmission (listen-only, overloaded, rejected by a policy, ...).
+    TransmitFailed = -2,
+    /// The interface or bus was closed.
+    Closed = -3,
+    /// The received frame does not fit a [`CanMockFrame`] (a CAN XL payload over 64 bytes); it
+    /// was removed from the queue.
+    FrameTooLarge = -4,
+}
+
+/// A classic or CAN FD frame.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanMockFrame {
+    /// Raw 11-bit or 29-bit ID.
+    pub id: u32,
+    /// `id` is a 29-bit extended ID.
+    pub extended: bool,
+    /// Remote frame: `len` is the requested DLC and `data` is unused.
+    pub remote: bool,
+    /// CAN FD frame.
+    pub fd: bool,
+    /// Payload length, up to 8 (64 for CAN FD frames).
+    pub len: u8,
+    /// Payload; bytes past `len` are ignored on send and zeroed on receive.
+    pub data: [u8; 64],
+}
+
+/// An acceptance filter: a frame is accepted when `frame.id & mask == id & mask`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanMockFilter {
+    /// Raw ID to match.
+    pub id: u32,
+    /// Bits of the ID that must match.
+    pub mask: u32,
+    /// Match extended (29-bit) rather than standard frames.
+    pub extended: bool,
+}
+
+/// Opaque handle to a bus.
+pub struct CanMockBus(BusHandle);
+
+/// Opaque handle to an interface on a bus.
+pub struct CanMockInterface(InterfaceHandle);
+
+fn make_id(id: u32, extended: bool) -> Option<Id> {
+    if extended {
+        ExtendedId::new(id).map(Id::Extended)
+    } else {
+        u16::try_from(id)
+            .ok()
+            .and_then(StandardId::new)
+            .map(Id::Standard)
+    }
+}
+
+impl CanMockFrame {
+    fn to_frame(self) -> Option<MockFrame> {
+        let id = make_id(self.id, self.extended)?;
+        let len = usize::from(self.len);
+        if len > if self.fd { 64 } else { 8 } {
+            return None;
+        }
+        let mut frame = if self.remote {
+            MockFrame::new_remote(id, len)?
+        } else {
+            MockFrame::new(id, &self.data[..len])?
+        };
+        if self.fd {
+            frame.meta_mut().fd = true;
+        }
+        Some(frame)
+    }
+
+    /// `None` if the payload (or requested DLC) exceeds 64 bytes.
+    fn from_frame(frame: &MockFrame) -> Option<Self> {
+        let (id, extended) = match frame.id() {
+            Id::Standard(id) => (u32::from(id.as_raw()), false),
+            Id::Extended(id) => (id.as_raw(), true),
+        };
+        let len = if frame.is_remote_frame() {
+            frame.dlc()
+        } else {
+            frame.data().len()
+        };
+        if len > 64 {
+            return None;
+        }
+        let mut data = [0; 64];
+        data[..frame.data().len()].copy_from_slice(frame.data());
+        Some(Self {
+            id,
+            extended,
+            remote: frame.is_remote_frame(),
+            fd: frame.meta().fd || frame.data().len() > 8,
+            len: len as u8,
+            data,
+        })
+    }
+}
+
+/// Create a bus.
+#[unsafe(no_mangle)]
+pub extern "C" fn can_mock_bus_new() -> *mut CanMockBus {
+    Box::into_raw(Box::new(CanMockBus(BusHandle::new())))
+}
+
+/// Open the bus registered under `name` (see [`BusHandle::named`]), or null if `name` is null
+/// or not UTF-8.
+///
+/// # Safety
+///
+/// `name` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_bus_named(name: *const c_char) -> *mut CanMockBus {
+    if name.is_null() {
+        return core::ptr::null_mut();
+    }
+    // SAFETY: the caller passes a valid NUL-terminated string.
+    match unsafe { CStr::from_ptr(name) }.to_str() {
+        Ok(name) => Box::into_raw(Box::new(CanMockBus(BusHandle::named(name)))),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Release a bus handle. Interfaces stay attached until they are freed themselves.
+///
+/// # Safety
+///
+/// `bus` must be null or a handle returned by this API that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_bus_free(bus: *mut CanMockBus) {
+    if !bus.is_null() {
+        // SAFETY: the caller passes an unfreed handle created by `Box::into_raw`.
+        drop(unsafe { Box::from_raw(bus) });
+    }
+}
+
+/// Virtual time in microseconds (0 for a null bus).
+///
+/// # Safety
+///
+/// `bus` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_bus_now_us(bus: *const CanMockBus) -> u64 {
+    // SAFETY: the caller passes null or a live handle.
+    unsafe { bus.as_ref() }.map_or(0, |bus| bus.0.now().as_micros() as u64)
+}
+
+/// Advance virtual time by `us` microseconds, delivering frames that become due.
+///
+/// # Safety
+///
+/// `bus` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_bus_advance_us(bus: *const CanMockBus, us: u64) -> CanMockStatus {
+    // SAFETY: the caller passes null or a live handle.
+    let Some(bus) = (unsafe { bus.as_ref() }) else {
+        return CanMockStatus::InvalidArgument;
+    };
+    bus.0.advance(Duration::from_micros(us));
+    CanMockStatus::Ok
+}
+
+/// Attach a new interface that accepts every frame, or return null if `bus` is null or full.
+///
+/// # Safety
+///
+/// `bus` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_interface_new(bus: *const CanMockBus) -> *mut CanMockInterface {
+    // SAFETY: the caller passes null or a live handle.
+    match unsafe { bus.as_ref() }.map(|bus| bus.0.add_interface(Vec::new())) {
+        Some(Ok(iface)) => Box::into_raw(Box::new(CanMockInterface(iface))),
+        _ => core::ptr::null_mut(),
+    }
+}
+
+/// Detach and release an interface.
+///
+/// # Safety
+///
+/// `iface` must be null or a handle returned by this API that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_interface_free(iface: *mut CanMockInterface) {
+    if !iface.is_null() {
+        // SAFETY: the caller passes an unfreed handle created by `Box::into_raw`.
+        drop(unsafe { Box::from_raw(iface) });
+    }
+}
+
+/// Process-wide unique ID of an interface (`u64::MAX` for a null interface).
+///
+/// # Safety
+///
+/// `iface` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_interface_id(iface: *const CanMockInterface) -> u64 {
+    // SAFETY: the caller passes null or a live handle.
+    unsafe { iface.as_ref() }.map_or(u64::MAX, |iface| iface.0.id().as_raw())
+}
+
+/// Replace the interface's acceptance filters with the `count` filters at `filters`; an empty
+/// list accepts every frame.
+///
+/// # Safety
+///
+/// `iface` must be null or a live handle, and `filters` must point to `count` filters (it may
+/// be null when `count` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_interface_set_filters(
+    iface: *const CanMockInterface,
+    filters: *const CanMockFilter,
+    count: usize,
+) -> CanMockStatus {
+    // SAFETY: the caller passes null or a live handle.
+    let Some(iface) = (unsafe { iface.as_ref() }) else {
+        return CanMockStatus::InvalidArgument;
+    };
+    let filters = match (filters.is_null(), count) {
+        (_, 0) => &[][..],
+        (true, _) => return CanMockStatus::InvalidArgument,
+        // SAFETY: the caller passes `count` readable filters.
+        (false, _) => unsafe { core::slice::from_raw_parts(filters, count) },
+    };
+    let filters: Option<Vec<IdMaskFilter>> = filters
+        .iter()
+        .map(|filter| {
+            let (id, mask) = match make_id(filter.id, filter.extended)? {
+                Id::Standard(id) => (
+                    embedded_can_interface::Id::Standard(id),
+                    IdMask::Standard(u16::try_from(filter.mask).ok()?),
+                ),
+                Id::Extended(id) => (
+                    embedded_can_interface::Id::Extended(id),
+                    IdMask::Extended(filter.mask),
+                ),
+            };
+            Some(IdMaskFilter { id, mask })
+        })
+        .collect();
+    match filters.map(|filters| iface.0.set_filters(filters)) {
+        Some(Ok(())) => CanMockStatus::Ok,
+        _ => CanMockStatus::InvalidArgument,
+    }
+}
+
+/// Transmit `frame`.
+///
+/// # Safety
+///
+/// `iface` and `frame` must each be null or valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_send(
+    iface: *const CanMockInterface,
+    frame: *const CanMockFrame,
+) -> CanMockStatus {
+    // SAFETY: the caller passes null or valid pointers.
+    let (Some(iface), Some(frame)) = (unsafe { iface.as_ref() }, unsafe { frame.as_ref() }) else {
+        return CanMockStatus::InvalidArgument;
+    };
+    let Some(frame) = frame.to_frame() else {
+        return CanMockStatus::InvalidArgument;
+    };
+    if iface.0.is_closed() {
+        return CanMockStatus::Closed;
+    }
+    match iface.0.transmit(frame) {
+        Ok(_) => CanMockStatus::Ok,
+        Err(_) => CanMockStatus::TransmitFailed,
+    }
+}
+
+/// Receive the oldest queued frame into `out`, waiting up to `timeout_us` microseconds for one
+/// to arrive: 0 polls, and a negative timeout waits forever. A frame too large for
+/// [`CanMockFrame`] is dropped and reported as [`CanMockStatus::FrameTooLarge`].
+///
+/// # Safety
+///
+/// `iface` and `out` must each be null or valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_recv(
+    iface: *const CanMockInterface,
+    out: *mut CanMockFrame,
+    timeout_us: i64,
+) -> CanMockStatus {
+    // SAFETY: the caller passes null or valid pointers.
+    let (Some(iface), Some(out)) = (unsafe { iface.as_ref() }, unsafe { out.as_mut() }) else {
+        return CanMockStatus::InvalidArgument;
+    };
+    let timeout = u64::try_from(timeout_us).ok().map(Duration::from_micros);
+    if timeout != Some(Duration::ZERO) {
+        iface.0.wait_for_frame(timeout);
+    }
+    match iface.0.pop_frame() {
+        Some(frame) => match CanMockFrame::from_frame(&frame) {
+            Some(frame) => {
+                *out = frame;
+                CanMockStatus::Ok
+            }
+            None => CanMockStatus::FrameTooLarge,
+        },
+        None if iface.0.is_closed() => CanMockStatus::Closed,
+        None => CanMockStatus::Empty,
+    }
+}
+
+/// Number of frames queued for receive (0 for a null interface).
+///
+/// # Safety
+///
+/// `iface` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn can_mock_pending(iface: *const CanMockInterface) -> usize {
+    // SAFETY: the caller passes null or a live handle.
+    unsafe { iface.as_ref() }.map_or(0, |iface| iface.0.queue_len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn c_api_sends_receives_and_rejects_bad_input() {
+        let frame = |id, data: &[u8]| {
+            let mut frame = CanMockFrame {
+                id,
+                extended: false,
+                remote: false,
+                fd: false,
+                len: data.len() as u8,
+                data: [0; 64],
+            };
+            frame.data[..data.len()].copy_from_slice(data);
+            frame
+        };
+        // SAFETY: every handle is live until freed at the end, and pointers are valid.
+        unsafe {
+            let bus = can_mock_bus_named(c"ffi-test".as_ptr());
+            let a = can_mock_interface_new(bus);
+            let b = can_mock_interface_new(bus);
+            assert_eq!(BusHandle::named("ffi-test").interface_count(), 2);
+            let filter = CanMockFilter {
+                id: 0x123,
+                mask: 0x7FF,
+                extended: false,
+            };
+            assert_eq!(
+                can_mock_interface_set_filters(b, &filter, 1),
+                CanMockStatus::Ok
+            );
+
+            assert_eq!(can_mock_send(a, &frame(0x122, &[1])), CanMockStatus::Ok);
+            assert_eq!(
+                can_mock_send(a, &frame(0x123, &[0xAA, 0xBB])),
+                CanMockStatus::Ok
+            );
+            assert_eq!(can_mock_pending(b), 1);
+            let mut out = frame(0, &[]);
+            assert_eq!(can_mock_recv(b, &mut out, 0), CanMockStatus::Ok);
+            assert_eq!(out, frame(0x123, &[0xAA, 0xBB]));
+            assert_eq!(can_mock_recv(b, &mut out, 1000), CanMockStatus::Empty);
+
+            // Payloads beyond CAN FD are reported instead of truncated.
+            let xl = MockFrame::new(StandardId::new(0x123).unwrap(), &[0; 65]).unwrap();
+            (*a).0.transmit(xl).unwrap();
+            assert_eq!(can_mock_recv(b, &mut out, 0), CanMockStatus::FrameTooLarge);
+            assert_eq!(out, frame(0x123, &[0xAA, 0xBB]));
+            assert_eq!(can_mock_pending(b), 0);
+
+            assert_eq!(
+                can_mock_send(a, &frame(0x800, &[])),
+                CanMockStatus::InvalidArgument
+            );
+            assert_eq!(
+                can_mock_send(core::ptr::null(), &frame(0x1, &[])),
+                CanMockStatus::InvalidArgument
+            );
+            BusHandle::named("ffi-test").close();
+            assert_eq!(can_mock_recv(b, &mut out, -1), CanMockStatus::Closed);
+
+            can_mock_interface_free(b);
+            can_mock_interface_free(a);
+            can_mock_bus_free(bus);
+        }
+    }
+}
@@ -1,17 +1,433 @@
 //! Acceptance filter validation for the mock bus.
 //!
 //! Filters are expressed as [`embedded_can_interface::IdMaskFilter`] values. The mock validates
-//! that the `id` and `mask` are of compatible kinds (standard vs extended). Mismatched kinds are
-//! rejected because they cannot sensibly match any incoming ID.
+//! that the `id` and `mask` are of compatible kinds (standard vs extended), and that the mask has
+//! no bits beyond the ID width (11 bits for standard IDs, 29 for extended ones). Mismatched kinds
+//! are rejected because they cannot sensibly match any incoming ID; out-of-range mask bits are
+//! rejected because they usually point at a mistake in the filter arithmetic. [`normalize`]
+//! brings a filter into canonical form instead.
+//!
+//! [`explain`] reports, filter by filter, why an ID is accepted or rejected:
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_interface::{Id as IfaceId, IdMask, IdMaskFilter};
+//! use embedded_can_mock::filter::{FilterVerdict, explain};
+//!
+//! let filters = [IdMaskFilter {
+//!     id: IfaceId::Standard(StandardId::new(0x100).unwrap()),
+//!     mask: IdMask::Standard(0x7F0),
+//! }];
+//! let report = explain(&filters, StandardId::new(0x123).unwrap());
+//! assert!(!report.accepted());
+//! assert_eq!(report.verdicts, [FilterVerdict::BitsDiffer { differing: 0x020 }]);
+//! println!("{report}");
+//! ```
+//!
+//! The `accept_*` constructors build filters for common patterns without hand-computed masks:
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::filter::{accept_exact, accept_range};
+//!
+//! let id = |raw| StandardId::new(raw).unwrap();
+//! let mut filters = accept_range(id(0x100), id(0x10B));
+//! assert_eq!(filters.len(), 2); // 0x100..=0x107 and 0x108..=0x10B
+//! filters.push(accept_exact(id(0x7DF)));
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
 
-use embedded_can::Id;
+use embedded_can::{ExtendedId, Id, StandardId};
 use embedded_can_interface::{IdMask, IdMaskFilter};
 
 /// Errors returned when validating acceptance filters.
+///
+/// The [`Display`](fmt::Display) output points at the offending part of the filter:
+///
+/// ```text
+/// filter mask exceeds the 11-bit standard ID width
+///   mask 0xFFF
+///          ^     bits 0x800 are outside 0x7FF
+/// ```
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FilterError {
     /// The filter’s `id` kind does not match its `mask` kind (standard vs extended).
     KindMismatch,
+    /// The mask has bits set beyond the ID width.
+    MaskOutOfRange {
+        /// The offending mask.
+        mask: u32,
+        /// `true` for an extended (29-bit) mask, `false` for a standard (11-bit) one.
+        extended: bool,
+    },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FilterError::KindMismatch => {
+                write!(
+                    f,
+                    "filter id and mask are of different kinds (standard vs extended)"
+                )
+            }
+            FilterError::MaskOutOfRange { mask, extended } => {
+                let (bits, kind, width, digits) = if extended {
+                    (29, "extended", EXTENDED_MASK, 8)
+                } else {
+                    (11, "standard", STANDARD_MASK, 3)
+                };
+                let excess = mask & !width;
+                writeln!(f, "filter mask exceeds the {bits}-bit {kind} ID width")?;
+                writeln!(f, "  mask 0x{mask:0digits$X}")?;
+                // Underline every hex digit holding an out-of-range bit.
+                let digits = digits.max((32 - mask.leading_zeros()).div_ceil(4) as usize);
+                let carets: String = (0..digits)
+                    .rev()
+                    .map(|nibble| {
+                        if (excess >> (4 * nibble)) & 0xF != 0 {
+                            '^'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect();
+                write!(
+                    f,
+                    "         {carets}   bits 0x{excess:X} are outside 0x{width:X}"
+                )
+            }
+        }
+    }
+}
+
+/// Acceptance counters of one interface, returned by
+/// [`InterfaceHandle::filter_stats`](crate::InterfaceHandle::filter_stats).
+///
+/// Counters restart whenever the filters are replaced or the interface is reset. A filter that
+/// never hits, or any false accept, usually points at an over-broad or misplaced filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterStats {
+    /// Frames accepted by each installed filter, in filter order: the first matching filter
+    /// under [`MatchMode::MatchAny`], every filter under [`MatchMode::MatchAll`].
+    pub hits: Vec<u64>,
+    /// Frames accepted although outside the range given to
+    /// [`InterfaceHandle::set_filter_range`](crate::InterfaceHandle::set_filter_range); always
+    /// 0 for filters installed otherwise.
+    pub false_accepts: u64,
+}
+
+/// What an interface with an empty filter list receives (see [`FilterSemantics`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyListPolicy {
+    /// An empty list accepts every frame, as most controllers do after reset.
+    #[default]
+    AcceptAllWhenEmpty,
+    /// An empty list accepts nothing, as on controllers whose filter banks must be enabled.
+    RejectAllWhenEmpty,
+}
+
+/// How several filters combine (see [`FilterSemantics`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// A frame is accepted if any filter matches it.
+    #[default]
+    MatchAny,
+    /// A frame is accepted only if every filter matches it.
+    MatchAll,
+}
+
+/// Per-interface filter semantics, set with
+/// [`InterfaceHandle::set_filter_semantics`](crate::InterfaceHandle::set_filter_semantics).
+///
+/// The default, accept-all-when-empty and match-any, is how the mock has always behaved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterSemantics {
+    /// Behaviour of an empty filter list.
+    pub empty: EmptyListPolicy,
+    /// How multiple filters combine.
+    pub mode: MatchMode,
+}
+
+impl FilterSemantics {
+    /// Index of the filter deciding that `id` is accepted (the first match, or 0 under
+    /// [`MatchMode::MatchAll`]), or `None` if `filters` reject it. An accepting empty list
+    /// reports 0.
+    pub(crate) fn route(&self, filters: &[IdMaskFilter], id: Id) -> Option<usize> {
+        if filters.is_empty() {
+            return (self.empty == EmptyListPolicy::AcceptAllWhenEmpty).then_some(0);
+        }
+        match self.mode {
+            MatchMode::MatchAny => filters.iter().position(|filter| matches(filter, id)),
+            MatchMode::MatchAll => filters
+                .iter()
+                .all(|filter| matches(filter, id))
+                .then_some(0),
+        }
+    }
+}
+
+pub(crate) const STANDARD_MASK: u32 = 0x7FF;
+pub(crate) const EXTENDED_MASK: u32 = 0x1FFF_FFFF;
+
+/// How one acceptance filter treated an ID, as reported by [`explain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterVerdict {
+    /// The filter accepts the ID.
+    Matched,
+    /// The filter is for the other ID kind (standard vs extended), or its `id` and `mask` kinds
+    /// disagree.
+    KindMismatch,
+    /// Some bits selected by the mask differ between the ID and the filter’s `id`.
+    BitsDiffer {
+        /// The differing bits.
+        differing: u32,
+    },
+}
+
+/// Filter-by-filter explanation of whether an ID passes a filter list, returned by [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchReport {
+    /// The ID that was checked.
+    pub id: Id,
+    /// One verdict per filter, in filter order.
+    pub verdicts: Vec<FilterVerdict>,
+}
+
+impl MatchReport {
+    /// Index of the first filter accepting the ID, if any.
+    pub fn matched(&self) -> Option<usize> {
+        self.verdicts
+            .iter()
+            .position(|verdict| *verdict == FilterVerdict::Matched)
+    }
+
+    /// Returns `true` if an interface with these filters receives the ID under the default
+    /// [`FilterSemantics`]; an empty filter list accepts everything.
+    pub fn accepted(&self) -> bool {
+        self.accepted_with(FilterSemantics::default())
+    }
+
+    /// Returns `true` if an interface with these filters receives the ID under `semantics`.
+    pub fn accepted_with(&self, semantics: FilterSemantics) -> bool {
+        let matched = |verdict: &FilterVerdict| *verdict == FilterVerdict::Matched;
+        match (self.verdicts.is_empty(), semantics.mode) {
+            (true, _) => semantics.empty == EmptyListPolicy::AcceptAllWhenEmpty,
+            (false, MatchMode::MatchAny) => self.verdicts.iter().any(matched),
+            (false, MatchMode::MatchAll) => self.verdicts.iter().all(matched),
+        }
+    }
+}
+
+impl fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (raw, width) = raw_id(self.id);
+        match self.matched() {
+            Some(index) => writeln!(f, "ID {raw:0width$X} accepted by filter #{index}")?,
+            None if self.verdicts.is_empty() => {
+                writeln!(f, "ID {raw:0width$X} accepted (no filters)")?
+            }
+            None => writeln!(
+                f,
+                "ID {raw:0width$X} rejected by all {} filters",
+                self.verdicts.len()
+            )?,
+        }
+        for (index, verdict) in self.verdicts.iter().enumerate() {
+            match verdict {
+                FilterVerdict::Matched => writeln!(f, "  #{index}: matched")?,
+                FilterVerdict::KindMismatch => writeln!(f, "  #{index}: ID kind mismatch")?,
+                FilterVerdict::BitsDiffer { differing } => {
+                    writeln!(f, "  #{index}: masked bits {differing:0width$X} differ")?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Raw value of `id` and its candump hex width.
+fn raw_id(id: Id) -> (u32, usize) {
+    match id {
+        Id::Standard(id) => (u32::from(id.as_raw()), 3),
+        Id::Extended(id) => (id.as_raw(), 8),
+    }
+}
+
+/// Explain how `filters` treat `id`: which filter accepts it, or why each one rejects it.
+pub fn explain(filters: &[IdMaskFilter], id: impl Into<Id>) -> MatchReport {
+    let id = id.into();
+    let verdicts = filters
+        .iter()
+        .map(|filter| {
+            let (fid, mask) = match (filter.id, filter.mask, id) {
+                (
+                    embedded_can_interface::Id::Standard(fid),
+                    IdMask::Standard(mask),
+                    Id::Standard(_),
+                ) => (u32::from(fid.as_raw()), u32::from(mask)),
+                (
+                    embedded_can_interface::Id::Extended(fid),
+                    IdMask::Extended(mask),
+                    Id::Extended(_),
+                ) => (fid.as_raw(), mask),
+                _ => return FilterVerdict::KindMismatch,
+            };
+            match (raw_id(id).0 ^ fid) & mask {
+                0 => FilterVerdict::Matched,
+                differing => FilterVerdict::BitsDiffer { differing },
+            }
+        })
+        .collect();
+    MatchReport { id, verdicts }
+}
+
+/// A filter accepting exactly `id`.
+pub fn accept_exact(id: impl Into<Id>) -> IdMaskFilter {
+    match id.into() {
+        Id::Standard(id) => IdMaskFilter {
+            id: embedded_can_interface::Id::Standard(id),
+            mask: IdMask::Standard(STANDARD_MASK as u16),
+        },
+        Id::Extended(id) => IdMaskFilter {
+            id: embedded_can_interface::Id::Extended(id),
+            mask: IdMask::Extended(EXTENDED_MASK),
+        },
+    }
+}
+
+/// A filter accepting every standard ID (and no extended one).
+pub fn accept_all_standard() -> IdMaskFilter {
+    IdMaskFilter {
+        id: embedded_can_interface::Id::Standard(StandardId::ZERO),
+        mask: IdMask::Standard(0),
+    }
+}
+
+/// A filter accepting every extended ID (and no standard one).
+pub fn accept_all_extended() -> IdMaskFilter {
+    IdMaskFilter {
+        id: embedded_can_interface::Id::Extended(ExtendedId::ZERO),
+        mask: IdMask::Extended(0),
+    }
+}
+
+/// Filters accepting exactly the IDs `lo..=hi`.
+///
+/// The range is split into the fewest aligned power-of-two blocks, each covered by one filter
+/// whose mask fixes the block’s common prefix. Returns no filters if `lo > hi` (an empty list
+/// accepts everything, so check before installing it).
+///
+/// # Panics
+///
+/// Panics if `lo` and `hi` are of different kinds (standard vs extended).
+pub fn accept_range(lo: impl Into<Id>, hi: impl Into<Id>) -> Vec<IdMaskFilter> {
+    let (lo, hi) = (lo.into(), hi.into());
+    let extended = matches!(lo, Id::Extended(_));
+    assert_eq!(
+        extended,
+        matches!(hi, Id::Extended(_)),
+        "range bounds must be the same ID kind"
+    );
+    let width = if extended {
+        EXTENDED_MASK
+    } else {
+        STANDARD_MASK
+    };
+    let (mut next, hi) = (raw_id(lo).0, raw_id(hi).0);
+    let mut filters = Vec::new();
+    while next <= hi {
+        // Largest aligned block starting at `next` that stays within the range.
+        let mut size = if next == 0 {
+            width + 1
+        } else {
+            1 << next.trailing_zeros()
+        };
+        while size > hi - next + 1 {
+            size >>= 1;
+        }
+        filters.push(raw_filter(next, width & !(size - 1), extended));
+        match next.checked_add(size) {
+            Some(after) => next = after,
+            None => break,
+        }
+    }
+    filters
+}
+
+/// At most `max_filters` filters accepting every ID in `lo..=hi`, for controllers with few
+/// filter banks.
+///
+/// Starts from the exact [`accept_range`] split and, while there are too many filters, merges
+/// the neighbouring pair whose covering block is smallest. The result may accept IDs outside
+/// the range; [`InterfaceHandle::set_filter_range`](crate::InterfaceHandle::set_filter_range)
+/// counts such false accepts. A `max_filters` of 0 is treated as 1.
+///
+/// # Panics
+///
+/// Panics if `lo` and `hi` are of different kinds (standard vs extended).
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::StandardId;
+/// use embedded_can_mock::filter::{FilterSet, accept_range, accept_range_limited};
+///
+/// let id = |raw| StandardId::new(raw).unwrap();
+/// assert_eq!(accept_range(id(0x101), id(0x10E)).len(), 6);
+/// let coarse = accept_range_limited(id(0x101), id(0x10E), 2);
+/// assert_eq!(coarse.len(), 2); // 0x100..=0x107 and 0x108..=0x10F
+/// let accepted = FilterSet::any_of(coarse).unwrap();
+/// assert!(accepted.contains_id(id(0x100)) && accepted.contains_id(id(0x10F)));
+/// ```
+pub fn accept_range_limited(
+    lo: impl Into<Id>,
+    hi: impl Into<Id>,
+    max_filters: usize,
+) -> Vec<IdMaskFilter> {
+    let mut filters = accept_range(lo, hi);
+    while filters.len() > max_filters.max(1) {
+        let merged = filters
+            .windows(2)
+            .map(|pair| block_cover(&pair[0], &pair[1]))
+            .max_by_key(|cover| raw_filter_parts(cover).1)
+            .expect("more than one filter");
+        filters.retain(|filter| !covers(&merged, filter));
+        let base = raw_filter_parts(&merged).0;
+        let at = filters.partition_point(|filter| raw_filter_parts(filter).0 < base);
+        filters.insert(at, merged);
+    }
+    filters
+}
+
+/// The smallest aligned block containing both blocks `a` and `b`.
+fn block_cover(a: &IdMaskFilter, b: &IdMaskFilter) -> IdMaskFilter {
+    let (a_id, a_mask, width, _) = raw_filter_parts(a);
+    let (b_id, b_mask, _, _) = raw_filter_parts(b);
+    let differing = ((a_id ^ b_id) | !a_mask | !b_mask) & width;
+    // Keep the common prefix above the highest differing bit.
+    let mask = match differing.leading_zeros() {
+        32 => width,
+        zeros => width & !(u32::MAX >> zeros),
+    };
+    raw_filter(a_id & mask, mask, width == EXTENDED_MASK)
+}
+
+pub(crate) fn raw_filter(id: u32, mask: u32, extended: bool) -> IdMaskFilter {
+    if extended {
+        IdMaskFilter {
+            id: embedded_can_interface::Id::Extended(ExtendedId::new(id).unwrap()),
+            mask: IdMask::Extended(mask),
+        }
+    } else {
+        IdMaskFilter {
+            id: embedded_can_interface::Id::Standard(StandardId::new(id as u16).unwrap()),
+            mask: IdMask::Standard(mask as u16),
+        }
+    }
 }
 
 pub(crate) fn matches(filter: &IdMaskFilter, match_id: Id) -> bool {
@@ -27,10 +443,55 @@ pub(crate) fn matches(filter: &IdMaskFilter, match_id: Id) -> bool {
 }
 
 pub(crate) fn validate_filter(filter: &IdMaskFilter) -> Result<(), FilterError> {
+    let (mask, extended) = match (filter.id, filter.mask) {
+        (embedded_can_interface::Id::Standard(_), IdMask::Standard(mask)) => {
+            (u32::from(mask), false)
+        }
+        (embedded_can_interface::Id::Extended(_), IdMask::Extended(mask)) => (mask, true),
+        _ => return Err(FilterError::KindMismatch),
+    };
+    let width = if extended {
+        EXTENDED_MASK
+    } else {
+        STANDARD_MASK
+    };
+    if mask & !width != 0 {
+        return Err(FilterError::MaskOutOfRange { mask, extended });
+    }
+    Ok(())
+}
+
+/// Canonical form of `filter`: mask bits beyond the ID width are cleared, as are `id` bits the
+/// mask ignores, so filters that accept the same IDs compare equal.
+///
+/// A filter whose `id` and `mask` kinds differ is returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::StandardId;
+/// use embedded_can_interface::{Id as IfaceId, IdMask, IdMaskFilter};
+/// use embedded_can_mock::filter::normalize;
+///
+/// let sloppy = IdMaskFilter {
+///     id: IfaceId::Standard(StandardId::new(0x12F).unwrap()),
+///     mask: IdMask::Standard(0xFFF0),
+/// };
+/// let canonical = normalize(&sloppy);
+/// assert_eq!(canonical.id, IfaceId::Standard(StandardId::new(0x120).unwrap()));
+/// assert_eq!(canonical.mask, IdMask::Standard(0x7F0));
+/// ```
+pub fn normalize(filter: &IdMaskFilter) -> IdMaskFilter {
     match (filter.id, filter.mask) {
-        (embedded_can_interface::Id::Standard(_), IdMask::Standard(_)) => Ok(()),
-        (embedded_can_interface::Id::Extended(_), IdMask::Extended(_)) => Ok(()),
-        _ => Err(FilterError::KindMismatch),
+        (embedded_can_interface::Id::Standard(id), IdMask::Standard(mask)) => {
+            let mask = u32::from(mask) & STANDARD_MASK;
+            raw_filter(u32::from(id.as_raw()) & mask, mask, false)
+        }
+        (embedded_can_interface::Id::Extended(id), IdMask::Extended(mask)) => {
+            let mask = mask & EXTENDED_MASK;
+            raw_filter(id.as_raw() & mask, mask, true)
+        }
+        _ => *filter,
     }
 }
 
@@ -41,6 +502,182 @@ pub(crate) fn validate_filters(filters: &[IdMaskFilter]) -> Result<(), FilterErr
     Ok(())
 }
 
+/// The set of IDs a filter configuration accepts, with set operations.
+///
+/// A `FilterSet` is a match-any list of normalized filters in which an empty list accepts
+/// nothing, so it can describe any configuration regardless of its [`FilterSemantics`]: a
+/// match-all list becomes the single filter intersecting them, and an accept-all empty list
+/// becomes one filter per ID kind. Filters covered by another one are dropped.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::StandardId;
+/// use embedded_can_mock::filter::{FilterSet, accept_exact, accept_range};
+///
+/// let id = |raw| StandardId::new(raw).unwrap();
+/// let diagnostics = FilterSet::any_of(accept_range(id(0x7E0), id(0x7EF))).unwrap();
+/// let broadcast = FilterSet::any_of([accept_exact(id(0x7DF))]).unwrap();
+/// let both = diagnostics.union(&broadcast);
+/// assert!(both.contains_id(id(0x7DF)));
+/// assert!(both.contains_id(id(0x7E8)));
+/// assert!(diagnostics.intersection(&broadcast).is_empty());
+/// assert_eq!(both.to_string(), "{7E0/7F0, 7DF}");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterSet {
+    filters: Vec<IdMaskFilter>,
+}
+
+impl FilterSet {
+    /// The set accepting nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The set accepting every standard and extended ID.
+    pub fn all() -> Self {
+        Self {
+            filters: alloc::vec![accept_all_standard(), accept_all_extended()],
+        }
+    }
+
+    /// The IDs accepted by any of `filters`.
+    pub fn any_of(filters: impl IntoIterator<Item = IdMaskFilter>) -> Result<Self, FilterError> {
+        let mut set = Self::new();
+        for filter in filters {
+            validate_filter(&filter)?;
+            set.insert(normalize(&filter));
+        }
+        Ok(set)
+    }
+
+    /// The IDs an interface with `filters` receives under `semantics`.
+    pub fn from_filters(
+        filters: &[IdMaskFilter],
+        semantics: FilterSemantics,
+    ) -> Result<Self, FilterError> {
+        validate_filters(filters)?;
+        if filters.is_empty() {
+            return Ok(match semantics.empty {
+                EmptyListPolicy::AcceptAllWhenEmpty => Self::all(),
+                EmptyListPolicy::RejectAllWhenEmpty => Self::new(),
+            });
+        }
+        match semantics.mode {
+            MatchMode::MatchAny => Self::any_of(filters.iter().copied()),
+            MatchMode::MatchAll => Ok(filters
+                .iter()
+                .map(|filter| Self::any_of([*filter]).unwrap())
+                .reduce(|acc, set| acc.intersection(&set))
+                .unwrap()),
+        }
+    }
+
+    /// The normalized filters making up the set; installing them with match-any semantics and
+    /// [`EmptyListPolicy::RejectAllWhenEmpty`] accepts exactly this set.
+    pub fn filters(&self) -> &[IdMaskFilter] {
+        &self.filters
+    }
+
+    /// Returns `true` if the set accepts no ID at all.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Returns `true` if `id` is in the set.
+    pub fn contains_id(&self, id: impl Into<Id>) -> bool {
+        let id = id.into();
+        self.filters.iter().any(|filter| matches(filter, id))
+    }
+
+    /// The IDs in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        for filter in &other.filters {
+            set.insert(*filter);
+        }
+        set
+    }
+
+    /// The IDs in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut set = Self::new();
+        for a in &self.filters {
+            for b in &other.filters {
+                if let Some(both) = intersect(a, b) {
+                    set.insert(both);
+                }
+            }
+        }
+        set
+    }
+
+    /// Add a normalized filter unless it is already covered, dropping filters it covers.
+    fn insert(&mut self, filter: IdMaskFilter) {
+        if self.filters.iter().any(|known| covers(known, &filter)) {
+            return;
+        }
+        self.filters.retain(|known| !covers(&filter, known));
+        self.filters.push(filter);
+    }
+}
+
+impl fmt::Display for FilterSet {
+    /// Formats as `{ID/MASK, ...}` in hex, omitting the mask of exact filters.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (index, filter) in self.filters.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            let (id, mask, width, digits) = raw_filter_parts(filter);
+            write!(f, "{id:0digits$X}")?;
+            if mask != width {
+                write!(f, "/{mask:0digits$X}")?;
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Raw id, mask, full-width mask and hex width of a valid filter.
+fn raw_filter_parts(filter: &IdMaskFilter) -> (u32, u32, u32, usize) {
+    match (filter.id, filter.mask) {
+        (embedded_can_interface::Id::Extended(id), IdMask::Extended(mask)) => {
+            (id.as_raw(), mask, EXTENDED_MASK, 8)
+        }
+        (embedded_can_interface::Id::Standard(id), IdMask::Standard(mask)) => {
+            (u32::from(id.as_raw()), u32::from(mask), STANDARD_MASK, 3)
+        }
+        _ => unreachable!("filter sets only hold valid filters"),
+    }
+}
+
+/// Returns `true` if every ID accepted by normalized filter `inner` is accepted by `outer`.
+fn covers(outer: &IdMaskFilter, inner: &IdMaskFilter) -> bool {
+    let (outer_id, outer_mask, outer_width, _) = raw_filter_parts(outer);
+    let (inner_id, inner_mask, inner_width, _) = raw_filter_parts(inner);
+    outer_width == inner_width
+        && outer_mask & !inner_mask == 0
+        && (outer_id ^ inner_id) & outer_mask == 0
+}
+
+/// The normalized filter accepting exactly the IDs both accept, if there are any.
+fn intersect(a: &IdMaskFilter, b: &IdMaskFilter) -> Option<IdMaskFilter> {
+    let (a_id, a_mask, a_width, _) = raw_filter_parts(a);
+    let (b_id, b_mask, b_width, _) = raw_filter_parts(b);
+    if a_width != b_width || (a_id ^ b_id) & a_mask & b_mask != 0 {
+        return None;
+    }
+    let extended = a_width == EXTENDED_MASK;
+    Some(raw_filter(
+        (a_id & a_mask) | (b_id & b_mask),
+        a_mask | b_mask,
+        extended,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,6 +707,178 @@ mod tests {
         assert!(!rejected);
     }
 
+    #[test]
+    fn explain_agrees_with_matching() {
+        let filters = [
+            IdMaskFilter {
+                id: embedded_can_interface::Id::Extended(ExtendedId::new(0x123).unwrap()),
+                mask: IdMask::Extended(0x1FFF_FFFF),
+            },
+            IdMaskFilter {
+                id: embedded_can_interface::Id::Standard(StandardId::new(0x120).unwrap()),
+                mask: IdMask::Standard(0x7F0),
+            },
+        ];
+        for raw in [0x120, 0x12F, 0x130, 0x523] {
+            let id = Id::Standard(StandardId::new(raw).unwrap());
+            let report = explain(&filters, id);
+            assert_eq!(report.verdicts[0], FilterVerdict::KindMismatch);
+            assert_eq!(report.accepted(), filters.iter().any(|f| matches(f, id)));
+        }
+        let report = explain(&filters, StandardId::new(0x523).unwrap());
+        assert_eq!(
+            report.verdicts[1],
+            FilterVerdict::BitsDiffer { differing: 0x400 }
+        );
+        assert_eq!(
+            alloc::format!("{report}"),
+            "ID 523 rejected by all 2 filters\n  #0: ID kind mismatch\n  #1: masked bits 400 differ\n"
+        );
+        assert!(explain(&[], StandardId::ZERO).accepted());
+    }
+
+    #[test]
+    fn range_filters_cover_exactly_the_range() {
+        let standard = |raw| Id::Standard(StandardId::new(raw).unwrap());
+        for (lo, hi, count) in [
+            (0x100, 0x10B, 2),
+            (0x0, 0x7FF, 1),
+            (0x7FF, 0x7FF, 1),
+            (0x001, 0x7FE, 20),
+            (0x123, 0x456, 12),
+        ] {
+            let filters = accept_range(standard(lo), standard(hi));
+            assert_eq!(filters.len(), count, "{lo:#x}..={hi:#x}");
+            for raw in 0..=0x7FF {
+                let accepted = filters.iter().any(|f| matches(f, standard(raw)));
+                assert_eq!(accepted, (lo..=hi).contains(&raw), "{raw:#x}");
+            }
+        }
+        assert!(accept_range(standard(2), standard(1)).is_empty());
+
+        let extended = |raw| Id::Extended(ExtendedId::new(raw).unwrap());
+        let all = accept_range(extended(0), extended(0x1FFF_FFFF));
+        assert_eq!(all, vec![accept_all_extended()]);
+        let top = accept_range(extended(0x1FFF_FFF0), extended(0x1FFF_FFFF));
+        assert!(top.iter().any(|f| matches(f, extended(0x1FFF_FFFF))));
+        assert!(!top.iter().any(|f| matches(f, extended(0x1FFF_FFEF))));
+
+        assert!(matches(&accept_exact(standard(0x55)), standard(0x55)));
+        assert!(!matches(&accept_exact(standard(0x55)), standard(0x54)));
+        assert!(matches(&accept_all_standard(), standard(0x3AB)));
+        assert!(!matches(&accept_all_standard(), extended(0x3AB)));
+    }
+
+    #[test]
+    fn out_of_range_masks_are_rejected_with_a_pointer() {
+        let standard = IdMaskFilter {
+            id: embedded_can_interface::Id::Standard(StandardId::new(0x123).unwrap()),
+            mask: IdMask::Standard(0x0FFF),
+        };
+        let err = validate_filter(&standard).unwrap_err();
+        assert!(matches!(
+            err,
+            FilterError::MaskOutOfRange {
+                mask: 0xFFF,
+                extended: false
+            }
+        ));
+        assert_eq!(
+            alloc::format!("{err}"),
+            "filter mask exceeds the 11-bit standard ID width\n  mask 0xFFF\n         ^     bits 0x800 are outside 0x7FF"
+        );
+        let normalized = normalize(&standard);
+        assert!(validate_filter(&normalized).is_ok());
+        assert_eq!(normalized.mask, IdMask::Standard(0x7FF));
+
+        let extended = IdMaskFilter {
+            id: embedded_can_interface::Id::Extended(ExtendedId::new(0x1ABC_DEF0).unwrap()),
+            mask: IdMask::Extended(0xFFFF_0000),
+        };
+        let err = validate_filter(&extended).unwrap_err();
+        assert!(alloc::format!("{err}").contains("^      "));
+        let normalized = normalize(&extended);
+        assert_eq!(
+            normalized,
+            IdMaskFilter {
+                id: embedded_can_interface::Id::Extended(ExtendedId::new(0x1ABC_0000).unwrap()),
+                mask: IdMask::Extended(0x1FFF_0000),
+            }
+        );
+        assert!(validate_filter(&accept_all_extended()).is_ok());
+    }
+
+    #[test]
+    fn semantics_change_empty_and_combined_lists() {
+        let id = |raw| Id::Standard(StandardId::new(raw).unwrap());
+        let high = raw_filter(0x400, 0x400, false);
+        let odd = raw_filter(0x001, 0x001, false);
+        let strict = FilterSemantics {
+            empty: EmptyListPolicy::RejectAllWhenEmpty,
+            mode: MatchMode::MatchAll,
+        };
+        assert_eq!(FilterSemantics::default().route(&[], id(0x1)), Some(0));
+        assert_eq!(strict.route(&[], id(0x1)), None);
+        assert_eq!(
+            FilterSemantics::default().route(&[high, odd], id(0x1)),
+            Some(1)
+        );
+        assert_eq!(strict.route(&[high, odd], id(0x1)), None);
+        assert_eq!(strict.route(&[high, odd], id(0x401)), Some(0));
+        for raw in [0x0, 0x1, 0x400, 0x401] {
+            for semantics in [FilterSemantics::default(), strict] {
+                let report = explain(&[high, odd], id(raw));
+                assert_eq!(
+                    report.accepted_with(semantics),
+                    semantics.route(&[high, odd], id(raw)).is_some()
+                );
+            }
+        }
+        assert!(!explain(&[], id(0)).accepted_with(strict));
+    }
+
+    #[test]
+    fn filter_sets_agree_with_interface_semantics() {
+        let id = |raw| Id::Standard(StandardId::new(raw).unwrap());
+        let filters = [
+            raw_filter(0x400, 0x400, false),
+            raw_filter(0x001, 0x001, false),
+            raw_filter(0x401, 0x7FF, false),
+        ];
+        for empty in [
+            EmptyListPolicy::AcceptAllWhenEmpty,
+            EmptyListPolicy::RejectAllWhenEmpty,
+        ] {
+            for mode in [MatchMode::MatchAny, MatchMode::MatchAll] {
+                let semantics = FilterSemantics { empty, mode };
+                for list in [&filters[..0], &filters[..2], &filters[..]] {
+                    let set = FilterSet::from_filters(list, semantics).unwrap();
+                    for raw in 0..=0x7FF {
+                        assert_eq!(
+                            set.contains_id(id(raw)),
+                            semantics.route(list, id(raw)).is_some()
+                        );
+                    }
+                }
+            }
+        }
+
+        let any = FilterSet::from_filters(&filters, FilterSemantics::default()).unwrap();
+        // 0x401 is covered by the 0x400/0x400 filter.
+        assert_eq!(any.filters().len(), 2);
+        assert_eq!(any.to_string(), "{400/400, 001/001}");
+        let extended = FilterSet::any_of([accept_all_extended()]).unwrap();
+        assert!(any.intersection(&extended).is_empty());
+        assert_eq!(any.union(&FilterSet::all()), FilterSet::all());
+        assert!(
+            FilterSet::any_of([IdMaskFilter {
+                id: embedded_can_interface::Id::Standard(StandardId::ZERO),
+                mask: IdMask::Extended(0),
+            }])
+            .is_err()
+        );
+    }
+
     #[test]
     fn mismatched_kinds_reject() {
         let filter = IdMaskFilter {
@@ -3,6 +3,17 @@
 //! [`MockFrame`] implements [`embedded_can::Frame`] and can be used anywhere a `Frame` is
 //! expected. This crate stores the payload as an owned `Vec<u8>` for data frames and stores only a
 //! DLC for remote frames.
+//!
+//! [`MockFrame::from_frame`] and [`MockFrame::to_frame`] convert to and from any other
+//! `embedded_can::Frame` implementation (socketcan’s `CanFrame`, HAL frame types, …), so bridging
+//! the mock to a real stack needs no dedicated glue.
+//!
+//! A `MockFrame` can also carry [`FrameMeta`]: CAN FD / XL flags and free-form user tags that
+//! travel with the frame across the bus, for protocol layers that want to annotate frames in
+//! tests without encoding anything into the payload.
+
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
 
 use embedded_can::Frame;
 
@@ -15,6 +26,34 @@ pub enum MockFrameType {
     Remote(usize),
 }
 
+/// Metadata carried alongside a [`MockFrame`]'s ID and payload.
+///
+/// The bus only reads the FD and BRS flags, to [time](crate::timing) the frame; everything is
+/// delivered with the frame so receivers and tests can inspect it. Only [`MockFrame`] carries
+/// metadata: conversions to other frame types drop it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// CAN FD frame format (FDF).
+    pub fd: bool,
+    /// CAN FD bit rate switch (BRS).
+    pub brs: bool,
+    /// CAN FD error state indicator (ESI): the transmitter is error passive.
+    pub esi: bool,
+    /// CAN XL SDU type, for XL frames.
+    pub xl_sdu_type: Option<u8>,
+    /// Free-form test annotations.
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Shared metadata of frames that carry none.
+static NO_META: FrameMeta = FrameMeta {
+    fd: false,
+    brs: false,
+    esi: false,
+    xl_sdu_type: None,
+    tags: BTreeMap::new(),
+};
+
 /// In-memory CAN frame implementing [`embedded_can::Frame`].
 ///
 /// # Example
@@ -28,16 +67,28 @@ pub enum MockFrameType {
 /// assert_eq!(frame.dlc(), 2);
 /// assert_eq!(frame.data(), &[0xAA, 0xBB]);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Frames compare equal when their ID, payload (or remote DLC) and [metadata](FrameMeta) are
+/// equal.
+#[derive(Debug, Clone, Eq)]
 pub struct MockFrame {
     frame_type: MockFrameType,
     id: embedded_can::Id,
+    /// Boxed so frames without metadata stay small.
+    meta: Option<Box<FrameMeta>>,
+}
+
+impl PartialEq for MockFrame {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.frame_type == other.frame_type && self.meta() == other.meta()
+    }
 }
 impl Frame for MockFrame {
     fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
         Some(Self {
             frame_type: MockFrameType::Standard(data.to_vec()),
             id: id.into(),
+            meta: None,
         })
     }
 
@@ -45,6 +96,7 @@ impl Frame for MockFrame {
         Some(Self {
             frame_type: MockFrameType::Remote(dlc),
             id: id.into(),
+            meta: None,
         })
     }
 
@@ -80,3 +132,618 @@ impl Frame for MockFrame {
         }
     }
 }
+
+impl MockFrame {
+    /// Copy any [`embedded_can::Frame`] into a `MockFrame` (ID, payload or remote DLC), without
+    /// metadata.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::MockFrame;
+    ///
+    /// let remote = MockFrame::new_remote(StandardId::new(0x10).unwrap(), 4).unwrap();
+    /// assert_eq!(MockFrame::from_frame(&remote), remote);
+    /// ```
+    pub fn from_frame<F: Frame>(frame: &F) -> Self {
+        let frame_type = if frame.is_remote_frame() {
+            MockFrameType::Remote(frame.dlc())
+        } else {
+            MockFrameType::Standard(frame.data().to_vec())
+        };
+        Self {
+            frame_type,
+            id: frame.id(),
+            meta: None,
+        }
+    }
+
+    /// Like [`from_frame`](Self::from_frame), but keeps the metadata when `frame` already is
+    /// a `MockFrame`.
+    pub(crate) fn from_any_frame<F: Frame + 'static>(frame: &F) -> Self {
+        match (frame as &dyn core::any::Any).downcast_ref::<Self>() {
+            Some(mock) => mock.clone(),
+            None => Self::from_frame(frame),
+        }
+    }
+
+    /// The frame’s metadata (all flags clear and no tags if none was set).
+    pub fn meta(&self) -> &FrameMeta {
+        self.meta.as_deref().unwrap_or(&NO_META)
+    }
+
+    /// Mutable access to the frame’s metadata.
+    pub fn meta_mut(&mut self) -> &mut FrameMeta {
+        self.meta.get_or_insert_with(Box::default)
+    }
+
+    /// Replace the frame’s metadata.
+    pub fn with_meta(mut self, meta: FrameMeta) -> Self {
+        self.meta = Some(Box::new(meta));
+        self
+    }
+
+    /// Attach the user tag `key` = `value`, replacing any previous value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    ///
+    /// let bus = BusHandle::new();
+    /// let node = bus.add_interface(vec![]).unwrap();
+    /// let frame = MockFrame::new(StandardId::new(0x10).unwrap(), &[1])
+    ///     .unwrap()
+    ///     .with_tag("layer", "isotp-first-frame");
+    /// node.transmit(frame).unwrap();
+    ///
+    /// let received = node.pop_frame().unwrap();
+    /// assert_eq!(received.tag("layer"), Some("isotp-first-frame"));
+    /// ```
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta_mut().tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Value of the user tag `key`, if set.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.meta().tags.get(key).map(String::as_str)
+    }
+
+    /// Build another [`embedded_can::Frame`] type from this frame.
+    ///
+    /// Returns `None` if `F` rejects the ID or payload (for example a payload longer than it
+    /// supports).
+    pub fn to_frame<F: Frame>(&self) -> Option<F> {
+        match &self.frame_type {
+            MockFrameType::Standard(data) => F::new(self.id, data),
+            MockFrameType::Remote(dlc) => F::new_remote(self.id, *dlc),
+        }
+    }
+}
+
+impl From<(embedded_can::Id, &[u8])> for MockFrame {
+    fn from((id, data): (embedded_can::Id, &[u8])) -> Self {
+        Self {
+            frame_type: MockFrameType::Standard(data.to_vec()),
+            id,
+            meta: None,
+        }
+    }
+}
+
+impl From<(embedded_can::Id, Vec<u8>)> for MockFrame {
+    fn from((id, data): (embedded_can::Id, Vec<u8>)) -> Self {
+        Self {
+            frame_type: MockFrameType::Standard(data),
+            id,
+            meta: None,
+        }
+    }
+}
+
+impl From<MockFrame> for (embedded_can::Id, Vec<u8>) {
+    /// Split a frame into ID and payload; remote frames yield an empty payload.
+    fn from(frame: MockFrame) -> Self {
+        match frame.frame_type {
+            MockFrameType::Standard(data) => (frame.id, data),
+            MockFrameType::Remote(_) => (frame.id, Vec::new()),
+        }
+    }
+}
+
+/// Errors returned by [`MockFrame::parse_candump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandumpParseError {
+    /// The text has no `#` between ID and payload.
+    MissingSeparator,
+    /// The ID is not 3 (standard) or 8 (extended) hex digits, or is out of range.
+    InvalidId,
+    /// The payload is not an even number of hex digits, or the remote DLC is not a digit.
+    InvalidData,
+    /// CAN FD (`##`) notation is not supported.
+    UnsupportedFd,
+}
+
+impl MockFrame {
+    /// Format the frame in `candump` / `cansend` notation: `123#DEADBEEF` for data frames,
+    /// `1ABCDE01#R4` for remote frames. Standard IDs use 3 hex digits, extended IDs 8.
+    ///
+    /// This is also the frame’s [`Display`](fmt::Display) output.
+    pub fn fmt_candump(&self) -> String {
+        alloc::format!("{self}")
+    }
+
+    /// Parse `candump` / `cansend` notation, the inverse of [`fmt_candump`](Self::fmt_candump).
+    ///
+    /// Payload bytes may be separated by `.` (as accepted by `cansend`), and a full `candump -L`
+    /// log line (`(1436509052.249713) vcan0 123#DEADBEEF`) is accepted as well: only its last
+    /// field is parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, ExtendedId, StandardId};
+    /// use embedded_can_mock::MockFrame;
+    ///
+    /// let frame = MockFrame::parse_candump("123#DE.AD.BE.EF").unwrap();
+    /// assert_eq!(frame, MockFrame::new(StandardId::new(0x123).unwrap(), &[0xDE, 0xAD, 0xBE, 0xEF]).unwrap());
+    /// assert_eq!(frame.to_string(), "123#DEADBEEF");
+    ///
+    /// let remote = MockFrame::parse_candump("(0.000001) vcan0 1ABCDE01#R4").unwrap();
+    /// assert_eq!(remote, MockFrame::new_remote(ExtendedId::new(0x1ABC_DE01).unwrap(), 4).unwrap());
+    /// ```
+    pub fn parse_candump(text: &str) -> Result<Self, CandumpParseError> {
+        let text = text.split_whitespace().last().unwrap_or("");
+        let (id, payload) = text
+            .split_once('#')
+            .ok_or(CandumpParseError::MissingSeparator)?;
+        if payload.starts_with('#') {
+            return Err(CandumpParseError::UnsupportedFd);
+        }
+        // `from_str_radix` also accepts a sign, so check for hex digits first.
+        if !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(CandumpParseError::InvalidId);
+        }
+        let raw = u32::from_str_radix(id, 16).map_err(|_| CandumpParseError::InvalidId)?;
+        let id = match id.len() {
+            3 => embedded_can::StandardId::new(raw as u16)
+                .filter(|_| raw <= 0x7FF)
+                .map(embedded_can::Id::Standard),
+            8 => embedded_can::ExtendedId::new(raw).map(embedded_can::Id::Extended),
+            _ => None,
+        }
+        .ok_or(CandumpParseError::InvalidId)?;
+
+        if let Some(dlc) = payload.strip_prefix(['R', 'r']) {
+            let dlc = match dlc.as_bytes() {
+                [] => 0,
+                [digit @ b'0'..=b'8'] => usize::from(digit - b'0'),
+                _ => return Err(CandumpParseError::InvalidData),
+            };
+            return Ok(Self {
+                frame_type: MockFrameType::Remote(dlc),
+                id,
+                meta: None,
+            });
+        }
+        let digits: Vec<u8> = payload.bytes().filter(|b| *b != b'.').collect();
+        if !digits.len().is_multiple_of(2) || !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(CandumpParseError::InvalidData);
+        }
+        let data = digits
+            .chunks(2)
+            .map(|pair| {
+                core::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or(CandumpParseError::InvalidData)
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        Ok(Self {
+            frame_type: MockFrameType::Standard(data),
+            id,
+            meta: None,
+        })
+    }
+
+    /// Parse a compact hex fixture such as `18FF50E9#0102030405060708` or `7E0#02 10 03`.
+    ///
+    /// Like [`parse_candump`](Self::parse_candump), but the ID may have any number of hex digits
+    /// (and a `0x` prefix): up to 3 digits within `0x7FF` make a standard ID, anything else an
+    /// extended one. Payload bytes may be separated by `.` or whitespace. Frames convert back
+    /// with [`fmt_candump`](Self::fmt_candump). [`frames!`](crate::frames) builds whole fixtures.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, ExtendedId, StandardId};
+    /// use embedded_can_mock::MockFrame;
+    ///
+    /// let frame = MockFrame::from_hex("18FF50E9#0102030405060708").unwrap();
+    /// assert_eq!(frame.id(), ExtendedId::new(0x18FF_50E9).unwrap().into());
+    /// assert_eq!(MockFrame::from_hex("0x7E0#02 10 03").unwrap().to_string(), "7E0#021003");
+    /// assert_eq!(MockFrame::from_hex("1#R").unwrap().id(), StandardId::new(1).unwrap().into());
+    /// ```
+    pub fn from_hex(text: &str) -> Result<Self, CandumpParseError> {
+        let (id, payload) = text
+            .trim()
+            .split_once('#')
+            .ok_or(CandumpParseError::MissingSeparator)?;
+        let id = id.trim_end();
+        let id = id
+            .strip_prefix("0x")
+            .or_else(|| id.strip_prefix("0X"))
+            .unwrap_or(id);
+        if id.is_empty() {
+            return Err(CandumpParseError::InvalidId);
+        }
+        let payload: String = payload.split_whitespace().collect();
+        let standard = id.len() <= 3 && u32::from_str_radix(id, 16).is_ok_and(|raw| raw <= 0x7FF);
+        let padded = if standard {
+            alloc::format!("{id:0>3}#{payload}")
+        } else {
+            alloc::format!("{id:0>8}#{payload}")
+        };
+        Self::parse_candump(&padded)
+    }
+}
+
+/// Parse every literal with [`MockFrame::from_hex`]; used by [`frames!`](crate::frames).
+///
+/// # Panics
+///
+/// Panics, naming the literal, if one does not parse.
+#[doc(hidden)]
+#[track_caller]
+pub fn hex_frames(literals: &[&str]) -> Vec<MockFrame> {
+    literals
+        .iter()
+        .map(|text| {
+            MockFrame::from_hex(text)
+                .unwrap_or_else(|error| panic!("invalid frame literal `{text}`: {error:?}"))
+        })
+        .collect()
+}
+
+/// Build a `Vec<MockFrame>` from compact hex literals (see [`MockFrame::from_hex`]).
+///
+/// Panics, naming the literal, if one does not parse.
+///
+/// ```
+/// use embedded_can_mock::{MockFrame, frames};
+///
+/// let fixture: Vec<MockFrame> = frames![
+///     "7DF#02 01 0C",
+///     "7E8#04 41 0C 1A F8",
+///     "18FF50E9#0102030405060708",
+///     "100#R8",
+/// ];
+/// assert_eq!(fixture.len(), 4);
+/// assert_eq!(fixture[1].to_string(), "7E8#04410C1AF8");
+/// ```
+#[macro_export]
+macro_rules! frames {
+    ($($literal:expr),* $(,)?) => {
+        $crate::frame::hex_frames(&[$($literal),*])
+    };
+}
+
+impl fmt::Display for MockFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_candump(f, self)
+    }
+}
+
+/// Logs the frame in candump notation, like its [`Display`](fmt::Display) output.
+#[cfg(feature = "defmt")]
+impl defmt::Format for MockFrame {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        format_candump(f, self);
+    }
+}
+
+fn write_candump(f: &mut fmt::Formatter<'_>, frame: &impl Frame) -> fmt::Result {
+    match frame.id() {
+        embedded_can::Id::Standard(id) => write!(f, "{:03X}#", id.as_raw())?,
+        embedded_can::Id::Extended(id) => write!(f, "{:08X}#", id.as_raw())?,
+    }
+    if frame.is_remote_frame() {
+        write!(f, "R{}", frame.dlc())
+    } else {
+        frame.data().iter().try_for_each(|b| write!(f, "{b:02X}"))
+    }
+}
+
+/// Compact, `Copy` classic CAN frame: an ID plus up to 8 inline data bytes.
+///
+/// `SmallFrame` never allocates, and is `Ord` (by ID priority first) and `Hash`, which makes it a
+/// better fit than [`MockFrame`] for tests that create millions of frames or keep them in sets.
+/// The bus routes it like any other frame type (`BusHandle::<SmallFrame>::default()`).
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{BusHandle, MockFrame, SmallFrame};
+///
+/// let frame = SmallFrame::new(StandardId::new(0x123).unwrap(), &[1, 2, 3]).unwrap();
+/// assert!(SmallFrame::new(StandardId::new(0x123).unwrap(), &[0; 9]).is_none());
+/// assert_eq!(SmallFrame::try_from(&MockFrame::from(frame)), Ok(frame));
+///
+/// let bus = BusHandle::<SmallFrame>::default();
+/// let node = bus.add_interface(vec![]).unwrap();
+/// node.transmit(frame).unwrap();
+/// assert_eq!(node.pop_frame(), Some(frame));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SmallFrame {
+    id: embedded_can::Id,
+    remote: bool,
+    len: u8,
+    /// Payload; bytes past `len` are always zero so derived comparisons stay consistent.
+    data: [u8; 8],
+}
+
+impl Frame for SmallFrame {
+    fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+        let mut bytes = [0; 8];
+        bytes.get_mut(..data.len())?.copy_from_slice(data);
+        Some(Self {
+            id: id.into(),
+            remote: false,
+            len: data.len() as u8,
+            data: bytes,
+        })
+    }
+
+    fn new_remote(id: impl Into<embedded_can::Id>, dlc: usize) -> Option<Self> {
+        (dlc <= 8).then(|| Self {
+            id: id.into(),
+            remote: true,
+            len: dlc as u8,
+            data: [0; 8],
+        })
+    }
+
+    fn is_extended(&self) -> bool {
+        matches!(self.id, embedded_can::Id::Extended(_))
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        self.remote
+    }
+
+    fn id(&self) -> embedded_can::Id {
+        self.id
+    }
+
+    fn dlc(&self) -> usize {
+        self.len as usize
+    }
+
+    fn data(&self) -> &[u8] {
+        if self.remote {
+            &[]
+        } else {
+            &self.data[..self.len as usize]
+        }
+    }
+}
+
+impl fmt::Display for SmallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_candump(f, self)
+    }
+}
+
+/// Logs the frame in candump notation, like its [`Display`](fmt::Display) output.
+#[cfg(feature = "defmt")]
+impl defmt::Format for SmallFrame {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        format_candump(f, self);
+    }
+}
+
+#[cfg(feature = "defmt")]
+fn format_candump(f: defmt::Formatter<'_>, frame: &impl Frame) {
+    match frame.id() {
+        embedded_can::Id::Standard(id) => defmt::write!(f, "{=u16:03X}#", id.as_raw()),
+        embedded_can::Id::Extended(id) => defmt::write!(f, "{=u32:08X}#", id.as_raw()),
+    }
+    if frame.is_remote_frame() {
+        defmt::write!(f, "R{=usize}", frame.dlc());
+    } else {
+        for byte in frame.data() {
+            defmt::write!(f, "{=u8:02X}", *byte);
+        }
+    }
+}
+
+impl From<SmallFrame> for MockFrame {
+    fn from(frame: SmallFrame) -> Self {
+        MockFrame::from_frame(&frame)
+    }
+}
+
+/// Error converting a [`MockFrame`] whose payload or remote DLC exceeds 8 bytes to a
+/// [`SmallFrame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameTooLarge {
+    /// The frame's payload length or remote DLC.
+    pub len: usize,
+}
+
+impl fmt::Display for FrameTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame of {} bytes does not fit in 8", self.len)
+    }
+}
+
+/// Fails if the payload or remote DLC exceeds 8 bytes.
+impl TryFrom<&MockFrame> for SmallFrame {
+    type Error = FrameTooLarge;
+
+    fn try_from(frame: &MockFrame) -> Result<Self, Self::Error> {
+        frame.to_frame().ok_or(FrameTooLarge { len: frame.dlc() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::{ExtendedId, Id, StandardId};
+
+    /// Minimal foreign frame type limited to 8 data bytes.
+    #[derive(Debug, PartialEq)]
+    struct Classic {
+        id: Id,
+        data: Vec<u8>,
+        remote: bool,
+    }
+
+    impl Frame for Classic {
+        fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+            (data.len() <= 8).then(|| Self {
+                id: id.into(),
+                data: data.to_vec(),
+                remote: false,
+            })
+        }
+        fn new_remote(id: impl Into<Id>, dlc: usize) -> Option<Self> {
+            (dlc <= 8).then(|| Self {
+                id: id.into(),
+                data: alloc::vec![0; dlc],
+                remote: true,
+            })
+        }
+        fn is_extended(&self) -> bool {
+            matches!(self.id, Id::Extended(_))
+        }
+        fn is_remote_frame(&self) -> bool {
+            self.remote
+        }
+        fn id(&self) -> Id {
+            self.id
+        }
+        fn dlc(&self) -> usize {
+            self.data.len()
+        }
+        fn data(&self) -> &[u8] {
+            if self.remote { &[] } else { &self.data }
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_foreign_frames() {
+        let id = Id::Extended(ExtendedId::new(0x1234).unwrap());
+        let data = MockFrame::from((id, &[1u8, 2][..]));
+        let foreign: Classic = data.to_frame().unwrap();
+        assert_eq!(MockFrame::from_frame(&foreign), data);
+
+        let remote = MockFrame::new_remote(StandardId::new(0x7).unwrap(), 3).unwrap();
+        let foreign: Classic = remote.to_frame().unwrap();
+        assert!(foreign.is_remote_frame());
+        assert_eq!(MockFrame::from_frame(&foreign), remote);
+
+        let long = MockFrame::from((id, alloc::vec![0u8; 12]));
+        assert_eq!(long.to_frame::<Classic>(), None);
+        assert_eq!(<(Id, Vec<u8>)>::from(long).1.len(), 12);
+    }
+
+    #[test]
+    fn metadata_survives_transit_and_takes_part_in_equality() {
+        let plain = MockFrame::new(StandardId::new(0x42).unwrap(), &[1, 2]).unwrap();
+        let mut fd = plain.clone();
+        fd.meta_mut();
+        assert_eq!(fd, plain);
+        fd.meta_mut().fd = true;
+        fd.meta_mut().brs = true;
+        let fd = fd.with_tag("trace", "7");
+        assert_ne!(fd, plain);
+
+        let bus = crate::BusHandle::new();
+        let tx = bus.add_interface(alloc::vec![]).unwrap();
+        let rx = bus.add_interface(alloc::vec![]).unwrap();
+        tx.transmit(fd.clone()).unwrap();
+        let received = rx.pop_frame().unwrap();
+        assert_eq!(received, fd);
+        assert!(received.meta().fd && received.meta().brs && !received.meta().esi);
+        assert_eq!(received.tag("trace"), Some("7"));
+
+        // Other frame types carry no metadata.
+        let classic: Classic = received.to_frame().unwrap();
+        assert_eq!(MockFrame::from_frame(&classic), plain);
+        let xl = plain.clone().with_meta(FrameMeta {
+            xl_sdu_type: Some(0x03),
+            ..FrameMeta::default()
+        });
+        assert_eq!(xl.meta().xl_sdu_type, Some(0x03));
+        assert_eq!(xl.tag("trace"), None);
+    }
+
+    #[test]
+    fn small_frames_compare_by_priority_and_ignore_stale_bytes() {
+        let low = SmallFrame::new(StandardId::new(0x200).unwrap(), &[9]).unwrap();
+        let high = SmallFrame::new(StandardId::new(0x100).unwrap(), &[1, 2]).unwrap();
+        assert!(high < low);
+        assert_eq!(high.to_string(), "100#0102");
+
+        let remote = SmallFrame::new_remote(StandardId::new(0x100).unwrap(), 2).unwrap();
+        assert_eq!(remote.data(), &[] as &[u8]);
+        assert_ne!(remote, high);
+        assert_eq!(MockFrame::from(remote).to_string(), "100#R2");
+
+        let long = MockFrame::new(StandardId::new(0x1).unwrap(), &[0; 9]).unwrap();
+        assert_eq!(SmallFrame::try_from(&long), Err(FrameTooLarge { len: 9 }));
+        assert_eq!(
+            FrameTooLarge { len: 9 }.to_string(),
+            "frame of 9 bytes does not fit in 8"
+        );
+    }
+
+    #[test]
+    fn candump_round_trips_and_rejects_malformed_input() {
+        for text in ["123#", "7FF#0011223344556677", "00000001#R0", "1FFFFFFF#AB"] {
+            assert_eq!(MockFrame::parse_candump(text).unwrap().fmt_candump(), text);
+        }
+        let errors = [
+            ("123", CandumpParseError::MissingSeparator),
+            ("800#00", CandumpParseError::InvalidId),
+            ("12#00", CandumpParseError::InvalidId),
+            ("123#ABC", CandumpParseError::InvalidData),
+            ("123#ZZ", CandumpParseError::InvalidData),
+            ("123#+A", CandumpParseError::InvalidData),
+            ("+12#00", CandumpParseError::InvalidId),
+            ("123#R9", CandumpParseError::InvalidData),
+            ("123#R99", CandumpParseError::InvalidData),
+            ("123##1AB", CandumpParseError::UnsupportedFd),
+        ];
+        for (text, error) in errors {
+            assert_eq!(MockFrame::parse_candump(text), Err(error), "{text}");
+        }
+    }
+
+    #[test]
+    fn hex_fixtures_infer_the_id_kind_and_reject_bad_literals() {
+        let fixture = crate::frames!["0#", "7FF#01", "800#01", "0x1FFFFFFF#aa.bb"];
+        let texts: Vec<String> = fixture.iter().map(MockFrame::fmt_candump).collect();
+        assert_eq!(texts, ["000#", "7FF#01", "00000800#01", "1FFFFFFF#AABB"]);
+        assert_eq!(
+            MockFrame::from_hex("#01"),
+            Err(CandumpParseError::InvalidId)
+        );
+        assert_eq!(
+            MockFrame::from_hex("123456789#01"),
+            Err(CandumpParseError::InvalidId)
+        );
+        let panic = std::panic::catch_unwind(|| hex_frames(&["123#0"])).unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "invalid frame literal `123#0`: InvalidData"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Gateway forwarding frames between two buses.
+//!
+//! A [`Gateway`] owns one interface on each side and forwards whatever they receive to the other
+//! side when [`pump`](Gateway::pump) is called. Real gateways rarely forward verbatim, so each
+//! direction carries its own translation rules: an ID remap table and payload transforms.
+//!
+//! ```
+//! use embedded_can::{Frame as _, Id, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::gateway::{Direction, Gateway};
+//!
+//! let body = BusHandle::new();
+//! let chassis = BusHandle::new();
+//! let gateway = Gateway::new(body.add_interface(vec![]).unwrap(), chassis.add_interface(vec![]).unwrap())
+//!     .with_remap(Direction::AToB, StandardId::new(0x100).unwrap(), StandardId::new(0x200).unwrap())
+//!     .with_payload_transform(Direction::AToB, |_id: Id, data: &mut Vec<u8>| data.reverse());
+//!
+//! let sensor = body.add_interface(vec![]).unwrap();
+//! let ecu = chassis.add_interface(vec![]).unwrap();
+//! sensor.transmit(MockFrame::new(StandardId::new(0x100).unwrap(), &[0x12, 0x34]).unwrap()).unwrap();
+//!
+//! assert_eq!(gateway.pump().unwrap(), 1);
+//! let forwarded = ecu.pop_frame().unwrap();
+//! assert_eq!(forwarded.id(), Id::Standard(StandardId::new(0x200).unwrap()));
+//! assert_eq!(forwarded.data(), [0x34, 0x12]);
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+
+use embedded_can::{Frame, Id};
+
+use crate::{
+    bus::{InterfaceHandle, TransmitError},
+    frame::MockFrame,
+};
+
+/// Forwarding direction through a [`Gateway`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// From the first interface's bus to the second's.
+    AToB,
+    /// From the second interface's bus to the first's.
+    BToA,
+}
+
+/// Payload transform applied to forwarded frames. Receives the ID the frame arrived with.
+pub type PayloadTransform = Box<dyn Fn(Id, &mut Vec<u8>) + Send + Sync>;
+
+/// Translation rules for one direction.
+#[derive(Default)]
+struct Rules {
+    remap: BTreeMap<Id, Id>,
+    transforms: Vec<PayloadTransform>,
+}
+
+impl Rules {
+    fn translate<F: Frame>(&self, frame: &F) -> Option<F> {
+        let id = self.remap.get(&frame.id()).copied().unwrap_or(frame.id());
+        if frame.is_remote_frame() {
+            return F::new_remote(id, frame.dlc());
+        }
+        let mut data = frame.data().to_vec();
+        for transform in &self.transforms {
+            transform(frame.id(), &mut data);
+        }
+        F::new(id, &data)
+    }
+}
+
+/// Store-and-forward gateway between two buses.
+///
+/// Both interfaces have self-reception disabled so the gateway never forwards its own frames
+/// back. Forwarded frames are sent from the gateway's interface on the destination bus, so
+/// filters on that interface decide which frames the gateway picks up.
+pub struct Gateway<F = MockFrame> {
+    a: InterfaceHandle<F>,
+    b: InterfaceHandle<F>,
+    a_to_b: Rules,
+    b_to_a: Rules,
+}
+
+impl<F: Frame + Clone + 'static> Gateway<F> {
+    /// Create a gateway between the buses `a` and `b` are attached to.
+    pub fn new(a: InterfaceHandle<F>, b: InterfaceHandle<F>) -> Self {
+        a.set_self_reception(false);
+        b.set_self_reception(false);
+        Self {
+            a,
+            b,
+            a_to_b: Rules::default(),
+            b_to_a: Rules::default(),
+        }
+    }
+
+    /// Forward frames arriving with ID `from` under ID `to` in `direction`.
+    pub fn with_remap(
+        mut self,
+        direction: Direction,
+        from: impl Into<Id>,
+        to: impl Into<Id>,
+    ) -> Self {
+        self.rules_mut(direction)
+            .remap
+            .insert(from.into(), to.into());
+        self
+    }
+
+    /// Apply `transform` to the payload of data frames forwarded in `direction`.
+    ///
+    /// Transforms run in the order they were added and receive the ID the frame arrived with
+    /// (before remapping). Frames whose transformed payload does not fit the frame type are
+    /// dropped.
+    pub fn with_payload_transform(
+        mut self,
+        direction: Direction,
+        transform: impl Fn(Id, &mut Vec<u8>) + Send + Sync + 'static,
+    ) -> Self {
+        self.rules_mut(direction)
+            .transforms
+            .push(Box::new(transform));
+        self
+    }
+
+    /// The gateway's interface on the first bus.
+    pub fn a(&self) -> &InterfaceHandle<F> {
+        &self.a
+    }
+
+    /// The gateway's interface on the second bus.
+    pub fn b(&self) -> &InterfaceHandle<F> {
+        &self.b
+    }
+
+    /// Forward every frame currently queued on either side, translating it on the way.
+    ///
+    /// Returns the number of frames forwarded. Frames that arrive as a consequence of forwarding
+    /// (for example replies) are left for the next call.
+    pub fn pump(&self) -> Result<usize, TransmitError> {
+        let forwarded = forward(&self.a, &self.b, &self.a_to_b)?;
+        Ok(forwarded + forward(&self.b, &self.a, &self.b_to_a)?)
+    }
+
+    fn rules_mut(&mut self, direction: Direction) -> &mut Rules {
+        match direction {
+            Direction::AToB => &mut self.a_to_b,
+            Direction::BToA => &mut self.b_to_a,
+        }
+    }
+}
+
+fn forward<F: Frame + Clone + 'static>(
+    from: &InterfaceHandle<F>,
+    to: &InterfaceHandle<F>,
+    rules: &Rules,
+) -> Result<usize, TransmitError> {
+    let mut forwarded = 0;
+    for frame in from.drain_frames() {
+        if let Some(frame) = rules.translate(&frame) {
+            to.transmit(frame)?;
+            forwarded += 1;
+        }
+    }
+    Ok(forwarded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusHandle, SmallFrame};
+    use embedded_can::{ExtendedId, StandardId};
+
+    fn frame(id: impl Into<Id>, data: &[u8]) -> SmallFrame {
+        SmallFrame::new(id, data).unwrap()
+    }
+
+    #[test]
+    fn rules_apply_per_direction_and_do_not_echo() {
+        let left = BusHandle::<SmallFrame>::default();
+        let right = BusHandle::<SmallFrame>::default();
+        let gateway = Gateway::new(
+            left.add_interface(vec![]).unwrap(),
+            right.add_interface(vec![]).unwrap(),
+        )
+        .with_remap(
+            Direction::BToA,
+            ExtendedId::new(0x18FF_0001).unwrap(),
+            StandardId::new(0x321).unwrap(),
+        )
+        .with_payload_transform(Direction::BToA, |_, data| data.push(0xEE));
+        let l = left.add_interface(vec![]).unwrap();
+        let r = right.add_interface(vec![]).unwrap();
+        l.set_self_reception(false);
+        r.set_self_reception(false);
+
+        l.transmit(frame(StandardId::new(0x10).unwrap(), &[1]))
+            .unwrap();
+        r.transmit(frame(ExtendedId::new(0x18FF_0001).unwrap(), &[2]))
+            .unwrap();
+        assert_eq!(gateway.pump().unwrap(), 2);
+
+        assert_eq!(
+            r.drain_frames(),
+            vec![frame(StandardId::new(0x10).unwrap(), &[1])]
+        );
+        assert_eq!(
+            l.drain_frames(),
+            vec![frame(StandardId::new(0x321).unwrap(), &[2, 0xEE])]
+        );
+        assert_eq!(gateway.pump().unwrap(), 0);
+
+        // A payload that no longer fits is dropped rather than forwarded.
+        r.transmit(frame(ExtendedId::new(0x18FF_0001).unwrap(), &[0; 8]))
+            .unwrap();
+        assert_eq!(gateway.pump().unwrap(), 0);
+        assert!(!l.has_frames());
+    }
+}
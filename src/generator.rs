@@ -0,0 +1,217 @@
+//! Deterministic background traffic.
+//!
+//! A [`TrafficGenerator`] owns its own interface on a bus and emits frames following a
+//! [`TrafficPattern`]: IDs drawn from a range, payloads from a [`PayloadPattern`], and bursts of
+//! frames at a fixed period on the bus’s virtual clock. All randomness comes from a seed, so the
+//! same seed always produces the same traffic.
+
+use alloc::vec::Vec;
+use core::{ops::RangeInclusive, time::Duration};
+
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+
+use crate::{
+    bus::{BusHandle, InterfaceHandle, MockInterfaceError, TransmitError},
+    frame::MockFrame,
+    rng::Rng,
+};
+
+/// Range of IDs a generator draws from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdRange {
+    /// Standard IDs (values above `0x7FF` are clamped).
+    Standard(RangeInclusive<u16>),
+    /// Extended IDs (values above `0x1FFF_FFFF` are clamped).
+    Extended(RangeInclusive<u32>),
+}
+
+/// How generated payloads are produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadPattern {
+    /// Always the same bytes.
+    Fixed(Vec<u8>),
+    /// Random bytes with a random length in the given range.
+    Random(RangeInclusive<usize>),
+    /// `len` bytes holding a little-endian counter incremented per frame.
+    Counter(usize),
+}
+
+/// Shape of the traffic emitted by a [`TrafficGenerator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficPattern {
+    /// IDs to draw from.
+    pub ids: IdRange,
+    /// Payload generation.
+    pub payload: PayloadPattern,
+    /// Virtual time between bursts.
+    pub period: Duration,
+    /// Number of frames per burst; a range makes traffic bursty.
+    pub burst: RangeInclusive<usize>,
+}
+
+impl Default for TrafficPattern {
+    /// One random 8-byte standard frame every millisecond.
+    fn default() -> Self {
+        Self {
+            ids: IdRange::Standard(0..=0x7FF),
+            payload: PayloadPattern::Random(8..=8),
+            period: Duration::from_millis(1),
+            burst: 1..=1,
+        }
+    }
+}
+
+/// Seeded source of background traffic attached to a bus.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can_mock::BusHandle;
+/// use embedded_can_mock::generator::{IdRange, TrafficGenerator, TrafficPattern};
+/// use std::time::Duration;
+///
+/// let bus = BusHandle::new();
+/// let sink = bus.add_interface(vec![]).unwrap();
+/// let pattern = TrafficPattern { ids: IdRange::Standard(0x100..=0x1FF), ..Default::default() };
+/// let mut generator = TrafficGenerator::attach(&bus, pattern, 7).unwrap();
+///
+/// let sent = generator.run_for(Duration::from_millis(10)).unwrap();
+/// assert_eq!(sent, 10);
+/// assert_eq!(sink.received_frames().len(), 10);
+/// assert_eq!(bus.now(), Duration::from_millis(10));
+/// ```
+pub struct TrafficGenerator {
+    iface: InterfaceHandle,
+    bus: BusHandle,
+    pattern: TrafficPattern,
+    rng: Rng,
+    counter: u64,
+    next_burst: Duration,
+}
+
+impl TrafficGenerator {
+    /// Attach a new generator interface to `bus`, seeded with `seed`.
+    ///
+    /// The first burst is due at the bus’s current virtual time.
+    pub fn attach(
+        bus: &BusHandle,
+        pattern: TrafficPattern,
+        seed: u64,
+    ) -> Result<Self, MockInterfaceError> {
+        Ok(Self {
+            iface: bus.add_interface(Vec::new())?,
+            bus: bus.clone(),
+            pattern,
+            rng: Rng::new(seed),
+            counter: 0,
+            next_burst: bus.now(),
+        })
+    }
+
+    /// The generator’s own interface (e.g. to look up its [`InterfaceId`](crate::InterfaceId)).
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.iface
+    }
+
+    /// Produce the next frame without transmitting it.
+    pub fn next_frame(&mut self) -> MockFrame {
+        let id = match &self.pattern.ids {
+            IdRange::Standard(range) => {
+                let raw = self.rng.range(*range.start() as u64, *range.end() as u64) as u16;
+                Id::Standard(StandardId::new(raw.min(0x7FF)).unwrap())
+            }
+            IdRange::Extended(range) => {
+                let raw = self.rng.range(*range.start() as u64, *range.end() as u64) as u32;
+                Id::Extended(ExtendedId::new(raw.min(0x1FFF_FFFF)).unwrap())
+            }
+        };
+        let data = match &self.pattern.payload {
+            PayloadPattern::Fixed(data) => data.clone(),
+            PayloadPattern::Random(len) => {
+                let len = self.rng.range(*len.start() as u64, *len.end() as u64) as usize;
+                (0..len).map(|_| self.rng.next_u64() as u8).collect()
+            }
+            PayloadPattern::Counter(len) => {
+                let bytes = self.counter.to_le_bytes();
+                (0..*len)
+                    .map(|i| bytes.get(i).copied().unwrap_or(0))
+                    .collect()
+            }
+        };
+        self.counter = self.counter.wrapping_add(1);
+        MockFrame::new(id, &data).unwrap()
+    }
+
+    /// Transmit one burst immediately; returns the number of frames sent.
+    pub fn emit_burst(&mut self) -> Result<usize, TransmitError> {
+        let burst = &self.pattern.burst;
+        let count = self.rng.range(*burst.start() as u64, *burst.end() as u64) as usize;
+        let frames: Vec<MockFrame> = (0..count).map(|_| self.next_frame()).collect();
+        self.iface.transmit_all(&frames)?;
+        // The generator does not consume traffic itself.
+        self.iface.pop_frames(usize::MAX);
+        Ok(count)
+    }
+
+    /// Emit every burst due within the next `duration` of virtual time, advancing the bus clock
+    /// to each burst and finally to the end of the window. Returns the number of frames sent.
+    pub fn run_for(&mut self, duration: Duration) -> Result<usize, TransmitError> {
+        let end = self.bus.now() + duration;
+        let mut sent = 0;
+        while self.next_burst < end {
+            let now = self.bus.now();
+            if self.next_burst > now {
+                self.bus.advance(self.next_burst - now);
+            }
+            sent += self.emit_burst()?;
+            self.next_burst += self.pattern.period.max(Duration::from_nanos(1));
+        }
+        let now = self.bus.now();
+        self.bus.advance(end.saturating_sub(now));
+        self.iface.pop_frames(usize::MAX);
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(seed: u64) -> Vec<MockFrame> {
+        let bus = BusHandle::new();
+        let sink = bus.add_interface(Vec::new()).unwrap();
+        let pattern = TrafficPattern {
+            ids: IdRange::Extended(0x1000..=0x10FF),
+            payload: PayloadPattern::Random(0..=8),
+            period: Duration::from_micros(500),
+            burst: 0..=3,
+        };
+        let mut generator = TrafficGenerator::attach(&bus, pattern, seed).unwrap();
+        generator.run_for(Duration::from_millis(20)).unwrap();
+        assert!(!generator.interface().has_frames());
+        sink.received_frames()
+    }
+
+    #[test]
+    fn same_seed_reproduces_traffic() {
+        let a = run(1234);
+        assert_eq!(a, run(1234));
+        assert_ne!(a, run(4321));
+        assert!(a.iter().all(|f| match f.id() {
+            Id::Extended(id) => (0x1000..=0x10FF).contains(&id.as_raw()) && f.dlc() <= 8,
+            Id::Standard(_) => false,
+        }));
+    }
+
+    #[test]
+    fn counter_payload_increments() {
+        let bus = BusHandle::new();
+        let pattern = TrafficPattern {
+            payload: PayloadPattern::Counter(2),
+            ..Default::default()
+        };
+        let mut generator = TrafficGenerator::attach(&bus, pattern, 0).unwrap();
+        assert_eq!(generator.next_frame().data(), &[0, 0]);
+        assert_eq!(generator.next_frame().data(), &[1, 0]);
+    }
+}
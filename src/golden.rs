@@ -0,0 +1,448 @@
+//! Golden-trace snapshot testing.
+//!
+//! [`Recorder::to_golden`](crate::recorder::Recorder::to_golden) renders a recording as text
+//! that does not depend on the run: one line per completed frame, in completion order, with
+//! nodes numbered by first appearance (`n0`, `n1`, ...) rather than by their process-wide
+//! [`InterfaceId`](crate::bus::InterfaceId)s, and receivers sorted.
+//!
+//! ```text
+//! # embedded-can-mock golden trace
+//! 0.000000 n0 123#AB -> n0 n1
+//! 0.000250 n1 020# ! CrcError
+//! ```
+//!
+//! Each line holds the virtual completion time in seconds, the transmitter, the frame in
+//! `candump` notation, and either `->` and the nodes that queued the frame or `!` and the
+//! injected error that replaced delivery. Blank lines and lines starting with `#` are ignored,
+//! so committed golden files can carry comments.
+//!
+//! [`compare_traces`] checks a recording against a golden file, allowing for timing jitter and
+//! ignoring IDs that are not part of the conversation under test (heartbeats, say). On mismatch
+//! the error lists both traces side by side with the differing lines marked.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::golden::{Tolerances, compare_traces};
+//! use embedded_can_mock::recorder::Recorder;
+//!
+//! let bus = BusHandle::new();
+//! let recorder = Recorder::attach(&bus);
+//! let a = bus.add_interface(vec![]).unwrap();
+//! let b = bus.add_interface(vec![]).unwrap();
+//! let frame = |id, data: &[u8]| MockFrame::new(StandardId::new(id).unwrap(), data).unwrap();
+//! a.transmit(frame(0x700, &[0x05])).unwrap();
+//! a.transmit(frame(0x7E0, &[0x02, 0x10, 0x03])).unwrap();
+//! b.transmit(frame(0x7E8, &[0x06, 0x50, 0x03])).unwrap();
+//!
+//! let golden = "\
+//! ## diagnostic session change
+//! 0.000000 n0 7E0#021003 -> n0 n1
+//! 0.000000 n1 7E8#065003 -> n0 n1
+//! ";
+//! let tolerances = Tolerances { ignore_ids: vec![StandardId::new(0x700).unwrap().into()], ..Default::default() };
+//! compare_traces(golden, &recorder.to_golden(), &tolerances).unwrap();
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, time::Duration};
+
+use embedded_can::{Frame, Id};
+
+use crate::{frame::MockFrame, recorder::TraceRecord};
+
+/// First line of rendered golden traces.
+pub const HEADER: &str = "# embedded-can-mock golden trace";
+
+/// One line of a golden trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenRecord {
+    /// Virtual time at which the frame completed.
+    pub at: Duration,
+    /// Transmitter, numbered by first appearance in the trace.
+    pub source: usize,
+    /// The frame.
+    pub frame: MockFrame,
+    /// Nodes that queued the frame, in ascending order.
+    pub receivers: Vec<usize>,
+    /// Injected error that replaced delivery, as rendered with `Debug`.
+    pub error: Option<String>,
+}
+
+impl GoldenRecord {
+    /// Convert `records` (in completion order), numbering nodes by first appearance.
+    pub fn from_records<F: Frame>(records: &[TraceRecord<F>]) -> Vec<Self> {
+        let mut nodes = Vec::new();
+        let mut number = |id| match nodes.iter().position(|n| *n == id) {
+            Some(n) => n,
+            None => {
+                nodes.push(id);
+                nodes.len() - 1
+            }
+        };
+        records
+            .iter()
+            .map(|record| {
+                let source = number(record.token.source());
+                let mut receivers: Vec<usize> =
+                    record.receivers.iter().map(|id| number(*id)).collect();
+                receivers.sort_unstable();
+                Self {
+                    at: record.at,
+                    source,
+                    frame: MockFrame::from_frame(&record.frame),
+                    receivers,
+                    error: record.error.as_ref().map(|error| format!("{error:?}")),
+                }
+            })
+            .collect()
+    }
+
+    /// Parse one line in the format written by `Display`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let mut next = |what| words.next().ok_or(format!("missing {what}"));
+        let at = parse_time(next("time")?)?;
+        let source = parse_node(next("transmitter")?)?;
+        let candump = next("frame")?;
+        let frame = MockFrame::parse_candump(candump)
+            .map_err(|err| format!("invalid frame `{candump}`: {err:?}"))?;
+        let (receivers, error) = match next("`->` or `!`")? {
+            "->" => (words.map(parse_node).collect::<Result<Vec<_>, _>>()?, None),
+            "!" => {
+                let error = words.collect::<Vec<_>>().join(" ");
+                if error.is_empty() {
+                    return Err("missing error after `!`".to_string());
+                }
+                (Vec::new(), Some(error))
+            }
+            other => return Err(format!("expected `->` or `!`, found `{other}`")),
+        };
+        Ok(Self {
+            at,
+            source,
+            frame,
+            receivers,
+            error,
+        })
+    }
+
+    fn id(&self) -> Id {
+        self.frame.id()
+    }
+}
+
+impl fmt::Display for GoldenRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} n{} {}",
+            self.at.as_secs(),
+            self.at.subsec_micros(),
+            self.source,
+            self.frame.fmt_candump()
+        )?;
+        match &self.error {
+            Some(error) => write!(f, " ! {error}"),
+            None => {
+                f.write_str(" ->")?;
+                self.receivers
+                    .iter()
+                    .try_for_each(|receiver| write!(f, " n{receiver}"))
+            }
+        }
+    }
+}
+
+fn parse_time(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid time `{text}`");
+    let (secs, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let secs = secs.parse().map_err(|_| invalid())?;
+    let nanos = format!("{fraction:0<9}").parse().map_err(|_| invalid())?;
+    Ok(Duration::new(secs, nanos))
+}
+
+fn parse_node(text: &str) -> Result<usize, String> {
+    text.strip_prefix('n')
+        .and_then(|n| n.parse().ok())
+        .ok_or(format!("invalid node `{text}`"))
+}
+
+/// Render `records` as a golden trace, header included.
+pub fn render(records: &[GoldenRecord]) -> String {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+    for record in records {
+        out += &format!("{record}\n");
+    }
+    out
+}
+
+/// Parse a golden trace, skipping blank lines and `#` comments.
+///
+/// Errors carry the 1-based line number.
+pub fn parse(text: &str) -> Result<Vec<GoldenRecord>, (usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| GoldenRecord::parse(line).map_err(|reason| (n, reason)))
+        .collect()
+}
+
+/// What [`compare_traces`] lets differ between the expected and actual traces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tolerances {
+    /// How far each frame's completion time may be from the expected one.
+    pub time: Duration,
+    /// Frames with these IDs are removed from both traces before comparing; nodes are then
+    /// renumbered, so a node that only sent ignored frames does not shift the others.
+    pub ignore_ids: Vec<Id>,
+}
+
+/// Two traces that [`compare_traces`] found different.
+///
+/// The `Display` output lists both traces side by side and marks each differing line.
+#[derive(Debug)]
+pub struct TraceMismatch {
+    /// Index of the first difference, after ignored frames are removed.
+    pub index: usize,
+    /// What differs at [`index`](Self::index).
+    pub reason: String,
+    /// Indices of every differing line.
+    pub differing: Vec<usize>,
+    /// Expected trace, after ignored frames are removed.
+    pub expected: Vec<GoldenRecord>,
+    /// Actual trace, after ignored frames are removed.
+    pub actual: Vec<GoldenRecord>,
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "trace mismatch at index {}: {} (expected {} frames, recorded {})",
+            self.index,
+            self.reason,
+            self.expected.len(),
+            self.actual.len()
+        )?;
+        writeln!(f, "      {:<40} actual", "expected")?;
+        let rows = self.expected.len().max(self.actual.len());
+        for i in 0..rows {
+            let marker = if self.differing.contains(&i) {
+                ">>"
+            } else {
+                "  "
+            };
+            let line = |records: &[GoldenRecord]| {
+                records
+                    .get(i)
+                    .map_or_else(|| "-".to_string(), |record| format!("{record}"))
+            };
+            writeln!(
+                f,
+                "{marker}{i:>3} {:<40} {}",
+                line(&self.expected),
+                line(&self.actual)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned by [`compare_traces`].
+#[derive(Debug)]
+pub enum CompareError {
+    /// A trace is not valid golden-trace text.
+    Parse {
+        /// `"expected"` or `"actual"`.
+        trace: &'static str,
+        /// 1-based line number.
+        line: usize,
+        /// What is wrong with the line.
+        reason: String,
+    },
+    /// The traces differ.
+    Mismatch(TraceMismatch),
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse {
+                trace,
+                line,
+                reason,
+            } => write!(f, "{trace} trace, line {line}: {reason}"),
+            Self::Mismatch(mismatch) => mismatch.fmt(f),
+        }
+    }
+}
+
+/// Compare two golden traces, typically a committed file and
+/// [`Recorder::to_golden`](crate::recorder::Recorder::to_golden), within `tolerances`.
+///
+/// Lines are compared in order: transmitter, frame, receivers and error must match exactly and
+/// the time within [`Tolerances::time`].
+pub fn compare_traces(
+    expected: &str,
+    actual: &str,
+    tolerances: &Tolerances,
+) -> Result<(), CompareError> {
+    let load = |trace, text| {
+        parse(text)
+            .map(|records| without_ignored(records, &tolerances.ignore_ids))
+            .map_err(|(line, reason)| CompareError::Parse {
+                trace,
+                line,
+                reason,
+            })
+    };
+    let expected = load("expected", expected)?;
+    let actual = load("actual", actual)?;
+
+    let mut reasons = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let reason = match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) => difference(e, a, tolerances.time),
+            (Some(_), None) => Some("frame missing".to_string()),
+            (None, Some(_)) => Some("unexpected frame".to_string()),
+            (None, None) => None,
+        };
+        if let Some(reason) = reason {
+            reasons.push((i, reason));
+        }
+    }
+    let Some((index, reason)) = reasons.first().cloned() else {
+        return Ok(());
+    };
+    Err(CompareError::Mismatch(TraceMismatch {
+        index,
+        reason,
+        differing: reasons.into_iter().map(|(i, _)| i).collect(),
+        expected,
+        actual,
+    }))
+}
+
+/// Compare two golden traces and panic with a side-by-side diff unless they match.
+#[track_caller]
+pub fn assert_traces(expected: &str, actual: &str, tolerances: &Tolerances) {
+    if let Err(err) = compare_traces(expected, actual, tolerances) {
+        panic!("{err}");
+    }
+}
+
+/// Drop records with ignored IDs and renumber the remaining nodes by first appearance.
+fn without_ignored(records: Vec<GoldenRecord>, ignore: &[Id]) -> Vec<GoldenRecord> {
+    let mut nodes = Vec::new();
+    let mut number = |n| match nodes.iter().position(|old| *old == n) {
+        Some(n) => n,
+        None => {
+            nodes.push(n);
+            nodes.len() - 1
+        }
+    };
+    records
+        .into_iter()
+        .filter(|record| !ignore.contains(&record.id()))
+        .map(|mut record| {
+            record.source = number(record.source);
+            for receiver in &mut record.receivers {
+                *receiver = number(*receiver);
+            }
+            record.receivers.sort_unstable();
+            record
+        })
+        .collect()
+}
+
+fn difference(expected: &GoldenRecord, actual: &GoldenRecord, time: Duration) -> Option<String> {
+    let skew = expected.at.abs_diff(actual.at);
+    if expected.frame.fmt_candump() != actual.frame.fmt_candump() {
+        Some("frame differs".to_string())
+    } else if expected.source != actual.source {
+        Some("transmitter differs".to_string())
+    } else if expected.receivers != actual.receivers || expected.error != actual.error {
+        Some("delivery differs".to_string())
+    } else if skew > time {
+        Some(format!("time off by {skew:?}, tolerance {time:?}"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison_tolerates_jitter_and_ignored_ids_but_reports_changes() {
+        let golden = "\
+# comment
+0.001000 n0 700#05 -> n1
+0.002000 n1 123#0102 -> n0 n2
+
+0.003000 n2 456# ! CrcError
+";
+        let records = parse(golden).unwrap();
+        assert_eq!(records[1].at, Duration::from_millis(2));
+        assert_eq!(records[2].error.as_deref(), Some("CrcError"));
+        assert_eq!(
+            parse(&render(&records)).unwrap(),
+            records,
+            "rendering round-trips"
+        );
+
+        // Without the heartbeat its sender is renumbered after the first receiver; times drift.
+        let actual = "\
+0.002040 n0 123#0102 -> n1 n2
+0.002990 n2 456# ! CrcError
+";
+        let tolerances = Tolerances {
+            time: Duration::from_micros(50),
+            ignore_ids: alloc::vec![embedded_can::StandardId::new(0x700).unwrap().into()],
+        };
+        compare_traces(golden, actual, &tolerances).unwrap();
+
+        let strict = Tolerances {
+            time: Duration::from_micros(20),
+            ..tolerances.clone()
+        };
+        let Err(CompareError::Mismatch(mismatch)) = compare_traces(golden, actual, &strict) else {
+            panic!("jitter beyond the tolerance must be reported");
+        };
+        assert_eq!(mismatch.differing, [0]);
+        assert_eq!(mismatch.reason, "time off by 40µs, tolerance 20µs");
+
+        let changed = "0.002000 n0 123#0103 -> n1 n2\n";
+        let Err(CompareError::Mismatch(mismatch)) = compare_traces(golden, changed, &tolerances)
+        else {
+            panic!("changed and missing frames must be reported");
+        };
+        assert_eq!((mismatch.index, &*mismatch.differing), (0, &[0, 1][..]));
+        let diff = mismatch.to_string();
+        assert!(
+            diff.contains(">>  0 0.002000 n0 123#0102 -> n1 n2"),
+            "{diff}"
+        );
+        assert!(diff.contains(">>  1 0.003000 n2 456# ! CrcError"), "{diff}");
+
+        assert!(matches!(
+            compare_traces("0.0 n0 123# => n1", "", &tolerances),
+            Err(CompareError::Parse {
+                trace: "expected",
+                line: 1,
+                ..
+            })
+        ));
+    }
+}
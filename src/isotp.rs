@@ -0,0 +1,321 @@
+//! Minimal ISO-TP (ISO 15765-2) transport over the mock bus.
+//!
+//! An [`IsoTpEndpoint`] segments outgoing messages into single, first and consecutive frames and
+//! reassembles incoming ones, answering first frames with a flow-control frame. It is driven by
+//! [`poll`](IsoTpEndpoint::poll) rather than by its own thread, which keeps exchanges
+//! deterministic. Frames are always padded to 8 bytes with [`PADDING`].
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::isotp::IsoTpEndpoint;
+//!
+//! let bus = BusHandle::new();
+//! let (a, b) = (StandardId::new(0x7E0).unwrap(), StandardId::new(0x7E8).unwrap());
+//! let mut tester = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), a, b);
+//! let mut ecu = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), b, a);
+//!
+//! let message: Vec<u8> = (0..20).collect();
+//! tester.send(&message).unwrap();
+//! assert!(ecu.poll().unwrap().is_empty()); // first frame received, flow control sent
+//! assert!(tester.poll().unwrap().is_empty()); // flow control received, consecutive frames sent
+//! assert_eq!(ecu.poll().unwrap(), vec![message]);
+//! ```
+//!
+//! Only classic CAN addressing with 8-byte frames is supported; block size and separation time in
+//! received flow-control frames are ignored apart from wait and overflow handling.
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use embedded_can::{Frame, Id};
+
+use crate::{
+    arbitrary,
+    bus::{InterfaceHandle, TransmitError},
+    frame::MockFrame,
+};
+
+/// Byte used to pad frames to 8 bytes.
+pub const PADDING: u8 = 0xCC;
+
+/// Largest message an ISO-TP first frame can announce (12-bit length).
+pub const MAX_MESSAGE_LEN: usize = 4095;
+
+struct Reassembly {
+    data: Vec<u8>,
+    len: usize,
+    next_sn: u8,
+}
+
+struct PendingTx {
+    data: Vec<u8>,
+    offset: usize,
+    next_sn: u8,
+}
+
+/// One side of an ISO-TP connection.
+pub struct IsoTpEndpoint {
+    interface: InterfaceHandle,
+    tx_id: Id,
+    rx_id: Id,
+    functional_id: Option<Id>,
+    rx: Option<Reassembly>,
+    tx: Option<PendingTx>,
+    /// Messages sent while a multi-frame message was still in flight, in order.
+    queued: VecDeque<Vec<u8>>,
+}
+
+impl IsoTpEndpoint {
+    /// Create an endpoint sending on `tx_id` and receiving on `rx_id` via `interface`.
+    ///
+    /// The interface's filters are replaced with one accepting only `rx_id`, and self-reception
+    /// is turned off.
+    pub fn new(interface: InterfaceHandle, tx_id: impl Into<Id>, rx_id: impl Into<Id>) -> Self {
+        interface.set_self_reception(false);
+        let mut endpoint = Self {
+            interface,
+            tx_id: tx_id.into(),
+            rx_id: rx_id.into(),
+            functional_id: None,
+            rx: None,
+            tx: None,
+            queued: VecDeque::new(),
+        };
+        endpoint.update_filters();
+        endpoint
+    }
+
+    /// Also accept single-frame requests on the functional (broadcast) address `id`.
+    pub fn with_functional_id(mut self, id: impl Into<Id>) -> Self {
+        self.functional_id = Some(id.into());
+        self.update_filters();
+        self
+    }
+
+    /// The interface this endpoint uses.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.interface
+    }
+
+    /// Returns `true` while a multi-frame message is waiting for flow control, or messages are
+    /// queued behind one.
+    pub fn is_sending(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// Send `message`, as a single frame if it fits or as a first frame otherwise; the remaining
+    /// consecutive frames go out from [`poll`](Self::poll) once flow control arrives.
+    ///
+    /// While a multi-frame message is still in flight, `message` is queued and goes out from
+    /// `poll` once the earlier ones are done; errors transmitting it are returned from there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `message` is empty or longer than [`MAX_MESSAGE_LEN`].
+    pub fn send(&mut self, message: &[u8]) -> Result<(), TransmitError> {
+        assert!(
+            (1..=MAX_MESSAGE_LEN).contains(&message.len()),
+            "ISO-TP message length {} out of range",
+            message.len()
+        );
+        if self.tx.is_some() {
+            self.queued.push_back(message.to_vec());
+            return Ok(());
+        }
+        self.start(message)
+    }
+
+    fn start(&mut self, message: &[u8]) -> Result<(), TransmitError> {
+        if message.len() <= 7 {
+            let mut pci = vec![message.len() as u8];
+            pci.extend_from_slice(message);
+            return self.transmit(&pci);
+        }
+        let len = message.len() as u16;
+        let mut first = vec![0x10 | (len >> 8) as u8, len as u8];
+        first.extend_from_slice(&message[..6]);
+        self.transmit(&first)?;
+        self.tx = Some(PendingTx {
+            data: message.to_vec(),
+            offset: 6,
+            next_sn: 1,
+        });
+        Ok(())
+    }
+
+    /// Process received frames, returning the messages completed by them.
+    pub fn poll(&mut self) -> Result<Vec<Vec<u8>>, TransmitError> {
+        let mut complete = Vec::new();
+        for frame in self.interface.drain_frames() {
+            let functional = Some(frame.id()) == self.functional_id && frame.id() != self.rx_id;
+            let data = frame.data();
+            let Some(&pci) = data.first() else {
+                continue;
+            };
+            match pci >> 4 {
+                0 => {
+                    let len = usize::from(pci & 0x0F);
+                    if (1..=7).contains(&len) && data.len() > len {
+                        self.rx = None;
+                        complete.push(data[1..=len].to_vec());
+                    }
+                }
+                1 if !functional && data.len() == 8 => {
+                    let len = usize::from(pci & 0x0F) << 8 | usize::from(data[1]);
+                    // Messages that fit a single frame must not be segmented (ISO 15765-2).
+                    if len < 8 {
+                        self.rx = None;
+                        continue;
+                    }
+                    self.rx = Some(Reassembly {
+                        data: data[2..].to_vec(),
+                        len,
+                        next_sn: 1,
+                    });
+                    self.transmit(&[0x30, 0x00, 0x00])?;
+                }
+                2 if !functional => {
+                    let Some(rx) = &mut self.rx else {
+                        continue;
+                    };
+                    if pci & 0x0F != rx.next_sn {
+                        self.rx = None;
+                        continue;
+                    }
+                    rx.next_sn = (rx.next_sn + 1) & 0x0F;
+                    let take = rx.len.saturating_sub(rx.data.len()).min(data.len() - 1);
+                    rx.data.extend_from_slice(&data[1..1 + take]);
+                    if rx.data.len() == rx.len {
+                        complete.push(self.rx.take().unwrap().data);
+                    }
+                }
+                3 if !functional => match pci & 0x0F {
+                    // Clear to send: the whole remainder goes out.
+                    0 => {
+                        self.send_consecutive()?;
+                        self.send_queued()?;
+                    }
+                    // Overflow/abort.
+                    2 => {
+                        self.tx = None;
+                        self.send_queued()?;
+                    }
+                    // Wait: keep the pending message for the next flow control.
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        Ok(complete)
+    }
+
+    /// Start queued messages until one needs flow control again.
+    fn send_queued(&mut self) -> Result<(), TransmitError> {
+        while self.tx.is_none() {
+            let Some(message) = self.queued.pop_front() else {
+                break;
+            };
+            self.start(&message)?;
+        }
+        Ok(())
+    }
+
+    fn send_consecutive(&mut self) -> Result<(), TransmitError> {
+        let Some(mut tx) = self.tx.take() else {
+            return Ok(());
+        };
+        while tx.offset < tx.data.len() {
+            let end = (tx.offset + 7).min(tx.data.len());
+            let mut frame = vec![0x20 | tx.next_sn];
+            frame.extend_from_slice(&tx.data[tx.offset..end]);
+            self.transmit(&frame)?;
+            tx.offset = end;
+            tx.next_sn = (tx.next_sn + 1) & 0x0F;
+        }
+        Ok(())
+    }
+
+    fn transmit(&self, payload: &[u8]) -> Result<(), TransmitError> {
+        let mut data = [PADDING; 8];
+        data[..payload.len()].copy_from_slice(payload);
+        self.interface
+            .transmit(MockFrame::new(self.tx_id, &data).unwrap())
+            .map(drop)
+    }
+
+    fn update_filters(&mut self) {
+        let exact = |id| arbitrary::filter(id, u32::MAX);
+        let mut filters = vec![exact(self.rx_id)];
+        filters.extend(self.functional_id.map(exact));
+        self.interface.set_filters(filters).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+    use embedded_can::StandardId;
+
+    #[test]
+    fn single_frames_and_flow_control_wait_are_handled() {
+        let bus = BusHandle::new();
+        let tx = StandardId::new(0x700).unwrap();
+        let rx = StandardId::new(0x708).unwrap();
+        let mut a = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), tx, rx);
+        let peer = bus.add_interface(vec![]).unwrap();
+        peer.set_self_reception(false);
+
+        a.send(&[0x3E, 0x00]).unwrap();
+        assert_eq!(
+            peer.pop_frame().unwrap().data(),
+            [
+                0x02, 0x3E, 0x00, PADDING, PADDING, PADDING, PADDING, PADDING
+            ]
+        );
+
+        a.send(&[0x11; 10]).unwrap();
+        assert_eq!(peer.pop_frame().unwrap().data()[..2], [0x10, 10]);
+        let fc = |status: u8| MockFrame::new(rx, &[0x30 | status, 0, 0]).unwrap();
+        peer.transmit(fc(1)).unwrap();
+        a.poll().unwrap();
+        assert!(a.is_sending());
+        assert!(!peer.has_frames());
+        peer.transmit(fc(0)).unwrap();
+        a.poll().unwrap();
+        assert!(!a.is_sending());
+        assert_eq!(
+            peer.pop_frame().unwrap().data()[..5],
+            [0x21, 0x11, 0x11, 0x11, 0x11]
+        );
+    }
+
+    #[test]
+    fn short_first_frames_are_ignored_and_sends_queue_behind_segmented_ones() {
+        let bus = BusHandle::new();
+        let tx = StandardId::new(0x700).unwrap();
+        let rx = StandardId::new(0x708).unwrap();
+        let mut a = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), tx, rx);
+        let peer = bus.add_interface(vec![]).unwrap();
+        peer.set_self_reception(false);
+        let frame = |data: &[u8]| MockFrame::new(rx, data).unwrap();
+
+        // A first frame announcing 3 bytes, then a consecutive frame.
+        peer.transmit(frame(&[0x10, 0x03, 1, 2, 3, 4, 5, 6]))
+            .unwrap();
+        peer.transmit(frame(&[0x21, 7, 8, 9, 10, 11, 12, 13]))
+            .unwrap();
+        assert!(a.poll().unwrap().is_empty());
+        assert!(!peer.has_frames());
+
+        a.send(&[0x22; 10]).unwrap();
+        a.send(&[0x33]).unwrap();
+        assert_eq!(peer.pop_frame().unwrap().data()[..2], [0x10, 10]);
+        assert!(!peer.has_frames());
+        peer.transmit(frame(&[0x30, 0, 0])).unwrap();
+        a.poll().unwrap();
+        assert_eq!(peer.pop_frame().unwrap().data()[0], 0x21);
+        assert_eq!(peer.pop_frame().unwrap().data()[..2], [0x01, 0x33]);
+        assert!(!a.is_sending());
+    }
+}
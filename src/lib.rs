@@ -4,9 +4,11 @@
 //! integration tests.
 //!
 //! This crate provides a simple, deterministic “bus” that routes frames between attached
-//! interfaces without any hardware, timing, arbitration, or bit-level behavior. Frames are
-//! delivered immediately and are **broadcast to all attached interfaces (including the
-//! transmitter)**.
+//! interfaces without any hardware or bit-level behavior. Frames are **broadcast to all attached
+//! interfaces (including the transmitter)**. By default they are delivered immediately; with
+//! [bitrate timing](BusHandle::set_timing) enabled the bus runs on a virtual clock, frames
+//! occupy the wire for their computed duration, and pending frames win arbitration by ID
+//! priority as the clock is [advanced](BusHandle::advance).
 //!
 //! The primary high-level type is [`MockCan`], which implements the
 //! [`embedded_can_interface`](https://docs.rs/embedded-can-interface) I/O traits (blocking and
@@ -62,32 +64,209 @@
 //!
 //! # Notes and limitations
 //!
-//! - No bus timing, bitrate, arbitration, error frames, ACK, or transceiver behavior.
-//! - Delivery is immediate and synchronous.
-//! - Transmitting broadcasts to all interfaces (including the transmitter itself).
-//! - Receive queues are unbounded in-memory collections.
+//! - No bit-level or transceiver behavior; arbitration, error frames and error counters are
+//!   modelled at frame granularity.
+//! - Delivery is immediate and synchronous, unless bitrate timing is enabled via
+//!   [`BusHandle::set_timing`], in which case frames are delivered as the virtual clock advances.
+//! - Transmitting broadcasts to all interfaces (including the transmitter itself, unless
+//!   self-reception is disabled).
+//! - Receive queues are unbounded in-memory collections unless a capacity is set.
+//! - [`BusHandle`] and [`InterfaceHandle`] can carry any frame type (see [`bus`]); [`MockCan`]
+//!   uses [`MockFrame`].
+//!
+//! # Feature flags
+//!
+//! - `std` (default): standard-library locking and blocking waits (`recv`, `recv_timeout`,
+//!   [`InterfaceHandle::wait_for_frame`]). Without it the crate is `no_std + alloc`: the bus is
+//!   protected by a spin lock, unbounded waits busy-poll, and bounded waits check once (there is
+//!   no clock to measure a timeout against).
+//! - `bench`: synthetic workload generators (`workload` module) for benchmarks.
+//! - `xl`: experimental CAN XL frame type (`xl` module).
+//! - `derive`: `#[derive(CanMessage)]` for typed messages (see [`message`]).
+//! - `cli`: interactive debug console attached to a bus (`console` module); implies `std`.
+//! - `websocket`: WebSocket endpoint streaming live traffic as JSON (`websocket` module);
+//!   implies `std`.
+//! - `ffi`: C API with a header in `include/` (`ffi` module); implies `std`.
+//! - `wasm`: async `recv` / `wait_not_empty` on [`MockCan`] and [`MockRx`] await the interface’s
+//!   wakers (see [`InterfaceHandle::poll_frame`]) instead of blocking, so browser simulators
+//!   built for `wasm32-unknown-unknown` can drive the bus from a JavaScript event loop. On
+//!   `wasm` targets, where threads cannot park and there is no clock, blocking waits never
+//!   block: they check once, with or without `std`.
+//! - `defmt`: [`defmt::Format`](https://docs.rs/defmt) for [`MockFrame`], [`SmallFrame`],
+//!   [`MockError`], [`TransmitError`], [`BusEvent`] and the error-state types, so a `no_std`
+//!   build running on target can log them with structured output.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// Lets derived code name this crate as `::embedded_can_mock` inside its own tests.
+extern crate self as embedded_can_mock;
 
 /// Shared mock “bus” and low-level interface handles.
 pub mod bus;
 
+/// Out-of-band bus events (bus errors and similar conditions).
+pub mod event;
+
 /// Filter validation and matching helpers used by the mock bus.
 pub mod filter;
 
 /// Mock CAN frame implementation.
 pub mod frame;
 
-pub use bus::{BusHandle, InterfaceHandle, MockInterfaceError, TransmitError};
-pub use filter::FilterError;
-pub use frame::MockFrame;
+mod sync;
+
+/// Assertion helpers for frame sequences.
+pub mod testing;
+
+/// Bitrate-accurate frame timing for the virtual clock.
+pub mod timing;
+
+/// Seeded background traffic generation.
+pub mod generator;
+
+/// Frame predicates used for selective receive.
+pub mod matcher;
+
+/// Pluggable bus media deciding which nodes a frame reaches.
+pub mod medium;
+
+/// Transmit access control for security testing.
+pub mod policy;
+
+/// Per-interface middleware that rewrites, delays or drops frames.
+pub mod middleware;
+
+/// Traffic recording and sequence-diagram export.
+pub mod recorder;
+
+/// Period, jitter, latency and priority inversion analysis of recorded traffic.
+pub mod measure;
+
+/// Request/response pairing with configurable correlation rules.
+pub mod conversation;
+
+/// Golden-trace export and comparison for snapshot tests.
+pub mod golden;
+
+/// pcapng export of recorded traffic with the SocketCAN link type.
+pub mod pcapng;
+
+/// Vector ASC trace import and export.
+pub mod asc;
+
+/// Structured activity hooks for logging.
+pub mod trace;
+
+/// Constructors mapping arbitrary input to valid frames, IDs and filters.
+pub mod arbitrary;
+
+/// Frame forwarding between buses with ID and payload translation.
+pub mod gateway;
+
+/// Whole-network topologies (buses, nodes, gateways, responders) from a builder or description.
+pub mod topology;
+
+/// Declarative scenario scripts run against a topology, with pass/fail reports.
+pub mod scenario;
+
+/// Multi-channel devices sharing a virtual clock and error status.
+pub mod device;
+
+/// Lock-step advancement of buses by an external simulation executor.
+pub mod sim;
+
+/// Time-triggered CAN schedules with exclusive and arbitration windows.
+pub mod ttcan;
+
+/// Seeded, reproducible bus-wide fault injection.
+pub mod chaos;
+
+/// History caps, memory usage estimates and leak detection for soak tests.
+pub mod memory;
+
+/// `mock://` configuration strings for opening interfaces.
+pub mod uri;
+
+/// Typed messages mapped onto CAN IDs and payload layouts.
+pub mod message;
+
+/// Typed publish/subscribe of registered message types over an interface.
+pub mod typed;
+
+/// Selecting the mock or another backend from the `CAN_BACKEND` environment variable.
+#[cfg(feature = "std")]
+pub mod backend;
+
+/// Interactive debug console for poking at a running bus.
+#[cfg(feature = "cli")]
+pub mod console;
+
+/// WebSocket endpoint streaming live bus traffic as JSON.
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
+/// C API for embedding the mock bus in C test harnesses.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+/// CANopen node simulation (NMT heartbeats and an SDO server).
+pub mod canopen;
+
+/// Minimal ISO-TP transport (segmentation and flow control).
+pub mod isotp;
+
+/// Scriptable UDS diagnostic server on top of ISO-TP.
+pub mod uds;
+
+/// OBD-II PID responder preset.
+pub mod obd;
+
+/// SecOC-style authenticated frames and a MAC-verifying bus observer.
+pub mod secoc;
+
+/// E2E protection: CRC8 checksums and alive counters on protected IDs.
+pub mod e2e;
+
+/// Synthetic workloads for benchmarks and rig sizing.
+#[cfg(feature = "bench")]
+pub mod workload;
+
+/// Experimental CAN XL frames.
+#[cfg(feature = "xl")]
+pub mod xl;
+
+mod rng;
+
+pub use bus::{
+    BusHandle, BusSnapshot, CatchUp, DEFAULT_REPLAY_HISTORY, DEFAULT_TX_HISTORY, Delivered,
+    InterfaceHandle, InterfaceId, InterfaceSnapshot, MockInterfaceError, ReceivedFrames, Rejection,
+    SnapshotParseError, TransmitError, TxToken,
+};
+#[cfg(feature = "std")]
+pub use bus::{FrameCallback, FrameReceiver};
+#[cfg(feature = "derive")]
+pub use embedded_can_mock_derive::CanMessage;
+pub use event::{BusEvent, ErrorCounters, ErrorState};
+pub use filter::{FilterError, FilterSemantics, FilterSet};
+pub use frame::{CandumpParseError, FrameMeta, FrameTooLarge, MockFrame, SmallFrame};
+pub use matcher::FrameMatcher;
+pub use medium::{Broadcast, BusMedium};
+pub use message::CanMessage;
+
+use alloc::{string::String, vec, vec::Vec};
+use core::{
+    task::{Context, Poll},
+    time::Duration,
+};
 use embedded_can_interface::{
     AsyncRxFrameIo, AsyncTxFrameIo, BlockingControl, BufferedIo, BuilderBinding, FilterConfig,
     IdMaskFilter, RxFrameIo, SplitTxRx, TxFrameIo, TxRxState,
 };
-use std::time::Duration;
 
 /// Error type for the mock backend.
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MockError {
     /// Attempted to transmit while not attached to a bus.
     BusNotAttached,
@@ -96,15 +275,40 @@ pub enum MockError {
     /// A receive operation timed out while waiting for a frame.
     Timeout,
     /// A non-blocking receive operation had no frames available.
+    ///
+    /// Also returned by `recv` / `recv_timeout` / `wait_not_empty` while the interface is in
+    /// non-blocking mode (see [`embedded_can_interface::BlockingControl`]).
     WouldBlock,
     /// A provided filter set failed validation.
     InvalidFilters,
+    /// The bus load limit rejected a transmit.
+    BusOverloaded,
+    /// Attempted to transmit from a listen-only interface.
+    ListenOnly,
+    /// The bus transmit policy rejected a transmit.
+    PolicyRejected,
+    /// The bus schedule does not allow the interface to transmit at this time.
+    OutsideWindow,
+    /// The interface’s pacing rejected a transmit.
+    RateLimited,
+    /// The bus has no room for another interface.
+    BusFull,
+    /// A blocking receive gave up because the interface was
+    /// [closed](InterfaceHandle::close).
+    Closed,
+    /// A configuration string passed to [`open`](BuilderBinding::open) is malformed.
+    InvalidUri(uri::UriError),
 }
 
 impl From<TransmitError> for MockError {
     fn from(err: TransmitError) -> Self {
         match err {
             TransmitError::BusNotAttached => MockError::BusNotAttached,
+            TransmitError::BusOverloaded => MockError::BusOverloaded,
+            TransmitError::ListenOnly => MockError::ListenOnly,
+            TransmitError::PolicyRejected => MockError::PolicyRejected,
+            TransmitError::OutsideWindow => MockError::OutsideWindow,
+            TransmitError::RateLimited => MockError::RateLimited,
         }
     }
 }
@@ -121,10 +325,21 @@ impl From<MockInterfaceError> for MockError {
             MockInterfaceError::BusAlreadyAttached => MockError::BusAlreadyAttached,
             MockInterfaceError::BusNotAttached => MockError::BusNotAttached,
             MockInterfaceError::InvalidFilters => MockError::InvalidFilters,
+            MockInterfaceError::BusFull => MockError::BusFull,
         }
     }
 }
 
+/// Receive FIFO of a dual-FIFO controller (bxCAN, FDCAN), used with
+/// [`MockCan::set_fifo_filters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxFifo {
+    /// FIFO 0 (mailbox 0).
+    Fifo0 = 0,
+    /// FIFO 1 (mailbox 1).
+    Fifo1 = 1,
+}
+
 /// Combined CAN interface over the mock backend.
 ///
 /// `MockCan` is the “high level” API: it implements the `embedded_can_interface` traits so it can
@@ -191,6 +406,95 @@ impl MockCan {
             bus: bus.clone(),
         })
     }
+
+    /// The underlying low-level interface, for configuration and inspection.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.iface
+    }
+
+    /// The set of IDs this interface accepts (see [`InterfaceHandle::filter_set`]).
+    pub fn filter_set(&self) -> FilterSet {
+        self.iface.filter_set()
+    }
+
+    /// Accept exactly the IDs in `set` (see [`InterfaceHandle::set_filter_set`]).
+    pub fn set_filter_set(&mut self, set: &FilterSet) {
+        self.iface.set_filter_set(set);
+    }
+
+    /// Replace the acceptance filters, assigning each one to a receive FIFO.
+    ///
+    /// Frames are routed to the FIFO of the first filter they match (FIFO 0 if the list is
+    /// empty) and taken with [`recv_fifo0`](Self::recv_fifo0) / [`recv_fifo1`](Self::recv_fifo1).
+    /// The plain receive traits keep returning frames from both FIFOs in arrival order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_interface::{Id, IdMask, IdMaskFilter, TxFrameIo};
+    /// use embedded_can_mock::{BusHandle, MockCan, MockFrame, RxFifo};
+    ///
+    /// let filter = |id| IdMaskFilter {
+    ///     id: Id::Standard(StandardId::new(id).unwrap()),
+    ///     mask: IdMask::Standard(0x7FF),
+    /// };
+    /// let bus = BusHandle::new();
+    /// let mut can = MockCan::new_with_bus(&bus, vec![]).unwrap();
+    /// can.set_fifo_filters(&[(filter(0x100), RxFifo::Fifo0), (filter(0x200), RxFifo::Fifo1)])
+    ///     .unwrap();
+    ///
+    /// let urgent = MockFrame::new(StandardId::new(0x200).unwrap(), &[1]).unwrap();
+    /// TxFrameIo::send(&mut can, &MockFrame::new(StandardId::new(0x100).unwrap(), &[]).unwrap()).unwrap();
+    /// TxFrameIo::send(&mut can, &urgent).unwrap();
+    /// assert_eq!(can.recv_fifo1().unwrap(), urgent);
+    /// ```
+    pub fn set_fifo_filters(
+        &mut self,
+        filters: &[(IdMaskFilter, RxFifo)],
+    ) -> Result<(), MockError> {
+        let filters = filters
+            .iter()
+            .map(|(filter, fifo)| (*filter, *fifo as usize))
+            .collect();
+        self.iface
+            .set_filters_with_mailboxes(filters)
+            .map_err(MockError::from)
+    }
+
+    /// Receive the next frame from FIFO 0, blocking like
+    /// [`RxFrameIo::recv`](embedded_can_interface::RxFrameIo::recv).
+    pub fn recv_fifo0(&mut self) -> Result<MockFrame, MockError> {
+        recv_fifo_from(&self.iface, RxFifo::Fifo0)
+    }
+
+    /// Receive the next frame from FIFO 1, blocking like
+    /// [`RxFrameIo::recv`](embedded_can_interface::RxFrameIo::recv).
+    pub fn recv_fifo1(&mut self) -> Result<MockFrame, MockError> {
+        recv_fifo_from(&self.iface, RxFifo::Fifo1)
+    }
+
+    /// Transmit all `frames` in order as one batch (see [`InterfaceHandle::transmit_all`]).
+    pub fn send_all(&mut self, frames: &[MockFrame]) -> Result<(), MockError> {
+        self.iface.transmit_all(frames).map_err(MockError::from)
+    }
+
+    /// Take the oldest pending [`BusEvent`] (bus errors and similar), if any.
+    pub fn poll_event(&mut self) -> Option<BusEvent> {
+        self.iface.pop_event()
+    }
+
+    /// Wait up to `timeout` for the next [`BusEvent`].
+    pub fn wait_event(&mut self, timeout: Duration) -> Option<BusEvent> {
+        self.iface.wait_for_event(Some(timeout))
+    }
+
+    /// Receive up to `max` already-queued frames without blocking.
+    ///
+    /// Returns an empty vector if nothing is queued.
+    pub fn recv_batch(&mut self, max: usize) -> Vec<MockFrame> {
+        self.iface.pop_frames(max)
+    }
 }
 
 impl TxFrameIo for MockCan {
@@ -198,11 +502,17 @@ impl TxFrameIo for MockCan {
     type Error = MockError;
 
     fn send(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
-        self.iface.transmit(frame.clone()).map_err(MockError::from)
+        self.iface
+            .transmit(frame.clone())
+            .map(drop)
+            .map_err(MockError::from)
     }
 
     fn try_send(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
-        self.iface.transmit(frame.clone()).map_err(MockError::from)
+        self.iface
+            .transmit(frame.clone())
+            .map(drop)
+            .map_err(MockError::from)
     }
 
     fn send_timeout(&mut self, frame: &Self::Frame, _timeout: Duration) -> Result<(), Self::Error> {
@@ -233,15 +543,7 @@ impl RxFrameIo for MockCan {
     type Error = MockError;
 
     fn recv(&mut self) -> Result<Self::Frame, Self::Error> {
-        if let Some(frame) = self.iface.pop_frame() {
-            return Ok(frame);
-        }
-        let has = self.iface.wait_for_frame(None);
-        if has {
-            self.iface.pop_frame().ok_or(MockError::Timeout)
-        } else {
-            Err(MockError::Timeout)
-        }
+        recv_from(&self.iface, None)
     }
 
     fn try_recv(&mut self) -> Result<Self::Frame, Self::Error> {
@@ -249,23 +551,11 @@ impl RxFrameIo for MockCan {
     }
 
     fn recv_timeout(&mut self, timeout: Duration) -> Result<Self::Frame, Self::Error> {
-        if let Some(frame) = self.iface.pop_frame() {
-            return Ok(frame);
-        }
-        let has = self.iface.wait_for_frame(Some(timeout));
-        if has {
-            self.iface.pop_frame().ok_or(MockError::Timeout)
-        } else {
-            Err(MockError::Timeout)
-        }
+        recv_from(&self.iface, Some(timeout))
     }
 
     fn wait_not_empty(&mut self) -> Result<(), Self::Error> {
-        if self.iface.has_frames() {
-            return Ok(());
-        }
-        let _ = self.iface.wait_for_frame(None);
-        Ok(())
+        wait_not_empty_on(&self.iface)
     }
 }
 
@@ -274,7 +564,7 @@ impl AsyncRxFrameIo for MockCan {
     type Error = MockError;
 
     async fn recv(&mut self) -> Result<Self::Frame, Self::Error> {
-        RxFrameIo::recv(self)
+        recv_async(&self.iface).await
     }
 
     async fn recv_timeout(&mut self, timeout: Duration) -> Result<Self::Frame, Self::Error> {
@@ -282,7 +572,7 @@ impl AsyncRxFrameIo for MockCan {
     }
 
     async fn wait_not_empty(&mut self) -> Result<(), Self::Error> {
-        RxFrameIo::wait_not_empty(self)
+        wait_not_empty_async(&self.iface).await
     }
 }
 
@@ -309,11 +599,17 @@ impl TxFrameIo for MockTx {
     type Error = MockError;
 
     fn send(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
-        self.iface.transmit(frame.clone()).map_err(MockError::from)
+        self.iface
+            .transmit(frame.clone())
+            .map(drop)
+            .map_err(MockError::from)
     }
 
     fn try_send(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
-        self.iface.transmit(frame.clone()).map_err(MockError::from)
+        self.iface
+            .transmit(frame.clone())
+            .map(drop)
+            .map_err(MockError::from)
     }
 
     fn send_timeout(&mut self, frame: &Self::Frame, _timeout: Duration) -> Result<(), Self::Error> {
@@ -343,12 +639,7 @@ impl RxFrameIo for MockRx {
     type Error = MockError;
 
     fn recv(&mut self) -> Result<Self::Frame, Self::Error> {
-        if let Some(frame) = self.iface.pop_frame() {
-            Ok(frame)
-        } else {
-            self.iface.wait_for_frame(None);
-            self.iface.pop_frame().ok_or(MockError::Timeout)
-        }
+        recv_from(&self.iface, None)
     }
 
     fn try_recv(&mut self) -> Result<Self::Frame, Self::Error> {
@@ -356,23 +647,11 @@ impl RxFrameIo for MockRx {
     }
 
     fn recv_timeout(&mut self, timeout: Duration) -> Result<Self::Frame, Self::Error> {
-        if let Some(frame) = self.iface.pop_frame() {
-            return Ok(frame);
-        }
-        let has = self.iface.wait_for_frame(Some(timeout));
-        if has {
-            self.iface.pop_frame().ok_or(MockError::Timeout)
-        } else {
-            Err(MockError::Timeout)
-        }
+        recv_from(&self.iface, Some(timeout))
     }
 
     fn wait_not_empty(&mut self) -> Result<(), Self::Error> {
-        if self.iface.has_frames() {
-            return Ok(());
-        }
-        let _ = self.iface.wait_for_frame(None);
-        Ok(())
+        wait_not_empty_on(&self.iface)
     }
 }
 
@@ -381,7 +660,7 @@ impl AsyncRxFrameIo for MockRx {
     type Error = MockError;
 
     async fn recv(&mut self) -> Result<Self::Frame, Self::Error> {
-        RxFrameIo::recv(self)
+        recv_async(&self.iface).await
     }
 
     async fn recv_timeout(&mut self, timeout: Duration) -> Result<Self::Frame, Self::Error> {
@@ -389,7 +668,185 @@ impl AsyncRxFrameIo for MockRx {
     }
 
     async fn wait_not_empty(&mut self) -> Result<(), Self::Error> {
-        RxFrameIo::wait_not_empty(self)
+        wait_not_empty_async(&self.iface).await
+    }
+}
+
+/// Stream-shaped receive.
+///
+/// With the `futures` feature, [`MockRx`] implements `futures::Stream` through
+/// [`poll_next`](MockRx::poll_next), for `select!`, `merge` and friends. Without it,
+/// `futures::stream::poll_fn` turns the same method into a stream:
+///
+/// ```text
+/// let frames = futures::stream::poll_fn(move |cx| rx.poll_next(cx));
+/// ```
+impl MockRx {
+    /// Poll for the next received frame, registering `cx`'s waker if none is queued.
+    ///
+    /// The stream ends (`None`) once the interface is [closed](InterfaceHandle::close) and its
+    /// queue is empty.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<MockFrame>> {
+        match self.iface.poll_frame(cx) {
+            Poll::Pending if self.iface.is_closed() => Poll::Ready(None),
+            poll => poll.map(Some),
+        }
+    }
+}
+
+/// Sink-shaped transmit, mirroring `futures::Sink<MockFrame>` (implemented with the `futures`
+/// feature).
+///
+/// Transmission hands the frame to the bus immediately, so the sink is always ready and flushing
+/// never waits (frames still on the wire of a [timed](BusHandle::set_timing) bus are not awaited).
+impl MockTx {
+    /// Always ready to accept a frame.
+    pub fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), MockError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Transmit `frame`.
+    pub fn start_send(&mut self, frame: MockFrame) -> Result<(), MockError> {
+        self.iface
+            .transmit(frame)
+            .map(drop)
+            .map_err(MockError::from)
+    }
+
+    /// Always flushed.
+    pub fn poll_flush(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), MockError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Closing is a no-op; the interface stays attached.
+    pub fn poll_close(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), MockError>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_core::Stream for MockRx {
+    type Item = MockFrame;
+
+    fn poll_next(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<MockFrame>> {
+        MockRx::poll_next(self.get_mut(), cx)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_sink::Sink<MockFrame> for MockTx {
+    type Error = MockError;
+
+    fn poll_ready(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), MockError>> {
+        MockTx::poll_ready(self.get_mut(), cx)
+    }
+
+    fn start_send(self: core::pin::Pin<&mut Self>, frame: MockFrame) -> Result<(), MockError> {
+        MockTx::start_send(self.get_mut(), frame)
+    }
+
+    fn poll_flush(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), MockError>> {
+        MockTx::poll_flush(self.get_mut(), cx)
+    }
+
+    fn poll_close(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), MockError>> {
+        MockTx::poll_close(self.get_mut(), cx)
+    }
+}
+
+/// Shared receive path for [`MockCan`] and [`MockRx`].
+///
+/// In non-blocking mode this never waits and reports [`MockError::WouldBlock`] when the queue is
+/// empty.
+fn recv_from(iface: &InterfaceHandle, timeout: Option<Duration>) -> Result<MockFrame, MockError> {
+    if iface.is_nonblocking() {
+        return iface.pop_frame().ok_or(MockError::WouldBlock);
+    }
+    iface
+        .recv_frame(timeout)
+        .ok_or_else(|| closed_or(iface, MockError::Timeout))
+}
+
+/// Async receive: with the `wasm` feature, await `iface`'s wakers instead of blocking the
+/// calling thread.
+#[cfg(feature = "wasm")]
+async fn recv_async(iface: &InterfaceHandle) -> Result<MockFrame, MockError> {
+    if iface.is_nonblocking() {
+        return iface.pop_frame().ok_or(MockError::WouldBlock);
+    }
+    core::future::poll_fn(|cx| match iface.poll_frame(cx) {
+        Poll::Ready(frame) => Poll::Ready(Ok(frame)),
+        Poll::Pending if iface.is_closed() => Poll::Ready(Err(MockError::Closed)),
+        Poll::Pending => Poll::Pending,
+    })
+    .await
+}
+
+#[cfg(not(feature = "wasm"))]
+async fn recv_async(iface: &InterfaceHandle) -> Result<MockFrame, MockError> {
+    recv_from(iface, None)
+}
+
+#[cfg(feature = "wasm")]
+async fn wait_not_empty_async(iface: &InterfaceHandle) -> Result<(), MockError> {
+    if iface.is_nonblocking() && !iface.has_frames() {
+        return Err(MockError::WouldBlock);
+    }
+    if core::future::poll_fn(|cx| iface.poll_has_frame(cx)).await {
+        Ok(())
+    } else {
+        Err(MockError::Closed)
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+async fn wait_not_empty_async(iface: &InterfaceHandle) -> Result<(), MockError> {
+    wait_not_empty_on(iface)
+}
+
+/// Error for a wait on `iface` that ended without result: [`MockError::Closed`] if the interface
+/// was closed, `otherwise` if not.
+fn closed_or(iface: &InterfaceHandle, otherwise: MockError) -> MockError {
+    if iface.is_closed() {
+        MockError::Closed
+    } else {
+        otherwise
+    }
+}
+
+fn recv_fifo_from(iface: &InterfaceHandle, fifo: RxFifo) -> Result<MockFrame, MockError> {
+    let mailbox = fifo as usize;
+    if let Some(frame) = iface.pop_mailbox(mailbox) {
+        return Ok(frame);
+    }
+    if iface.is_nonblocking() {
+        return Err(MockError::WouldBlock);
+    }
+    iface
+        .wait_for_mailbox(mailbox, None)
+        .ok_or_else(|| closed_or(iface, MockError::Timeout))
+}
+
+fn wait_not_empty_on(iface: &InterfaceHandle) -> Result<(), MockError> {
+    if iface.has_frames() {
+        return Ok(());
+    }
+    if iface.is_nonblocking() {
+        return Err(MockError::WouldBlock);
+    }
+    if iface.wait_for_frame(None) {
+        Ok(())
+    } else {
+        Err(MockError::Closed)
     }
 }
 
@@ -412,15 +869,17 @@ impl TxRxState for MockCan {
     type Error = MockError;
 
     fn is_transmitter_idle(&self) -> Result<bool, Self::Error> {
-        // Mock transmit is immediate.
-        Ok(true)
+        // Busy while a frame is queued for arbitration or on the wire; with timing disabled,
+        // transmit completes immediately and this is always idle.
+        Ok(!self.iface.is_transmit_pending())
     }
 }
 
 impl BlockingControl for MockCan {
     type Error = MockError;
 
-    fn set_nonblocking(&mut self, _on: bool) -> Result<(), Self::Error> {
+    fn set_nonblocking(&mut self, on: bool) -> Result<(), Self::Error> {
+        self.iface.set_nonblocking(on);
         Ok(())
     }
 }
@@ -459,17 +918,51 @@ impl BufferedIo for MockCan {
 /// Builder type used by [`embedded_can_interface::BuilderBinding`] for [`MockCan`].
 ///
 /// This is mainly useful when higher-level code expects to construct a CAN interface using the
-/// `embedded_can_interface` builder patterns.
+/// `embedded_can_interface` builder patterns. By default each builder creates its own bus; use
+/// [`on_bus`](Self::on_bus) to build several nodes on the same bus.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_interface::{BuilderBinding, RxFrameIo, TxFrameIo};
+/// use embedded_can_mock::{BusHandle, MockCan, MockFrame};
+///
+/// let bus = BusHandle::new();
+/// let mut ecu = MockCan::builder().on_bus(&bus).name("ecu").self_reception(false).build().unwrap();
+/// let mut tester = MockCan::builder().on_bus(&bus).name("tester").build().unwrap();
+///
+/// let frame = MockFrame::new(StandardId::new(0x7E0).unwrap(), &[0x02, 0x10, 0x01]).unwrap();
+/// TxFrameIo::send(&mut ecu, &frame).unwrap();
+/// assert_eq!(RxFrameIo::recv(&mut tester).unwrap(), frame);
+/// assert_eq!(ecu.interface().name().as_deref(), Some("ecu"));
+/// assert!(!ecu.interface().has_frames());
+/// ```
 pub struct MockBuilder {
     bus: BusHandle,
     filters: Vec<IdMaskFilter>,
+    filter_semantics: FilterSemantics,
+    name: Option<String>,
+    rx_capacity: Option<usize>,
+    self_reception: bool,
+    listen_only: bool,
+    nonblocking: bool,
+    catch_up: CatchUp,
 }
 
 impl BuilderBinding for MockCan {
     type Error = MockError;
     type Builder = MockBuilder;
 
-    fn open(_name: &str) -> Result<Self, Self::Error> {
+    /// Open a mock interface.
+    ///
+    /// A `mock://` configuration string selects a named bus and configures the interface (see
+    /// [`uri`]); any other name opens an interface on a new private bus.
+    fn open(name: &str) -> Result<Self, Self::Error> {
+        if name.starts_with(uri::SCHEME) {
+            let uri = uri::MockUri::parse(name).map_err(MockError::InvalidUri)?;
+            return uri.builder().build();
+        }
         let bus = BusHandle::new();
         MockCan::new_with_bus(&bus, vec![])
     }
@@ -478,11 +971,24 @@ impl BuilderBinding for MockCan {
         MockBuilder {
             bus: BusHandle::new(),
             filters: Vec::new(),
+            filter_semantics: FilterSemantics::default(),
+            name: None,
+            rx_capacity: None,
+            self_reception: true,
+            listen_only: false,
+            nonblocking: false,
+            catch_up: CatchUp::Nothing,
         }
     }
 }
 
 impl MockBuilder {
+    /// Build the interface on `bus` instead of a new private bus.
+    pub fn on_bus(mut self, bus: &BusHandle) -> Self {
+        self.bus = bus.clone();
+        self
+    }
+
     /// Set the initial filter list for the interface produced by [`build`](Self::build).
     ///
     /// Filters are validated when the interface is created.
@@ -506,11 +1012,69 @@ impl MockBuilder {
         Ok(self)
     }
 
-    /// Build a [`MockCan`] attached to this builder’s internal bus.
+    /// Set how the filters are interpreted (see [`InterfaceHandle::set_filter_semantics`]).
+    pub fn filter_semantics(mut self, semantics: FilterSemantics) -> Self {
+        self.filter_semantics = semantics;
+        self
+    }
+
+    /// Name the interface (see [`InterfaceHandle::set_name`]).
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Bound the receive queue (see [`InterfaceHandle::set_rx_capacity`]).
+    pub fn rx_capacity(mut self, capacity: usize) -> Self {
+        self.rx_capacity = Some(capacity);
+        self
+    }
+
+    /// Choose whether the interface receives its own frames; enabled by default.
+    pub fn self_reception(mut self, on: bool) -> Self {
+        self.self_reception = on;
+        self
+    }
+
+    /// Start in listen-only mode (see [`InterfaceHandle::set_listen_only`]).
+    pub fn listen_only(mut self, on: bool) -> Self {
+        self.listen_only = on;
+        self
+    }
+
+    /// Start in non-blocking mode (see [`BlockingControl`]).
+    pub fn nonblocking(mut self, on: bool) -> Self {
+        self.nonblocking = on;
+        self
+    }
+
+    /// Replay earlier bus traffic to the new interface when it attaches (see
+    /// [`InterfaceHandle::attach_with`]). The replay passes through the configured filters.
+    pub fn catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Build a [`MockCan`] attached to this builder’s bus.
     ///
     /// The returned interface is immediately usable for transmit and receive.
     pub fn build(self) -> Result<MockCan, MockError> {
-        MockCan::new_with_bus(&self.bus, self.filters)
+        filter::validate_filters(&self.filters)?;
+        // Configure before attaching, so the catch-up replay already sees the final settings.
+        let iface = InterfaceHandle::new_unattached(self.filters);
+        if let Some(name) = self.name {
+            iface.set_name(name);
+        }
+        iface.set_filter_semantics(self.filter_semantics);
+        iface.set_rx_capacity(self.rx_capacity);
+        iface.set_self_reception(self.self_reception);
+        iface.set_listen_only(self.listen_only);
+        iface.set_nonblocking(self.nonblocking);
+        iface.attach_with(&self.bus, self.catch_up)?;
+        Ok(MockCan {
+            iface,
+            bus: self.bus,
+        })
     }
 }
 
@@ -580,6 +1144,26 @@ mod tests {
             MockError::from(TransmitError::BusNotAttached),
             MockError::BusNotAttached
         ));
+        assert!(matches!(
+            MockError::from(TransmitError::BusOverloaded),
+            MockError::BusOverloaded
+        ));
+        assert!(matches!(
+            MockError::from(TransmitError::ListenOnly),
+            MockError::ListenOnly
+        ));
+        assert!(matches!(
+            MockError::from(TransmitError::PolicyRejected),
+            MockError::PolicyRejected
+        ));
+        assert!(matches!(
+            MockError::from(TransmitError::OutsideWindow),
+            MockError::OutsideWindow
+        ));
+        assert!(matches!(
+            MockError::from(TransmitError::RateLimited),
+            MockError::RateLimited
+        ));
         assert!(matches!(
             MockError::from(MockInterfaceError::BusAlreadyAttached),
             MockError::BusAlreadyAttached
@@ -592,6 +1176,10 @@ mod tests {
             MockError::from(MockInterfaceError::InvalidFilters),
             MockError::InvalidFilters
         ));
+        assert!(matches!(
+            MockError::from(MockInterfaceError::BusFull),
+            MockError::BusFull
+        ));
         assert!(matches!(
             MockError::from(FilterError::KindMismatch),
             MockError::InvalidFilters
@@ -619,6 +1207,49 @@ mod tests {
         assert_eq!(got, frame);
     }
 
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_async_recv_awaits_wakers_instead_of_blocking() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+        use std::task::Wake;
+
+        struct Count(AtomicUsize);
+        impl Wake for Count {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let (_, mut rx) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        let wakes = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let frame = standard_frame(0x42, &[0x01]);
+        {
+            let mut recv = std::pin::pin!(embedded_can_interface::AsyncRxFrameIo::recv(&mut rx));
+            assert!(recv.as_mut().poll(&mut cx).is_pending());
+            tx.transmit(frame.clone()).unwrap();
+            assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+            assert!(matches!(recv.as_mut().poll(&mut cx), Poll::Ready(Ok(f)) if f == frame));
+        }
+
+        let mut wait = std::pin::pin!(embedded_can_interface::AsyncRxFrameIo::wait_not_empty(
+            &mut rx
+        ));
+        assert!(wait.as_mut().poll(&mut cx).is_pending());
+        bus.close();
+        assert!(matches!(
+            wait.as_mut().poll(&mut cx),
+            Poll::Ready(Err(MockError::Closed))
+        ));
+    }
+
     #[test]
     fn mock_rx_recv_blocks_until_frame_arrives() {
         use std::thread;
@@ -691,39 +1322,1781 @@ mod tests {
     }
 
     #[test]
-    fn transmit_arc_forwards_frames_via_trait_extension() {
-        let frame = extended_frame(0x1ABCDE0, &[0xAB, 0xCD]);
+    fn recv_matching_keeps_or_discards_non_matching_frames() {
         let bus = BusHandle::new();
         let node = bus.add_interface(vec![]).unwrap();
-        node.transmit(frame.clone()).unwrap();
+        let first = standard_frame(0x100, &[0x01]);
+        let wanted = standard_frame(0x200, &[0x02]);
+        let last = standard_frame(0x100, &[0x03]);
+        for frame in [&first, &wanted, &last] {
+            node.transmit(frame.clone()).unwrap();
+        }
 
-        assert_eq!(node.received_frames(), vec![frame]);
+        let by_data = |f: &MockFrame| f.data() == [0x02];
+        assert_eq!(node.recv_matching(by_data, None), Some(wanted.clone()));
+        assert_eq!(node.received_frames(), vec![first.clone(), last.clone()]);
+
+        node.transmit(wanted.clone()).unwrap();
+        let id = Id::Standard(StandardId::new(0x200).unwrap());
+        assert_eq!(node.recv_matching_discard(id, None), Some(wanted));
+        assert!(node.received_frames().is_empty());
+
+        node.transmit(first).unwrap();
+        assert_eq!(node.recv_matching(id, Some(Duration::from_millis(5))), None);
+        assert_eq!(node.received_frames().len(), 1);
     }
 
     #[test]
-    fn standard_filters_gate_delivery_to_matching_frames() {
-        let matching = standard_frame(0x123, &[0xAA]);
-        let rejected = standard_frame(0x321, &[0xBB]);
-
+    fn recv_matching_waits_for_late_frames() {
         let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let sender = bus.add_interface(vec![]).unwrap();
+        let frame = standard_frame(0x321, &[0xAA]);
+        let to_send = frame.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            sender.transmit(standard_frame(0x1, &[])).unwrap();
+            sender.transmit(to_send).unwrap();
+        });
 
-        let filtered_node = bus
-            .add_interface(vec![IdMaskFilter {
-                id: IfaceId::Standard(StandardId::new(0x123).unwrap()),
-                mask: IdMask::Standard(0x7FF),
-            }])
-            .unwrap();
-        let unfiltered_node = bus.add_interface(vec![]).unwrap();
-
-        unfiltered_node.transmit(matching.clone()).unwrap();
-        unfiltered_node.transmit(rejected.clone()).unwrap();
+        let id = Id::Standard(StandardId::new(0x321).unwrap());
+        assert_eq!(
+            node.recv_matching(id, Some(Duration::from_secs(5))),
+            Some(frame)
+        );
+        handle.join().unwrap();
+    }
 
-        let filtered_received = filtered_node.received_frames();
-        let unfiltered_received = unfiltered_node.received_frames();
+    #[test]
+    fn concurrent_transmitters_deliver_every_frame_to_every_node() {
+        let bus = BusHandle::new();
+        let nodes: Vec<_> = (0..50)
+            .map(|_| bus.add_interface(vec![]).unwrap())
+            .collect();
+
+        let senders: Vec<_> = nodes[..8]
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, node)| {
+                std::thread::spawn(move || {
+                    for n in 0..100u8 {
+                        node.transmit(standard_frame(i as u16, &[n])).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for sender in senders {
+            sender.join().unwrap();
+        }
 
-        assert_eq!(unfiltered_received.len(), 2);
-        assert_eq!(filtered_received, vec![matching.clone()]);
-        assert!(!filtered_received.contains(&rejected));
+        for node in &nodes {
+            let frames = node.received_frames();
+            assert_eq!(frames.len(), 800);
+            // Per-sender order is preserved.
+            let from_first: Vec<u8> = frames
+                .iter()
+                .filter(|f| f.id() == Id::Standard(StandardId::new(0).unwrap()))
+                .map(|f| f.data()[0])
+                .collect();
+            assert_eq!(from_first, (0..100).collect::<Vec<u8>>());
+        }
+    }
+
+    #[test]
+    fn batch_transmit_and_receive_preserve_order() {
+        let bus = BusHandle::new();
+        let mut a = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let mut b = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let frames: Vec<_> = (0..10u8).map(|n| standard_frame(0x100, &[n])).collect();
+
+        a.send_all(&frames).unwrap();
+        assert_eq!(b.recv_batch(4), frames[..4].to_vec());
+        assert_eq!(b.recv_batch(100), frames[4..].to_vec());
+        assert!(b.recv_batch(1).is_empty());
+
+        let detached = InterfaceHandle::new_unattached(vec![]);
+        assert!(matches!(
+            detached.transmit_all(&frames),
+            Err(TransmitError::BusNotAttached)
+        ));
+    }
+
+    #[test]
+    fn transmit_arc_forwards_frames_via_trait_extension() {
+        let frame = extended_frame(0x1ABCDE0, &[0xAB, 0xCD]);
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.transmit(frame.clone()).unwrap();
+
+        assert_eq!(node.received_frames(), vec![frame]);
+    }
+
+    #[test]
+    fn reset_clears_queues_but_keeps_configuration() {
+        let bus = BusHandle::new();
+        let filtered = bus
+            .add_interface(vec![IdMaskFilter {
+                id: IfaceId::Standard(StandardId::new(0x10).unwrap()),
+                mask: IdMask::Standard(0x7FF),
+            }])
+            .unwrap();
+        let open = bus.add_interface(vec![]).unwrap();
+        open.transmit(standard_frame(0x10, &[0x01])).unwrap();
+        open.transmit(standard_frame(0x11, &[0x02])).unwrap();
+
+        let snapshot = bus.snapshot();
+        assert_eq!(snapshot.interfaces.len(), 2);
+        assert_eq!(snapshot.interfaces[0].id, filtered.id());
+        assert_eq!(snapshot.interfaces[0].frames.len(), 1);
+        assert_eq!(snapshot.interfaces[1].frames.len(), 2);
+
+        bus.reset();
+        assert!(
+            bus.snapshot()
+                .interfaces
+                .iter()
+                .all(|i| i.frames.is_empty())
+        );
+        assert_eq!(bus.interface_count(), 2);
+
+        open.transmit(standard_frame(0x11, &[0x03])).unwrap();
+        assert!(filtered.received_frames().is_empty());
+        assert_eq!(open.received_frames().len(), 1);
+    }
+
+    #[test]
+    fn snapshots_round_trip_through_text_and_restore_queues() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+        use std::task::Wake;
+
+        struct CountingWaker(AtomicUsize);
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let bus = BusHandle::new();
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        a.transmit(standard_frame(0x123, &[0xDE, 0xAD])).unwrap();
+        b.transmit(MockFrame::new_remote(ExtendedId::new(0x1ABC_DE01).unwrap(), 4).unwrap())
+            .unwrap();
+        let snapshot = bus.snapshot();
+        let text = snapshot.to_string();
+        assert_eq!(BusSnapshot::parse(&text).unwrap(), snapshot);
+
+        bus.reset();
+        a.transmit(standard_frame(0x7FF, &[])).unwrap();
+        // Restored frames wake async receivers waiting on an empty queue.
+        assert_eq!(b.drain_frames(), vec![standard_frame(0x7FF, &[])]);
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        assert!(b.poll_frame(&mut Context::from_waker(&waker)).is_pending());
+        bus.restore(&snapshot);
+        assert_eq!(bus.snapshot(), snapshot);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        let delivery = b.pop_delivery().unwrap();
+        assert_eq!(delivery.source, bus.injector_id());
+        assert!(!delivery.echo);
+
+        assert_eq!(
+            BusSnapshot::parse("123#00"),
+            Err(SnapshotParseError::FrameBeforeInterface { line: 1 })
+        );
+        assert_eq!(
+            BusSnapshot::parse("interface 1\n\ninterface x"),
+            Err(SnapshotParseError::InvalidInterface { line: 3 })
+        );
+        assert!(matches!(
+            BusSnapshot::parse("interface 1\n12#00"),
+            Err(SnapshotParseError::InvalidFrame { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn drop_probability_is_seeded_and_cleared_by_reset() {
+        let run = |seed| {
+            let bus = BusHandle::new();
+            let sender = bus.add_interface(vec![]).unwrap();
+            let flaky = bus.add_interface(vec![]).unwrap();
+            flaky.set_drop_probability(0.5, seed);
+            for n in 0..200u8 {
+                sender.transmit(standard_frame(0x10, &[n])).unwrap();
+            }
+            assert_eq!(sender.received_frames().len(), 200);
+            let received = flaky.received_frames();
+            assert_eq!(received.len() as u64 + flaky.dropped_count(), 200);
+            (bus, sender, flaky, received)
+        };
+
+        let (bus, sender, flaky, first) = run(9);
+        assert!(first.len() > 50 && first.len() < 150);
+        assert_eq!(run(9).3, first);
+
+        bus.reset();
+        assert_eq!(flaky.dropped_count(), 0);
+        sender.transmit(standard_frame(0x10, &[])).unwrap();
+        assert_eq!(flaky.received_frames().len(), 1);
+    }
+
+    #[test]
+    fn corrupted_frames_raise_events_instead_of_delivering() {
+        let bus = BusHandle::new();
+        let mut tx = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let mut rx = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let frame = standard_frame(0x10, &[0x01]);
+        tx.iface.set_auto_retransmit(false);
+
+        bus.corrupt_next(BusEvent::StuffError);
+        bus.corrupt_next(BusEvent::AckError);
+        TxFrameIo::send(&mut tx, &frame).unwrap();
+        TxFrameIo::send(&mut tx, &frame).unwrap();
+        TxFrameIo::send(&mut tx, &frame).unwrap();
+
+        assert_eq!(rx.poll_event(), Some(BusEvent::StuffError));
+        assert_eq!(rx.poll_event(), None);
+        assert_eq!(tx.poll_event(), Some(BusEvent::AckError));
+        assert_eq!(RxFrameIo::try_recv(&mut rx).unwrap(), frame);
+        assert!(matches!(
+            RxFrameIo::try_recv(&mut rx),
+            Err(MockError::WouldBlock)
+        ));
+
+        let waiter = {
+            let mut rx = rx.clone();
+            std::thread::spawn(move || rx.wait_event(Duration::from_secs(5)))
+        };
+        std::thread::sleep(Duration::from_millis(10));
+        rx.iface.inject_event(BusEvent::CrcError);
+        assert_eq!(waiter.join().unwrap(), Some(BusEvent::CrcError));
+    }
+
+    #[test]
+    fn error_frames_and_corruption_move_error_counters() {
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+
+        bus.inject_error_frame();
+        bus.inject_error_frame();
+        assert_eq!(rx.pending_events(), vec![BusEvent::ErrorFrame; 2]);
+        assert_eq!(tx.error_counters().rec, 2);
+
+        tx.set_auto_retransmit(false);
+        bus.corrupt_next(BusEvent::BitError);
+        tx.transmit(standard_frame(0x1, &[])).unwrap();
+        assert_eq!(tx.error_counters(), ErrorCounters { tec: 8, rec: 2 });
+        assert_eq!(rx.error_counters(), ErrorCounters { tec: 0, rec: 2 });
+
+        tx.transmit(standard_frame(0x1, &[])).unwrap();
+        assert_eq!(tx.error_counters(), ErrorCounters { tec: 7, rec: 2 });
+        assert_eq!(rx.error_counters().rec, 1);
+        assert_eq!(rx.error_counters().state(), ErrorState::Active);
+    }
+
+    #[test]
+    fn failed_transmissions_are_retried_through_arbitration() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rival = bus.add_interface(vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+        assert!(tx.is_auto_retransmit());
+
+        bus.corrupt_next(BusEvent::BitError);
+        bus.corrupt_next(BusEvent::BitError);
+        let token = tx.transmit_confirmed(standard_frame(0x100, &[1])).unwrap();
+        // The retry competes with traffic requested meanwhile, and a lower ID wins.
+        rival.transmit(standard_frame(0x050, &[])).unwrap();
+        bus.advance(Duration::from_millis(5));
+        assert_eq!(
+            rx.drain_frames(),
+            vec![standard_frame(0x050, &[]), standard_frame(0x100, &[1])]
+        );
+        assert!(tx.wait_tx_confirmation(token, Some(Duration::ZERO)));
+        // The second corruption hit the rival's frame, which won the next round.
+        assert_eq!(tx.retransmit_count(), 1);
+        assert_eq!(rival.retransmit_count(), 1);
+        assert_eq!(tx.error_counters().tec, 7);
+
+        // Retries stop at bus-off.
+        for _ in 0..40 {
+            bus.corrupt_next(BusEvent::AckError);
+        }
+        tx.transmit(standard_frame(0x100, &[2])).unwrap();
+        bus.advance(Duration::from_millis(50));
+        assert_eq!(tx.error_counters().state(), ErrorState::BusOff);
+        assert!(!rx.has_frames());
+        assert_eq!(tx.retransmit_count(), 32);
+    }
+
+    #[test]
+    fn aborting_cancels_held_and_retrying_frames_only_for_that_node() {
+        let bus = BusHandle::new();
+        bus.set_stepped(true);
+        let node = bus.add_interface(vec![]).unwrap();
+        let other = bus.add_interface(vec![]).unwrap();
+
+        let first = node.transmit(standard_frame(0x1, &[])).unwrap();
+        other.transmit(standard_frame(0x2, &[])).unwrap();
+        let last = node.transmit(standard_frame(0x3, &[])).unwrap();
+        assert_eq!(node.abort_tx(last), Some(standard_frame(0x3, &[])));
+        assert_eq!(node.abort_tx(last), None);
+        assert_eq!(
+            node.abort_pending_tx(),
+            vec![(first, standard_frame(0x1, &[]))]
+        );
+        assert_eq!(bus.run_until_idle(), 1);
+        assert_eq!(node.drain_frames(), vec![standard_frame(0x2, &[])]);
+
+        // A frame waiting to be retried after an error can be aborted too.
+        bus.set_stepped(false);
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        bus.corrupt_next(BusEvent::AckError);
+        let retry = node.transmit(standard_frame(0x4, &[])).unwrap();
+        other.transmit(standard_frame(0x1, &[0; 8])).unwrap();
+        // The failed frame is retried but loses arbitration to the waiting higher-priority one.
+        bus.advance(timing.wire_time(&standard_frame(0x4, &[])));
+        assert!(node.is_transmit_pending());
+        assert_eq!(node.abort_tx(retry), Some(standard_frame(0x4, &[])));
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(node.drain_frames(), vec![standard_frame(0x1, &[0; 8])]);
+    }
+
+    #[test]
+    fn sleeping_interface_wakes_on_matching_traffic() {
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let filter = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(0x100).unwrap()),
+            mask: IdMask::Standard(0x7FF),
+        };
+        let sleeper = bus.add_interface(vec![filter]).unwrap();
+
+        sleeper.sleep(false);
+        tx.transmit(standard_frame(0x200, &[])).unwrap();
+        assert!(sleeper.is_asleep());
+        assert_eq!(sleeper.pop_event(), None);
+
+        tx.transmit(standard_frame(0x100, &[1])).unwrap();
+        assert!(!sleeper.is_asleep());
+        assert_eq!(sleeper.pending_events(), vec![BusEvent::WakeUp]);
+        assert_eq!(sleeper.pop_frame(), Some(standard_frame(0x100, &[1])));
+
+        sleeper.sleep(true);
+        sleeper.transmit(standard_frame(0x300, &[])).unwrap();
+        assert!(!sleeper.is_asleep());
+    }
+
+    #[test]
+    fn loopback_interface_is_isolated_from_the_bus() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let other = bus.add_interface(vec![]).unwrap();
+        node.set_loopback(true);
+
+        node.transmit(standard_frame(0x1, &[1])).unwrap();
+        other.transmit(standard_frame(0x2, &[2])).unwrap();
+        assert_eq!(node.pop_frames(usize::MAX), vec![standard_frame(0x1, &[1])]);
+        assert_eq!(
+            other.pop_frames(usize::MAX),
+            vec![standard_frame(0x2, &[2])]
+        );
+
+        bus.reset();
+        assert!(node.is_loopback());
+        node.set_loopback(false);
+        node.transmit(standard_frame(0x3, &[])).unwrap();
+        assert!(other.has_frames());
+    }
+
+    #[test]
+    fn tx_confirmations_follow_wire_completion() {
+        let bus = BusHandle::new();
+        bus.set_timing(Some(timing::BusTiming::new(500_000)));
+        let node = bus.add_interface(vec![]).unwrap();
+        node.set_auto_retransmit(false);
+
+        let low = node
+            .transmit_confirmed(standard_frame(0x200, &[0; 8]))
+            .unwrap();
+        node.transmit(standard_frame(0x300, &[])).unwrap();
+        let high = node.transmit_confirmed(standard_frame(0x100, &[])).unwrap();
+        assert!(low < high);
+        assert_eq!(high.source(), node.id());
+        assert_eq!(node.pop_tx_confirmation(), None);
+
+        // The frame already on the wire is corrupted and never confirmed.
+        bus.corrupt_next(BusEvent::CrcError);
+        bus.advance(Duration::from_millis(1));
+        assert!(node.wait_tx_confirmation(high, Some(Duration::ZERO)));
+        assert!(!node.wait_tx_confirmation(low, Some(Duration::ZERO)));
+        assert_eq!(node.pop_tx_confirmation(), None);
+    }
+
+    #[test]
+    fn transmit_tokens_correlate_identical_frames_across_receivers() {
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rx_a = bus.add_interface(vec![]).unwrap();
+        let rx_b = bus.add_interface(vec![]).unwrap();
+        let frame = standard_frame(0x42, &[0xAA]);
+
+        let tokens: Vec<TxToken> = (0..3)
+            .map(|_| tx.transmit(frame.clone()).unwrap())
+            .collect();
+        assert_eq!(
+            tokens.iter().map(TxToken::seq).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        rx_a.pop_frame();
+        let (second_a, _) = rx_a.pop_frame_with_token().unwrap();
+        assert_eq!(rx_b.pop_frames(2).len(), 2);
+        let (third_b, received) = rx_b.pop_frame_with_token().unwrap();
+        assert_eq!(received, frame);
+        assert_eq!(second_a, tokens[1]);
+        assert_eq!(third_b, tokens[2]);
+        assert_eq!(third_b.source(), tx.id());
+    }
+
+    #[test]
+    fn tracer_reports_deliveries_rejections_and_drops() {
+        use std::sync::{Arc, Mutex};
+        use trace::TraceEvent;
+
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let filtered = bus
+            .add_interface(vec![IdMaskFilter {
+                id: IfaceId::Standard(StandardId::new(0x10).unwrap()),
+                mask: IdMask::Standard(0x7FF),
+            }])
+            .unwrap();
+        tx.set_drop_probability(1.0, 0);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sink = log.clone();
+        bus.set_tracer(Some(Arc::new(move |event: &TraceEvent<'_>| {
+            let line = match event {
+                TraceEvent::Transmit { token, .. } => format!("tx {}", token.seq()),
+                TraceEvent::Deliver { receiver, .. } => format!("rx {}", receiver.as_raw()),
+                TraceEvent::FilterReject { receiver, .. } => {
+                    format!("reject {}", receiver.as_raw())
+                }
+                TraceEvent::Drop { receiver, .. } => format!("drop {}", receiver.as_raw()),
+                TraceEvent::QueueOverflow { receiver, .. } => {
+                    format!("overflow {}", receiver.as_raw())
+                }
+                TraceEvent::NodeLeft { node } => format!("left {}", node.as_raw()),
+            };
+            sink.lock().unwrap().push(line);
+        })));
+
+        tx.transmit(standard_frame(0x11, &[])).unwrap();
+        let (t, f) = (tx.id().as_raw(), filtered.id().as_raw());
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "tx 0".to_string(),
+                format!("drop {t}"),
+                format!("reject {f}")
+            ]
+        );
+
+        bus.set_tracer(None);
+        tx.transmit(standard_frame(0x10, &[])).unwrap();
+        assert_eq!(log.lock().unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_feature_emits_events_inside_delivery_spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Records span names and event messages, prefixing events with the span they are in.
+        #[derive(Clone, Default)]
+        struct Collect {
+            log: Arc<Mutex<Vec<String>>>,
+            current: Arc<Mutex<Option<String>>>,
+        }
+        struct Message(String);
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{value:?}");
+                }
+            }
+        }
+        impl Subscriber for Collect {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                *self.current.lock().unwrap() = Some(span.metadata().name().to_string());
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                let span = self.current.lock().unwrap().clone().unwrap_or_default();
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("{span}/{}", message.0));
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {
+                *self.current.lock().unwrap() = None;
+            }
+        }
+
+        let collect = Collect::default();
+        let log = collect.log.clone();
+        tracing::subscriber::with_default(collect, || {
+            let bus = BusHandle::new();
+            let tx = bus.add_interface(vec![]).unwrap();
+            tx.set_self_reception(false);
+            let rx = bus.add_interface(vec![]).unwrap();
+            rx.set_filters(vec![filter::accept_exact(StandardId::new(0x10).unwrap())])
+                .unwrap();
+            tx.transmit(standard_frame(0x10, &[1])).unwrap();
+            tx.transmit(standard_frame(0x11, &[2])).unwrap();
+        });
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "/transmit",
+                "deliver/deliver",
+                "/transmit",
+                "deliver/filter reject",
+                "/node left",
+                "/node left"
+            ]
+        );
+    }
+
+    #[test]
+    fn stepped_bus_controls_interleaving() {
+        let bus = BusHandle::new();
+        bus.set_stepped(true);
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+
+        a.transmit(standard_frame(0x1, &[])).unwrap();
+        b.transmit(standard_frame(0x2, &[])).unwrap();
+        assert_eq!(bus.in_flight_count(), 2);
+        assert!(a.is_transmit_pending());
+        assert!(!b.has_frames());
+
+        assert!(bus.step());
+        assert_eq!(b.pop_frames(usize::MAX), vec![standard_frame(0x1, &[])]);
+        assert!(!a.is_transmit_pending());
+
+        a.transmit(standard_frame(0x3, &[])).unwrap();
+        bus.set_stepped(false);
+        assert_eq!(bus.in_flight_count(), 0);
+        assert_eq!(
+            b.pop_frames(usize::MAX),
+            vec![standard_frame(0x2, &[]), standard_frame(0x3, &[])]
+        );
+    }
+
+    #[test]
+    fn scheduled_transmits_follow_the_virtual_clock() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        let rx = bus.add_interface(vec![]).unwrap();
+
+        let early = standard_frame(0x300, &[0x01]);
+        let urgent = standard_frame(0x010, &[0x02]);
+        let late = standard_frame(0x020, &[0x03]);
+        bus.schedule_transmit(Duration::from_millis(1), early.clone());
+        bus.schedule_transmit(Duration::from_millis(1), urgent.clone());
+        bus.schedule_transmit(Duration::from_millis(5), late.clone());
+        assert_eq!(bus.scheduled_count(), 3);
+
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(bus.scheduled_count(), 1);
+        assert_eq!(bus.in_flight_count(), 2);
+        bus.advance(Duration::from_millis(1));
+        // Released together, the two frames arbitrate and the lower ID goes first.
+        assert_eq!(rx.drain_frames(), vec![urgent, early]);
+
+        bus.advance(Duration::from_millis(3) + timing.wire_time(&late));
+        assert_eq!(
+            rx.pop_frame_with_token().unwrap().0.source(),
+            bus.injector_id()
+        );
+        assert_eq!(bus.scheduled_count(), 0);
+
+        bus.schedule_transmit(Duration::ZERO, late.clone());
+        assert_eq!(bus.in_flight_count(), 1);
+    }
+
+    #[test]
+    fn capacity_limits_attaches_and_delivery_follows_attach_order() {
+        use std::sync::{Arc, Mutex};
+        use trace::TraceEvent;
+
+        let bus = BusHandle::with_capacity(3);
+        // Created first, attached last: attach order, not creation order, decides delivery order.
+        let late = InterfaceHandle::new_unattached(vec![]);
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        late.attach_to_bus(&bus).unwrap();
+        assert!(matches!(
+            bus.add_interface(vec![]),
+            Err(MockInterfaceError::BusFull)
+        ));
+        let spare = InterfaceHandle::new_unattached(vec![]);
+        spare.set_self_reception(false);
+        assert!(matches!(
+            spare.attach_to_bus(&bus),
+            Err(MockInterfaceError::BusFull)
+        ));
+        // A rejected interface keeps its state and can join another bus.
+        let other = BusHandle::new();
+        spare.attach_to_bus(&other).unwrap();
+        spare.transmit(standard_frame(0x1, &[])).unwrap();
+        assert!(!spare.has_frames());
+        let tap = bus.tap();
+        assert_eq!(bus.interface_count(), 4);
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let sink = order.clone();
+        bus.set_tracer(Some(Arc::new(move |event: &TraceEvent<'_>| {
+            if let TraceEvent::Deliver { receiver, .. } = event {
+                sink.lock().unwrap().push(*receiver);
+            }
+        })));
+        for _ in 0..3 {
+            late.transmit(standard_frame(0x2, &[])).unwrap();
+            b.transmit(standard_frame(0x3, &[])).unwrap();
+        }
+        let expected = [a.id(), b.id(), late.id(), tap.id()];
+        let order = order.lock().unwrap();
+        assert_eq!(order.len(), 6 * 4);
+        assert!(order.chunks(4).all(|chunk| chunk == expected));
+
+        let seqs = |node: &InterfaceHandle| {
+            core::iter::from_fn(|| node.pop_delivery())
+                .map(|delivery| delivery.seq)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seqs(&a), (0..6).collect::<Vec<_>>());
+        assert_eq!(seqs(&late), seqs(&tap));
+    }
+
+    #[test]
+    fn groups_multiplex_channels_over_one_bus() {
+        let bus = BusHandle::new();
+        let controller = bus.add_interface(vec![]).unwrap();
+        let can1 = bus.add_interface(vec![]).unwrap();
+        let can2 = bus
+            .add_interface(vec![IdMaskFilter {
+                id: IfaceId::Standard(StandardId::new(0x20).unwrap()),
+                mask: IdMask::Standard(0x7FF),
+            }])
+            .unwrap();
+        let tap = bus.tap();
+        can1.join_group("CAN1");
+        can1.join_group("CAN1");
+        can2.join_group("CAN2");
+        controller.join_group("CAN2");
+        assert_eq!(can1.groups(), vec!["CAN1".to_string()]);
+
+        controller
+            .transmit_to_group("CAN1", standard_frame(0x10, &[1]))
+            .unwrap();
+        controller
+            .transmit_to_group("CAN2", standard_frame(0x10, &[2]))
+            .unwrap();
+        controller
+            .transmit_to_group("CAN2", standard_frame(0x20, &[3]))
+            .unwrap();
+        assert_eq!(can1.drain_frames(), vec![standard_frame(0x10, &[1])]);
+        // Group members still apply their acceptance filters.
+        assert_eq!(can2.drain_frames(), vec![standard_frame(0x20, &[3])]);
+        assert_eq!(controller.drain_frames().len(), 3);
+        // Taps observe the whole wire.
+        assert_eq!(tap.drain_frames().len(), 3);
+
+        // Ordinary broadcasts ignore groups.
+        can1.transmit(standard_frame(0x20, &[4])).unwrap();
+        assert!(can2.has_frames());
+        assert!(can1.leave_group("CAN1"));
+        assert!(!can1.leave_group("CAN1"));
+        controller
+            .transmit_to_group("CAN1", standard_frame(0x10, &[5]))
+            .unwrap();
+        assert_eq!(can1.drain_frames(), vec![standard_frame(0x20, &[4])]);
+    }
+
+    #[test]
+    fn tx_history_records_each_accepted_transmit_once() {
+        use policy::PolicyDecision;
+        use std::sync::Arc;
+
+        let bus = BusHandle::new();
+        let mut can = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let peer = bus.add_interface(vec![]).unwrap();
+        assert_eq!(peer.tx_history_capacity(), Some(DEFAULT_TX_HISTORY));
+
+        TxFrameIo::send(&mut can, &standard_frame(0x1, &[1])).unwrap();
+        // A corrupted frame is retried, but recorded only once.
+        bus.corrupt_next(BusEvent::AckError);
+        TxFrameIo::send(&mut can, &standard_frame(0x2, &[2])).unwrap();
+        assert_eq!(can.interface().retransmit_count(), 1);
+        // Held frames are recorded before they reach the wire.
+        bus.set_stepped(true);
+        TxFrameIo::send(&mut can, &standard_frame(0x3, &[3])).unwrap();
+        bus.set_stepped(false);
+        bus.set_transmit_policy(Some(Arc::new(|frame: &MockFrame, _| {
+            if frame.data() == [4] {
+                PolicyDecision::Reject
+            } else {
+                PolicyDecision::Allow
+            }
+        })));
+        assert!(TxFrameIo::send(&mut can, &standard_frame(0x4, &[4])).is_err());
+
+        assert_eq!(
+            can.interface().sent_frames(),
+            vec![
+                standard_frame(0x1, &[1]),
+                standard_frame(0x2, &[2]),
+                standard_frame(0x3, &[3]),
+            ]
+        );
+        assert!(peer.sent_frames().is_empty());
+
+        can.interface().set_tx_history_capacity(Some(1));
+        assert_eq!(
+            can.interface().sent_frames(),
+            vec![standard_frame(0x3, &[3])]
+        );
+        can.interface().set_tx_history_capacity(Some(0));
+        TxFrameIo::send(&mut can, &standard_frame(0x5, &[5])).unwrap();
+        assert!(can.interface().sent_frames().is_empty());
+        can.interface().set_tx_history_capacity(None);
+        TxFrameIo::send(&mut can, &standard_frame(0x6, &[6])).unwrap();
+        bus.reset();
+        assert!(can.interface().sent_frames().is_empty());
+    }
+
+    #[test]
+    fn pacing_queues_or_rejects_excess_frames() {
+        use timing::{Pacing, RateLimit};
+
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let tap = bus.tap();
+        let limit = |reject| Pacing {
+            min_gap: Duration::ZERO,
+            rate_limit: Some(RateLimit {
+                frames_per_second: 100,
+                burst: 5,
+            }),
+            reject,
+        };
+        node.set_pacing(Some(limit(false)));
+        for i in 0..50 {
+            node.transmit(standard_frame(0x10, &[i])).unwrap();
+        }
+        bus.advance(Duration::from_secs(1));
+        let deliveries: Vec<_> = core::iter::from_fn(|| tap.pop_delivery()).collect();
+        assert_eq!(deliveries.len(), 50);
+        assert!(
+            deliveries
+                .iter()
+                .enumerate()
+                .all(|(i, d)| d.frame.data() == [i as u8])
+        );
+        assert_eq!(deliveries[4].timestamp, Duration::ZERO);
+        assert_eq!(deliveries[49].timestamp, Duration::from_millis(450));
+
+        // After a second idle the bucket is full again; in reject mode the sixth frame fails.
+        node.set_pacing(Some(limit(true)));
+        node.clear_sent_frames();
+        for i in 0..5 {
+            node.transmit(standard_frame(0x20, &[i])).unwrap();
+        }
+        assert!(matches!(
+            node.transmit(standard_frame(0x20, &[5])),
+            Err(TransmitError::RateLimited)
+        ));
+        assert_eq!(node.sent_frames().len(), 5);
+        bus.advance(Duration::from_millis(10));
+        node.transmit(standard_frame(0x20, &[6])).unwrap();
+
+        node.set_pacing(None);
+        for _ in 0..10 {
+            node.transmit(standard_frame(0x30, &[])).unwrap();
+        }
+    }
+
+    #[test]
+    fn chaos_replays_identically_from_seed_and_reset() {
+        use chaos::ChaosConfig;
+
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+        let run = |config: ChaosConfig| {
+            bus.reset();
+            bus.set_chaos(Some(config));
+            for i in 0..200u8 {
+                tx.transmit(standard_frame(0x10, &[i])).unwrap();
+                bus.advance(Duration::from_millis(1));
+            }
+            let data: Vec<u8> = rx.drain_frames().iter().map(|f| f.data()[0]).collect();
+            (data, rx.dropped_count())
+        };
+        let config = ChaosConfig {
+            seed: 42,
+            drop: 0.1,
+            dup: 0.1,
+            reorder: 0.1,
+            delay_jitter: Duration::from_micros(200),
+            corrupt: 0.0,
+        };
+        let (data, dropped) = run(config);
+        assert!(dropped > 0);
+        assert!(
+            data.len() as u64 + dropped > 200,
+            "some frames were duplicated"
+        );
+        assert!(
+            data.windows(2).any(|w| w[0] > w[1]),
+            "some frames were reordered"
+        );
+        assert_eq!(run(config), (data.clone(), dropped));
+        assert_ne!(run(ChaosConfig { seed: 43, ..config }).0, data);
+        assert!(config.to_string().contains("seed=42"));
+
+        // Corruption goes through the error path, so the transmitter retries and the receiver
+        // still ends up with every frame exactly once.
+        let (data, dropped) = run(ChaosConfig {
+            corrupt: 0.05,
+            ..ChaosConfig::default()
+        });
+        assert_eq!((data, dropped), ((0..200).collect(), 0));
+        assert!(tx.retransmit_count() > 0);
+
+        // Corrupting every attempt drives the transmitter bus-off instead of retrying forever.
+        bus.reset();
+        bus.set_chaos(Some(ChaosConfig {
+            corrupt: 1.0,
+            ..ChaosConfig::default()
+        }));
+        tx.transmit(standard_frame(0x10, &[0])).unwrap();
+        assert_eq!(tx.error_counters().state(), ErrorState::BusOff);
+        assert_eq!(tx.retransmit_count(), 31);
+        assert!(!rx.has_frames());
+
+        // Resetting the bus switches chaos mode off with the other injected faults.
+        bus.reset();
+        assert_eq!(bus.chaos(), None);
+
+        bus.set_chaos(None);
+        assert_eq!(bus.chaos(), None);
+    }
+
+    #[test]
+    fn chaos_duplicates_that_overflow_are_counted_and_traced() {
+        use std::sync::{Arc, Mutex};
+        use trace::TraceEvent;
+
+        let bus = BusHandle::new();
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+        tx.set_self_reception(false);
+        rx.set_rx_capacity(Some(1));
+        tx.transmit(standard_frame(0x10, &[0])).unwrap();
+        let overflows = Arc::new(Mutex::new(0));
+        let sink = overflows.clone();
+        bus.set_tracer(Some(Arc::new(move |event: &TraceEvent<'_>| {
+            if let TraceEvent::QueueOverflow { .. } = event {
+                *sink.lock().unwrap() += 1;
+            }
+        })));
+        bus.set_chaos(Some(chaos::ChaosConfig {
+            dup: 1.0,
+            ..chaos::ChaosConfig::default()
+        }));
+
+        // Both the duplicate and the original hit the full queue.
+        tx.transmit(standard_frame(0x10, &[1])).unwrap();
+        assert_eq!(rx.overflow_count(), 2);
+        assert_eq!(*overflows.lock().unwrap(), 2);
+        assert_eq!(rx.drain_frames(), vec![standard_frame(0x10, &[0])]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "unbounded queue growth: 9 received frames on interface 1")]
+    fn memory_limits_cap_histories_and_catch_queue_growth() {
+        let bus = BusHandle::new();
+        let recorder = recorder::Recorder::attach(&bus);
+        let tx = bus.add_interface(vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+        tx.set_self_reception(false);
+        tx.set_tx_history_capacity(Some(3));
+        bus.set_log_rejections(true);
+        rx.set_filters(vec![filter::accept_exact(StandardId::MAX)])
+            .unwrap();
+        bus.set_memory_limits(memory::MemoryLimits {
+            history: Some(5),
+            queue_watermark: Some(8),
+        });
+        for i in 0..20 {
+            tx.transmit(standard_frame(0x10, &[i])).unwrap();
+        }
+        assert_eq!(recorder.records().len(), 5);
+        assert_eq!(recorder.records()[4].frame.data(), [19]);
+        // The interface's own, smaller capacity still wins.
+        assert_eq!(tx.sent_frames().len(), 3);
+        assert_eq!(bus.take_rejections().len(), 5);
+        let usage = bus.memory_usage();
+        assert_eq!((usage.queued, usage.history), (0, 5 + 3 + 5));
+
+        // A receiver that never drains its queue trips the watermark.
+        rx.set_filters(vec![]).unwrap();
+        for i in 0..9 {
+            tx.transmit(standard_frame(0x10, &[i])).unwrap();
+        }
+    }
+
+    #[test]
+    fn partitioned_segments_run_independently_until_healed() {
+        let bus = BusHandle::new();
+        let nodes: Vec<_> = (0..4).map(|_| bus.add_interface(vec![]).unwrap()).collect();
+        let ids: Vec<_> = nodes.iter().map(InterfaceHandle::id).collect();
+        bus.partition(&[&ids[..2], &ids[2..3]]);
+        assert!(bus.is_partitioned());
+
+        nodes[1].transmit(standard_frame(0x1, &[])).unwrap();
+        nodes[2].transmit(standard_frame(0x2, &[])).unwrap();
+        nodes[3].transmit(standard_frame(0x3, &[])).unwrap();
+        let queued = |bus_nodes: &[InterfaceHandle]| {
+            bus_nodes
+                .iter()
+                .map(InterfaceHandle::drain_frames)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            queued(&nodes),
+            vec![
+                vec![standard_frame(0x1, &[])],
+                vec![standard_frame(0x1, &[])],
+                vec![standard_frame(0x2, &[])],
+                // Unlisted nodes are isolated but still hear themselves.
+                vec![standard_frame(0x3, &[])],
+            ]
+        );
+
+        bus.reset();
+        assert!(!bus.is_partitioned());
+        nodes[3].transmit(standard_frame(0x4, &[])).unwrap();
+        assert!(queued(&nodes).iter().all(|frames| frames.len() == 1));
+    }
+
+    #[test]
+    fn babbling_idiot_starves_lower_priority_traffic() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(250_000);
+        bus.set_timing(Some(timing));
+        let idiot = bus.add_interface(vec![]).unwrap();
+        let node = bus.add_interface(vec![]).unwrap();
+        let flood = standard_frame(0x001, &[0xEE; 8]);
+
+        idiot.babble(flood.clone());
+        node.transmit(standard_frame(0x200, &[])).unwrap();
+        let window = timing.wire_time(&flood) * 20;
+        bus.advance(window);
+        let seen = node.drain_frames();
+        assert_eq!(seen.len(), 20);
+        assert!(seen.iter().all(|f| *f == flood));
+        assert!(bus.load().occupancy > 0.99);
+
+        // A stuck-dominant wire stalls even the babbler, and nothing completes until released.
+        bus.set_stuck_dominant(true);
+        bus.advance(window);
+        assert!(!node.has_frames());
+        assert_eq!(node.error_counters().rec, 1);
+        idiot.stop_babbling();
+        bus.set_stuck_dominant(false);
+        bus.advance(window);
+        // The frozen frame on the wire completes, then the copy already queued behind it.
+        assert_eq!(
+            node.drain_frames(),
+            vec![flood.clone(), flood, standard_frame(0x200, &[])]
+        );
+    }
+
+    #[test]
+    fn breakpoints_freeze_a_timed_conversation() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        b.set_self_reception(false);
+        bus.pause_on(|f: &MockFrame| f.data() == [0xBB]);
+
+        a.transmit(standard_frame(0x10, &[0xAA])).unwrap();
+        a.transmit(standard_frame(0x11, &[0xBB])).unwrap();
+        a.transmit(standard_frame(0x12, &[0xCC])).unwrap();
+        bus.advance(Duration::from_millis(10));
+        assert!(bus.is_paused());
+        assert_eq!(b.queue_len(), 2);
+        // The frame after the breakpoint stays queued for the wire while paused.
+        assert_eq!(bus.in_flight_count(), 1);
+        b.transmit(standard_frame(0x20, &[])).unwrap();
+        assert_eq!(bus.in_flight_count(), 2);
+
+        bus.resume();
+        assert!(!bus.is_paused());
+        bus.advance(timing.wire_time(&standard_frame(0x12, &[0xCC])) * 2);
+        assert_eq!(bus.in_flight_count(), 0);
+        assert_eq!(
+            a.drain_frames()
+                .iter()
+                .map(|f| f.data()[..].to_vec())
+                .collect::<Vec<_>>(),
+            vec![vec![0xAA], vec![0xBB], vec![0xCC], vec![]]
+        );
+    }
+
+    #[test]
+    fn receive_ttl_bounds_a_periodic_soak() {
+        let bus = BusHandle::new();
+        let sink = bus.add_interface(vec![]).unwrap();
+        sink.set_rx_ttl(Some(Duration::from_millis(25)));
+        for tick in 0..100 {
+            bus.schedule_transmit(Duration::from_millis(10 * tick), standard_frame(0x10, &[]));
+        }
+
+        bus.advance(Duration::from_millis(996));
+        // Only the frames from the last 25 ms (sent at 980 and 990 ms) remain.
+        assert_eq!(sink.queue_len(), 2);
+        assert_eq!(sink.expired_count(), 98);
+        assert_eq!(
+            sink.pop_delivery().unwrap().timestamp,
+            Duration::from_millis(980)
+        );
+
+        sink.set_rx_ttl(Some(Duration::ZERO));
+        assert_eq!(sink.queue_len(), 0);
+        bus.reset();
+        assert_eq!(sink.expired_count(), 0);
+    }
+
+    #[test]
+    fn builder_configures_modes_on_a_shared_bus() {
+        let bus = BusHandle::new();
+        let mut talker = MockCan::builder()
+            .on_bus(&bus)
+            .self_reception(false)
+            .build()
+            .unwrap();
+        let mut monitor = MockCan::builder()
+            .on_bus(&bus)
+            .listen_only(true)
+            .rx_capacity(2)
+            .nonblocking(true)
+            .build()
+            .unwrap();
+        assert_eq!(bus.interface_count(), 2);
+
+        for id in 1..=3 {
+            TxFrameIo::send(&mut talker, &standard_frame(id, &[])).unwrap();
+        }
+        assert!(!talker.interface().has_frames());
+        assert_eq!(monitor.interface().overflow_count(), 1);
+        assert_eq!(monitor.poll_event(), Some(BusEvent::RxOverflow));
+        assert!(matches!(
+            TxFrameIo::send(&mut monitor, &standard_frame(0x4, &[])),
+            Err(MockError::ListenOnly)
+        ));
+
+        assert_eq!(
+            RxFrameIo::recv(&mut monitor).unwrap(),
+            standard_frame(1, &[])
+        );
+        assert_eq!(
+            RxFrameIo::recv(&mut monitor).unwrap(),
+            standard_frame(2, &[])
+        );
+        assert!(matches!(
+            RxFrameIo::recv(&mut monitor),
+            Err(MockError::WouldBlock)
+        ));
+    }
+
+    #[test]
+    fn bus_routes_user_frame_types() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct TaggedFrame(MockFrame, &'static str);
+
+        impl embedded_can::Frame for TaggedFrame {
+            fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+                MockFrame::new(id, data).map(|f| Self(f, ""))
+            }
+            fn new_remote(id: impl Into<Id>, dlc: usize) -> Option<Self> {
+                MockFrame::new_remote(id, dlc).map(|f| Self(f, ""))
+            }
+            fn is_extended(&self) -> bool {
+                self.0.is_extended()
+            }
+            fn is_remote_frame(&self) -> bool {
+                self.0.is_remote_frame()
+            }
+            fn id(&self) -> Id {
+                self.0.id()
+            }
+            fn dlc(&self) -> usize {
+                self.0.dlc()
+            }
+            fn data(&self) -> &[u8] {
+                self.0.data()
+            }
+        }
+
+        let bus = BusHandle::<TaggedFrame>::default();
+        bus.set_timing(Some(timing::BusTiming::new(500_000)));
+        let recorder = recorder::Recorder::attach(&bus);
+        let node = bus.add_interface(vec![]).unwrap();
+        let tagged = TaggedFrame(standard_frame(0x20, &[1]), "from-test");
+
+        node.transmit(TaggedFrame(standard_frame(0x30, &[]), ""))
+            .unwrap();
+        node.transmit(tagged.clone()).unwrap();
+        bus.advance(Duration::from_millis(1));
+
+        let id = Id::Standard(StandardId::new(0x20).unwrap());
+        assert_eq!(node.recv_matching(id, Some(Duration::ZERO)), Some(tagged));
+        assert_eq!(recorder.records().len(), 2);
+    }
+
+    #[test]
+    fn queue_inspection_does_not_consume_until_asked() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let frames: Vec<MockFrame> = (1..=3).map(|id| standard_frame(id, &[])).collect();
+        node.transmit_all(&frames).unwrap();
+
+        assert_eq!(node.peek_frame(), Some(frames[0].clone()));
+        assert_eq!(node.queue_len(), 3);
+        assert_eq!(node.pop_frame(), Some(frames[0].clone()));
+        assert_eq!(node.drain_frames(), frames[1..].to_vec());
+        assert_eq!(node.peek_frame(), None);
+
+        node.transmit_all(&frames).unwrap();
+        assert_eq!(node.clear_rx(), 3);
+        assert!(!node.has_frames());
+    }
+
+    #[test]
+    fn reordering_is_bounded_and_replays_from_its_seed() {
+        let run = |seed| {
+            let bus = BusHandle::new();
+            let node = bus.add_interface(vec![]).unwrap();
+            node.set_reordering(3, seed);
+            for id in 0..50 {
+                node.transmit(standard_frame(id, &[])).unwrap();
+            }
+            core::iter::from_fn(|| node.pop_delivery())
+                .map(|d| d.seq)
+                .collect::<Vec<_>>()
+        };
+
+        let order = run(11);
+        assert_eq!(order, run(11));
+        assert_ne!(order, (0..50).collect::<Vec<_>>());
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+        // Each frame overtakes at most 3 queued frames, so none is read more than 3 places early.
+        for (position, &seq) in order.iter().enumerate() {
+            assert!(position as u64 + 3 >= seq);
+        }
+    }
+
+    #[test]
+    fn deliveries_carry_source_time_and_bus_order() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        let observer = bus.add_interface(vec![]).unwrap();
+
+        let first = standard_frame(0x10, &[1]);
+        let second = standard_frame(0x20, &[2]);
+        a.transmit(first.clone()).unwrap();
+        b.transmit(second.clone()).unwrap();
+        bus.advance(Duration::from_millis(1));
+
+        let one = observer.pop_delivery().unwrap();
+        let two = observer.pop_delivery().unwrap();
+        assert_eq!((one.frame, one.source, one.seq), (first, a.id(), 0));
+        assert_eq!(
+            (two.frame, two.source, two.seq),
+            (second.clone(), b.id(), 1)
+        );
+        assert_eq!(one.timestamp, timing.wire_time(&standard_frame(0x10, &[1])));
+        assert_eq!(two.timestamp, one.timestamp + timing.wire_time(&second));
+
+        let from_a = b.pop_delivery().unwrap();
+        assert_eq!(from_a.seq, 0);
+        assert!(!from_a.echo);
+        assert!(b.pop_delivery().unwrap().echo);
+    }
+
+    #[test]
+    fn tap_sees_all_traffic_and_cannot_transmit() {
+        let bus = BusHandle::new();
+        let tap = bus.tap();
+        let a = bus.add_interface(vec![]).unwrap();
+        a.set_self_reception(false);
+        tap.set_filters(vec![IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(0x7FF).unwrap()),
+            mask: IdMask::Standard(0x7FF),
+        }])
+        .unwrap();
+
+        a.transmit(standard_frame(0x1, &[])).unwrap();
+        bus.schedule_transmit(Duration::ZERO, standard_frame(0x2, &[]));
+        assert!(tap.is_tap() && tap.is_listen_only());
+        assert_eq!(
+            tap.drain_frames(),
+            vec![standard_frame(0x1, &[]), standard_frame(0x2, &[])]
+        );
+        assert!(matches!(
+            tap.transmit(standard_frame(0x3, &[])),
+            Err(TransmitError::ListenOnly)
+        ));
+        assert_eq!(bus.interface_count(), 2);
+    }
+
+    #[test]
+    fn split_halves_poll_like_stream_and_sink() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+        use std::task::{Wake, Waker};
+
+        struct CountingWaker(AtomicUsize);
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let bus = BusHandle::new();
+        let (mut tx, _) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        let (_, mut rx) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(rx.poll_next(&mut cx).is_pending());
+        assert!(rx.poll_next(&mut cx).is_pending());
+        assert!(tx.poll_ready(&mut cx).is_ready());
+        tx.start_send(standard_frame(0x42, &[1])).unwrap();
+        tx.start_send(standard_frame(0x43, &[2])).unwrap();
+        // The waker is registered once and woken once, by the first delivery.
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            rx.poll_next(&mut cx),
+            Poll::Ready(Some(standard_frame(0x42, &[1])))
+        );
+        assert_eq!(
+            rx.poll_next(&mut cx),
+            Poll::Ready(Some(standard_frame(0x43, &[2])))
+        );
+        assert!(matches!(tx.poll_flush(&mut cx), Poll::Ready(Ok(()))));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_channels_shuttle_frames_and_stop_with_their_receiver() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let bus = BusHandle::new();
+            let node = bus.add_interface(vec![]).unwrap();
+            node.set_self_reception(false);
+            let peer = bus.add_interface(vec![]).unwrap();
+            peer.set_self_reception(false);
+            let (tx, mut rx, mut errors) = node.clone().into_tokio_channels(4);
+            let (_peer_tx, mut peer_rx, _) = peer.clone().into_tokio_channels(4);
+
+            peer.transmit(standard_frame(0x10, &[1])).unwrap();
+            assert_eq!(rx.recv().await, Some(standard_frame(0x10, &[1])));
+            tx.send(standard_frame(0x11, &[2])).await.unwrap();
+            assert_eq!(peer_rx.recv().await, Some(standard_frame(0x11, &[2])));
+
+            node.set_listen_only(true);
+            tx.send(standard_frame(0x12, &[])).await.unwrap();
+            let (refused, error) = errors.recv().await.unwrap();
+            assert_eq!(refused, standard_frame(0x12, &[]));
+            assert!(matches!(error, TransmitError::ListenOnly));
+
+            // Once the receiver is gone, frames stay queued on the interface.
+            drop(rx);
+            for _ in 0..4 {
+                tokio::task::yield_now().await;
+            }
+            peer.transmit(standard_frame(0x13, &[])).unwrap();
+            for _ in 0..4 {
+                tokio::task::yield_now().await;
+            }
+            assert!(node.has_frames());
+        });
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn split_halves_implement_futures_stream_and_sink() {
+        use futures_core::Stream;
+        use futures_sink::Sink;
+
+        let bus = BusHandle::new();
+        let (mut tx, _) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        let (_, mut rx) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(Pin::new(&mut rx).poll_next(&mut cx).is_pending());
+        assert!(matches!(
+            Pin::new(&mut tx).poll_ready(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        Pin::new(&mut tx)
+            .start_send(standard_frame(0x42, &[1]))
+            .unwrap();
+        assert!(matches!(
+            Pin::new(&mut tx).poll_flush(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(
+            Pin::new(&mut rx).poll_next(&mut cx),
+            Poll::Ready(Some(standard_frame(0x42, &[1])))
+        );
+        bus.close();
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
+        assert!(matches!(
+            Pin::new(&mut tx).poll_close(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+    }
+
+    #[test]
+    fn channel_pumps_stop_with_their_own_end() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.set_self_reception(false);
+        let peer = bus.add_interface(vec![]).unwrap();
+        peer.set_self_reception(false);
+        let (tx, rx, _errors) = node.clone().into_channels();
+
+        // Dropping the sender leaves the receive side running.
+        drop(tx);
+        peer.transmit(standard_frame(0x20, &[2])).unwrap();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+            standard_frame(0x20, &[2])
+        );
+
+        // Dropping the receiver stops the receive pump even while the bus is idle.
+        drop(rx);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(node.waiter_count(), 0);
+        peer.transmit(standard_frame(0x21, &[3])).unwrap();
+        assert!(node.has_frames());
+    }
+
+    #[test]
+    fn blocked_receivers_are_served_in_arrival_order() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.set_self_reception(false);
+        let sender = bus.add_interface(vec![]).unwrap();
+
+        let mut receivers = Vec::new();
+        for expected in 1..=3 {
+            let handle = node.clone();
+            receivers.push(std::thread::spawn(move || {
+                handle.recv_frame(Some(Duration::from_secs(5)))
+            }));
+            while node.waiter_count() < expected {
+                std::thread::yield_now();
+            }
+        }
+        for id in 1..=3 {
+            sender.transmit(standard_frame(id, &[])).unwrap();
+        }
+        let received: Vec<_> = receivers
+            .into_iter()
+            .map(|r| r.join().unwrap().unwrap())
+            .collect();
+        assert_eq!(
+            received,
+            (1..=3)
+                .map(|id| standard_frame(id, &[]))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(node.waiter_count(), 0);
+    }
+
+    #[test]
+    fn blocked_receivers_follow_their_interface_onto_a_bus() {
+        let node = InterfaceHandle::new_unattached(vec![]);
+        let waiter = node.clone();
+        let blocked = std::thread::spawn(move || waiter.recv_frame(Some(Duration::from_secs(5))));
+        while node.waiter_count() < 1 {
+            std::thread::yield_now();
+        }
+
+        let bus = BusHandle::new();
+        node.attach_to_bus(&bus).unwrap();
+        let sender = bus.add_interface(vec![]).unwrap();
+        sender.transmit(standard_frame(0x1, &[])).unwrap();
+        assert_eq!(blocked.join().unwrap(), Some(standard_frame(0x1, &[])));
+    }
+
+    #[test]
+    fn detaching_leaves_blocked_receivers_waiting_off_the_bus() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let sender = bus.add_interface(vec![]).unwrap();
+        let waiter = node.clone();
+        let blocked =
+            std::thread::spawn(move || waiter.wait_for_frame(Some(Duration::from_millis(200))));
+        while node.waiter_count() < 1 {
+            std::thread::yield_now();
+        }
+
+        node.detach().unwrap();
+        sender.transmit(standard_frame(0x1, &[])).unwrap();
+        assert!(!blocked.join().unwrap());
+        assert_eq!(node.waiter_count(), 0);
+    }
+
+    #[test]
+    fn closing_the_bus_releases_blocked_receivers() {
+        let bus = BusHandle::new();
+        let mut can = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let node = bus.add_interface(vec![]).unwrap();
+        let (_, mut rx) = MockCan::new_with_bus(&bus, vec![]).unwrap().split();
+        TxFrameIo::send(&mut can, &standard_frame(0x1, &[])).unwrap();
+
+        let waiting = [node.clone(), node.clone()].map(|node| {
+            std::thread::spawn(move || {
+                let event = node.wait_for_event(None);
+                (event, node.recv_frame(None), node.recv_frame(None))
+            })
+        });
+        let blocked_rx = std::thread::spawn(move || {
+            let first = RxFrameIo::recv(&mut rx);
+            (
+                first,
+                RxFrameIo::recv(&mut rx),
+                RxFrameIo::wait_not_empty(&mut rx),
+            )
+        });
+        while node.waiter_count() < 2 {
+            std::thread::yield_now();
+        }
+        bus.close();
+        assert!(can.iface.is_closed());
+        let mut frames = 0;
+        for handle in waiting {
+            let (event, first, second) = handle.join().unwrap();
+            assert_eq!(event, None);
+            assert_eq!(second, None);
+            frames += usize::from(first.is_some());
+        }
+        // The frame queued before closing is still handed out, once.
+        assert_eq!(frames, 1);
+        let (first, second, empty) = blocked_rx.join().unwrap();
+        assert_eq!(first.unwrap(), standard_frame(0x1, &[]));
+        assert!(matches!(second, Err(MockError::Closed)));
+        assert!(matches!(empty, Err(MockError::Closed)));
+
+        bus.reset();
+        assert!(!node.is_closed());
+    }
+
+    #[test]
+    fn a_panicking_node_does_not_poison_the_bus() {
+        use std::sync::Arc;
+        use trace::TraceEvent;
+
+        let bus = BusHandle::new();
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        a.transmit(standard_frame(0x1, &[])).unwrap();
+
+        let node = b.clone();
+        let crashed = std::thread::spawn(move || {
+            node.with_received_frames(|_| panic!("assertion failed in a node thread"))
+        });
+        assert!(crashed.join().is_err());
+        assert_eq!(b.pop_frame(), Some(standard_frame(0x1, &[])));
+
+        // A tracer panicking mid-transmit loses that frame, but nothing else.
+        bus.set_tracer(Some(Arc::new(|event: &TraceEvent<'_>| {
+            if let TraceEvent::Transmit { frame, .. } = event {
+                assert_ne!(frame.data(), [0xBA, 0xD0]);
+            }
+        })));
+        let node = a.clone();
+        let crashed = std::thread::spawn(move || node.transmit(standard_frame(0x2, &[0xBA, 0xD0])));
+        assert!(crashed.join().is_err());
+        a.transmit(standard_frame(0x3, &[])).unwrap();
+        assert_eq!(b.drain_frames(), vec![standard_frame(0x3, &[])]);
+        assert_eq!(a.sent_frames().len(), 3);
+    }
+
+    #[test]
+    fn departing_nodes_are_reported_to_observers() {
+        use std::sync::{Arc, Mutex};
+        use trace::TraceEvent;
+
+        let bus = BusHandle::new();
+        bus.set_timing(Some(timing::BusTiming::new(500_000)));
+        let tap = bus.tap();
+        let peer = bus.add_interface(vec![]).unwrap();
+        let left = Arc::new(Mutex::new(Vec::new()));
+        let sink = left.clone();
+        bus.set_tracer(Some(Arc::new(move |event: &TraceEvent<'_>| {
+            if let TraceEvent::NodeLeft { node } = event {
+                sink.lock().unwrap().push(*node);
+            }
+        })));
+
+        // A frame still waiting for the wire outlives its sender.
+        let sender = bus.add_interface(vec![]).unwrap();
+        let sender_id = sender.id();
+        sender.transmit(standard_frame(0x1, &[])).unwrap();
+        drop(sender);
+        assert_eq!(tap.pop_event(), Some(BusEvent::NodeLeft(sender_id)));
+        assert_eq!(peer.pop_event(), None);
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(peer.drain_frames(), vec![standard_frame(0x1, &[])]);
+
+        bus.set_announce_departures(true);
+        let node = bus.add_interface(vec![]).unwrap();
+        node.transmit(standard_frame(0x2, &[])).unwrap();
+        bus.advance(Duration::from_millis(1));
+        let node_id = node.id();
+        node.detach().unwrap();
+        assert_eq!(peer.pop_event(), Some(BusEvent::NodeLeft(node_id)));
+        assert!(matches!(
+            node.transmit(standard_frame(0x3, &[])),
+            Err(TransmitError::BusNotAttached)
+        ));
+        assert_eq!(node.drain_frames(), vec![standard_frame(0x2, &[])]);
+        // Dropping a detached interface reports nothing further.
+        drop(node);
+        assert_eq!(peer.pending_events(), Vec::new());
+        assert_eq!(*left.lock().unwrap(), vec![sender_id, node_id]);
+    }
+
+    #[test]
+    fn late_joiners_catch_up_through_their_filters() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        for id in 0..8 {
+            node.transmit(standard_frame(0x100 + id, &[])).unwrap();
+        }
+        bus.add_interface(vec![])
+            .unwrap()
+            .transmit_to_group("private", standard_frame(0x101, &[]))
+            .unwrap();
+
+        let filter = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(0x101).unwrap()),
+            mask: IdMask::Standard(0x7F1),
+        };
+        let mut late = MockCan::builder()
+            .on_bus(&bus)
+            .with_filters(vec![filter])
+            .unwrap()
+            .rx_capacity(2)
+            .catch_up(CatchUp::Last(6))
+            .build()
+            .unwrap();
+        // Of 0x102..=0x107, the filter passes 0x103, 0x105 and 0x107; the queue holds two.
+        assert_eq!(
+            late.iface.drain_frames(),
+            vec![standard_frame(0x103, &[]), standard_frame(0x105, &[])]
+        );
+        assert_eq!(late.iface.pop_event(), Some(BusEvent::RxOverflow));
+        assert!(matches!(
+            RxFrameIo::try_recv(&mut late),
+            Err(MockError::WouldBlock)
+        ));
+
+        let fresh = bus.add_interface(vec![]).unwrap();
+        assert!(!fresh.has_frames());
+        bus.reset();
+        let after_reset = bus.add_interface_with(vec![], CatchUp::Last(8)).unwrap();
+        assert!(!after_reset.has_frames());
+    }
+
+    #[test]
+    fn subscribers_consume_independently_of_their_interface() {
+        let bus = BusHandle::new();
+        let node = bus
+            .add_interface(vec![IdMaskFilter {
+                id: IfaceId::Standard(StandardId::new(0x100).unwrap()),
+                mask: IdMask::Standard(0x700),
+            }])
+            .unwrap();
+        let logger = node.subscribe();
+        let second = node.subscribe();
+        let sender = bus.add_interface(vec![]).unwrap();
+
+        for id in [0x100, 0x200, 0x1FF] {
+            sender.transmit(standard_frame(id, &[])).unwrap();
+        }
+        let expected = vec![standard_frame(0x100, &[]), standard_frame(0x1FF, &[])];
+        assert_eq!(node.drain_frames(), expected);
+        assert_eq!(logger.drain_frames(), expected);
+        assert_eq!(second.pop_frame(), Some(standard_frame(0x100, &[])));
+        assert_eq!(second.pop_delivery().unwrap().source, sender.id());
+        assert_eq!(logger.subscribed_to(), Some(node.id()));
+        assert!(matches!(
+            logger.transmit(standard_frame(0x1, &[])),
+            Err(TransmitError::ListenOnly)
+        ));
+    }
+
+    #[test]
+    fn received_frames_can_be_inspected_in_place() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.transmit_all(&[standard_frame(0x1, &[]), standard_frame(0x2, &[])])
+            .unwrap();
+
+        let (len, newest) = node.with_received_frames(|mut frames| {
+            let len = frames.len();
+            (len, frames.next_back().cloned())
+        });
+        assert_eq!(len, 2);
+        assert_eq!(newest, Some(standard_frame(0x2, &[])));
+        assert_eq!(
+            node.with_received_frames(|frames| frames.cloned().collect::<Vec<_>>()),
+            vec![standard_frame(0x1, &[]), standard_frame(0x2, &[])]
+        );
+        assert_eq!(node.queue_len(), 2);
+    }
+
+    #[test]
+    fn mailbox_mode_routes_by_first_matching_filter() {
+        let bus = BusHandle::new();
+        let exact = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(0x123).unwrap()),
+            mask: IdMask::Standard(0x7FF),
+        };
+        let group = IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(0x100).unwrap()),
+            mask: IdMask::Standard(0x700),
+        };
+        let node = bus.add_interface(vec![exact, group]).unwrap();
+        node.set_mailbox_mode(true);
+
+        node.transmit_all(&[
+            standard_frame(0x150, &[]),
+            standard_frame(0x123, &[]),
+            standard_frame(0x300, &[]),
+        ])
+        .unwrap();
+        assert_eq!(node.queue_len(), 2);
+        assert_eq!(
+            node.wait_for_mailbox(0, Some(Duration::ZERO)),
+            Some(standard_frame(0x123, &[]))
+        );
+        assert_eq!(node.wait_for_mailbox(0, Some(Duration::ZERO)), None);
+        assert_eq!(node.pop_frame(), Some(standard_frame(0x150, &[])));
+
+        node.set_mailbox_mode(false);
+        node.transmit(standard_frame(0x150, &[])).unwrap();
+        assert_eq!(node.mailbox_len(0), 1);
+    }
+
+    #[test]
+    fn dual_fifo_filters_route_frames_to_their_fifo() {
+        let bus = BusHandle::new();
+        let mut can = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let mut sender = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let filter = |id| IdMaskFilter {
+            id: IfaceId::Standard(StandardId::new(id).unwrap()),
+            mask: IdMask::Standard(0x7FF),
+        };
+        can.set_fifo_filters(&[(filter(0x10), RxFifo::Fifo1), (filter(0x20), RxFifo::Fifo0)])
+            .unwrap();
+        can.interface().set_nonblocking(true);
+
+        for id in [0x10, 0x20, 0x10] {
+            TxFrameIo::send(&mut sender, &standard_frame(id, &[])).unwrap();
+        }
+        assert_eq!(can.recv_fifo0().unwrap(), standard_frame(0x20, &[]));
+        assert!(matches!(can.recv_fifo0(), Err(MockError::WouldBlock)));
+        assert_eq!(can.recv_fifo1().unwrap(), standard_frame(0x10, &[]));
+        assert_eq!(
+            RxFrameIo::recv(&mut can).unwrap(),
+            standard_frame(0x10, &[])
+        );
+
+        // Plain filter updates drop the FIFO assignment.
+        FilterConfig::set_filters(&mut can, &[filter(0x10)]).unwrap();
+        TxFrameIo::send(&mut sender, &standard_frame(0x10, &[])).unwrap();
+        assert_eq!(can.recv_fifo0().unwrap(), standard_frame(0x10, &[]));
+    }
+
+    #[test]
+    fn standard_filters_gate_delivery_to_matching_frames() {
+        let matching = standard_frame(0x123, &[0xAA]);
+        let rejected = standard_frame(0x321, &[0xBB]);
+
+        let bus = BusHandle::new();
+
+        let filtered_node = bus
+            .add_interface(vec![IdMaskFilter {
+                id: IfaceId::Standard(StandardId::new(0x123).unwrap()),
+                mask: IdMask::Standard(0x7FF),
+            }])
+            .unwrap();
+        let unfiltered_node = bus.add_interface(vec![]).unwrap();
+
+        unfiltered_node.transmit(matching.clone()).unwrap();
+        unfiltered_node.transmit(rejected.clone()).unwrap();
+
+        let filtered_received = filtered_node.received_frames();
+        let unfiltered_received = unfiltered_node.received_frames();
+
+        assert_eq!(unfiltered_received.len(), 2);
+        assert_eq!(filtered_received, vec![matching.clone()]);
+        assert!(!filtered_received.contains(&rejected));
     }
 
     #[test]
@@ -842,6 +3215,91 @@ mod tests {
         assert!(matches!(result, Err(MockError::InvalidFilters)));
     }
 
+    #[test]
+    fn filter_stats_count_hits_and_restart_with_new_filters() {
+        let bus = BusHandle::new();
+        let sender = bus.add_interface(vec![]).unwrap();
+        let node = bus.add_interface(vec![]).unwrap();
+        let id = |raw| StandardId::new(raw).unwrap();
+        node.set_filters(vec![
+            filter::accept_exact(id(0x10)),
+            filter::accept_all_standard(),
+        ])
+        .unwrap();
+        for raw in [0x10, 0x11, 0x12, 0x10] {
+            sender.transmit(standard_frame(raw, &[])).unwrap();
+        }
+        assert_eq!(node.filter_stats().hits, [2, 2]);
+        assert_eq!(node.filter_stats().false_accepts, 0);
+
+        node.set_filter_semantics(FilterSemantics {
+            mode: filter::MatchMode::MatchAll,
+            ..FilterSemantics::default()
+        });
+        sender.transmit(standard_frame(0x10, &[])).unwrap();
+        assert_eq!(node.filter_stats().hits, [3, 3]);
+
+        // A single bank must over-accept 0x7E1..=0x7E2 as 0x7E0..=0x7E3.
+        node.set_filter_range(id(0x7E1), id(0x7E2), 1);
+        assert_eq!(node.filter_stats().hits, [0]);
+        for raw in 0x7DF..=0x7E4 {
+            sender.transmit(standard_frame(raw, &[])).unwrap();
+        }
+        let stats = node.filter_stats();
+        assert_eq!((stats.hits, stats.false_accepts), (vec![4], 2));
+        bus.reset();
+        assert_eq!(node.filter_stats().false_accepts, 0);
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_format_covers_frames_errors_and_events() {
+        fn loggable<T: defmt::Format>() {}
+        loggable::<MockFrame>();
+        loggable::<SmallFrame>();
+        loggable::<MockError>();
+        loggable::<TransmitError>();
+        loggable::<FilterError>();
+        loggable::<BusEvent>();
+        loggable::<ErrorState>();
+        loggable::<ErrorCounters>();
+        loggable::<TxToken>();
+    }
+
+    #[test]
+    fn frame_callbacks_leave_the_bus_on_drop_and_surface_panics() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let before = bus.interface_count();
+        let (seen_tx, seen_rx) = std::sync::mpsc::channel();
+        let counter = node.on_frame(
+            |frame: &MockFrame| frame.data().first() == Some(&1),
+            move |frame| seen_tx.send(frame).unwrap(),
+        );
+        assert_eq!(bus.interface_count(), before + 1);
+        node.transmit(standard_frame(0x10, &[0])).unwrap();
+        node.transmit(standard_frame(0x10, &[1])).unwrap();
+        let seen = seen_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(seen, standard_frame(0x10, &[1]));
+        // The worker only watches: the interface still has both frames.
+        assert_eq!(node.received_frames().len(), 2);
+        drop(counter);
+        assert_eq!(bus.interface_count(), before);
+
+        let failing = node.on_frame(Id::Standard(StandardId::new(0x20).unwrap()), |_| {
+            panic!("double rejected the frame")
+        });
+        node.transmit(standard_frame(0x20, &[])).unwrap();
+        while failing.is_running() {
+            std::thread::yield_now();
+        }
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(failing)));
+        assert_eq!(
+            *panic.unwrap_err().downcast::<&str>().unwrap(),
+            "double rejected the frame"
+        );
+    }
+
     #[test]
     fn tx_rx_state_and_blocking_control_paths_work() {
         let bus = BusHandle::new();
@@ -858,6 +3316,138 @@ mod tests {
         assert_eq!(RxFrameIo::recv(&mut node).unwrap(), frame);
     }
 
+    #[test]
+    fn nonblocking_mode_makes_receive_return_would_block() {
+        let bus = BusHandle::new();
+        let node = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let (mut tx, mut rx) = node.clone().split();
+        let mut node = node;
+
+        BlockingControl::set_nonblocking(&mut node, true).unwrap();
+        assert!(matches!(
+            RxFrameIo::recv(&mut node),
+            Err(MockError::WouldBlock)
+        ));
+        assert!(matches!(
+            RxFrameIo::recv_timeout(&mut rx, Duration::from_secs(5)),
+            Err(MockError::WouldBlock)
+        ));
+        assert!(matches!(
+            RxFrameIo::wait_not_empty(&mut rx),
+            Err(MockError::WouldBlock)
+        ));
+
+        let frame = standard_frame(0x10, &[0x01]);
+        TxFrameIo::send(&mut tx, &frame).unwrap();
+        assert_eq!(RxFrameIo::recv(&mut rx).unwrap(), frame);
+
+        BlockingControl::set_nonblocking(&mut node, false).unwrap();
+        assert!(matches!(
+            RxFrameIo::recv_timeout(&mut node, Duration::from_millis(1)),
+            Err(MockError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn timed_bus_spaces_deliveries_by_wire_time() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(500_000);
+        bus.set_timing(Some(timing));
+        let mut tx = MockCan::new_with_bus(&bus, vec![]).unwrap();
+        let rx = bus.add_interface(vec![]).unwrap();
+
+        let first = standard_frame(0x100, &[0x11; 8]);
+        let second = standard_frame(0x200, &[0x22; 4]);
+        TxFrameIo::send(&mut tx, &first).unwrap();
+        TxFrameIo::send(&mut tx, &second).unwrap();
+        assert!(!TxRxState::is_transmitter_idle(&tx).unwrap());
+        assert_eq!(bus.in_flight_count(), 2);
+
+        let first_done = timing.wire_time(&first);
+        bus.advance(first_done - Duration::from_nanos(1));
+        assert!(rx.received_frames().is_empty());
+        bus.advance(Duration::from_nanos(1));
+        assert_eq!(rx.received_frames(), vec![first.clone()]);
+
+        bus.advance(timing.wire_time(&second));
+        assert_eq!(rx.received_frames(), vec![first, second]);
+        assert_eq!(
+            bus.now(),
+            first_done + timing.wire_time(&standard_frame(0x200, &[0x22; 4]))
+        );
+        assert!(TxRxState::is_transmitter_idle(&tx).unwrap());
+    }
+
+    #[test]
+    fn saturated_bus_arbitrates_by_priority_and_reports_load() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(250_000);
+        bus.set_timing(Some(timing));
+        let node = bus.add_interface(vec![]).unwrap();
+
+        let blocker = standard_frame(0x400, &[0; 8]);
+        let low = standard_frame(0x300, &[1]);
+        let high = standard_frame(0x010, &[2]);
+        node.transmit(blocker.clone()).unwrap();
+        node.transmit(low.clone()).unwrap();
+        node.transmit(high.clone()).unwrap();
+        assert_eq!(bus.load().waiting, 2);
+
+        bus.advance(Duration::from_millis(10));
+        assert_eq!(
+            node.received_frames(),
+            vec![blocker.clone(), high.clone(), low.clone()]
+        );
+        let load = bus.load();
+        assert!(load.occupancy > 0.0 && load.occupancy < 1.0);
+        assert_eq!(load.backlog, Duration::ZERO);
+        assert!(!load.overloaded);
+
+        // With rejection, a high-priority frame evicts a waiting low-priority one.
+        bus.reset();
+        let limit = timing.wire_time(&blocker) + timing.wire_time(&low);
+        bus.set_load_limit(Some(timing::LoadLimit {
+            max_backlog: limit,
+            reject: true,
+        }));
+        node.transmit(blocker.clone()).unwrap();
+        node.transmit(low.clone()).unwrap();
+        node.transmit(high.clone()).unwrap();
+        assert!(matches!(
+            node.transmit(low.clone()),
+            Err(TransmitError::BusOverloaded)
+        ));
+        let load = bus.load();
+        assert!(load.overloaded);
+        assert_eq!(load.rejected, 2);
+
+        bus.advance(Duration::from_millis(10));
+        assert_eq!(node.received_frames(), vec![blocker, high]);
+        assert!(!bus.load().overloaded);
+    }
+
+    #[test]
+    fn rejected_frames_are_not_recorded_as_sent() {
+        let bus = BusHandle::new();
+        let timing = timing::BusTiming::new(125_000);
+        bus.set_timing(Some(timing));
+        let frame = standard_frame(0x700, &[0; 8]);
+        bus.set_load_limit(Some(timing::LoadLimit {
+            max_backlog: timing.wire_time(&frame),
+            reject: true,
+        }));
+        let node = bus.add_interface(vec![]).unwrap();
+
+        let first = node.transmit(frame.clone()).unwrap();
+        assert!(matches!(
+            node.transmit(frame.clone()),
+            Err(TransmitError::BusOverloaded)
+        ));
+        assert_eq!(node.sent_frames(), vec![frame.clone()]);
+        bus.advance(Duration::from_millis(10));
+        assert_eq!(node.transmit(frame).unwrap().seq(), first.seq() + 1);
+    }
+
     #[test]
     fn buffered_io_creates_wrapper() {
         let bus = BusHandle::new();
@@ -0,0 +1,73 @@
+//! Frame predicates used by selective receive.
+//!
+//! A [`FrameMatcher`] decides whether a given frame (a [`MockFrame`] by default) is “interesting”.
+//! It is implemented for closures (`Fn(&F) -> bool`), for [`embedded_can::Id`] (exact ID match),
+//! and for [`embedded_can_interface::IdMaskFilter`] (the same ID/mask semantics as acceptance
+//! filters).
+
+use embedded_can::{Frame, Id};
+use embedded_can_interface::IdMaskFilter;
+
+use crate::{filter::matches as filter_matches, frame::MockFrame};
+
+/// Predicate over received frames.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::{Frame as _, Id, StandardId};
+/// use embedded_can_mock::{FrameMatcher, MockFrame};
+///
+/// let id = Id::Standard(StandardId::new(0x123).unwrap());
+/// let frame = MockFrame::new(id, &[0x01]).unwrap();
+///
+/// assert!(id.matches(&frame));
+/// assert!((|f: &MockFrame| f.data() == [0x01]).matches(&frame));
+/// ```
+pub trait FrameMatcher<F = MockFrame> {
+    /// Returns `true` if `frame` satisfies this matcher.
+    fn matches(&self, frame: &F) -> bool;
+}
+
+impl<F, M> FrameMatcher<F> for M
+where
+    M: Fn(&F) -> bool,
+{
+    fn matches(&self, frame: &F) -> bool {
+        self(frame)
+    }
+}
+
+impl<F: Frame> FrameMatcher<F> for Id {
+    fn matches(&self, frame: &F) -> bool {
+        frame.id() == *self
+    }
+}
+
+impl<F: Frame> FrameMatcher<F> for IdMaskFilter {
+    fn matches(&self, frame: &F) -> bool {
+        filter_matches(self, frame.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::{ExtendedId, StandardId};
+    use embedded_can_interface::IdMask;
+
+    #[test]
+    fn id_mask_filter_matcher_uses_filter_semantics() {
+        let filter = IdMaskFilter {
+            id: embedded_can_interface::Id::Standard(StandardId::new(0x120).unwrap()),
+            mask: IdMask::Standard(0x7F0),
+        };
+        let hit = MockFrame::new(StandardId::new(0x12F).unwrap(), &[]).unwrap();
+        let miss = MockFrame::new(StandardId::new(0x130).unwrap(), &[]).unwrap();
+        let ext = MockFrame::new(ExtendedId::new(0x120).unwrap(), &[]).unwrap();
+
+        assert!(filter.matches(&hit));
+        assert!(!filter.matches(&miss));
+        assert!(!filter.matches(&ext));
+    }
+}
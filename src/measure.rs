@@ -0,0 +1,385 @@
+//! Timing statistics over recorded traffic.
+//!
+//! Timing requirements such as "the status frame is sent every 10 ms ± 1 ms" or "the ECU
+//! answers within 10 ms" are checked against a [recording](crate::recorder): [`period_stats`]
+//! summarizes the spacing of one ID's frames and [`latency_stats`] the delay between requests and
+//! the responses they trigger. The [`Recorder`](crate::recorder::Recorder) has shortcuts for
+//! both, so an assertion is one line:
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::recorder::Recorder;
+//!
+//! let bus = BusHandle::new();
+//! let recorder = Recorder::attach(&bus);
+//! let ecu = bus.add_interface(vec![]).unwrap();
+//! let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+//! for offset_us in [0, 10_100, 19_900, 30_000] {
+//!     bus.advance(Duration::from_micros(offset_us) - bus.now());
+//!     ecu.transmit(frame(0x100)).unwrap();
+//! }
+//!
+//! let status = recorder.period_stats(0x100).unwrap();
+//! assert_eq!(status.mean, Duration::from_millis(10));
+//! assert!(status.jitter <= Duration::from_millis(1), "{status}");
+//! ```
+//!
+//! Frames that were corrupted on the bus are ignored, as a real receiver would never see them.
+//!
+//! [`priority_inversions`] looks at transmit queueing instead: it flags frames that waited for
+//! the bus while lower-priority frames went ahead, as happens when a driver queues frames in a
+//! FIFO or a single TX buffer rather than by priority.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{fmt, time::Duration};
+
+use embedded_can::{Frame, Id};
+
+use crate::{frame::MockFrame, recorder::TraceRecord, timing::arbitration_key};
+
+/// Spacing between consecutive frames of one ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeriodStats {
+    /// Number of frames measured (one more than the number of periods).
+    pub frames: usize,
+    /// Shortest period.
+    pub min: Duration,
+    /// Average period.
+    pub mean: Duration,
+    /// Longest period.
+    pub max: Duration,
+    /// Peak-to-peak jitter: `max - min`.
+    pub jitter: Duration,
+}
+
+impl PeriodStats {
+    /// Statistics of the gaps between `times`, which must be in order; `None` with fewer than
+    /// two times.
+    pub fn from_times(times: &[Duration]) -> Option<Self> {
+        let periods: Vec<Duration> = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        let (min, mean, max) = summarize(&periods)?;
+        Some(Self {
+            frames: times.len(),
+            min,
+            mean,
+            max,
+            jitter: max - min,
+        })
+    }
+}
+
+impl fmt::Display for PeriodStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} frames, period mean {:?} min {:?} max {:?}, jitter {:?}",
+            self.frames, self.mean, self.min, self.max, self.jitter
+        )
+    }
+}
+
+/// Delay between requests and their responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Requests that got a response.
+    pub answered: usize,
+    /// Requests without a response.
+    pub unanswered: usize,
+    /// Best-case latency.
+    pub min: Duration,
+    /// Average latency.
+    pub mean: Duration,
+    /// Worst-case latency.
+    pub max: Duration,
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} answered ({} unanswered), latency mean {:?} min {:?} worst {:?}",
+            self.answered, self.unanswered, self.mean, self.min, self.max
+        )
+    }
+}
+
+/// Period statistics of the frames with raw ID `id` (standard or extended) in `records`, or
+/// `None` if fewer than two were recorded.
+pub fn period_stats<F: Frame>(records: &[TraceRecord<F>], id: u32) -> Option<PeriodStats> {
+    let times: Vec<Duration> = delivered(records)
+        .filter(|record| raw_id(record.frame.id()) == id)
+        .map(|record| record.at)
+        .collect();
+    PeriodStats::from_times(&times)
+}
+
+/// Period statistics of every raw ID recorded at least twice.
+pub fn period_stats_by_id<F: Frame>(records: &[TraceRecord<F>]) -> BTreeMap<u32, PeriodStats> {
+    let mut times: BTreeMap<u32, Vec<Duration>> = BTreeMap::new();
+    for record in delivered(records) {
+        times
+            .entry(raw_id(record.frame.id()))
+            .or_default()
+            .push(record.at);
+    }
+    times
+        .into_iter()
+        .filter_map(|(id, times)| Some((id, PeriodStats::from_times(&times)?)))
+        .collect()
+}
+
+/// Latency from each frame with raw ID `request` to the next frame with raw ID `response`.
+///
+/// A response answers the latest outstanding request; a request followed by another request
+/// before any response counts as unanswered, and responses with no outstanding request are
+/// ignored. Returns `None` if no request was answered.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{BusHandle, MockFrame};
+/// use embedded_can_mock::measure::latency_stats;
+/// use embedded_can_mock::recorder::Recorder;
+///
+/// let bus = BusHandle::new();
+/// let recorder = Recorder::attach(&bus);
+/// let node = bus.add_interface(vec![]).unwrap();
+/// let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+/// node.transmit(frame(0x7E0)).unwrap();
+/// bus.advance(Duration::from_millis(4));
+/// node.transmit(frame(0x7E8)).unwrap();
+/// node.transmit(frame(0x7E0)).unwrap();
+///
+/// let stats = latency_stats(&recorder.records(), 0x7E0, 0x7E8).unwrap();
+/// assert_eq!((stats.answered, stats.unanswered), (1, 1));
+/// assert_eq!(stats.max, Duration::from_millis(4));
+/// ```
+pub fn latency_stats<F: Frame>(
+    records: &[TraceRecord<F>],
+    request: u32,
+    response: u32,
+) -> Option<LatencyStats> {
+    let mut outstanding = None;
+    let mut unanswered = 0;
+    let mut latencies = Vec::new();
+    for record in delivered(records) {
+        let id = raw_id(record.frame.id());
+        if id == request {
+            unanswered += usize::from(outstanding.is_some());
+            outstanding = Some(record.at);
+        } else if id == response
+            && let Some(sent) = outstanding.take()
+        {
+            latencies.push(record.at - sent);
+        }
+    }
+    unanswered += usize::from(outstanding.is_some());
+    let (min, mean, max) = summarize(&latencies)?;
+    Some(LatencyStats {
+        answered: latencies.len(),
+        unanswered,
+        min,
+        mean,
+        max,
+    })
+}
+
+/// A frame that waited for the bus while lower-priority frames were sent, found by
+/// [`priority_inversions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityInversion<F = MockFrame> {
+    /// The higher-priority frame that waited.
+    pub blocked: TraceRecord<F>,
+    /// Lower-priority frames that went on the wire while it waited, in order.
+    pub overtaken_by: Vec<TraceRecord<F>>,
+}
+
+impl<F> PriorityInversion<F> {
+    /// Time from the blocked frame's request until it went on the wire.
+    pub fn waited(&self) -> Duration {
+        self.blocked.started.saturating_sub(self.blocked.requested)
+    }
+}
+
+impl<F: Frame> fmt::Display for PriorityInversion<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:X} requested at {:?} waited {:?} while {} lower-priority frame(s) were sent:",
+            raw_id(self.blocked.frame.id()),
+            self.blocked.requested,
+            self.waited(),
+            self.overtaken_by.len()
+        )?;
+        for record in &self.overtaken_by {
+            write!(f, " {:X}@{:?}", raw_id(record.frame.id()), record.started)?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames in `records` that waited longer than `threshold` between their request and going on
+/// the wire while at least one lower-priority frame started, in order of the blocked frames.
+///
+/// Arbitration on the mock bus is always by priority, so inversions come from frames held back
+/// before they reach it: by [pacing](crate::InterfaceHandle::set_pacing), a
+/// [schedule](crate::BusHandle::set_schedule), or a driver that hands frames over in queue
+/// order. A frame requested while a lower-priority one is already on the wire is not an
+/// inversion, as transmissions cannot be preempted.
+///
+/// # Example
+///
+/// ```
+/// use core::time::Duration;
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{BusHandle, MockFrame};
+/// use embedded_can_mock::measure::priority_inversions;
+/// use embedded_can_mock::recorder::Recorder;
+/// use embedded_can_mock::timing::Pacing;
+///
+/// let bus = BusHandle::new();
+/// let recorder = Recorder::attach(&bus);
+/// let node = bus.add_interface(vec![]).unwrap();
+/// // A paced FIFO: one frame per millisecond, in the order they were queued.
+/// node.set_pacing(Some(Pacing { min_gap: Duration::from_millis(1), rate_limit: None, reject: false }));
+/// for id in [0x300, 0x300, 0x300, 0x100] {
+///     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()).unwrap();
+/// }
+/// bus.advance(Duration::from_millis(5));
+///
+/// let inversions = priority_inversions(&recorder.records(), Duration::from_micros(500));
+/// assert_eq!(inversions.len(), 1);
+/// assert_eq!(inversions[0].waited(), Duration::from_millis(3));
+/// assert_eq!(inversions[0].overtaken_by.len(), 2);
+/// ```
+pub fn priority_inversions<F: Frame + Clone>(
+    records: &[TraceRecord<F>],
+    threshold: Duration,
+) -> Vec<PriorityInversion<F>> {
+    records
+        .iter()
+        .filter(|blocked| blocked.started.saturating_sub(blocked.requested) > threshold)
+        .filter_map(|blocked| {
+            let key = arbitration_key(&blocked.frame);
+            let overtaken_by: Vec<_> = records
+                .iter()
+                .filter(|other| {
+                    other.started > blocked.requested
+                        && other.started < blocked.started
+                        && arbitration_key(&other.frame) > key
+                })
+                .cloned()
+                .collect();
+            (!overtaken_by.is_empty()).then(|| PriorityInversion {
+                blocked: blocked.clone(),
+                overtaken_by,
+            })
+        })
+        .collect()
+}
+
+/// Records whose frame was delivered rather than corrupted.
+fn delivered<F>(records: &[TraceRecord<F>]) -> impl Iterator<Item = &TraceRecord<F>> {
+    records.iter().filter(|record| record.error.is_none())
+}
+
+/// Minimum, mean and maximum of `values`, or `None` if empty.
+fn summarize(values: &[Duration]) -> Option<(Duration, Duration, Duration)> {
+    let min = *values.iter().min()?;
+    let max = *values.iter().max()?;
+    let total: Duration = values.iter().sum();
+    let mean = total / u32::try_from(values.len()).unwrap_or(u32::MAX);
+    Some((min, mean, max))
+}
+
+fn raw_id(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => u32::from(id.as_raw()),
+        Id::Extended(id) => id.as_raw(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusEvent, BusHandle, MockFrame, recorder::Recorder};
+    use embedded_can::{ExtendedId, StandardId};
+
+    #[test]
+    fn measures_periods_per_id_and_skips_corrupted_frames() {
+        let bus = BusHandle::new();
+        let recorder = Recorder::attach(&bus);
+        let node = bus.add_interface(vec![]).unwrap();
+        node.set_auto_retransmit(false);
+        let standard = MockFrame::new(StandardId::new(0x10).unwrap(), &[]).unwrap();
+        let extended = MockFrame::new(ExtendedId::new(0x1000).unwrap(), &[]).unwrap();
+        for ms in [0, 9, 20, 31] {
+            bus.advance(Duration::from_millis(ms) - bus.now());
+            node.transmit(standard.clone()).unwrap();
+            node.transmit(extended.clone()).unwrap();
+        }
+        bus.corrupt_next(BusEvent::CrcError);
+        node.transmit(standard).unwrap();
+
+        let by_id = period_stats_by_id(&recorder.records());
+        assert_eq!(by_id.len(), 2);
+        let stats = by_id[&0x10];
+        assert_eq!(stats, by_id[&0x1000]);
+        assert_eq!(stats.frames, 4);
+        assert_eq!(
+            (stats.min, stats.max, stats.jitter),
+            (
+                Duration::from_millis(9),
+                Duration::from_millis(11),
+                Duration::from_millis(2)
+            )
+        );
+        assert_eq!(stats.mean, Duration::from_nanos(10_333_333));
+        assert_eq!(period_stats(&recorder.records(), 0x11), None);
+        assert_eq!(
+            stats.to_string(),
+            "4 frames, period mean 10.333333ms min 9ms max 11ms, jitter 2ms"
+        );
+    }
+
+    #[test]
+    fn flags_inversions_but_not_waiting_for_the_current_frame() {
+        let bus = BusHandle::new();
+        bus.set_timing(Some(crate::timing::BusTiming::new(500_000)));
+        let recorder = Recorder::attach(&bus);
+        let slow = bus.add_interface(vec![]).unwrap();
+        let urgent = bus.add_interface(vec![]).unwrap();
+        let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[0; 8]).unwrap();
+        slow.transmit(frame(0x400)).unwrap();
+        urgent.transmit(frame(0x100)).unwrap();
+        bus.advance(Duration::from_millis(1));
+        let records = recorder.records();
+        assert!(records[1].started > records[1].requested);
+        assert!(priority_inversions(&records, Duration::ZERO).is_empty());
+
+        // Paced behind two low-priority frames, 0x100 is overtaken by the second one.
+        slow.set_pacing(Some(crate::timing::Pacing {
+            min_gap: Duration::from_millis(1),
+            rate_limit: None,
+            reject: false,
+        }));
+        recorder.clear();
+        for id in [0x400, 0x400, 0x100] {
+            slow.transmit(frame(id)).unwrap();
+        }
+        bus.advance(Duration::from_millis(5));
+        let inversions = recorder.priority_inversions(Duration::from_micros(1500));
+        assert_eq!(inversions.len(), 1);
+        assert_eq!(inversions[0].overtaken_by.len(), 1);
+        assert!(inversions[0].to_string().starts_with("100 requested at"));
+        assert!(
+            recorder
+                .priority_inversions(inversions[0].waited())
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Pluggable physical medium of a mock bus.
+//!
+//! A bus hands every frame that completes on the wire to its [`BusMedium`], which decides which
+//! attached interfaces the frame physically reaches. Each reached interface then applies its own
+//! self-reception setting, acceptance filters and fault injection as usual. The default medium,
+//! [`Broadcast`], reaches every interface, like a single shared CAN segment; custom media can
+//! model lossy links, star couplers or partitioned segments without changing the bus itself.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, BusMedium, InterfaceId, MockFrame};
+//!
+//! /// A star coupler whose first port is cut off from the others.
+//! struct IsolatedPort;
+//!
+//! impl BusMedium for IsolatedPort {
+//!     fn deliver(
+//!         &mut self,
+//!         _frame: &MockFrame,
+//!         source: InterfaceId,
+//!         interfaces: &[InterfaceId],
+//!     ) -> Vec<InterfaceId> {
+//!         let isolated = interfaces[0];
+//!         interfaces
+//!             .iter()
+//!             .copied()
+//!             .filter(|&id| (id == isolated) == (source == isolated))
+//!             .collect()
+//!     }
+//! }
+//!
+//! let bus = BusHandle::new();
+//! let (a, b, c) = (
+//!     bus.add_interface(vec![]).unwrap(),
+//!     bus.add_interface(vec![]).unwrap(),
+//!     bus.add_interface(vec![]).unwrap(),
+//! );
+//! bus.set_medium(IsolatedPort);
+//!
+//! b.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+//! assert!(!a.has_frames());
+//! assert!(c.has_frames());
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{bus::InterfaceId, frame::MockFrame};
+
+/// Decides which interfaces a frame on the bus reaches.
+pub trait BusMedium<F = MockFrame>: Send {
+    /// Return the interfaces, out of the attached `interfaces` (in attach order), that `frame`
+    /// transmitted by `source` reaches.
+    ///
+    /// `source` is included in `interfaces` when the transmitter is attached; leaving it out of
+    /// the result suppresses self-reception for this frame. Identifiers not in `interfaces` are
+    /// ignored.
+    fn deliver(
+        &mut self,
+        frame: &F,
+        source: InterfaceId,
+        interfaces: &[InterfaceId],
+    ) -> Vec<InterfaceId>;
+}
+
+/// The default medium: one shared segment on which every frame reaches every interface.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Broadcast;
+
+impl<F> BusMedium<F> for Broadcast {
+    fn deliver(
+        &mut self,
+        _frame: &F,
+        _source: InterfaceId,
+        interfaces: &[InterfaceId],
+    ) -> Vec<InterfaceId> {
+        interfaces.to_vec()
+    }
+}
+
+/// A segmented bus: frames only reach the interfaces in their transmitter's segment.
+///
+/// Interfaces listed in no segment share one implicit segment of their own, so partitioning
+/// off a few nodes leaves the rest of the bus intact. Reinstall [`Broadcast`] to heal the bus.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{BusHandle, MockFrame};
+/// use embedded_can_mock::medium::Partition;
+///
+/// let bus = BusHandle::new();
+/// let (a, b, c) = (
+///     bus.add_interface(vec![]).unwrap(),
+///     bus.add_interface(vec![]).unwrap(),
+///     bus.add_interface(vec![]).unwrap(),
+/// );
+/// bus.set_medium(Partition::new(vec![vec![a.id()]]));
+///
+/// b.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+/// assert!(!a.has_frames());
+/// assert!(c.has_frames());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Partition {
+    segments: Vec<Vec<InterfaceId>>,
+}
+
+impl Partition {
+    /// Split the bus into `segments`.
+    pub fn new(segments: Vec<Vec<InterfaceId>>) -> Self {
+        Self { segments }
+    }
+
+    /// Index of the segment holding `id`, or `None` for the implicit one.
+    fn segment_of(&self, id: InterfaceId) -> Option<usize> {
+        self.segments
+            .iter()
+            .position(|segment| segment.contains(&id))
+    }
+}
+
+impl<F> BusMedium<F> for Partition {
+    fn deliver(
+        &mut self,
+        _frame: &F,
+        source: InterfaceId,
+        interfaces: &[InterfaceId],
+    ) -> Vec<InterfaceId> {
+        let segment = self.segment_of(source);
+        interfaces
+            .iter()
+            .copied()
+            .filter(|&id| self.segment_of(id) == segment)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+    use embedded_can::{Frame as _, StandardId};
+
+    /// Loses every other frame and never echoes to the transmitter.
+    struct Lossy(bool);
+
+    impl BusMedium for Lossy {
+        fn deliver(
+            &mut self,
+            _frame: &MockFrame,
+            source: InterfaceId,
+            interfaces: &[InterfaceId],
+        ) -> Vec<InterfaceId> {
+            self.0 = !self.0;
+            if !self.0 {
+                return Vec::new();
+            }
+            interfaces
+                .iter()
+                .copied()
+                .filter(|&id| id != source)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn custom_media_decide_reach_and_broadcast_restores_it() {
+        let bus = BusHandle::new();
+        let a = bus.add_interface(vec![]).unwrap();
+        let b = bus.add_interface(vec![]).unwrap();
+        let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+        bus.set_medium(Lossy(false));
+
+        a.transmit_all(&[frame(0x1), frame(0x2), frame(0x3)])
+            .unwrap();
+        assert!(!a.has_frames());
+        assert_eq!(b.drain_frames(), vec![frame(0x1), frame(0x3)]);
+
+        bus.set_medium(Broadcast);
+        a.transmit(frame(0x4)).unwrap();
+        assert_eq!(a.pop_frame(), Some(frame(0x4)));
+        assert_eq!(b.pop_frame(), Some(frame(0x4)));
+    }
+}
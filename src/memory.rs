@@ -0,0 +1,90 @@
+//! Memory bounds for long-running soak tests.
+//!
+//! By default the bus keeps every entry of its diagnostic histories, which is convenient in short
+//! tests but grows without bound in a soak test that runs for hours of virtual time.
+//! [`MemoryLimits`] installed with
+//! [`BusHandle::set_memory_limits`](crate::BusHandle::set_memory_limits) caps all of them at
+//! once, and can additionally watch the bus's queues for unbounded growth, which usually means a
+//! node stopped draining its frames or events. [`BusHandle::memory_usage`](crate::BusHandle::memory_usage)
+//! reports how much the bus currently holds.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::memory::MemoryLimits;
+//! use embedded_can_mock::recorder::Recorder;
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//!
+//! let bus = BusHandle::new();
+//! let recorder = Recorder::attach(&bus);
+//! let tx = bus.add_interface(vec![]).unwrap();
+//! let rx = bus.add_interface(vec![]).unwrap();
+//! bus.set_memory_limits(MemoryLimits {
+//!     history: Some(16),
+//!     queue_watermark: Some(100),
+//! });
+//! for i in 0..10_000u16 {
+//!     tx.transmit(MockFrame::new(StandardId::new(0x100).unwrap(), &i.to_le_bytes()).unwrap())
+//!         .unwrap();
+//!     tx.drain_frames();
+//!     rx.drain_frames();
+//! }
+//! assert_eq!(recorder.records().len(), 16);
+//! assert_eq!(tx.sent_frames().len(), 16);
+//! assert_eq!(bus.memory_usage().queued, 0);
+//! ```
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+/// Caps on the bus's internal bookkeeping. The default imposes none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryLimits {
+    /// Maximum number of entries kept in each history: every attached
+    /// [`Recorder`](crate::recorder::Recorder), each interface's
+    /// [TX history](crate::InterfaceHandle::sent_frames), the policy log, the filter rejection
+    /// log, the TTCAN window log and the [catch-up](crate::CatchUp) replay buffer. The oldest entries are discarded first. A TX history
+    /// capacity set on an interface still applies if it is smaller.
+    pub history: Option<usize>,
+    /// Leak detection: in builds with debug assertions, panic as soon as any queue (a receive
+    /// queue, pending events or TX confirmations of an interface, or the frames waiting for the
+    /// wire, held or scheduled on the bus) holds more than this many entries. Ignored in release
+    /// builds.
+    pub queue_watermark: Option<usize>,
+}
+
+/// Estimate of what a bus currently holds, from [`BusHandle::memory_usage`](crate::BusHandle::memory_usage).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Entries in queues: received frames, pending events and TX confirmations of every interface,
+    /// plus frames waiting for the wire, held or scheduled on the bus.
+    pub queued: usize,
+    /// Entries in histories: recorders, TX histories, the policy, rejection and window logs and
+    /// the replay buffer.
+    pub history: usize,
+    /// Approximate heap bytes used by those entries. Frames shared between receive queues are
+    /// counted once per queue, so this errs on the high side.
+    pub bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Account for `len` entries of type `T`.
+    pub(crate) fn add_queue<T>(&mut self, len: usize) {
+        self.queued += len;
+        self.bytes += len * size_of::<T>();
+    }
+
+    /// Account for `len` history entries of type `T`.
+    pub(crate) fn add_history<T>(&mut self, len: usize) {
+        self.history += len;
+        self.bytes += len * size_of::<T>();
+    }
+}
+
+/// Discard the oldest entries of `log` beyond `cap`.
+pub(crate) fn trim<T>(log: &mut Vec<T>, cap: Option<usize>) {
+    if let Some(cap) = cap
+        && log.len() > cap
+    {
+        log.drain(..log.len() - cap);
+    }
+}
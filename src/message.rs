@@ -0,0 +1,281 @@
+//! Typed CAN messages.
+//!
+//! A [`CanMessage`] is a Rust struct mapped onto one CAN ID and a payload layout, so tests can
+//! build and inspect frames as values with named, scaled fields instead of raw byte slices. With
+//! the `derive` feature, `#[derive(CanMessage)]` generates the mapping from attributes:
+//!
+//! - on the struct, `#[can(id = 0x123)]`, plus `extended` for a 29-bit ID and optionally
+//!   `dlc = 8` (by default the payload is just long enough for the last signal);
+//! - on each field, `#[can(start = 0, len = 16, scale = 0.1, offset = -40, signed)]`, all
+//!   optional: `start` defaults to the bit after the previous field, `len` to the width of the
+//!   field's integer type (1 for `bool`; floating-point fields need an explicit `len`), `scale`
+//!   and `offset` to the identity. Raw values are unsigned unless `signed` is given, which a
+//!   negative integer narrower than its field type needs.
+//!
+//! Signals use the little-endian ("Intel") layout of DBC files: bit `n` of the payload is bit
+//! `n % 8` of byte `n / 8`, a signal occupies bits `start .. start + len`, and its physical value
+//! is `raw * scale + offset`.
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use embedded_can::Frame as _;
+//! use embedded_can_mock::CanMessage;
+//!
+//! #[derive(Debug, PartialEq, CanMessage)]
+//! #[can(id = 0x0C4)]
+//! struct EngineStatus {
+//!     #[can(len = 16, scale = 0.25)]
+//!     rpm: f32,
+//!     #[can(len = 8, offset = -40)]
+//!     coolant_c: i16,
+//!     #[can(start = 24)]
+//!     running: bool,
+//! }
+//!
+//! let status = EngineStatus { rpm: 1500.0, coolant_c: 90, running: true };
+//! let frame = status.to_frame();
+//! assert_eq!(frame.data(), [0x70, 0x17, 130, 0x01]);
+//! assert_eq!(EngineStatus::from_frame(&frame), Some(status));
+//! # }
+//! ```
+
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+
+use crate::frame::MockFrame;
+
+/// A struct mapped onto a CAN ID and payload layout; usually derived (see the
+/// [module documentation](self)).
+pub trait CanMessage: Sized {
+    /// Raw CAN ID.
+    const ID: u32;
+    /// Whether [`ID`](Self::ID) is a 29-bit extended ID.
+    const EXTENDED: bool;
+    /// Payload length in bytes.
+    const DLC: usize;
+
+    /// Write the fields into `data`, which is [`DLC`](Self::DLC) bytes of zeros.
+    fn encode_payload(&self, data: &mut [u8]);
+
+    /// Read the fields from `data`, which is at least [`DLC`](Self::DLC) bytes long.
+    fn decode_payload(data: &[u8]) -> Self;
+
+    /// The message's CAN ID.
+    fn id() -> Id {
+        if Self::EXTENDED {
+            Id::Extended(ExtendedId::new(Self::ID).expect("extended ID fits 29 bits"))
+        } else {
+            Id::Standard(StandardId::new(Self::ID as u16).expect("standard ID fits 11 bits"))
+        }
+    }
+
+    /// Encode the message as a frame. Payloads over 8 bytes are flagged as CAN FD.
+    fn to_frame(&self) -> MockFrame {
+        let mut data = alloc::vec![0; Self::DLC];
+        self.encode_payload(&mut data);
+        let mut frame = MockFrame::new(Self::id(), &data).expect("mock frames take any payload");
+        if Self::DLC > 8 {
+            frame.meta_mut().fd = true;
+        }
+        frame
+    }
+
+    /// Decode `frame`, or `None` if it has another ID, is a remote frame, or is too short.
+    fn from_frame(frame: &impl Frame) -> Option<Self> {
+        (frame.id() == Self::id() && !frame.is_remote_frame() && frame.data().len() >= Self::DLC)
+            .then(|| Self::decode_payload(frame.data()))
+    }
+}
+
+/// Position and scaling of one signal within a payload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// First (least significant) bit.
+    pub start: u32,
+    /// Width in bits, 1 to 64.
+    pub len: u32,
+    /// Whether the raw value is two's complement.
+    pub signed: bool,
+    /// Physical value per raw unit.
+    pub scale: f64,
+    /// Physical value of raw zero.
+    pub offset: f64,
+}
+
+impl Layout {
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.len)
+    }
+
+    fn is_identity(&self) -> bool {
+        self.scale == 1.0 && self.offset == 0.0
+    }
+
+    /// Interpret a raw field as a number, sign-extending signed signals.
+    fn raw_to_i128(&self, raw: u64) -> i128 {
+        let sign = 1u64 << (self.len - 1);
+        if self.signed && raw & sign != 0 {
+            i128::from(raw) - (i128::from(self.mask()) + 1)
+        } else {
+            i128::from(raw)
+        }
+    }
+
+    fn physical(&self, raw: u64) -> f64 {
+        self.raw_to_i128(raw) as f64 * self.scale + self.offset
+    }
+
+    fn raw(&self, physical: f64) -> u64 {
+        let raw = round((physical - self.offset) / self.scale);
+        (raw as i64 as u64) & self.mask()
+    }
+}
+
+/// Round half away from zero; `f64::round` needs `std`.
+fn round(x: f64) -> f64 {
+    let truncated = x as i64 as f64;
+    if x - truncated >= 0.5 {
+        truncated + 1.0
+    } else if truncated - x >= 0.5 {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+/// Field types a [`CanMessage`] signal can have.
+pub trait Signal: Sized {
+    /// Default width in bits, or 0 if the field must state it.
+    const LEN: u32;
+
+    /// Raw bits for this value.
+    fn to_raw(&self, layout: &Layout) -> u64;
+
+    /// Value for raw bits (already masked to the signal width).
+    fn from_raw(raw: u64, layout: &Layout) -> Self;
+}
+
+macro_rules! integer_signal {
+    ($($ty:ty),*) => {$(
+        impl Signal for $ty {
+            const LEN: u32 = <$ty>::BITS;
+
+            fn to_raw(&self, layout: &Layout) -> u64 {
+                if layout.is_identity() {
+                    (*self as i128 as u64) & layout.mask()
+                } else {
+                    layout.raw(*self as f64)
+                }
+            }
+
+            fn from_raw(raw: u64, layout: &Layout) -> Self {
+                if layout.is_identity() {
+                    layout.raw_to_i128(raw) as $ty
+                } else {
+                    round(layout.physical(raw)) as $ty
+                }
+            }
+        }
+    )*};
+}
+
+integer_signal!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Signal for bool {
+    const LEN: u32 = 1;
+
+    fn to_raw(&self, layout: &Layout) -> u64 {
+        u64::from(*self) & layout.mask()
+    }
+
+    fn from_raw(raw: u64, _layout: &Layout) -> Self {
+        raw != 0
+    }
+}
+
+impl Signal for f64 {
+    const LEN: u32 = 0;
+
+    fn to_raw(&self, layout: &Layout) -> u64 {
+        layout.raw(*self)
+    }
+
+    fn from_raw(raw: u64, layout: &Layout) -> Self {
+        layout.physical(raw)
+    }
+}
+
+impl Signal for f32 {
+    const LEN: u32 = 0;
+
+    fn to_raw(&self, layout: &Layout) -> u64 {
+        layout.raw(f64::from(*self))
+    }
+
+    fn from_raw(raw: u64, layout: &Layout) -> Self {
+        layout.physical(raw) as f32
+    }
+}
+
+/// Write `value` into its bits of `data`.
+pub fn write_signal<S: Signal>(data: &mut [u8], layout: &Layout, value: &S) {
+    let raw = value.to_raw(layout);
+    for bit in 0..layout.len {
+        let at = layout.start + bit;
+        let byte = &mut data[(at / 8) as usize];
+        let mask = 1 << (at % 8);
+        if raw >> bit & 1 == 1 {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+}
+
+/// Read a signal's value from `data`.
+pub fn read_signal<S: Signal>(data: &[u8], layout: &Layout) -> S {
+    let raw = (0..layout.len).fold(0u64, |raw, bit| {
+        let at = layout.start + bit;
+        raw | u64::from(data[(at / 8) as usize] >> (at % 8) & 1) << bit
+    });
+    S::from_raw(raw, layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(start: u32, len: u32, signed: bool, scale: f64, offset: f64) -> Layout {
+        Layout {
+            start,
+            len,
+            signed,
+            scale,
+            offset,
+        }
+    }
+
+    #[test]
+    fn signals_round_trip_across_byte_boundaries() {
+        let mut data = [0xFFu8; 4];
+        let temp = layout(4, 12, true, 0.5, 0.0);
+        write_signal(&mut data, &temp, &-20.5f32);
+        // -41 as 12-bit two's complement is 0xFD7; the surrounding bits are untouched.
+        assert_eq!(data, [0x7F, 0xFD, 0xFF, 0xFF]);
+        assert_eq!(read_signal::<f32>(&data, &temp), -20.5);
+
+        let flag = layout(31, 1, false, 1.0, 0.0);
+        write_signal(&mut data, &flag, &false);
+        assert!(!read_signal::<bool>(&data, &flag));
+        assert_eq!(data[3], 0x7F);
+
+        let wide = layout(0, 32, false, 1.0, 0.0);
+        write_signal(&mut data, &wide, &0xDEAD_BEEFu32);
+        assert_eq!(read_signal::<u32>(&data, &wide), 0xDEAD_BEEF);
+        let narrow = layout(0, 4, true, 1.0, 0.0);
+        assert_eq!(read_signal::<i8>(&data, &narrow), -1);
+        let offset = layout(8, 8, false, 1.0, -40.0);
+        write_signal(&mut data, &offset, &-40i16);
+        assert_eq!((data[1], read_signal::<i16>(&data, &offset)), (0, -40));
+    }
+}
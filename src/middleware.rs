@@ -0,0 +1,133 @@
+//! Per-interface frame rewriting, delaying and dropping.
+//!
+//! Middleware sits between one interface and the bus, like a protocol shim in a driver: TX
+//! middleware sees every frame the interface transmits before the bus does, RX middleware every
+//! frame the interface's filters accepted before it is queued. Each returns a
+//! [`MiddlewareAction`] (or an `Option`, where `None` drops the frame), so targeted fault
+//! injection needs no bus-wide configuration. Middleware can also
+//! [report](MiddlewareAction::Report) events on its interface, the way
+//! [E2E protection](crate::e2e) flags frames that fail their checks:
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::middleware::MiddlewareAction;
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//!
+//! let frame = |id, data: &[u8]| MockFrame::new(StandardId::new(id).unwrap(), data).unwrap();
+//! let bus = BusHandle::new();
+//! let ecu = bus.add_interface(vec![]).unwrap();
+//! let tester = bus.add_interface(vec![]).unwrap();
+//! // The ECU's counter byte gets stuck at 0, and the tester drops every 0x7FF frame.
+//! ecu.on_tx(|frame: MockFrame| {
+//!     let mut data = frame.data().to_vec();
+//!     if let Some(counter) = data.first_mut() {
+//!         *counter = 0;
+//!     }
+//!     MockFrame::new(frame.id(), &data)
+//! });
+//! tester.on_rx(|frame: MockFrame| (frame.id() != StandardId::new(0x7FF).unwrap().into()).then_some(frame));
+//! // Responses to the tester arrive 2 ms late.
+//! tester.on_rx(|frame: MockFrame| MiddlewareAction::Delay(frame, Duration::from_millis(2)));
+//!
+//! ecu.transmit(frame(0x100, &[7])).unwrap();
+//! ecu.transmit(frame(0x7FF, &[])).unwrap();
+//! assert!(!tester.has_frames());
+//! bus.advance(Duration::from_millis(2));
+//! assert_eq!(tester.pop_frame(), Some(frame(0x100, &[0])));
+//! assert!(!tester.has_frames());
+//! ```
+//!
+//! Middleware runs in registration order while the bus is locked, so it must not use the bus
+//! itself. Delays are in virtual time: a delayed transmission requests the bus later, and a
+//! delayed reception is queued once the clock reaches it. Internal
+//! [loopback](crate::InterfaceHandle::set_loopback) traffic is rewritten and dropped but not
+//! delayed.
+
+use alloc::{sync::Arc, vec::Vec};
+use core::time::Duration;
+
+use crate::{event::BusEvent, frame::MockFrame};
+
+/// What a middleware does with a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiddlewareAction<F = MockFrame> {
+    /// Pass the (possibly rewritten) frame on.
+    Pass(F),
+    /// Pass the frame on after a delay; delays of chained middleware add up.
+    Delay(F, Duration),
+    /// Drop the frame. A dropped transmission still succeeds.
+    Drop,
+    /// Pass the frame on and queue `event` on the interface, as if its controller had flagged
+    /// the frame.
+    Report(F, BusEvent),
+}
+
+impl<F> From<Option<F>> for MiddlewareAction<F> {
+    fn from(frame: Option<F>) -> Self {
+        frame.map_or(MiddlewareAction::Drop, MiddlewareAction::Pass)
+    }
+}
+
+/// A middleware closure, as registered with
+/// [`InterfaceHandle::on_tx`](crate::InterfaceHandle::on_tx) or
+/// [`InterfaceHandle::on_rx`](crate::InterfaceHandle::on_rx).
+pub type Middleware<F = MockFrame> = Arc<dyn Fn(F) -> MiddlewareAction<F> + Send + Sync>;
+
+/// Run `frame` through `chain`: the resulting frame and total delay, or `None` if dropped.
+/// Reported events are added to `events`, even for frames a later middleware drops.
+pub(crate) fn apply<F>(
+    chain: &[Middleware<F>],
+    mut frame: F,
+    events: &mut Vec<BusEvent>,
+) -> Option<(F, Duration)> {
+    let mut delay = Duration::ZERO;
+    for middleware in chain {
+        match middleware(frame) {
+            MiddlewareAction::Pass(next) => frame = next,
+            MiddlewareAction::Delay(next, extra) => {
+                frame = next;
+                delay += extra;
+            }
+            MiddlewareAction::Drop => return None,
+            MiddlewareAction::Report(next, event) => {
+                frame = next;
+                events.push(event);
+            }
+        }
+    }
+    Some((frame, delay))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusHandle, timing::BusTiming};
+    use embedded_can::{Frame as _, StandardId};
+
+    #[test]
+    fn tx_middleware_delays_and_drops_before_the_bus_sees_frames() {
+        let bus = BusHandle::new();
+        bus.set_timing(Some(BusTiming::new(500_000)));
+        let node = bus.add_interface(vec![]).unwrap();
+        let peer = bus.add_interface(vec![]).unwrap();
+        node.on_tx(|frame: MockFrame| {
+            let delay = Duration::from_millis(u64::from(frame.data()[0]));
+            MiddlewareAction::Delay(frame, delay)
+        });
+        node.on_tx(|frame: MockFrame| (frame.data()[0] != 0xFF).then_some(frame));
+        let frame = |data| MockFrame::new(StandardId::new(0x10).unwrap(), &[data]).unwrap();
+
+        node.transmit(frame(3)).unwrap();
+        node.transmit(frame(0xFF)).unwrap();
+        node.transmit(frame(1)).unwrap();
+        bus.advance(Duration::from_millis(10));
+        assert_eq!(peer.received_frames(), [frame(1), frame(3)]);
+        assert_eq!(node.sent_frames(), [frame(3), frame(1)]);
+
+        node.clear_middleware();
+        node.transmit(frame(0xFF)).unwrap();
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(peer.received_frames().len(), 3);
+    }
+}
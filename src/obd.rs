@@ -0,0 +1,222 @@
+//! OBD-II (SAE J1979) PID responder preset.
+//!
+//! An [`ObdResponder`] emulates an engine control unit answering OBD-II requests: functional
+//! requests on `0x7DF` and physical requests on `0x7E0 + n`, with responses on `0x7E8 + n`. Values
+//! come from a configurable PID map; the "supported PIDs" bitmaps (`0x00`, `0x20`, ...) are
+//! derived from it automatically, and unsupported PIDs are ignored as a real ECU would, so scan
+//! tools see only what was configured.
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::isotp::IsoTpEndpoint;
+//! use embedded_can_mock::obd::{ObdResponder, PID_ENGINE_RPM, PID_VEHICLE_SPEED};
+//!
+//! let bus = BusHandle::new();
+//! let mut ecu = ObdResponder::new(bus.add_interface(vec![]).unwrap());
+//! ecu.set_pid(PID_ENGINE_RPM, vec![0x1A, 0xF8]); // 1726 rpm
+//! ecu.set_pid(PID_VEHICLE_SPEED, vec![60]);
+//!
+//! let mut scan_tool = IsoTpEndpoint::new(
+//!     bus.add_interface(vec![]).unwrap(),
+//!     StandardId::new(0x7DF).unwrap(),
+//!     StandardId::new(0x7E8).unwrap(),
+//! );
+//! scan_tool.send(&[0x01, 0x0C]).unwrap();
+//! ecu.poll().unwrap();
+//! assert_eq!(scan_tool.poll().unwrap(), vec![vec![0x41, 0x0C, 0x1A, 0xF8]]);
+//!
+//! scan_tool.send(&[0x01, 0x00]).unwrap();
+//! ecu.poll().unwrap();
+//! assert_eq!(scan_tool.poll().unwrap(), vec![vec![0x41, 0x00, 0x00, 0x18, 0x00, 0x00]]);
+//! ```
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use embedded_can::StandardId;
+
+use crate::{
+    bus::{InterfaceHandle, TransmitError},
+    isotp::IsoTpEndpoint,
+    uds::NRC_SERVICE_NOT_SUPPORTED,
+};
+
+/// Functional (broadcast) request ID.
+pub const FUNCTIONAL_REQUEST_ID: u16 = 0x7DF;
+/// Physical request ID of the first ECU.
+pub const PHYSICAL_REQUEST_BASE: u16 = 0x7E0;
+/// Response ID of the first ECU.
+pub const RESPONSE_BASE: u16 = 0x7E8;
+
+/// Mode `0x01`: show current data.
+pub const MODE_CURRENT_DATA: u8 = 0x01;
+/// Mode `0x09`: request vehicle information.
+pub const MODE_VEHICLE_INFO: u8 = 0x09;
+
+/// Mode 01 PID: engine coolant temperature (`A - 40` °C).
+pub const PID_COOLANT_TEMP: u8 = 0x05;
+/// Mode 01 PID: engine speed (`(256A + B) / 4` rpm).
+pub const PID_ENGINE_RPM: u8 = 0x0C;
+/// Mode 01 PID: vehicle speed (`A` km/h).
+pub const PID_VEHICLE_SPEED: u8 = 0x0D;
+/// Mode 09 PID: vehicle identification number.
+pub const PID_VIN: u8 = 0x02;
+
+/// Emulated OBD-II ECU.
+pub struct ObdResponder {
+    endpoint: IsoTpEndpoint,
+    values: BTreeMap<(u8, u8), Vec<u8>>,
+}
+
+impl ObdResponder {
+    /// Create the first ECU (`0x7E0`/`0x7E8`) on `interface`.
+    pub fn new(interface: InterfaceHandle) -> Self {
+        Self::with_ecu(interface, 0)
+    }
+
+    /// Create ECU number `ecu` (0..=7), answering on `0x7E8 + ecu`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ecu` is greater than 7.
+    pub fn with_ecu(interface: InterfaceHandle, ecu: u8) -> Self {
+        assert!(ecu < 8, "OBD-II ECU number {ecu} out of range");
+        let id = |raw: u16| StandardId::new(raw).unwrap();
+        let endpoint = IsoTpEndpoint::new(
+            interface,
+            id(RESPONSE_BASE + u16::from(ecu)),
+            id(PHYSICAL_REQUEST_BASE + u16::from(ecu)),
+        )
+        .with_functional_id(id(FUNCTIONAL_REQUEST_ID));
+        Self {
+            endpoint,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// The ISO-TP endpoint the responder answers on.
+    pub fn endpoint(&self) -> &IsoTpEndpoint {
+        &self.endpoint
+    }
+
+    /// Set the raw value returned for mode `0x01` PID `pid`.
+    pub fn set_pid(&mut self, pid: u8, value: Vec<u8>) {
+        self.set(MODE_CURRENT_DATA, pid, value);
+    }
+
+    /// Set the raw value returned for PID `pid` of `mode`.
+    pub fn set(&mut self, mode: u8, pid: u8, value: Vec<u8>) {
+        self.values.insert((mode, pid), value);
+    }
+
+    /// Stop answering PID `pid` of `mode`.
+    pub fn remove(&mut self, mode: u8, pid: u8) {
+        self.values.remove(&(mode, pid));
+    }
+
+    /// Set the vehicle identification number returned for mode `0x09` PID `0x02`.
+    pub fn set_vin(&mut self, vin: &str) {
+        let mut value = vec![0x01];
+        value.extend_from_slice(vin.as_bytes());
+        self.set(MODE_VEHICLE_INFO, PID_VIN, value);
+    }
+
+    /// Answer the requests received so far, returning how many were answered.
+    pub fn poll(&mut self) -> Result<usize, TransmitError> {
+        let mut answered = 0;
+        for request in self.endpoint.poll()? {
+            if let Some(response) = self.respond(&request) {
+                self.endpoint.send(&response)?;
+                answered += 1;
+            }
+        }
+        Ok(answered)
+    }
+
+    /// Build the positive response to `request`; unsupported requests get none, and bytes
+    /// outside the request range (`0x40` and above) a negative response.
+    fn respond(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let (&mode, pids) = request.split_first()?;
+        if mode >= 0x40 {
+            return Some(vec![0x7F, mode, NRC_SERVICE_NOT_SUPPORTED]);
+        }
+        // Mode 01 accepts up to six PIDs per request; other modes one.
+        let max = if mode == MODE_CURRENT_DATA { 6 } else { 1 };
+        if pids.is_empty() || pids.len() > max {
+            return None;
+        }
+        let mut response = vec![mode + 0x40];
+        for &pid in pids {
+            if let Some(value) = self.value(mode, pid) {
+                response.push(pid);
+                response.extend_from_slice(&value);
+            }
+        }
+        (response.len() > 1).then_some(response)
+    }
+
+    fn value(&self, mode: u8, pid: u8) -> Option<Vec<u8>> {
+        if !pid.is_multiple_of(0x20) {
+            return self.values.get(&(mode, pid)).cloned();
+        }
+        // Supported-PIDs bitmap: bit 31 is `pid + 1`, bit 0 is `pid + 0x20`, which also flags
+        // the next bitmap when anything beyond it is configured.
+        let mut bits = 0u32;
+        for &(_, other) in self.values.keys().filter(|(m, _)| *m == mode) {
+            if other > pid && u32::from(other) <= u32::from(pid) + 0x20 {
+                bits |= 1 << (0x20 - (other - pid));
+            } else if u32::from(other) > u32::from(pid) + 0x20 {
+                bits |= 1;
+            }
+        }
+        (bits != 0 || pid == 0).then(|| bits.to_be_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+
+    #[test]
+    fn physical_vin_request_and_bitmap_chaining() {
+        let bus = BusHandle::new();
+        let mut ecu = ObdResponder::with_ecu(bus.add_interface(vec![]).unwrap(), 1);
+        ecu.set_pid(0x0D, vec![42]);
+        ecu.set_pid(0x2F, vec![0x80]);
+        ecu.set_vin("1HGCM82633A004352");
+        let mut tool = IsoTpEndpoint::new(
+            bus.add_interface(vec![]).unwrap(),
+            StandardId::new(0x7E1).unwrap(),
+            StandardId::new(0x7E9).unwrap(),
+        );
+
+        tool.send(&[0x01, 0x00, 0x20, 0x0D, 0x0E]).unwrap();
+        ecu.poll().unwrap();
+        tool.poll().unwrap();
+        ecu.poll().unwrap();
+        assert_eq!(
+            tool.poll().unwrap(),
+            vec![vec![
+                0x41, 0x00, 0x00, 0x08, 0x00, 0x01, 0x20, 0x00, 0x02, 0x00, 0x00, 0x0D, 42
+            ]]
+        );
+
+        // Unsupported PIDs get no answer at all.
+        tool.send(&[0x01, 0x0E]).unwrap();
+        assert_eq!(ecu.poll().unwrap(), 0);
+
+        // Response codes are not modes, and the high ones must not overflow the reply.
+        tool.send(&[0xC0, 0x00]).unwrap();
+        assert_eq!(ecu.poll().unwrap(), 1);
+        assert_eq!(tool.poll().unwrap(), vec![vec![0x7F, 0xC0, 0x11]]);
+
+        tool.send(&[0x09, 0x02]).unwrap();
+        ecu.poll().unwrap();
+        tool.poll().unwrap();
+        ecu.poll().unwrap();
+        let vin = tool.poll().unwrap().remove(0);
+        assert_eq!(&vin[..3], [0x49, 0x02, 0x01]);
+        assert_eq!(&vin[3..], b"1HGCM82633A004352");
+    }
+}
@@ -0,0 +1,260 @@
+//! pcapng export of recorded traffic.
+//!
+//! [`Recorder::to_pcapng`](crate::recorder::Recorder::to_pcapng) writes a recording as a
+//! [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html) capture with the
+//! `LINKTYPE_CAN_SOCKETCAN` link type, the format `candump`-style Linux captures use, so
+//! Wireshark opens it with its CAN dissectors. Every record becomes one packet stamped with its
+//! virtual completion time (captures therefore start at the Unix epoch):
+//!
+//! - data and remote frames as SocketCAN `can_frame`s, or `canfd_frame`s for CAN FD frames;
+//! - corrupted deliveries as SocketCAN error frames whose class matches the injected
+//!   [`BusEvent`].
+//!
+//! ```no_run
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::recorder::Recorder;
+//!
+//! let bus = BusHandle::new();
+//! let recorder = Recorder::attach(&bus);
+//! // ... run the test ...
+//! std::fs::write("traffic.pcapng", recorder.to_pcapng()).unwrap();
+//! ```
+
+use alloc::vec::Vec;
+use embedded_can::{Frame, Id};
+
+use crate::{event::BusEvent, frame::MockFrame, recorder::TraceRecord};
+
+/// `LINKTYPE_CAN_SOCKETCAN`, the link type of the capture's interface.
+pub const LINKTYPE_CAN_SOCKETCAN: u16 = 227;
+
+const SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION: u32 = 1;
+const ENHANCED_PACKET: u32 = 6;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// `if_tsresol` option: timestamps are in units of 10^-9 seconds.
+const OPTION_TSRESOL: u16 = 9;
+const NANOSECONDS: u8 = 9;
+
+// SocketCAN `can_id` flags and error classes (linux/can.h, linux/can/error.h).
+const CAN_EFF_FLAG: u32 = 0x8000_0000;
+const CAN_RTR_FLAG: u32 = 0x4000_0000;
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+const CAN_ERR_PROT: u32 = 0x0000_0008;
+const CAN_ERR_ACK: u32 = 0x0000_0020;
+const CAN_ERR_BUSERROR: u32 = 0x0000_0080;
+const CAN_ERR_PROT_BIT: u8 = 0x01;
+const CAN_ERR_PROT_FORM: u8 = 0x02;
+const CAN_ERR_PROT_STUFF: u8 = 0x04;
+const CAN_ERR_PROT_LOC_CRC_SEQ: u8 = 0x08;
+// `canfd_frame.flags`.
+const CANFD_BRS: u8 = 0x01;
+const CANFD_ESI: u8 = 0x02;
+const CANFD_FDF: u8 = 0x04;
+
+/// Encode `records` as a pcapng capture with one SocketCAN interface.
+///
+/// Frame types other than [`MockFrame`] carry no CAN FD flags, so their payloads over 8 bytes
+/// are written as CAN FD frames without bit rate switching.
+pub fn encode<F: Frame + 'static>(records: &[TraceRecord<F>]) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    let mut shb = Vec::new();
+    put_u32(&mut shb, BYTE_ORDER_MAGIC);
+    put_u16(&mut shb, 1);
+    put_u16(&mut shb, 0);
+    // Section length: not specified.
+    shb.extend_from_slice(&(-1i64).to_le_bytes());
+    block(&mut out, SECTION_HEADER, &shb);
+
+    let mut idb = Vec::new();
+    put_u16(&mut idb, LINKTYPE_CAN_SOCKETCAN);
+    put_u16(&mut idb, 0);
+    // Snap length: unlimited.
+    put_u32(&mut idb, 0);
+    put_u16(&mut idb, OPTION_TSRESOL);
+    put_u16(&mut idb, 1);
+    idb.extend_from_slice(&[NANOSECONDS, 0, 0, 0]);
+    // opt_endofopt
+    put_u32(&mut idb, 0);
+    block(&mut out, INTERFACE_DESCRIPTION, &idb);
+
+    for record in records {
+        let packet = match &record.error {
+            Some(error) => error_frame(error),
+            None => can_frame(&record.frame),
+        };
+        let nanos = u64::try_from(record.at.as_nanos()).unwrap_or(u64::MAX);
+        let mut epb = Vec::new();
+        put_u32(&mut epb, 0);
+        put_u32(&mut epb, (nanos >> 32) as u32);
+        put_u32(&mut epb, nanos as u32);
+        put_u32(&mut epb, packet.len() as u32);
+        put_u32(&mut epb, packet.len() as u32);
+        epb.extend_from_slice(&packet);
+        pad(&mut epb);
+        block(&mut out, ENHANCED_PACKET, &epb);
+    }
+    out
+}
+
+/// A SocketCAN `can_frame` (16 bytes) or `canfd_frame` (72 bytes). The `can_id` is big-endian,
+/// as in Linux captures.
+fn can_frame<F: Frame + 'static>(frame: &F) -> Vec<u8> {
+    let frame = MockFrame::from_any_frame(frame);
+    let meta = frame.meta();
+    let fd = meta.fd || frame.data().len() > 8;
+    let flags = [(meta.brs, CANFD_BRS), (meta.esi, CANFD_ESI)]
+        .into_iter()
+        .filter(|(on, _)| *on)
+        .fold(CANFD_FDF, |flags, (_, flag)| flags | flag);
+    let mut can_id = match frame.id() {
+        Id::Standard(id) => u32::from(id.as_raw()),
+        Id::Extended(id) => id.as_raw() | CAN_EFF_FLAG,
+    };
+    if frame.is_remote_frame() {
+        can_id |= CAN_RTR_FLAG;
+    }
+    let len = if frame.is_remote_frame() {
+        frame.dlc()
+    } else {
+        frame.data().len()
+    };
+    let mut packet = Vec::with_capacity(72);
+    packet.extend_from_slice(&can_id.to_be_bytes());
+    packet.push(len as u8);
+    packet.extend_from_slice(&[if fd { flags } else { 0 }, 0, 0]);
+    packet.extend_from_slice(frame.data());
+    packet.resize(if fd { 72 } else { 16 }, 0);
+    packet
+}
+
+/// A SocketCAN error frame describing `error`.
+fn error_frame(error: &BusEvent) -> Vec<u8> {
+    let mut data = [0u8; 8];
+    let class = match error {
+        BusEvent::CrcError => {
+            data[3] = CAN_ERR_PROT_LOC_CRC_SEQ;
+            CAN_ERR_PROT
+        }
+        BusEvent::StuffError => {
+            data[2] = CAN_ERR_PROT_STUFF;
+            CAN_ERR_PROT
+        }
+        BusEvent::FormError => {
+            data[2] = CAN_ERR_PROT_FORM;
+            CAN_ERR_PROT
+        }
+        BusEvent::BitError => {
+            data[2] = CAN_ERR_PROT_BIT;
+            CAN_ERR_PROT
+        }
+        BusEvent::AckError => CAN_ERR_ACK,
+        _ => CAN_ERR_BUSERROR,
+    };
+    let mut packet = Vec::with_capacity(16);
+    packet.extend_from_slice(&(CAN_ERR_FLAG | class).to_be_bytes());
+    packet.extend_from_slice(&[8, 0, 0, 0]);
+    packet.extend_from_slice(&data);
+    packet
+}
+
+/// Append a block: type, total length, `body`, total length.
+fn block(out: &mut Vec<u8>, kind: u32, body: &[u8]) {
+    let total = (body.len() + 12) as u32;
+    put_u32(out, kind);
+    put_u32(out, total);
+    out.extend_from_slice(body);
+    put_u32(out, total);
+}
+
+fn pad(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use embedded_can::{ExtendedId, StandardId};
+
+    use super::*;
+    use crate::{BusHandle, recorder::Recorder};
+
+    /// Split a capture into `(block type, body)` pairs, checking the framing.
+    fn blocks(mut capture: &[u8]) -> Vec<(u32, &[u8])> {
+        let word =
+            |bytes: &[u8], at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let mut blocks = Vec::new();
+        while !capture.is_empty() {
+            let total = word(capture, 4) as usize;
+            assert_eq!(total % 4, 0);
+            assert_eq!(word(capture, total - 4) as usize, total);
+            blocks.push((word(capture, 0), &capture[8..total - 4]));
+            capture = &capture[total..];
+        }
+        blocks
+    }
+
+    #[test]
+    fn capture_holds_socketcan_packets_with_virtual_timestamps() {
+        let bus = BusHandle::new();
+        let recorder = Recorder::attach(&bus);
+        let node = bus.add_interface(Vec::new()).unwrap();
+        node.set_auto_retransmit(false);
+        let classic = MockFrame::new(StandardId::new(0x123).unwrap(), &[0xAA, 0xBB]).unwrap();
+        let mut fd = MockFrame::new(ExtendedId::new(0x18DA_F110).unwrap(), &[7; 12]).unwrap();
+        fd.meta_mut().fd = true;
+        fd.meta_mut().brs = true;
+        node.transmit(classic).unwrap();
+        bus.advance(Duration::new(1, 5));
+        node.transmit(fd).unwrap();
+        bus.corrupt_next(BusEvent::CrcError);
+        node.transmit(MockFrame::new(StandardId::new(0x7).unwrap(), &[]).unwrap())
+            .unwrap();
+
+        let capture = recorder.to_pcapng();
+        let blocks = blocks(&capture);
+        assert_eq!(
+            blocks.iter().map(|(kind, _)| *kind).collect::<Vec<_>>(),
+            [
+                SECTION_HEADER,
+                INTERFACE_DESCRIPTION,
+                ENHANCED_PACKET,
+                ENHANCED_PACKET,
+                ENHANCED_PACKET
+            ]
+        );
+        assert_eq!(&blocks[1].1[..2], &LINKTYPE_CAN_SOCKETCAN.to_le_bytes());
+
+        let packet = |n: usize| {
+            let body = blocks[n].1;
+            let len = u32::from_le_bytes(body[12..16].try_into().unwrap()) as usize;
+            (&body[4..12], &body[20..20 + len])
+        };
+        let (_, first) = packet(2);
+        assert_eq!(
+            first,
+            [0, 0, 0x01, 0x23, 2, 0, 0, 0, 0xAA, 0xBB, 0, 0, 0, 0, 0, 0]
+        );
+        let (stamp, second) = packet(3);
+        // 1.000000005 s in nanoseconds, as high and low words.
+        assert_eq!(stamp, [0, 0, 0, 0, 0x05, 0xCA, 0x9A, 0x3B]);
+        assert_eq!(second.len(), 72);
+        assert_eq!(
+            &second[..8],
+            [0x98, 0xDA, 0xF1, 0x10, 12, CANFD_FDF | CANFD_BRS, 0, 0]
+        );
+        let (_, error) = packet(4);
+        assert_eq!(&error[..4], (CAN_ERR_FLAG | CAN_ERR_PROT).to_be_bytes());
+        assert_eq!(error[8 + 3], CAN_ERR_PROT_LOC_CRC_SEQ);
+    }
+}
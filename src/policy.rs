@@ -0,0 +1,117 @@
+//! Transmit access control for security testing.
+//!
+//! A bus can be given a [`TransmitPolicy`] that sees every frame an interface transmits, together
+//! with the transmitting interface, before it reaches the wire. The policy decides whether the
+//! frame goes through, is silently dropped, is rejected with
+//! [`TransmitError::PolicyRejected`](crate::TransmitError::PolicyRejected), or goes through but
+//! is logged. This makes it possible to put an intrusion-detection or firewall layer built above
+//! CAN in front of adversarial traffic, or to emulate a gateway that blocks spoofed IDs.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::policy::PolicyDecision;
+//! use embedded_can_mock::{BusHandle, MockFrame, TransmitError};
+//!
+//! let bus = BusHandle::new();
+//! let ecu = bus.add_interface(vec![]).unwrap();
+//! let attacker = bus.add_interface(vec![]).unwrap();
+//! let ecu_id = ecu.id();
+//! // Only the ECU may send 0x100; diagnostics from anyone are logged.
+//! bus.set_transmit_policy(Some(Arc::new(move |frame: &MockFrame, source| {
+//!     let raw = match frame.id() {
+//!         embedded_can::Id::Standard(id) => id.as_raw(),
+//!         embedded_can::Id::Extended(_) => return PolicyDecision::Reject,
+//!     };
+//!     match raw {
+//!         0x100 if source != ecu_id => PolicyDecision::Reject,
+//!         0x7DF => PolicyDecision::Log,
+//!         _ => PolicyDecision::Allow,
+//!     }
+//! })));
+//!
+//! let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+//! assert!(matches!(
+//!     attacker.transmit(frame(0x100)),
+//!     Err(TransmitError::PolicyRejected)
+//! ));
+//! ecu.transmit(frame(0x100)).unwrap();
+//! attacker.transmit(frame(0x7DF)).unwrap();
+//!
+//! let log = bus.take_policy_log();
+//! assert_eq!(log.len(), 2);
+//! assert_eq!(log[0].decision, PolicyDecision::Reject);
+//! assert_eq!(log[1].frame, frame(0x7DF));
+//! ```
+//!
+//! The policy only applies to frames transmitted by interfaces; frames injected by the bus
+//! itself and internal [loopback](crate::InterfaceHandle::set_loopback) traffic bypass it.
+
+use alloc::sync::Arc;
+
+use crate::{bus::InterfaceId, frame::MockFrame};
+
+/// What a [`TransmitPolicy`] does with a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// Let the frame onto the bus.
+    Allow,
+    /// Accept the transmit call but never put the frame on the bus.
+    Drop,
+    /// Fail the transmit call with [`TransmitError::PolicyRejected`](crate::TransmitError::PolicyRejected).
+    Reject,
+    /// Let the frame onto the bus and record it in the policy log.
+    Log,
+}
+
+/// A frame the policy did not simply allow, as returned by
+/// [`BusHandle::take_policy_log`](crate::BusHandle::take_policy_log).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRecord<F = MockFrame> {
+    /// Interface that transmitted the frame.
+    pub source: InterfaceId,
+    /// The frame itself.
+    pub frame: F,
+    /// What the policy decided.
+    pub decision: PolicyDecision,
+}
+
+/// Callback deciding the fate of every transmitted frame.
+pub type TransmitPolicy<F = MockFrame> =
+    Arc<dyn Fn(&F, InterfaceId) -> PolicyDecision + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+    use embedded_can::{Frame as _, StandardId};
+
+    #[test]
+    fn dropped_frames_vanish_silently_and_loopback_bypasses_policy() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        let peer = bus.add_interface(vec![]).unwrap();
+        bus.set_transmit_policy(Some(Arc::new(|_: &MockFrame, _| PolicyDecision::Drop)));
+        let frame = MockFrame::new(StandardId::new(0x10).unwrap(), &[1]).unwrap();
+
+        node.transmit(frame.clone()).unwrap();
+        assert!(!peer.has_frames());
+        assert!(!node.has_frames());
+
+        node.set_loopback(true);
+        node.transmit(frame.clone()).unwrap();
+        assert_eq!(node.pop_frame(), Some(frame.clone()));
+
+        let log = bus.take_policy_log();
+        assert_eq!(
+            log,
+            alloc::vec![PolicyRecord {
+                source: node.id(),
+                frame,
+                decision: PolicyDecision::Drop,
+            }]
+        );
+        assert!(bus.take_policy_log().is_empty());
+    }
+}
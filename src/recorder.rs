@@ -0,0 +1,264 @@
+//! Traffic recording and sequence-diagram export.
+//!
+//! A [`Recorder`] attached to a bus records every frame that completes on it: who sent it, which
+//! interfaces queued it, and whether it was corrupted. Recordings can be rendered as
+//! [Mermaid](https://mermaid.js.org) or PlantUML sequence diagrams, with one lane per node, so a
+//! failing integration test can dump a readable picture of what happened on the bus.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::recorder::Recorder;
+//!
+//! let bus = BusHandle::new();
+//! let recorder = Recorder::attach(&bus);
+//! let a = bus.add_interface(vec![]).unwrap();
+//! let b = bus.add_interface(vec![]).unwrap();
+//! a.transmit(MockFrame::new(StandardId::new(0x123).unwrap(), &[0xAB]).unwrap()).unwrap();
+//!
+//! let diagram = recorder.to_mermaid();
+//! assert!(diagram.starts_with("sequenceDiagram"));
+//! assert!(diagram.contains(&format!("n{}->>n{}: 123#AB", a.id().as_raw(), b.id().as_raw())));
+//! ```
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{fmt::Write as _, time::Duration};
+
+use embedded_can::Frame;
+
+use crate::{
+    bus::{BusHandle, InterfaceId, TxToken},
+    event::BusEvent,
+    frame::MockFrame,
+    golden::{self, GoldenRecord},
+    measure::{self, LatencyStats, PeriodStats, PriorityInversion},
+    sync::{Mutex, lock},
+};
+
+/// One frame completing on the bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord<F = MockFrame> {
+    /// Virtual time at which the transmitter handed the frame to the bus.
+    pub requested: Duration,
+    /// Virtual time at which the frame won arbitration and went on the wire.
+    pub started: Duration,
+    /// Virtual time at which the frame completed.
+    pub at: Duration,
+    /// Transmission the frame came from.
+    pub token: TxToken,
+    /// The frame itself.
+    pub frame: F,
+    /// Interfaces that queued the frame (may include the transmitter).
+    pub receivers: Vec<InterfaceId>,
+    /// Injected error that replaced delivery, if any.
+    pub error: Option<BusEvent>,
+}
+
+pub(crate) type TraceSink<F> = Mutex<Vec<TraceRecord<F>>>;
+
+/// Records the traffic of one bus while it is alive.
+///
+/// Dropping the recorder detaches it.
+pub struct Recorder<F = MockFrame> {
+    records: Arc<TraceSink<F>>,
+}
+
+impl<F: Frame + Clone + 'static> Recorder<F> {
+    /// Start recording everything that completes on `bus` from now on.
+    pub fn attach(bus: &BusHandle<F>) -> Self {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        bus.add_recorder(Arc::downgrade(&records));
+        Self { records }
+    }
+
+    /// Copy of all records so far, in completion order.
+    pub fn records(&self) -> Vec<TraceRecord<F>> {
+        lock(&self.records).clone()
+    }
+
+    /// Remove and return all records so far, in completion order.
+    pub fn take_records(&self) -> Vec<TraceRecord<F>> {
+        core::mem::take(&mut *lock(&self.records))
+    }
+
+    /// Discard all records so far.
+    pub fn clear(&self) {
+        lock(&self.records).clear();
+    }
+
+    /// Period statistics of the frames recorded with raw ID `id` (see
+    /// [`measure::period_stats`](crate::measure::period_stats)).
+    pub fn period_stats(&self, id: u32) -> Option<PeriodStats> {
+        measure::period_stats(&lock(&self.records), id)
+    }
+
+    /// Request-to-response latency between two raw IDs (see
+    /// [`measure::latency_stats`](crate::measure::latency_stats)).
+    pub fn latency_stats(&self, request: u32, response: u32) -> Option<LatencyStats> {
+        measure::latency_stats(&lock(&self.records), request, response)
+    }
+
+    /// Frames that waited longer than `threshold` while lower-priority frames were sent (see
+    /// [`measure::priority_inversions`](crate::measure::priority_inversions)).
+    pub fn priority_inversions(&self, threshold: Duration) -> Vec<PriorityInversion<F>> {
+        measure::priority_inversions(&lock(&self.records), threshold)
+    }
+
+    /// Render the recording as a [golden trace](crate::golden) for snapshot tests.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can::{Frame as _, StandardId};
+    /// use embedded_can_mock::{BusHandle, MockFrame};
+    /// use embedded_can_mock::recorder::Recorder;
+    ///
+    /// let bus = BusHandle::new();
+    /// let recorder = Recorder::attach(&bus);
+    /// let a = bus.add_interface(vec![]).unwrap();
+    /// let _b = bus.add_interface(vec![]).unwrap();
+    /// a.transmit(MockFrame::new(StandardId::new(0x123).unwrap(), &[0xAB]).unwrap()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     recorder.to_golden(),
+    ///     "# embedded-can-mock golden trace\n0.000000 n0 123#AB -> n0 n1\n",
+    /// );
+    /// ```
+    pub fn to_golden(&self) -> String {
+        golden::render(&GoldenRecord::from_records(&lock(&self.records)))
+    }
+
+    /// Encode the recording as a pcapng capture for Wireshark (see [`pcapng`](crate::pcapng)).
+    pub fn to_pcapng(&self) -> Vec<u8>
+    where
+        F: 'static,
+    {
+        crate::pcapng::encode(&lock(&self.records))
+    }
+
+    /// Write the recording as a Vector ASC log (see [`asc`](crate::asc)).
+    pub fn to_asc(&self) -> String
+    where
+        F: 'static,
+    {
+        crate::asc::write(&lock(&self.records))
+    }
+
+    /// Render the recording as a Mermaid sequence diagram.
+    pub fn to_mermaid(&self) -> String {
+        let records = lock(&self.records);
+        let mut out = String::from("sequenceDiagram\n");
+        for id in participants(&records) {
+            let _ = writeln!(out, "    participant n{0} as node {0}", id.as_raw());
+        }
+        for record in records.iter() {
+            let source = record.token.source().as_raw();
+            let label = label(record);
+            match &record.error {
+                Some(error) => {
+                    let _ = writeln!(out, "    Note over n{source}: {label} {error:?}");
+                }
+                None if record.receivers.iter().all(|r| *r == record.token.source()) => {
+                    let _ = writeln!(out, "    Note over n{source}: {label} (no receivers)");
+                }
+                None => {
+                    for receiver in receivers(record) {
+                        let _ = writeln!(out, "    n{source}->>n{}: {label}", receiver.as_raw());
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Render the recording as a PlantUML sequence diagram.
+    pub fn to_plantuml(&self) -> String {
+        let records = lock(&self.records);
+        let mut out = String::from("@startuml\n");
+        for id in participants(&records) {
+            let _ = writeln!(out, "participant \"node {0}\" as n{0}", id.as_raw());
+        }
+        for record in records.iter() {
+            let source = record.token.source().as_raw();
+            let label = label(record);
+            match &record.error {
+                Some(error) => {
+                    let _ = writeln!(out, "note over n{source}: {label} {error:?}");
+                }
+                None if record.receivers.iter().all(|r| *r == record.token.source()) => {
+                    let _ = writeln!(out, "note over n{source}: {label} (no receivers)");
+                }
+                None => {
+                    for receiver in receivers(record) {
+                        let _ = writeln!(out, "n{source} -> n{}: {label}", receiver.as_raw());
+                    }
+                }
+            }
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+}
+
+/// Every node appearing in `records`, in order of first appearance.
+fn participants<F>(records: &[TraceRecord<F>]) -> Vec<InterfaceId> {
+    let mut ids = Vec::new();
+    for record in records {
+        for id in core::iter::once(record.token.source()).chain(record.receivers.iter().copied()) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// Receivers other than the transmitter.
+fn receivers<F>(record: &TraceRecord<F>) -> impl Iterator<Item = &InterfaceId> {
+    record
+        .receivers
+        .iter()
+        .filter(move |r| **r != record.token.source())
+}
+
+fn label<F: Frame>(record: &TraceRecord<F>) -> String {
+    MockFrame::from_frame(&record.frame).fmt_candump()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::StandardId;
+
+    fn frame(id: u16, data: &[u8]) -> MockFrame {
+        MockFrame::new(StandardId::new(id).unwrap(), data).unwrap()
+    }
+
+    #[test]
+    fn records_receivers_and_corruption() {
+        let bus = BusHandle::new();
+        let a = bus.add_interface(Vec::new()).unwrap();
+        let b = bus.add_interface(Vec::new()).unwrap();
+        let recorder = Recorder::attach(&bus);
+        b.set_auto_retransmit(false);
+
+        a.transmit(frame(0x10, &[1, 2])).unwrap();
+        bus.corrupt_next(BusEvent::CrcError);
+        b.transmit(frame(0x20, &[])).unwrap();
+
+        let records = recorder.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].receivers, alloc::vec![a.id(), b.id()]);
+        assert_eq!(records[1].error, Some(BusEvent::CrcError));
+        assert!(records[1].receivers.is_empty());
+
+        let (na, nb) = (a.id().as_raw(), b.id().as_raw());
+        let plantuml = recorder.to_plantuml();
+        assert!(plantuml.contains(&alloc::format!("n{na} -> n{nb}: 010#0102")));
+        assert!(plantuml.contains(&alloc::format!("note over n{nb}: 020# CrcError")));
+        assert!(plantuml.ends_with("@enduml\n"));
+
+        drop(recorder);
+        a.transmit(frame(0x30, &[])).unwrap();
+    }
+}
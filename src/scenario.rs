@@ -0,0 +1,593 @@
+//! Declarative test scenarios run against a [`Topology`].
+//!
+//! A scenario is a plain-text script, one step per line, that test engineers can write without
+//! touching Rust: send a frame, expect a response within a deadline, inject a fault, partition a
+//! bus. [`Scenario::run`] executes it on the topology's virtual clocks and returns a
+//! [`ScenarioReport`] with the outcome of every step:
+//!
+//! ```
+//! use embedded_can_mock::scenario::Scenario;
+//! use embedded_can_mock::topology::{Responder, ResponderKind, TopologyBuilder};
+//!
+//! let mut net = TopologyBuilder::new()
+//!     .timed_bus("powertrain", 500_000)
+//!     .node("tester", "powertrain")
+//!     .responder("engine", "powertrain", ResponderKind::Obd { ecu: 0 })
+//!     .build()
+//!     .unwrap();
+//! let Responder::Obd(engine) = net.responder_mut("engine") else { unreachable!() };
+//! engine.set_pid(0x0D, vec![60]);
+//!
+//! let scenario = Scenario::parse(
+//!     "
+//!     ## Vehicle speed over OBD-II.
+//!     at 5ms send tester 7DF#02010D
+//!     expect tester 7E8#03410D??* within 10ms
+//!
+//!     ## Cut off from the tester, the engine goes quiet.
+//!     at 20ms partition powertrain engine
+//!     send tester 7DF#02010D
+//!     expect_none tester 7E8#* within 10ms
+//!     ",
+//! )
+//! .unwrap();
+//! let report = scenario.run(&mut net);
+//! assert!(report.passed(), "{report}");
+//! ```
+//!
+//! # Steps
+//!
+//! Each step may start with `at TIME`, which first advances the clock to that time since the
+//! start of the scenario; other steps run where the previous one left off. Times and durations
+//! are a number with a unit: `250us`, `5ms`, `1.5s`.
+//!
+//! | step | effect |
+//! |------|--------|
+//! | `send NODE FRAME` | transmit `FRAME` (`candump` notation) from `NODE` |
+//! | `expect NODE PATTERN within DURATION` | pass once `NODE` receives a matching frame |
+//! | `expect_none NODE PATTERN within DURATION` | fail if `NODE` receives a matching frame |
+//! | `fault BUS KIND` | corrupt the next frame on `BUS` (`crc`, `stuff`, `form`, `bit`, `ack`), or broadcast an `error_frame` |
+//! | `partition BUS NAME... \| NAME...` | split `BUS` into segments of nodes, responders and gateways (see [`Partition`]) |
+//! | `heal BUS` | undo a partition |
+//! | `wait DURATION` | let time pass |
+//!
+//! A `PATTERN` is `ID#DATA` where `??` matches any byte and a trailing `*` any number of further
+//! bytes, so `7E8#0341*` matches padded responses and `7E8#*` any payload. Frames a
+//! node received before an `expect` step count too; matching frames are consumed. Blank lines
+//! and `#` comments are ignored.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, time::Duration};
+
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+
+use crate::{
+    bus::InterfaceHandle,
+    event::BusEvent,
+    frame::MockFrame,
+    medium::{Broadcast, Partition},
+    topology::Topology,
+};
+
+/// Virtual time step used by [`Scenario::run`] unless [changed](Scenario::with_tick).
+pub const DEFAULT_TICK: Duration = Duration::from_micros(100);
+
+/// A malformed scenario line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioError {
+    /// 1-based line number.
+    pub line: usize,
+    /// What is wrong with it.
+    pub reason: String,
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "scenario line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Expected frame: an ID and payload bytes (`None` for a wildcard), possibly followed by any
+/// number of further bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+    id: Id,
+    data: Vec<Option<u8>>,
+    prefix: bool,
+}
+
+impl Pattern {
+    fn parse(text: &str) -> Option<Self> {
+        let (id, data) = text.split_once('#')?;
+        let raw = u32::from_str_radix(id, 16).ok()?;
+        let id = if id.len() > 3 {
+            Id::Extended(ExtendedId::new(raw)?)
+        } else {
+            Id::Standard(StandardId::new(u16::try_from(raw).ok()?)?)
+        };
+        let (data, prefix) = match data.strip_suffix('*') {
+            Some(data) => (data, true),
+            None => (data, false),
+        };
+        let data = data.replace('.', "");
+        if data.len() % 2 != 0 {
+            return None;
+        }
+        let bytes = (0..data.len())
+            .step_by(2)
+            .map(|at| match &data[at..at + 2] {
+                "??" => Some(None),
+                byte => u8::from_str_radix(byte, 16).ok().map(Some),
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            id,
+            data: bytes,
+            prefix,
+        })
+    }
+
+    fn matches(&self, frame: &MockFrame) -> bool {
+        let data = frame.data();
+        let length_ok = if self.prefix {
+            data.len() >= self.data.len()
+        } else {
+            data.len() == self.data.len()
+        };
+        frame.id() == self.id
+            && length_ok
+            && self
+                .data
+                .iter()
+                .zip(data)
+                .all(|(want, got)| want.is_none_or(|want| want == *got))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Send(String, MockFrame),
+    Expect(String, Pattern, Duration),
+    ExpectNone(String, Pattern, Duration),
+    Fault(String, Option<BusEvent>),
+    Partition(String, Vec<Vec<String>>),
+    Heal(String),
+    Wait(Duration),
+}
+
+/// A parsed step with its source line and optional start time.
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    text: String,
+    at: Option<Duration>,
+    step: Step,
+}
+
+/// A parsed scenario script (see the [module documentation](self)).
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    lines: Vec<Line>,
+    tick: Duration,
+}
+
+impl Scenario {
+    /// Parse a script.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::scenario::Scenario;
+    ///
+    /// let err = Scenario::parse("send tester 7DF#02010D\nexpect tester 7E8 within 5ms").unwrap_err();
+    /// assert_eq!(err.to_string(), "scenario line 2: invalid frame pattern `7E8`");
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        let mut lines = Vec::new();
+        for (n, raw) in text.lines().enumerate() {
+            let text = strip_comment(raw);
+            let mut words: Vec<&str> = text.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            let error = |reason: String| ScenarioError {
+                line: n + 1,
+                reason,
+            };
+            let at = match words.as_slice() {
+                ["at", time, ..] => {
+                    let at = parse_duration(time)
+                        .ok_or_else(|| error(format!("invalid time `{time}`")))?;
+                    words.drain(..2);
+                    Some(at)
+                }
+                _ => None,
+            };
+            let step = parse_step(&words).map_err(error)?;
+            lines.push(Line {
+                number: n + 1,
+                text: text.trim().to_string(),
+                at,
+                step,
+            });
+        }
+        Ok(Self {
+            lines,
+            tick: DEFAULT_TICK,
+        })
+    }
+
+    /// Advance virtual time in steps of `tick` (default [`DEFAULT_TICK`]), pumping the topology
+    /// after each; expectations are checked at this resolution.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero.
+    pub fn with_tick(mut self, tick: Duration) -> Self {
+        assert!(!tick.is_zero(), "scenario tick must be positive");
+        self.tick = tick;
+        self
+    }
+
+    /// Run every step against `topology`, starting the scenario clock at zero.
+    ///
+    /// Failing steps are reported and the run goes on with the next step. Every bus of the
+    /// topology is advanced together.
+    pub fn run(&self, topology: &mut Topology) -> ScenarioReport {
+        let mut runner = Runner {
+            topology,
+            now: Duration::ZERO,
+            tick: self.tick,
+        };
+        let steps = self
+            .lines
+            .iter()
+            .map(|line| {
+                if let Some(at) = line.at {
+                    runner.advance_to(at);
+                }
+                let at = runner.now;
+                StepReport {
+                    line: line.number,
+                    step: line.text.clone(),
+                    at,
+                    outcome: runner.execute(&line.step),
+                }
+            })
+            .collect();
+        ScenarioReport { steps }
+    }
+}
+
+/// Outcome of one scenario step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepReport {
+    /// 1-based script line.
+    pub line: usize,
+    /// The step as written.
+    pub step: String,
+    /// Scenario time at which the step started.
+    pub at: Duration,
+    /// `Err` with the reason if the step failed.
+    pub outcome: Result<(), String>,
+}
+
+/// Outcome of a whole [`Scenario::run`].
+///
+/// Its [`Display`](fmt::Display) output lists every step as `PASS` or `FAIL`, suitable for a
+/// test's failure message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioReport {
+    /// One report per step, in script order.
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    /// Returns `true` if every step passed.
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.outcome.is_ok())
+    }
+
+    /// The failed steps.
+    pub fn failures(&self) -> impl Iterator<Item = &StepReport> {
+        self.steps.iter().filter(|step| step.outcome.is_err())
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            let millis = step.at.as_secs_f64() * 1e3;
+            match &step.outcome {
+                Ok(()) => writeln!(
+                    f,
+                    "PASS {millis:>10.3} ms  line {}: {}",
+                    step.line, step.step
+                )?,
+                Err(reason) => writeln!(
+                    f,
+                    "FAIL {millis:>10.3} ms  line {}: {}: {reason}",
+                    step.line, step.step
+                )?,
+            }
+        }
+        let failed = self.failures().count();
+        write!(f, "{} passed, {failed} failed", self.steps.len() - failed)
+    }
+}
+
+struct Runner<'a> {
+    topology: &'a mut Topology,
+    now: Duration,
+    tick: Duration,
+}
+
+impl Runner<'_> {
+    fn advance_to(&mut self, at: Duration) {
+        while self.now < at {
+            self.tick((at - self.now).min(self.tick));
+        }
+    }
+
+    /// Advance every bus by `by`, then pump gateways and responders.
+    fn tick(&mut self, by: Duration) {
+        let buses: Vec<String> = self.topology.bus_names().map(String::from).collect();
+        for bus in &buses {
+            self.topology.bus(bus).advance(by);
+        }
+        self.now += by;
+        // A transmit error from a responder surfaces as a missing frame in a later expectation.
+        let _ = self.topology.pump();
+    }
+
+    fn node(&self, name: &str) -> Result<InterfaceHandle, String> {
+        if self.topology.node_names().any(|node| node == name) {
+            Ok(self.topology.node(name).clone())
+        } else {
+            Err(format!("unknown node `{name}`"))
+        }
+    }
+
+    fn check_bus(&self, name: &str) -> Result<(), String> {
+        if self.topology.bus_names().any(|bus| bus == name) {
+            Ok(())
+        } else {
+            Err(format!("unknown bus `{name}`"))
+        }
+    }
+
+    /// Advance until `node` holds a frame matching `pattern` (consuming it) or `within` passes.
+    fn wait_for(
+        &mut self,
+        node: &InterfaceHandle,
+        pattern: &Pattern,
+        within: Duration,
+    ) -> Option<MockFrame> {
+        let deadline = self.now + within;
+        loop {
+            let found = node.recv_matching(
+                |frame: &MockFrame| pattern.matches(frame),
+                Some(Duration::ZERO),
+            );
+            if found.is_some() || self.now >= deadline {
+                return found;
+            }
+            self.tick((deadline - self.now).min(self.tick));
+        }
+    }
+
+    fn execute(&mut self, step: &Step) -> Result<(), String> {
+        match step {
+            Step::Send(node, frame) => self
+                .node(node)?
+                .transmit(frame.clone())
+                .map(drop)
+                .map_err(|err| format!("transmit failed: {err:?}")),
+            Step::Expect(node, pattern, within) => {
+                let node = self.node(node)?;
+                match self.wait_for(&node, pattern, *within) {
+                    Some(_) => Ok(()),
+                    None => Err("no matching frame".to_string()),
+                }
+            }
+            Step::ExpectNone(node, pattern, within) => {
+                let node = self.node(node)?;
+                match self.wait_for(&node, pattern, *within) {
+                    Some(frame) => Err(format!("unexpected frame {frame}")),
+                    None => Ok(()),
+                }
+            }
+            Step::Fault(bus, event) => {
+                self.check_bus(bus)?;
+                let bus = self.topology.bus(bus);
+                match event {
+                    Some(event) => bus.corrupt_next(event.clone()),
+                    None => bus.inject_error_frame(),
+                }
+                Ok(())
+            }
+            Step::Partition(bus, segments) => {
+                self.check_bus(bus)?;
+                let mut partition = Vec::new();
+                for segment in segments {
+                    let mut members = Vec::new();
+                    for name in segment {
+                        let interfaces = self.topology.interfaces(name);
+                        if interfaces.is_empty() {
+                            return Err(format!("unknown node `{name}`"));
+                        }
+                        members.extend(interfaces.into_iter().map(InterfaceHandle::id));
+                    }
+                    partition.push(members);
+                }
+                self.topology.bus(bus).set_medium(Partition::new(partition));
+                Ok(())
+            }
+            Step::Heal(bus) => {
+                self.check_bus(bus)?;
+                self.topology.bus(bus).set_medium(Broadcast);
+                Ok(())
+            }
+            Step::Wait(by) => {
+                self.advance_to(self.now + *by);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_step(words: &[&str]) -> Result<Step, String> {
+    let duration =
+        |text: &str| parse_duration(text).ok_or_else(|| format!("invalid duration `{text}`"));
+    let pattern =
+        |text: &str| Pattern::parse(text).ok_or_else(|| format!("invalid frame pattern `{text}`"));
+    match words {
+        ["send", node, frame] => MockFrame::parse_candump(frame)
+            .map(|frame| Step::Send(node.to_string(), frame))
+            .map_err(|_| format!("invalid frame `{frame}`")),
+        ["expect", node, expected, "within", within] => Ok(Step::Expect(
+            node.to_string(),
+            pattern(expected)?,
+            duration(within)?,
+        )),
+        ["expect_none", node, expected, "within", within] => Ok(Step::ExpectNone(
+            node.to_string(),
+            pattern(expected)?,
+            duration(within)?,
+        )),
+        ["expect" | "expect_none", _, expected, ..] => {
+            pattern(expected)?;
+            Err("expected `within DURATION`".to_string())
+        }
+        ["fault", bus, kind] => {
+            let event = match *kind {
+                "crc" => Some(BusEvent::CrcError),
+                "stuff" => Some(BusEvent::StuffError),
+                "form" => Some(BusEvent::FormError),
+                "bit" => Some(BusEvent::BitError),
+                "ack" => Some(BusEvent::AckError),
+                "error_frame" => None,
+                _ => return Err(format!("unknown fault `{kind}`")),
+            };
+            Ok(Step::Fault(bus.to_string(), event))
+        }
+        ["partition", bus, nodes @ ..] if !nodes.is_empty() => {
+            let segments = nodes
+                .split(|word| *word == "|")
+                .map(|segment| segment.iter().map(|node| node.to_string()).collect())
+                .collect();
+            Ok(Step::Partition(bus.to_string(), segments))
+        }
+        ["heal", bus] => Ok(Step::Heal(bus.to_string())),
+        ["wait", by] => Ok(Step::Wait(duration(by)?)),
+        [command, ..] => Err(format!("unknown or malformed step `{command}`")),
+        [] => unreachable!("blank lines are skipped"),
+    }
+}
+
+/// `line` up to its comment. `#` also separates a frame's ID from its data, so only a `#` that
+/// starts a word opens a comment.
+fn strip_comment(line: &str) -> &str {
+    line.match_indices('#')
+        .find(|(at, _)| {
+            line[..*at]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace)
+        })
+        .map_or(line, |(at, _)| &line[..at])
+}
+
+/// `250us`, `5ms`, `1.5s` and the like.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = text.split_at(split);
+    let scale: u64 = match unit {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        _ => return None,
+    };
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut nanos = whole.parse::<u64>().ok()?.checked_mul(scale)?;
+    let mut place = scale;
+    for digit in fraction.bytes() {
+        place /= 10;
+        nanos = nanos.checked_add(u64::from(digit - b'0') * place)?;
+    }
+    Some(Duration::from_nanos(nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::TopologyBuilder;
+
+    #[test]
+    fn reports_each_step_and_honours_partitions() {
+        let mut net = TopologyBuilder::new()
+            .timed_bus("body", 125_000)
+            .node("a", "body")
+            .node("b", "body")
+            .node("c", "body")
+            .build()
+            .unwrap();
+        let scenario = Scenario::parse(
+            "at 1ms send a 100#01
+             expect b 100#?? within 2ms
+             partition body a | b
+             send a 101#
+             expect_none b 101#* within 2ms
+             expect c 101#* within 2ms
+             heal body
+             at 10ms send b 102#0203
+             expect a 102#02 within 1ms
+             expect c 1FF#* within 1ms",
+        )
+        .unwrap()
+        .with_tick(Duration::from_micros(50));
+        let report = scenario.run(&mut net);
+
+        let outcomes: Vec<_> = report.steps.iter().map(|s| s.outcome.is_ok()).collect();
+        assert_eq!(
+            outcomes,
+            [
+                true, true, true, true, true, false, true, true, false, false
+            ]
+        );
+        assert_eq!(report.steps[0].at, Duration::from_millis(1));
+        assert_eq!(report.steps[7].at, Duration::from_millis(10));
+        assert_eq!(report.failures().count(), 3);
+        let text = report.to_string();
+        assert!(text.contains("line 6: expect c 101#* within 2ms: no matching frame"));
+        assert!(text.ends_with("7 passed, 3 failed"));
+    }
+
+    #[test]
+    fn parses_durations_and_rejects_bad_lines() {
+        assert_eq!(parse_duration("1.5ms"), Some(Duration::from_micros(1500)));
+        assert_eq!(parse_duration("250us"), Some(Duration::from_micros(250)));
+        assert_eq!(parse_duration("5"), None);
+        let line = |text: &str| Scenario::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            line("  # comment\nat 5 send a 1#"),
+            "scenario line 2: invalid time `5`"
+        );
+        assert_eq!(
+            line("fault body smoke"),
+            "scenario line 1: unknown fault `smoke`"
+        );
+        assert_eq!(
+            line("send a 100#01 # first\nexpect a 100#01"),
+            "scenario line 2: expected `within DURATION`"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! SecOC-style authenticated frames (AUTOSAR Secure Onboard Communication).
+//!
+//! A secured frame carries its payload followed by the least significant bytes of a freshness
+//! value (a per-ID message counter) and a truncated MAC. The MAC is computed over the frame ID,
+//! the payload and the *full* freshness value by a caller-supplied closure, which holds the key
+//! and picks the algorithm, so the mock needs no cryptography of its own. Receivers rebuild the
+//! full freshness from the last value they accepted, which makes replayed frames fail
+//! verification just like tampered ones.
+//!
+//! [`SecOcMonitor`] is a bus observer that verifies every protected frame it sees and flags
+//! failures, for testing intrusion detection or SecOC layers in the system under test.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::secoc::{SecOc, SecOcMonitor};
+//!
+//! // A keyed FNV-1a hash standing in for CMAC-AES128.
+//! let key = 0x0BAD_CAFE_u32;
+//! let secoc = SecOc::new(
+//!     move |input: &[u8]| {
+//!         let mac = input
+//!             .iter()
+//!             .fold(0x811C_9DC5 ^ key, |acc, b| (acc ^ u32::from(*b)).wrapping_mul(0x0100_0193));
+//!         mac.to_be_bytes().to_vec()
+//!     },
+//!     3,
+//!     1,
+//! );
+//! let id = StandardId::new(0x120).unwrap();
+//!
+//! let bus = BusHandle::new();
+//! let mut monitor = SecOcMonitor::new(&bus, secoc.clone(), [id.into()]);
+//! let ecu = bus.add_interface(vec![]).unwrap();
+//!
+//! let frame = secoc.authenticate(id, &[0x01, 0x02], 1).unwrap();
+//! assert_eq!(frame.data().len(), 2 + 1 + 3);
+//! ecu.transmit(frame.clone()).unwrap();
+//! ecu.transmit(frame).unwrap(); // replayed
+//!
+//! let failures = monitor.poll();
+//! assert_eq!(failures.len(), 1);
+//! assert_eq!(monitor.verified_count(), 1);
+//! ```
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+
+use embedded_can::{Frame, Id};
+
+use crate::{
+    bus::{BusHandle, InterfaceHandle, InterfaceId},
+    frame::MockFrame,
+};
+
+/// Closure computing the full MAC over its input; the key lives inside the closure.
+pub type MacFn = Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
+/// Why a secured frame failed verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecOcError {
+    /// The frame is too short to hold the freshness value and MAC.
+    Truncated,
+    /// The MAC does not match (tampered, forged or replayed frame).
+    MacMismatch,
+    /// The MAC matches but the freshness value is not above the last accepted one (a replayed
+    /// frame whose freshness value is sent in full).
+    Replayed,
+}
+
+/// SecOC profile: MAC function and the truncated MAC and freshness lengths.
+#[derive(Clone)]
+pub struct SecOc {
+    mac: MacFn,
+    mac_len: usize,
+    freshness_len: usize,
+}
+
+impl SecOc {
+    /// Create a profile transmitting `mac_len` bytes of the MAC computed by `mac` and the
+    /// `freshness_len` least significant bytes of the freshness value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `freshness_len` is greater than 8.
+    pub fn new(
+        mac: impl Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static,
+        mac_len: usize,
+        freshness_len: usize,
+    ) -> Self {
+        assert!(
+            freshness_len <= 8,
+            "freshness length {freshness_len} exceeds 8 bytes"
+        );
+        Self {
+            mac: Arc::new(mac),
+            mac_len,
+            freshness_len,
+        }
+    }
+
+    /// Number of bytes the freshness value and MAC add to each payload.
+    pub fn overhead(&self) -> usize {
+        self.freshness_len + self.mac_len
+    }
+
+    /// Build the secured frame for `payload` with freshness value `freshness`, or `None` if `mac`
+    /// returns fewer than `mac_len` bytes. The length is not checked against a CAN FD payload.
+    pub fn authenticate(
+        &self,
+        id: impl Into<Id>,
+        payload: &[u8],
+        freshness: u64,
+    ) -> Option<MockFrame> {
+        let id = id.into();
+        let mut data = payload.to_vec();
+        data.extend_from_slice(&freshness.to_be_bytes()[8 - self.freshness_len..]);
+        data.extend_from_slice(&self.truncated_mac(id, payload, freshness)?);
+        MockFrame::new(id, &data)
+    }
+
+    /// Verify `frame`, given the last freshness value accepted for its ID. Returns the payload
+    /// and the frame's full freshness value, which becomes the new last accepted value.
+    pub fn verify(&self, frame: &MockFrame, last: u64) -> Result<(Vec<u8>, u64), SecOcError> {
+        let data = frame.data();
+        let payload_len = data
+            .len()
+            .checked_sub(self.overhead())
+            .ok_or(SecOcError::Truncated)?;
+        let (payload, rest) = data.split_at(payload_len);
+        let (fresh_bytes, mac) = rest.split_at(self.freshness_len);
+        let freshness = self.reconstruct(last, fresh_bytes);
+        if self
+            .truncated_mac(frame.id(), payload, freshness)
+            .as_deref()
+            != Some(mac)
+        {
+            return Err(SecOcError::MacMismatch);
+        }
+        if freshness <= last {
+            return Err(SecOcError::Replayed);
+        }
+        Ok((payload.to_vec(), freshness))
+    }
+
+    /// Smallest freshness value above `last` whose low bytes are `received` (the received value
+    /// itself when it is sent in full).
+    fn reconstruct(&self, last: u64, received: &[u8]) -> u64 {
+        if self.freshness_len == 8 {
+            return u64::from_be_bytes(received.try_into().unwrap());
+        }
+        let low = received
+            .iter()
+            .fold(0u64, |acc, b| acc << 8 | u64::from(*b));
+        let span = 1u64 << (8 * self.freshness_len);
+        let candidate = (last & !(span - 1)) | low;
+        if candidate > last {
+            candidate
+        } else {
+            candidate.wrapping_add(span)
+        }
+    }
+
+    fn truncated_mac(&self, id: Id, payload: &[u8], freshness: u64) -> Option<Vec<u8>> {
+        let raw = match id {
+            Id::Standard(id) => u32::from(id.as_raw()),
+            Id::Extended(id) => id.as_raw(),
+        };
+        let mut input = raw.to_be_bytes().to_vec();
+        input.extend_from_slice(payload);
+        input.extend_from_slice(&freshness.to_be_bytes());
+        let mut mac = (self.mac)(&input);
+        if mac.len() < self.mac_len {
+            return None;
+        }
+        mac.truncate(self.mac_len);
+        Some(mac)
+    }
+}
+
+/// A protected frame that failed verification, reported by [`SecOcMonitor::poll`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacFailure {
+    /// The offending frame.
+    pub frame: MockFrame,
+    /// Interface that transmitted it.
+    pub source: InterfaceId,
+    /// Why verification failed.
+    pub error: SecOcError,
+}
+
+/// Bus observer verifying every frame on the protected IDs.
+pub struct SecOcMonitor {
+    tap: InterfaceHandle,
+    secoc: SecOc,
+    /// Last accepted freshness value per protected ID.
+    freshness: BTreeMap<Id, u64>,
+    verified: u64,
+    failures: u64,
+}
+
+impl SecOcMonitor {
+    /// Watch `bus` through a [tap](BusHandle::tap), verifying frames with the IDs in
+    /// `protected` against `secoc`. Freshness starts at 0 for every ID.
+    pub fn new(bus: &BusHandle, secoc: SecOc, protected: impl IntoIterator<Item = Id>) -> Self {
+        Self {
+            tap: bus.tap(),
+            secoc,
+            freshness: protected.into_iter().map(|id| (id, 0)).collect(),
+            verified: 0,
+            failures: 0,
+        }
+    }
+
+    /// Verify the frames seen since the last call, returning the ones that failed.
+    pub fn poll(&mut self) -> Vec<MacFailure> {
+        let mut failed = Vec::new();
+        while let Some(delivery) = self.tap.pop_delivery() {
+            let Some(last) = self.freshness.get_mut(&delivery.frame.id()) else {
+                continue;
+            };
+            match self.secoc.verify(&delivery.frame, *last) {
+                Ok((_, freshness)) => {
+                    *last = freshness;
+                    self.verified += 1;
+                }
+                Err(error) => {
+                    self.failures += 1;
+                    failed.push(MacFailure {
+                        frame: delivery.frame,
+                        source: delivery.source,
+                        error,
+                    });
+                }
+            }
+        }
+        failed
+    }
+
+    /// Number of protected frames that verified successfully.
+    pub fn verified_count(&self) -> u64 {
+        self.verified
+    }
+
+    /// Number of protected frames that failed verification.
+    pub fn failure_count(&self) -> u64 {
+        self.failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::StandardId;
+
+    fn secoc(freshness_len: usize) -> SecOc {
+        SecOc::new(
+            |input: &[u8]| {
+                let sum = input.iter().fold(0x1234_5678_u32, |acc, b| {
+                    acc.wrapping_mul(31) ^ u32::from(*b)
+                });
+                sum.to_le_bytes().to_vec()
+            },
+            2,
+            freshness_len,
+        )
+    }
+
+    #[test]
+    fn freshness_wraps_and_tampering_is_detected() {
+        let secoc = secoc(1);
+        let id = StandardId::new(0x42).unwrap();
+
+        // Counter 0x1FF is sent as 0xFF; the receiver at 0x1FE rebuilds it, then wraps to 0x200.
+        let frame = secoc.authenticate(id, &[7, 8, 9], 0x1FF).unwrap();
+        assert_eq!(secoc.verify(&frame, 0x1FE), Ok((vec![7, 8, 9], 0x1FF)));
+        let next = secoc.authenticate(id, &[7, 8, 9], 0x200).unwrap();
+        assert_eq!(secoc.verify(&next, 0x1FF).unwrap().1, 0x200);
+
+        let mut tampered = frame.data().to_vec();
+        tampered[0] ^= 1;
+        let tampered = MockFrame::new(id, &tampered).unwrap();
+        assert_eq!(secoc.verify(&tampered, 0x1FE), Err(SecOcError::MacMismatch));
+        let short = MockFrame::new(id, &[0; 2]).unwrap();
+        assert_eq!(secoc.verify(&short, 0), Err(SecOcError::Truncated));
+        assert!(secoc.authenticate(id, &[0; 64], 1).unwrap().data().len() > 64);
+        assert!(
+            SecOc::new(|_: &[u8]| vec![0], 2, 0)
+                .authenticate(id, &[], 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn full_freshness_values_are_checked_for_replay() {
+        let secoc = secoc(8);
+        let id = StandardId::new(0x42).unwrap();
+
+        let frame = secoc.authenticate(id, &[1], 5).unwrap();
+        assert_eq!(secoc.verify(&frame, 4), Ok((vec![1], 5)));
+        assert_eq!(secoc.verify(&frame, 5), Err(SecOcError::Replayed));
+        assert_eq!(secoc.verify(&frame, 9), Err(SecOcError::Replayed));
+    }
+}
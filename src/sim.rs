@@ -0,0 +1,157 @@
+//! Lock-step advancement by an external simulation executor.
+//!
+//! A firmware-in-the-loop scheduler or co-simulation owns the notion of time and steps every
+//! component one tick at a time. [`SimTick`] is the hook it calls: each tick moves a bus (or a
+//! [device](crate::device::MockDevice), or a whole [topology](crate::topology::Topology)) to the
+//! end of that tick on the virtual clock, so arbitration, delivery, pacing and receive TTLs
+//! follow the tick count and never the host's wall clock. [`TickClock`] keeps the count for
+//! executors that do not have one of their own.
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::sim::{SimTick, TickClock};
+//! use embedded_can_mock::timing::BusTiming;
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//!
+//! let mut bus = BusHandle::new();
+//! bus.set_timing(Some(BusTiming::new(500_000)));
+//! let firmware = bus.add_interface(vec![]).unwrap();
+//! let tester = bus.add_interface(vec![]).unwrap();
+//! let mut clock = TickClock::new(Duration::from_micros(100));
+//!
+//! firmware.transmit(MockFrame::new(StandardId::new(0x100).unwrap(), &[0; 8]).unwrap()).unwrap();
+//! // An 8-byte frame takes about 220 µs at 500 kbit/s: it completes during the third tick.
+//! let mut ticks = 0;
+//! while !tester.has_frames() {
+//!     clock.step(&mut [&mut bus]).unwrap();
+//!     ticks += 1;
+//! }
+//! assert_eq!(ticks, 3);
+//! assert_eq!(bus.now(), clock.now());
+//! ```
+//!
+//! Ticks are absolute, so replaying or skipping a tick never moves a clock backwards. Blocking
+//! receive timeouts still count wall time; a lock-step executor should poll with the
+//! non-blocking calls between ticks.
+
+use core::time::Duration;
+
+use embedded_can::Frame;
+
+use crate::{BusHandle, TransmitError, device::MockDevice, topology::Topology};
+
+/// A component an external executor advances one tick at a time.
+pub trait SimTick {
+    /// Bring the component to the end of tick `tick`, where every tick lasts `period` of
+    /// virtual time (tick 0 ends at time zero).
+    ///
+    /// Errors come from frames the component transmits in response, such as a gateway
+    /// forwarding onto an overloaded bus.
+    fn on_tick(&mut self, tick: u64, period: Duration) -> Result<(), TransmitError>;
+}
+
+/// Virtual time at the end of tick `tick`, saturating at the largest representable time.
+pub fn tick_time(tick: u64, period: Duration) -> Duration {
+    let nanos = period.as_nanos().saturating_mul(u128::from(tick));
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
+
+impl<F: Frame + Clone + 'static> SimTick for BusHandle<F> {
+    fn on_tick(&mut self, tick: u64, period: Duration) -> Result<(), TransmitError> {
+        self.advance(tick_time(tick, period).saturating_sub(self.now()));
+        Ok(())
+    }
+}
+
+impl SimTick for MockDevice {
+    fn on_tick(&mut self, tick: u64, period: Duration) -> Result<(), TransmitError> {
+        self.advance(tick_time(tick, period).saturating_sub(self.now()));
+        Ok(())
+    }
+}
+
+/// Advances every bus, then [pumps](Topology::pump) gateways and responders once.
+impl SimTick for Topology {
+    fn on_tick(&mut self, tick: u64, period: Duration) -> Result<(), TransmitError> {
+        for bus in self.buses() {
+            bus.clone().on_tick(tick, period)?;
+        }
+        self.pump().map(|_| ())
+    }
+}
+
+/// Tick counter driving [`SimTick`] components in lock-step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickClock {
+    period: Duration,
+    tick: u64,
+}
+
+impl TickClock {
+    /// A clock at tick 0 whose ticks last `period`.
+    pub fn new(period: Duration) -> Self {
+        Self { period, tick: 0 }
+    }
+
+    /// Duration of one tick.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Number of the last completed tick.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Virtual time at the end of the last completed tick.
+    pub fn now(&self) -> Duration {
+        tick_time(self.tick, self.period)
+    }
+
+    /// Run the next tick on every component, in order, stopping at the first error.
+    ///
+    /// Returns the number of the tick that ran.
+    pub fn step(&mut self, components: &mut [&mut dyn SimTick]) -> Result<u64, TransmitError> {
+        self.tick += 1;
+        for component in components.iter_mut() {
+            component.on_tick(self.tick, self.period)?;
+        }
+        Ok(self.tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockFrame, topology::TopologyBuilder};
+    use embedded_can::StandardId;
+
+    #[test]
+    fn topology_follows_the_tick_count_and_never_rewinds() {
+        let mut net = TopologyBuilder::parse(
+            "[bus.a]\n[bus.b]\n\
+             [[node]]\nname = \"ecu\"\nbus = \"a\"\n\
+             [[node]]\nname = \"tester\"\nbus = \"b\"\n\
+             [[gateway]]\nname = \"gw\"\na = \"a\"\nb = \"b\"\n",
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let mut clock = TickClock::new(Duration::from_millis(1));
+        net.node("ecu")
+            .transmit(MockFrame::new(StandardId::new(0x10).unwrap(), &[]).unwrap())
+            .unwrap();
+
+        assert_eq!(clock.step(&mut [&mut net]).unwrap(), 1);
+        assert!(net.node("tester").has_frames());
+        assert_eq!(net.bus("b").now(), Duration::from_millis(1));
+
+        net.on_tick(0, clock.period()).unwrap();
+        assert_eq!(net.bus("a").now(), Duration::from_millis(1));
+        assert_eq!(
+            tick_time(u64::MAX, Duration::from_secs(1)),
+            Duration::from_nanos(u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Locking primitives used by the bus.
+//!
+//! With the `std` feature, these are the standard library’s `Mutex` / `Condvar`. Without it, a
+//! small spin lock is used and condition variables are no-ops: blocking waits are only available
+//! with `std`, and `no_std` receivers poll instead. On `wasm` targets the standard library cannot
+//! park a thread or read a clock, so the bus never waits on its condition variables there.
+//!
+//! Locks ignore poisoning. A thread that panics while holding a bus lock (a failing assertion in a
+//! tracer, a policy or a [`with_received_frames`](crate::InterfaceHandle::with_received_frames)
+//! closure) leaves the bus as it was at the moment of the panic, and every other handle keeps
+//! working on that state instead of panicking in turn. User callbacks only run between complete
+//! state updates, so at worst the operation that panicked is lost part-way: a frame may have
+//! reached some receivers but not others.
+
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
+#[cfg(feature = "std")]
+pub(crate) use std::sync::{Condvar, Mutex, MutexGuard};
+
+/// Lock `mutex`, recovering it if a previous holder panicked.
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Block on `condvar`, reacquiring `guard` even if another holder panicked meanwhile.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub(crate) fn wait<'a, T>(condvar: &Condvar, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+    condvar.wait(guard).unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Like [`wait`], giving up after `timeout`.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub(crate) fn wait_timeout<'a, T>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, T>,
+    timeout: core::time::Duration,
+) -> MutexGuard<'a, T> {
+    condvar
+        .wait_timeout(guard, timeout)
+        .unwrap_or_else(PoisonError::into_inner)
+        .0
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::{Condvar, Mutex, MutexGuard};
+
+/// Lock `mutex`, spinning until it becomes available.
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock()
+}
+
+#[cfg(not(feature = "std"))]
+mod spin {
+    use core::{
+        cell::UnsafeCell,
+        ops::{Deref, DerefMut},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    /// Minimal test-and-set spin lock.
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    // SAFETY: access to `value` is serialized by `locked`.
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+    unsafe impl<T: Send> Send for Mutex<T> {}
+
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            while self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            MutexGuard { mutex: self }
+        }
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            // SAFETY: the guard holds the lock.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: the guard holds the lock exclusively.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+
+    /// Stand-in for `std::sync::Condvar`; there is nothing to wake without threads.
+    pub(crate) struct Condvar;
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Self
+        }
+
+        pub(crate) fn notify_all(&self) {}
+    }
+}
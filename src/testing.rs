@@ -0,0 +1,382 @@
+//! Assertion helpers for tests built on the mock bus.
+//!
+//! [`assert_sequence`] drains an interface’s receive queue and compares it against a list of
+//! [`Expect`]ations, panicking with a side-by-side listing of expected vs actual frames on
+//! mismatch:
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::testing::{assert_sequence, expect_any, expect_data, expect_id};
+//!
+//! let bus = BusHandle::new();
+//! let node = bus.add_interface(vec![]).unwrap();
+//! for (id, data) in [(0x100, &[0xAA][..]), (0x101, &[0x01]), (0x7FF, &[])] {
+//!     node.transmit(MockFrame::new(StandardId::new(id).unwrap(), data).unwrap()).unwrap();
+//! }
+//!
+//! assert_sequence(&node, [expect_id(0x100), expect_data(0x101, [0x01]), expect_any()]);
+//! assert!(!node.has_frames());
+//! ```
+//!
+//! [`assert_frame_sent!`](crate::assert_frame_sent) and
+//! [`assert_no_traffic!`](crate::assert_no_traffic) cover the two assertions most tests write by
+//! hand, typically against a [tap](crate::BusHandle::tap); on failure they dump the recent
+//! traffic:
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame, assert_frame_sent, assert_no_traffic};
+//!
+//! let bus = BusHandle::new();
+//! let tap = bus.tap();
+//! let node = bus.add_interface(vec![]).unwrap();
+//!
+//! assert_no_traffic!(tap, for = Duration::from_millis(5));
+//! node.transmit(MockFrame::new(StandardId::new(0x123).unwrap(), &[0x01]).unwrap()).unwrap();
+//! assert_frame_sent!(tap, id = 0x123, data = [0x01], within = Duration::from_millis(50));
+//! ```
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{fmt, time::Duration};
+
+use embedded_can::{Frame, Id};
+
+use crate::{bus::InterfaceHandle, frame::MockFrame};
+
+/// A single expected frame in a sequence.
+pub enum Expect {
+    /// Any frame.
+    Any,
+    /// A frame with this raw ID (standard or extended).
+    Id(u32),
+    /// A frame with this raw ID and exactly this payload.
+    Data(u32, Vec<u8>),
+    /// A frame satisfying a predicate; the string describes it in failure output.
+    Matching(String, Box<dyn Fn(&MockFrame) -> bool>),
+}
+
+/// Expect any frame.
+pub fn expect_any() -> Expect {
+    Expect::Any
+}
+
+/// Expect a frame whose raw ID is `id`, regardless of payload.
+pub fn expect_id(id: u32) -> Expect {
+    Expect::Id(id)
+}
+
+/// Expect a frame whose raw ID is `id` carrying exactly `data`.
+pub fn expect_data(id: u32, data: impl AsRef<[u8]>) -> Expect {
+    Expect::Data(id, data.as_ref().to_vec())
+}
+
+/// Expect a frame satisfying `predicate`, described as `description` in failure output.
+pub fn expect_matching(
+    description: impl Into<String>,
+    predicate: impl Fn(&MockFrame) -> bool + 'static,
+) -> Expect {
+    Expect::Matching(description.into(), Box::new(predicate))
+}
+
+impl Expect {
+    /// Returns `true` if `frame` satisfies this expectation.
+    pub fn matches(&self, frame: &MockFrame) -> bool {
+        match self {
+            Expect::Any => true,
+            Expect::Id(id) => raw_id(frame.id()) == *id,
+            Expect::Data(id, data) => raw_id(frame.id()) == *id && frame.data() == data.as_slice(),
+            Expect::Matching(_, predicate) => predicate(frame),
+        }
+    }
+}
+
+impl fmt::Display for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expect::Any => f.write_str("<any>"),
+            Expect::Id(id) => write!(f, "{id:03X}#*"),
+            Expect::Data(id, data) => {
+                write!(f, "{id:03X}#")?;
+                data.iter().try_for_each(|b| write!(f, "{b:02X}"))
+            }
+            Expect::Matching(description, _) => write!(f, "<{description}>"),
+        }
+    }
+}
+
+impl fmt::Debug for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A received sequence that did not match its expectations.
+///
+/// The `Display` output lists expected and actual frames side by side and marks the first
+/// mismatching position.
+#[derive(Debug)]
+pub struct SequenceMismatch {
+    /// Index of the first mismatch.
+    pub index: usize,
+    /// Rendered expectations.
+    pub expected: Vec<String>,
+    /// Frames that were actually received.
+    pub actual: Vec<MockFrame>,
+}
+
+impl fmt::Display for SequenceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "frame sequence mismatch at index {} (expected {} frames, received {})",
+            self.index,
+            self.expected.len(),
+            self.actual.len()
+        )?;
+        writeln!(f, "      {:<24} actual", "expected")?;
+        let rows = self.expected.len().max(self.actual.len());
+        for i in 0..rows {
+            let marker = if i == self.index { ">>" } else { "  " };
+            let expected = self.expected.get(i).map_or("-", String::as_str);
+            let actual = self
+                .actual
+                .get(i)
+                .map_or_else(|| String::from("-"), MockFrame::fmt_candump);
+            writeln!(f, "{marker}{i:>3} {expected:<24} {actual}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Drain `iface`’s receive queue and compare it against `expected`.
+///
+/// The received sequence must have exactly as many frames as there are expectations.
+pub fn check_sequence(
+    iface: &InterfaceHandle,
+    expected: impl IntoIterator<Item = Expect>,
+) -> Result<(), SequenceMismatch> {
+    let expected: Vec<Expect> = expected.into_iter().collect();
+    let actual = iface.pop_frames(usize::MAX);
+    let first_mismatch = expected
+        .iter()
+        .zip(&actual)
+        .position(|(e, frame)| !e.matches(frame))
+        .or((expected.len() != actual.len()).then(|| expected.len().min(actual.len())));
+    match first_mismatch {
+        None => Ok(()),
+        Some(index) => Err(SequenceMismatch {
+            index,
+            expected: expected.iter().map(|e| alloc::format!("{e}")).collect(),
+            actual,
+        }),
+    }
+}
+
+/// Drain `iface`’s receive queue and panic with a readable diff unless it matches `expected`.
+#[track_caller]
+pub fn assert_sequence(iface: &InterfaceHandle, expected: impl IntoIterator<Item = Expect>) {
+    if let Err(mismatch) = check_sequence(iface, expected) {
+        panic!("{mismatch}");
+    }
+}
+
+/// Number of frames shown in a [`TrafficMismatch`] dump.
+const RECENT_TRAFFIC: usize = 16;
+
+/// A traffic assertion that failed: an expected frame was not sent, or traffic appeared when
+/// none was expected.
+///
+/// The `Display` output explains what was expected and lists the most recent frames queued on the
+/// observed interface.
+#[derive(Debug)]
+pub struct TrafficMismatch {
+    /// Rendered expectation, or `None` if no traffic was expected.
+    pub expected: Option<String>,
+    /// How long the assertion waited.
+    pub window: Duration,
+    /// Frames queued on the interface when the assertion failed, oldest first.
+    pub traffic: Vec<MockFrame>,
+}
+
+impl fmt::Display for TrafficMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.expected {
+            Some(expected) => writeln!(
+                f,
+                "expected frame {expected} within {:?}, but it was not sent",
+                self.window
+            )?,
+            None => writeln!(
+                f,
+                "expected no traffic for {:?}, but {} frame(s) arrived",
+                self.window,
+                self.traffic.len()
+            )?,
+        }
+        let skipped = self.traffic.len().saturating_sub(RECENT_TRAFFIC);
+        match skipped {
+            _ if self.traffic.is_empty() => writeln!(f, "recent traffic: none")?,
+            0 => writeln!(f, "recent traffic:")?,
+            _ => writeln!(f, "recent traffic ({skipped} older frames omitted):")?,
+        }
+        for frame in &self.traffic[skipped..] {
+            writeln!(f, "  {}", frame.fmt_candump())?;
+        }
+        Ok(())
+    }
+}
+
+/// Wait up to `within` for a frame matching `expected` to be queued on `iface`, and remove it.
+///
+/// Other frames stay queued. With a zero window only frames already queued count; waiting follows
+/// [`InterfaceHandle::wait_for_frame`] (real time, and a single check without `std`), so on a
+/// bus with a virtual clock advance it first.
+pub fn check_frame_sent(
+    iface: &InterfaceHandle,
+    expected: Expect,
+    within: Duration,
+) -> Result<MockFrame, TrafficMismatch> {
+    iface
+        .recv_matching(|frame: &MockFrame| expected.matches(frame), Some(within))
+        .ok_or_else(|| TrafficMismatch {
+            expected: Some(alloc::format!("{expected}")),
+            window: within,
+            traffic: iface.received_frames(),
+        })
+}
+
+/// Wait `window` and fail if any frame is (or already was) queued on `iface`.
+pub fn check_no_traffic(iface: &InterfaceHandle, window: Duration) -> Result<(), TrafficMismatch> {
+    if !iface.wait_for_frame(Some(window)) {
+        return Ok(());
+    }
+    Err(TrafficMismatch {
+        expected: None,
+        window,
+        traffic: iface.received_frames(),
+    })
+}
+
+/// Assert that a frame with the given raw ID (and optionally payload) is sent on an interface,
+/// waiting up to an optional window. See [`check_frame_sent`].
+///
+/// ```
+/// # use core::time::Duration;
+/// # use embedded_can::{Frame as _, StandardId};
+/// # use embedded_can_mock::{BusHandle, MockFrame, assert_frame_sent};
+/// # let bus = BusHandle::new();
+/// # let tap = bus.tap();
+/// # let node = bus.add_interface(vec![]).unwrap();
+/// # node.transmit(MockFrame::new(StandardId::new(0x123).unwrap(), &[0x01]).unwrap()).unwrap();
+/// # node.transmit(MockFrame::new(StandardId::new(0x124).unwrap(), &[]).unwrap()).unwrap();
+/// assert_frame_sent!(tap, id = 0x123, data = [0x01]);
+/// assert_frame_sent!(tap, id = 0x124, within = Duration::from_millis(10));
+/// ```
+#[macro_export]
+macro_rules! assert_frame_sent {
+    ($iface:expr, id = $id:expr $(, data = $data:expr)? $(, within = $within:expr)? $(,)?) => {{
+        let expected = match None::<&[u8]> $(.or(Some(&$data[..])))? {
+            Some(data) => $crate::testing::expect_data($id, data),
+            None => $crate::testing::expect_id($id),
+        };
+        let within = ::core::time::Duration::ZERO $(.max($within))?;
+        match $crate::testing::check_frame_sent(&$iface, expected, within) {
+            Ok(frame) => frame,
+            Err(mismatch) => panic!("{mismatch}"),
+        }
+    }};
+}
+
+/// Assert that no frame arrives on an interface for the given window. See
+/// [`check_no_traffic`].
+#[macro_export]
+macro_rules! assert_no_traffic {
+    ($iface:expr, for = $window:expr $(,)?) => {
+        if let Err(mismatch) = $crate::testing::check_no_traffic(&$iface, $window) {
+            panic!("{mismatch}");
+        }
+    };
+}
+
+fn raw_id(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => id.as_raw() as u32,
+        Id::Extended(id) => id.as_raw(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::BusHandle;
+    use alloc::string::ToString;
+    use embedded_can::StandardId;
+
+    fn frame(id: u16, data: &[u8]) -> MockFrame {
+        MockFrame::new(StandardId::new(id).unwrap(), data).unwrap()
+    }
+
+    #[test]
+    fn mismatch_reports_expected_and_actual_side_by_side() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.transmit(frame(0x100, &[0x01])).unwrap();
+        node.transmit(frame(0x102, &[0xBE, 0xEF])).unwrap();
+
+        let err = check_sequence(
+            &node,
+            [
+                expect_id(0x100),
+                expect_data(0x101, [0x01]),
+                expect_matching("empty", |f| f.data().is_empty()),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(err.index, 1);
+        assert!(!node.has_frames());
+
+        let text = err.to_string();
+        assert!(text.contains(">>  1 101#01"), "{text}");
+        assert!(text.contains("102#BEEF"), "{text}");
+        assert!(text.contains("<empty>"), "{text}");
+    }
+
+    #[test]
+    fn traffic_assertions_dump_recent_frames() {
+        let bus = BusHandle::new();
+        let tap = bus.tap();
+        let node = bus.add_interface(vec![]).unwrap();
+        for id in 0..20 {
+            node.transmit(frame(0x100 + id, &[])).unwrap();
+        }
+
+        let err = check_frame_sent(&tap, expect_data(0x105, [0x01]), Duration::ZERO).unwrap_err();
+        let text = err.to_string();
+        assert!(text.contains("expected frame 105#01"), "{text}");
+        assert!(text.contains("(4 older frames omitted)"), "{text}");
+        assert!(!text.contains("103#"), "{text}");
+        assert!(text.contains("113#"), "{text}");
+
+        assert_eq!(
+            crate::assert_frame_sent!(tap, id = 0x105),
+            frame(0x105, &[])
+        );
+        assert_eq!(tap.queue_len(), 19);
+        tap.clear_rx();
+        crate::assert_no_traffic!(tap, for = Duration::ZERO);
+        node.transmit(frame(0x7FF, &[])).unwrap();
+        let err = check_no_traffic(&tap, Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("1 frame(s) arrived"));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 0 frames, received 1")]
+    fn assert_sequence_panics_on_unexpected_frames() {
+        let bus = BusHandle::new();
+        let node = bus.add_interface(vec![]).unwrap();
+        node.transmit(frame(0x100, &[])).unwrap();
+        assert_sequence(&node, []);
+    }
+}
@@ -0,0 +1,471 @@
+//! Bit-level frame timing.
+//!
+//! [`BusTiming`] describes the nominal (arbitration) bitrate and, optionally, a CAN FD data-phase
+//! bitrate. Given a frame, it computes how long the frame occupies the wire, including stuff bits
+//! and interframe space. A bus configured via [`BusHandle::set_timing`](crate::BusHandle::set_timing)
+//! uses this to space deliveries on its virtual clock.
+//!
+//! [`MockFrame`]s flagged as CAN FD in their [metadata](crate::FrameMeta) are timed as FD
+//! frames, switching to the data bitrate only when their BRS flag is set; frames of other types
+//! are treated as FD (with bitrate switching) when they carry more than 8 data bytes. Classic
+//! frames are stuffed exactly (the CRC-15 is computed and the real bit stream is walked); FD frames use the
+//! worst-case dynamic stuffing estimate plus the fixed stuff bits of the FD CRC field.
+
+use alloc::vec::Vec;
+use core::{any::Any, time::Duration};
+
+use embedded_can::{Frame, Id};
+
+use crate::{InterfaceId, MockFrame};
+
+/// Bitrate configuration of a simulated bus.
+///
+/// # Example
+///
+/// ```
+/// use embedded_can::{Frame as _, StandardId};
+/// use embedded_can_mock::{MockFrame, timing::BusTiming};
+///
+/// let timing = BusTiming::new(500_000);
+/// let frame = MockFrame::new(StandardId::new(0x123).unwrap(), &[0; 8]).unwrap();
+///
+/// // An 8-byte standard frame is at least 111 bits long (2 µs per bit at 500 kbit/s).
+/// assert!(timing.wire_time(&frame).as_micros() >= 222);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusTiming {
+    /// Nominal (arbitration-phase) bitrate in bit/s.
+    pub bitrate: u32,
+    /// CAN FD data-phase bitrate in bit/s. When set, FD frames requesting bitrate switching send
+    /// their data phase at this rate.
+    pub data_bitrate: Option<u32>,
+}
+
+/// Number of bits a frame occupies in each bitrate phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameBits {
+    /// Bits sent at the nominal bitrate (including interframe space).
+    pub nominal: u32,
+    /// Bits sent at the data bitrate (zero for classic frames or FD without bitrate switching).
+    pub data: u32,
+}
+
+impl BusTiming {
+    /// Classic CAN timing at `bitrate` bit/s.
+    pub fn new(bitrate: u32) -> Self {
+        Self {
+            bitrate,
+            data_bitrate: None,
+        }
+    }
+
+    /// Enable CAN FD bitrate switching with the given data-phase bitrate.
+    pub fn with_data_bitrate(mut self, data_bitrate: u32) -> Self {
+        self.data_bitrate = Some(data_bitrate);
+        self
+    }
+
+    /// Bit counts of `frame` on the wire, per bitrate phase.
+    pub fn frame_bits<F: Frame + 'static>(&self, frame: &F) -> FrameBits {
+        match fd_format(frame) {
+            Some(brs) => fd_frame_bits(frame, brs && self.data_bitrate.is_some()),
+            None => FrameBits {
+                nominal: classic_frame_bits(frame),
+                data: 0,
+            },
+        }
+    }
+
+    /// Time `frame` occupies the bus, including interframe space.
+    pub fn wire_time<F: Frame + 'static>(&self, frame: &F) -> Duration {
+        let bits = self.frame_bits(frame);
+        let mut nanos = bits_to_nanos(bits.nominal, self.bitrate);
+        if let Some(data_bitrate) = self.data_bitrate {
+            nanos += bits_to_nanos(bits.data, data_bitrate);
+        }
+        Duration::from_nanos(nanos)
+    }
+
+    /// Duration of a single nominal bit.
+    pub fn bit_time(&self) -> Duration {
+        Duration::from_nanos(bits_to_nanos(1, self.bitrate))
+    }
+}
+
+/// Throughput limit for a timed bus, set via
+/// [`BusHandle::set_load_limit`](crate::BusHandle::set_load_limit).
+///
+/// The bus is overloaded when transmitting another frame would push the backlog (time needed to
+/// drain everything waiting for or occupying the wire) above `max_backlog`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadLimit {
+    /// Largest tolerated backlog.
+    pub max_backlog: Duration,
+    /// When `true`, overload rejects the lowest-priority frame (the new one, or an already
+    /// waiting frame with a lower priority). When `false`, frames are only delayed.
+    pub reject: bool,
+}
+
+/// Deliberately unfair arbitration for a timed bus, set via
+/// [`BusHandle::set_starvation`](crate::BusHandle::set_starvation).
+///
+/// A frame is starved when its ID has a lower priority than `below`, is listed in `ids`, or it
+/// comes from one of `interfaces`. Starved frames never win arbitration while other frames are
+/// waiting, and only go once the bus has carried no other traffic for `quiet`, so a steady flow
+/// of unstarved frames keeps them off the bus indefinitely. Use this to check that a system
+/// notices messages that stop arriving although their sender keeps trying.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Starvation {
+    /// Frames with a lower arbitration priority than this ID are starved.
+    pub below: Option<Id>,
+    /// Frames with these IDs are starved.
+    pub ids: Vec<Id>,
+    /// Frames transmitted by these interfaces are starved.
+    pub interfaces: Vec<InterfaceId>,
+    /// How long the bus must have been free of unstarved traffic before a starved frame may go.
+    pub quiet: Duration,
+}
+
+impl Starvation {
+    /// Whether `frame`, transmitted by `source`, is starved.
+    pub fn starves<F: Frame>(&self, source: InterfaceId, frame: &F) -> bool {
+        self.interfaces.contains(&source)
+            || self.ids.contains(&frame.id())
+            || self
+                .below
+                .is_some_and(|below| arbitration_key(frame) > id_key(below, false))
+    }
+}
+
+/// Transmit pacing for one interface, set via
+/// [`InterfaceHandle::set_pacing`](crate::InterfaceHandle::set_pacing).
+///
+/// Pacing works on the virtual clock, with or without bit timing. A transmit arriving earlier
+/// than the pacing allows is either held back until it conforms (its bus request is delayed) or,
+/// with `reject`, fails with
+/// [`TransmitError::RateLimited`](crate::TransmitError::RateLimited).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    /// Smallest interval between the bus requests of consecutive frames.
+    pub min_gap: Duration,
+    /// Optional token-bucket limit on the average rate.
+    pub rate_limit: Option<RateLimit>,
+    /// When `true`, non-conforming transmits are rejected instead of delayed.
+    pub reject: bool,
+}
+
+/// Token-bucket rate limit within a [`Pacing`] configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Long-term average rate.
+    pub frames_per_second: u32,
+    /// Frames that may be sent back to back after an idle period (bucket depth, at least 1).
+    pub burst: u32,
+}
+
+/// Pacing progress of one interface.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PaceState {
+    /// Theoretical arrival time of the next conforming frame (generic cell rate algorithm).
+    tat: Duration,
+    /// Bus request time of the previous frame.
+    last: Option<Duration>,
+}
+
+impl Pacing {
+    /// Earliest time at or after `now` at which a frame conforms to this pacing.
+    pub(crate) fn release(&self, state: &PaceState, now: Duration) -> Duration {
+        let mut at = now;
+        if let Some(last) = state.last {
+            at = at.max(last + self.min_gap);
+        }
+        if let Some(limit) = self.rate_limit {
+            let interval = limit.interval();
+            let tolerance = interval * limit.burst.max(1).saturating_sub(1);
+            at = at.max(state.tat.saturating_sub(tolerance));
+        }
+        at
+    }
+
+    /// Account for a frame requesting the bus at `at`.
+    pub(crate) fn commit(&self, state: &mut PaceState, at: Duration) {
+        state.last = Some(at);
+        if let Some(limit) = self.rate_limit {
+            state.tat = state.tat.max(at) + limit.interval();
+        }
+    }
+}
+
+impl RateLimit {
+    fn interval(&self) -> Duration {
+        Duration::from_nanos(1_000_000_000 / u64::from(self.frames_per_second.max(1)))
+    }
+}
+
+/// Bus occupancy and overload metrics, as returned by [`BusHandle::load`](crate::BusHandle::load).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusLoad {
+    /// Fraction of elapsed virtual time the wire was busy (`0.0..=1.0`).
+    pub occupancy: f64,
+    /// Time needed to drain every frame waiting for or occupying the wire.
+    pub backlog: Duration,
+    /// Frames waiting for arbitration (not yet on the wire).
+    pub waiting: usize,
+    /// `true` from the first transmit exceeding the [`LoadLimit`] until the bus drains.
+    pub overloaded: bool,
+    /// Frames rejected because of the load limit.
+    pub rejected: u64,
+}
+
+/// Arbitration priority of `frame`: lower keys win the bus.
+///
+/// Orders frames by the bits they put on the wire during arbitration, so a standard frame beats
+/// an extended frame with the same base ID, and a data frame beats a remote frame with the same
+/// ID.
+pub fn arbitration_key<F: Frame>(frame: &F) -> u64 {
+    id_key(frame.id(), frame.is_remote_frame())
+}
+
+fn id_key(id: Id, remote: bool) -> u64 {
+    let rtr = remote as u64;
+    match id {
+        Id::Standard(id) => ((id.as_raw() as u64) << 21) | (rtr << 20),
+        Id::Extended(id) => {
+            let raw = id.as_raw() as u64;
+            ((raw >> 18) << 21) | (1 << 20) | (1 << 19) | ((raw & 0x3FFFF) << 1) | rtr
+        }
+    }
+}
+
+/// `Some(brs)` if `frame` is sent in CAN FD format, `None` for a classic frame.
+fn fd_format<F: Frame + 'static>(frame: &F) -> Option<bool> {
+    match (frame as &dyn Any).downcast_ref::<MockFrame>() {
+        Some(frame) if frame.meta().fd => Some(frame.meta().brs),
+        _ => (frame.data().len() > 8).then_some(true),
+    }
+}
+
+fn bits_to_nanos(bits: u32, bitrate: u32) -> u64 {
+    (bits as u64 * 1_000_000_000).div_ceil(bitrate.max(1) as u64)
+}
+
+/// CRC delimiter, ACK slot + delimiter, EOF and interframe space.
+const TRAILER_BITS: u32 = 1 + 2 + 7 + 3;
+
+/// Exact bit count of a classic frame, stuff bits included.
+fn classic_frame_bits<F: Frame>(frame: &F) -> u32 {
+    let mut bits = BitStream::default();
+    bits.push(false, 1); // SOF
+    match frame.id() {
+        Id::Standard(id) => {
+            bits.push_value(id.as_raw() as u32, 11);
+            bits.push(frame.is_remote_frame(), 1); // RTR
+            bits.push(false, 1); // IDE
+            bits.push(false, 1); // r0
+        }
+        Id::Extended(id) => {
+            let raw = id.as_raw();
+            bits.push_value(raw >> 18, 11);
+            bits.push(true, 1); // SRR
+            bits.push(true, 1); // IDE
+            bits.push_value(raw & 0x3FFFF, 18);
+            bits.push(frame.is_remote_frame(), 1); // RTR
+            bits.push(false, 2); // r1, r0
+        }
+    }
+    bits.push_value(frame.dlc().min(15) as u32, 4);
+    for byte in frame.data() {
+        bits.push_value(*byte as u32, 8);
+    }
+    let crc = bits.crc15();
+    bits.push_value(crc as u32, 15);
+    bits.len + bits.stuff_bits + TRAILER_BITS
+}
+
+/// Payload sizes representable by a CAN FD DLC.
+const FD_LENGTHS: [u32; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Bit count of an FD frame using the worst-case dynamic stuffing estimate.
+fn fd_frame_bits<F: Frame>(frame: &F, bitrate_switch: bool) -> FrameBits {
+    let len = frame.data().len() as u32;
+    let padded = FD_LENGTHS
+        .iter()
+        .copied()
+        .find(|l| *l >= len)
+        .unwrap_or(len);
+    // SOF, ID, RRS, IDE, FDF, res, BRS (extended adds SRR + 18 ID bits).
+    let arbitration = match frame.id() {
+        Id::Standard(_) => 17,
+        Id::Extended(_) => 36,
+    };
+    // ESI, DLC, payload, stuff count (4), CRC and its fixed stuff bits.
+    let crc = if padded <= 16 { 17 } else { 21 };
+    let data_unstuffed = 1 + 4 + 8 * padded;
+    let dynamic_stuff = (arbitration + data_unstuffed - 1) / 4;
+    let data = data_unstuffed + 4 + crc + crc.div_ceil(4) + 1;
+
+    if bitrate_switch {
+        FrameBits {
+            nominal: arbitration + TRAILER_BITS,
+            data: data + dynamic_stuff,
+        }
+    } else {
+        FrameBits {
+            nominal: arbitration + data + dynamic_stuff + TRAILER_BITS,
+            data: 0,
+        }
+    }
+}
+
+/// Bit sequence (SOF..CRC) with running CRC-15 and stuff-bit accounting.
+#[derive(Default)]
+struct BitStream {
+    len: u32,
+    crc: u16,
+    last: Option<bool>,
+    run: u32,
+    stuff_bits: u32,
+}
+
+impl BitStream {
+    fn push(&mut self, bit: bool, count: u32) {
+        for _ in 0..count {
+            self.push_bit(bit);
+        }
+    }
+
+    fn push_value(&mut self, value: u32, width: u32) {
+        for i in (0..width).rev() {
+            self.push_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        self.len += 1;
+        let next = bit ^ ((self.crc >> 14) & 1 == 1);
+        self.crc = (self.crc << 1) & 0x7FFF;
+        if next {
+            self.crc ^= 0x4599;
+        }
+        if self.last == Some(bit) {
+            self.run += 1;
+        } else {
+            self.last = Some(bit);
+            self.run = 1;
+        }
+        if self.run == 5 {
+            // The inserted complement bit starts a new run.
+            self.stuff_bits += 1;
+            self.last = Some(!bit);
+            self.run = 1;
+        }
+    }
+
+    /// CRC-15 of the bits pushed so far.
+    fn crc15(&self) -> u16 {
+        self.crc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::MockFrame;
+    use embedded_can::{ExtendedId, StandardId};
+
+    #[test]
+    fn classic_frame_bits_include_stuffing_within_bounds() {
+        let timing = BusTiming::new(500_000);
+        for id in [0x000u16, 0x123, 0x7FF] {
+            for len in 0..=8 {
+                let frame = MockFrame::new(StandardId::new(id).unwrap(), &[0u8; 8][..len]).unwrap();
+                let bits = timing.frame_bits(&frame).nominal;
+                let unstuffed = 47 + 8 * len as u32;
+                let worst = unstuffed + (34 + 8 * len as u32 - 1) / 4;
+                assert!(bits >= unstuffed && bits <= worst, "{id:#x}/{len}: {bits}");
+            }
+        }
+
+        // All-zero payloads force stuffing; alternating payloads avoid it.
+        let zeros = MockFrame::new(StandardId::new(0x555).unwrap(), &[0x00; 8]).unwrap();
+        let alternating = MockFrame::new(StandardId::new(0x555).unwrap(), &[0x55; 8]).unwrap();
+        assert!(timing.frame_bits(&zeros).nominal > timing.frame_bits(&alternating).nominal);
+    }
+
+    #[test]
+    fn arbitration_key_orders_like_the_wire() {
+        let std = |id, remote| {
+            if remote {
+                MockFrame::new_remote(StandardId::new(id).unwrap(), 0).unwrap()
+            } else {
+                MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap()
+            }
+        };
+        let ext = |id| MockFrame::new(ExtendedId::new(id).unwrap(), &[]).unwrap();
+
+        assert!(arbitration_key(&std(0x100, false)) < arbitration_key(&std(0x101, false)));
+        assert!(arbitration_key(&std(0x100, false)) < arbitration_key(&std(0x100, true)));
+        assert!(arbitration_key(&std(0x100, true)) < arbitration_key(&ext(0x100 << 18)));
+        assert!(arbitration_key(&ext(0x100 << 18 | 0x3FFFF)) < arbitration_key(&std(0x101, false)));
+    }
+
+    #[test]
+    fn extended_and_fd_frames_take_longer() {
+        let timing = BusTiming::new(500_000).with_data_bitrate(2_000_000);
+        let std = MockFrame::new(StandardId::new(0x1).unwrap(), &[0xAA; 8]).unwrap();
+        let ext = MockFrame::new(ExtendedId::new(0x1).unwrap(), &[0xAA; 8]).unwrap();
+        assert!(timing.wire_time(&ext) > timing.wire_time(&std));
+
+        let fd = MockFrame::new(StandardId::new(0x1).unwrap(), &[0xAA; 64]).unwrap();
+        let bits = timing.frame_bits(&fd);
+        assert!(bits.data > 512);
+        let no_brs = BusTiming::new(500_000);
+        assert!(no_brs.wire_time(&fd) > timing.wire_time(&fd));
+    }
+
+    #[test]
+    fn short_fd_frames_follow_their_format_flags() {
+        let timing = BusTiming::new(500_000).with_data_bitrate(2_000_000);
+        let classic = MockFrame::new(StandardId::new(0x1).unwrap(), &[0xAA; 8]).unwrap();
+        let fd = |brs| {
+            let mut frame = classic.clone();
+            frame.meta_mut().fd = true;
+            frame.meta_mut().brs = brs;
+            frame
+        };
+
+        // FDF, BRS, ESI and the stuff count lengthen the frame; BRS moves it to the data phase.
+        let with_brs = timing.frame_bits(&fd(true));
+        assert_eq!(with_brs.nominal, 17 + 13);
+        assert!(with_brs.data > 64);
+        assert!(timing.wire_time(&fd(true)) < timing.wire_time(&classic));
+        let without_brs = timing.frame_bits(&fd(false));
+        assert_eq!(without_brs.data, 0);
+        assert!(without_brs.nominal > timing.frame_bits(&classic).nominal);
+    }
+
+    #[test]
+    fn starved_interfaces_lose_to_any_traffic_until_released() {
+        let bus = crate::BusHandle::new();
+        bus.set_timing(Some(BusTiming::new(500_000)));
+        let starved = bus.add_interface(vec![]).unwrap();
+        bus.set_starvation(Some(Starvation {
+            interfaces: vec![starved.id()],
+            quiet: Duration::from_millis(5),
+            ..Starvation::default()
+        }));
+        let other = bus.add_interface(vec![]).unwrap();
+        let listener = bus.add_interface(vec![]).unwrap();
+        let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+
+        starved.transmit(frame(0x001)).unwrap();
+        other.transmit(frame(0x7FF)).unwrap();
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(listener.received_frames(), [frame(0x7FF)]);
+
+        bus.set_starvation(None);
+        assert_eq!(listener.received_frames(), [frame(0x7FF)]);
+        bus.advance(Duration::from_millis(1));
+        assert_eq!(listener.received_frames(), [frame(0x7FF), frame(0x001)]);
+    }
+}
@@ -0,0 +1,816 @@
+//! Whole-network topologies declared in one place.
+//!
+//! Tests of a multi-bus system need the same scaffolding every time: the buses, named nodes with
+//! their filters, gateways between buses and responders standing in for the ECUs outside the
+//! test's scope. A [`TopologyBuilder`] declares all of it, in code or from a text description,
+//! and [`build`](TopologyBuilder::build) returns a [`Topology`] mapping every name to its handle:
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::MockFrame;
+//! use embedded_can_mock::isotp::IsoTpEndpoint;
+//! use embedded_can_mock::topology::{Responder, TopologyBuilder};
+//!
+//! let mut net = TopologyBuilder::parse(
+//!     r#"
+//!     [bus.body]
+//!     [bus.powertrain]
+//!     bitrate = 500000
+//!
+//!     [[node]]
+//!     name = "door"
+//!     bus = "body"
+//!     filters = ["0x200/0x700"]
+//!
+//!     [[node]]
+//!     name = "tester"
+//!     bus = "powertrain"
+//!
+//!     [[gateway]]
+//!     name = "central"
+//!     a = "body"
+//!     b = "powertrain"
+//!
+//!     [[responder]]
+//!     name = "engine"
+//!     bus = "powertrain"
+//!     kind = "obd"
+//!     "#,
+//! )
+//! .unwrap()
+//! .build()
+//! .unwrap();
+//!
+//! // Traffic crosses the gateway when the topology is pumped.
+//! net.node("tester").transmit(MockFrame::new(StandardId::new(0x210).unwrap(), &[1]).unwrap()).unwrap();
+//! net.bus("powertrain").advance(core::time::Duration::from_millis(1));
+//! net.pump().unwrap();
+//! assert_eq!(net.node("door").pop_frame().unwrap().data(), [1]);
+//!
+//! // Responders are configured through the map too.
+//! let Responder::Obd(engine) = net.responder_mut("engine") else { unreachable!() };
+//! engine.set_pid(0x0D, vec![42]);
+//! ```
+//!
+//! # Description format
+//!
+//! Descriptions use a subset of TOML: `[bus.NAME]` tables and `[[node]]`, `[[gateway]]` and
+//! `[[responder]]` array tables, with `key = value` lines holding strings, integers (decimal or
+//! `0x` hex), booleans or single-line arrays of strings. `#` starts a comment.
+//!
+//! | table | keys |
+//! |-------|------|
+//! | `[bus.NAME]` | `bitrate` (optional; enables [timing](crate::BusHandle::set_timing)) |
+//! | `[[node]]` | `name`, `bus`, `filters` (`"id/mask"` in hex, as in [`uri`](crate::uri)), `listen_only`, `self_reception` |
+//! | `[[gateway]]` | `name`, `a`, `b` (bus names) |
+//! | `[[responder]]` | `name`, `bus`, `kind` = `"obd"` (`ecu`), `"uds"` (`tx`, `rx`) or `"sdo"` (`node_id`) |
+//!
+//! YAML descriptions are not supported.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use embedded_can::{ExtendedId, Id, StandardId};
+use embedded_can_interface::IdMaskFilter;
+
+use crate::{
+    bus::{BusHandle, InterfaceHandle, MockInterfaceError, TransmitError},
+    canopen::SdoServer,
+    gateway::Gateway,
+    isotp::IsoTpEndpoint,
+    obd::ObdResponder,
+    timing::BusTiming,
+    uds::UdsServer,
+    uri,
+};
+
+/// Errors returned while parsing or building a topology.
+#[derive(Debug)]
+pub enum TopologyError {
+    /// A malformed description line.
+    Parse {
+        /// 1-based line number.
+        line: usize,
+        /// What is wrong with it.
+        reason: String,
+    },
+    /// A node, gateway or responder refers to a bus that was not declared.
+    UnknownBus(String),
+    /// A bus, node, gateway or responder name is declared twice.
+    DuplicateName(String),
+    /// Attaching the named node's interface failed.
+    Interface {
+        /// The node, gateway or responder being attached.
+        name: String,
+        /// Why it failed.
+        error: MockInterfaceError,
+    },
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyError::Parse { line, reason } => write!(f, "topology line {line}: {reason}"),
+            TopologyError::UnknownBus(bus) => write!(f, "unknown bus `{bus}`"),
+            TopologyError::DuplicateName(name) => write!(f, "`{name}` is declared twice"),
+            TopologyError::Interface { name, error } => {
+                write!(f, "cannot attach `{name}`: {error:?}")
+            }
+        }
+    }
+}
+
+/// Per-node settings; the default accepts every frame and hears its own frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeOptions {
+    /// Acceptance filters (empty accepts everything).
+    pub filters: Vec<IdMaskFilter>,
+    /// Start in listen-only mode.
+    pub listen_only: bool,
+    /// Receive the node's own frames.
+    pub self_reception: bool,
+}
+
+impl Default for NodeOptions {
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+            listen_only: false,
+            self_reception: true,
+        }
+    }
+}
+
+/// Which protocol simulation a responder runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponderKind {
+    /// An [`ObdResponder`] for ECU number `ecu` (0..=7).
+    Obd {
+        /// ECU number.
+        ecu: u8,
+    },
+    /// A [`UdsServer`] sending on `tx_id` and listening on `rx_id`.
+    Uds {
+        /// Response ID.
+        tx_id: Id,
+        /// Request ID.
+        rx_id: Id,
+    },
+    /// A CANopen [`SdoServer`] for `node_id` (1..=127).
+    Sdo {
+        /// CANopen node-id.
+        node_id: u8,
+    },
+}
+
+/// A running responder in a [`Topology`].
+pub enum Responder {
+    /// OBD-II ECU.
+    Obd(ObdResponder),
+    /// UDS diagnostic server.
+    Uds(UdsServer),
+    /// CANopen SDO server.
+    Sdo(SdoServer),
+}
+
+impl Responder {
+    /// The interface the responder answers on.
+    pub fn interface(&self) -> &InterfaceHandle {
+        match self {
+            Responder::Obd(obd) => obd.endpoint().interface(),
+            Responder::Uds(uds) => uds.endpoint().interface(),
+            Responder::Sdo(sdo) => sdo.interface(),
+        }
+    }
+
+    /// Answer the requests received so far; `now` is the bus's virtual time.
+    pub fn poll(&mut self, now: core::time::Duration) -> Result<usize, TransmitError> {
+        match self {
+            Responder::Obd(obd) => obd.poll(),
+            Responder::Uds(uds) => uds.poll(now),
+            Responder::Sdo(sdo) => sdo.poll(),
+        }
+    }
+}
+
+/// Declaration of a network, turned into live handles by [`build`](Self::build).
+///
+/// Names are resolved when building, so declarations may come in any order.
+#[derive(Debug, Clone, Default)]
+pub struct TopologyBuilder {
+    buses: Vec<(String, Option<u32>)>,
+    nodes: Vec<(String, String, NodeOptions)>,
+    gateways: Vec<(String, String, String)>,
+    responders: Vec<(String, String, ResponderKind)>,
+}
+
+impl TopologyBuilder {
+    /// An empty topology.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a bus without bit timing.
+    pub fn bus(mut self, name: impl Into<String>) -> Self {
+        self.buses.push((name.into(), None));
+        self
+    }
+
+    /// Declare a bus with [timing](BusHandle::set_timing) at `bitrate` bit/s.
+    pub fn timed_bus(mut self, name: impl Into<String>, bitrate: u32) -> Self {
+        self.buses.push((name.into(), Some(bitrate)));
+        self
+    }
+
+    /// Declare a node on `bus` accepting every frame.
+    pub fn node(self, name: impl Into<String>, bus: impl Into<String>) -> Self {
+        self.node_with(name, bus, NodeOptions::default())
+    }
+
+    /// Declare a node on `bus` with `options`.
+    pub fn node_with(
+        mut self,
+        name: impl Into<String>,
+        bus: impl Into<String>,
+        options: NodeOptions,
+    ) -> Self {
+        self.nodes.push((name.into(), bus.into(), options));
+        self
+    }
+
+    /// Declare a [`Gateway`] forwarding between buses `a` and `b`.
+    pub fn gateway(
+        mut self,
+        name: impl Into<String>,
+        a: impl Into<String>,
+        b: impl Into<String>,
+    ) -> Self {
+        self.gateways.push((name.into(), a.into(), b.into()));
+        self
+    }
+
+    /// Declare a responder of `kind` on `bus`.
+    ///
+    /// Building panics if `kind` holds an out-of-range ECU number or node-id, as the responder's
+    /// constructor does.
+    pub fn responder(
+        mut self,
+        name: impl Into<String>,
+        bus: impl Into<String>,
+        kind: ResponderKind,
+    ) -> Self {
+        self.responders.push((name.into(), bus.into(), kind));
+        self
+    }
+
+    /// Parse a description (see the [module documentation](self)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::topology::{TopologyBuilder, TopologyError};
+    ///
+    /// let err = TopologyBuilder::parse("[[node]]\nname = \"ecu\"\nbus = body\n").unwrap_err();
+    /// assert_eq!(err.to_string(), "topology line 3: invalid value `body`");
+    ///
+    /// let unknown = TopologyBuilder::parse("[[node]]\nname = \"ecu\"\nbus = \"body\"\n").unwrap();
+    /// assert!(matches!(unknown.build(), Err(TopologyError::UnknownBus(bus)) if bus == "body"));
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, TopologyError> {
+        let mut builder = Self::new();
+        let mut table: Option<(TableKind, Table)> = None;
+        for (n, raw) in text.lines().enumerate() {
+            let line = n + 1;
+            let content = strip_comment(raw).trim();
+            if content.is_empty() {
+                continue;
+            }
+            let error = |reason: String| TopologyError::Parse { line, reason };
+            if content.starts_with('[') {
+                if let Some((kind, done)) = table.take() {
+                    builder = done.finish(kind, builder)?;
+                }
+                let kind = if let Some(name) = content
+                    .strip_prefix("[bus.")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                {
+                    TableKind::Bus(unquote(name.trim()).to_string())
+                } else {
+                    match content {
+                        "[[node]]" => TableKind::Node,
+                        "[[gateway]]" => TableKind::Gateway,
+                        "[[responder]]" => TableKind::Responder,
+                        _ => return Err(error(format!("unknown table `{content}`"))),
+                    }
+                };
+                table = Some((
+                    kind,
+                    Table {
+                        line,
+                        entries: Vec::new(),
+                    },
+                ));
+                continue;
+            }
+            let Some((key, value)) = content.split_once('=') else {
+                return Err(error(format!("expected `key = value`, found `{content}`")));
+            };
+            let Some((_, current)) = table.as_mut() else {
+                return Err(error("key outside of a table".to_string()));
+            };
+            let key = key.trim();
+            if current.entries.iter().any(|(k, _, _)| k == key) {
+                return Err(error(format!("key `{key}` given twice")));
+            }
+            let value = Value::parse(value.trim())
+                .ok_or_else(|| error(format!("invalid value `{}`", value.trim())))?;
+            current.entries.push((key.to_string(), value, line));
+        }
+        match table {
+            Some((kind, done)) => done.finish(kind, builder),
+            None => Ok(builder),
+        }
+    }
+
+    /// Create every bus, node, gateway and responder.
+    pub fn build(self) -> Result<Topology, TopologyError> {
+        let mut topology = Topology {
+            buses: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            gateways: BTreeMap::new(),
+            responders: BTreeMap::new(),
+        };
+        for (name, bitrate) in self.buses {
+            if topology.buses.contains_key(&name) {
+                return Err(TopologyError::DuplicateName(name));
+            }
+            let bus = BusHandle::new();
+            if let Some(bitrate) = bitrate {
+                bus.set_timing(Some(BusTiming::new(bitrate)));
+            }
+            topology.buses.insert(name, bus);
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut claim = |name: &str| {
+            if names.iter().any(|n| n == name) {
+                return Err(TopologyError::DuplicateName(name.to_string()));
+            }
+            names.push(name.to_string());
+            Ok(())
+        };
+        for (name, bus, options) in self.nodes {
+            claim(&name)?;
+            let node = topology.attach(&name, &bus, options.filters)?;
+            node.set_listen_only(options.listen_only);
+            node.set_self_reception(options.self_reception);
+            topology.nodes.insert(name, node);
+        }
+        for (name, a, b) in self.gateways {
+            claim(&name)?;
+            let a = topology.attach(&name, &a, Vec::new())?;
+            let b = topology.attach(&name, &b, Vec::new())?;
+            topology.gateways.insert(name, Gateway::new(a, b));
+        }
+        for (name, bus, kind) in self.responders {
+            claim(&name)?;
+            let interface = topology.attach(&name, &bus, Vec::new())?;
+            let responder = match kind {
+                ResponderKind::Obd { ecu } => {
+                    Responder::Obd(ObdResponder::with_ecu(interface, ecu))
+                }
+                ResponderKind::Uds { tx_id, rx_id } => {
+                    Responder::Uds(UdsServer::new(IsoTpEndpoint::new(interface, tx_id, rx_id)))
+                }
+                ResponderKind::Sdo { node_id } => {
+                    Responder::Sdo(SdoServer::new(interface, node_id))
+                }
+            };
+            let home = topology.buses[&bus].clone();
+            topology.responders.insert(name, (home, responder));
+        }
+        Ok(topology)
+    }
+}
+
+/// Live handles of a built topology, looked up by name.
+///
+/// Every interface is [named](InterfaceHandle::set_name) after its declaration, so traces and
+/// diagrams show the topology's names.
+pub struct Topology {
+    buses: BTreeMap<String, BusHandle>,
+    nodes: BTreeMap<String, InterfaceHandle>,
+    gateways: BTreeMap<String, Gateway>,
+    responders: BTreeMap<String, (BusHandle, Responder)>,
+}
+
+impl Topology {
+    /// The bus declared as `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no bus has that name.
+    pub fn bus(&self, name: &str) -> &BusHandle {
+        self.buses
+            .get(name)
+            .unwrap_or_else(|| panic!("no bus named `{name}` in topology"))
+    }
+
+    /// The interface of the node declared as `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no node has that name.
+    pub fn node(&self, name: &str) -> &InterfaceHandle {
+        self.nodes
+            .get(name)
+            .unwrap_or_else(|| panic!("no node named `{name}` in topology"))
+    }
+
+    /// The gateway declared as `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no gateway has that name.
+    pub fn gateway(&self, name: &str) -> &Gateway {
+        self.gateways
+            .get(name)
+            .unwrap_or_else(|| panic!("no gateway named `{name}` in topology"))
+    }
+
+    /// The responder declared as `name`, for configuring it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no responder has that name.
+    pub fn responder_mut(&mut self, name: &str) -> &mut Responder {
+        self.responders
+            .get_mut(name)
+            .map(|(_, responder)| responder)
+            .unwrap_or_else(|| panic!("no responder named `{name}` in topology"))
+    }
+
+    /// Every interface declared as `name`: a node's, a responder's, or both sides of a gateway.
+    /// Empty if nothing has that name.
+    pub fn interfaces(&self, name: &str) -> Vec<&InterfaceHandle> {
+        let mut interfaces: Vec<&InterfaceHandle> = self.nodes.get(name).into_iter().collect();
+        if let Some(gateway) = self.gateways.get(name) {
+            interfaces.extend([gateway.a(), gateway.b()]);
+        }
+        if let Some((_, responder)) = self.responders.get(name) {
+            interfaces.push(responder.interface());
+        }
+        interfaces
+    }
+
+    /// Names of the declared buses, in sorted order.
+    pub fn bus_names(&self) -> impl Iterator<Item = &str> {
+        self.buses.keys().map(String::as_str)
+    }
+
+    /// Names of the declared nodes, in sorted order.
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.keys().map(String::as_str)
+    }
+
+    /// Pump every gateway once, then let every responder answer what it has received.
+    ///
+    /// Returns the number of frames forwarded plus requests answered. Traffic caused by this
+    /// call, such as a response that still has to cross a gateway, is left for the next one.
+    pub fn pump(&mut self) -> Result<usize, TransmitError> {
+        let mut handled = 0;
+        for gateway in self.gateways.values() {
+            handled += gateway.pump()?;
+        }
+        for (bus, responder) in self.responders.values_mut() {
+            handled += responder.poll(bus.now())?;
+        }
+        Ok(handled)
+    }
+
+    /// Every declared bus, in name order.
+    pub(crate) fn buses(&self) -> impl Iterator<Item = &BusHandle> {
+        self.buses.values()
+    }
+
+    /// Attach a named interface for `owner` to the declared bus `bus`.
+    fn attach(
+        &self,
+        owner: &str,
+        bus: &str,
+        filters: Vec<IdMaskFilter>,
+    ) -> Result<InterfaceHandle, TopologyError> {
+        let handle = self
+            .buses
+            .get(bus)
+            .ok_or_else(|| TopologyError::UnknownBus(bus.to_string()))?;
+        let interface =
+            handle
+                .add_interface(filters)
+                .map_err(|error| TopologyError::Interface {
+                    name: owner.to_string(),
+                    error,
+                })?;
+        interface.set_name(owner);
+        Ok(interface)
+    }
+}
+
+enum TableKind {
+    Bus(String),
+    Node,
+    Gateway,
+    Responder,
+}
+
+/// A description table's header line and `(key, value, line)` entries.
+struct Table {
+    line: usize,
+    entries: Vec<(String, Value, usize)>,
+}
+
+impl Table {
+    /// Add the table's declaration to `builder`, rejecting unknown and missing keys.
+    fn finish(
+        mut self,
+        kind: TableKind,
+        builder: TopologyBuilder,
+    ) -> Result<TopologyBuilder, TopologyError> {
+        let builder = match kind {
+            TableKind::Bus(name) => match self.int::<u32>("bitrate")? {
+                Some(bitrate) => builder.timed_bus(name, bitrate),
+                None => builder.bus(name),
+            },
+            TableKind::Node => {
+                let (name, bus) = (self.required("name")?, self.required("bus")?);
+                let mut options = NodeOptions::default();
+                if let Some((filters, line)) = self.take("filters") {
+                    let Value::List(filters) = filters else {
+                        return Err(invalid(line, "`filters` must be a list"));
+                    };
+                    options.filters = filters
+                        .iter()
+                        .map(|pair| {
+                            uri::parse_filter(pair)
+                                .ok_or_else(|| invalid(line, &format!("invalid filter `{pair}`")))
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                if let Some(flag) = self.bool("listen_only")? {
+                    options.listen_only = flag;
+                }
+                if let Some(flag) = self.bool("self_reception")? {
+                    options.self_reception = flag;
+                }
+                builder.node_with(name, bus, options)
+            }
+            TableKind::Gateway => {
+                let name = self.required("name")?;
+                let (a, b) = (self.required("a")?, self.required("b")?);
+                builder.gateway(name, a, b)
+            }
+            TableKind::Responder => {
+                let (name, bus) = (self.required("name")?, self.required("bus")?);
+                let kind = match self.required("kind")?.as_str() {
+                    "obd" => {
+                        let ecu = self.int::<u8>("ecu")?.unwrap_or(0);
+                        if ecu > 7 {
+                            return Err(invalid(self.line, "`ecu` must be 0..=7"));
+                        }
+                        ResponderKind::Obd { ecu }
+                    }
+                    "uds" => ResponderKind::Uds {
+                        tx_id: self.id("tx")?,
+                        rx_id: self.id("rx")?,
+                    },
+                    "sdo" => {
+                        let node_id = self.int::<u8>("node_id")?.unwrap_or(0);
+                        if !(1..=127).contains(&node_id) {
+                            return Err(invalid(self.line, "`node_id` must be 1..=127"));
+                        }
+                        ResponderKind::Sdo { node_id }
+                    }
+                    other => {
+                        return Err(invalid(self.line, &format!("unknown responder `{other}`")));
+                    }
+                };
+                builder.responder(name, bus, kind)
+            }
+        };
+        match self.entries.first() {
+            Some((key, _, line)) => Err(invalid(*line, &format!("unknown key `{key}`"))),
+            None => Ok(builder),
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<(Value, usize)> {
+        let index = self.entries.iter().position(|(k, _, _)| k == key)?;
+        let (_, value, line) = self.entries.remove(index);
+        Some((value, line))
+    }
+
+    fn required(&mut self, key: &str) -> Result<String, TopologyError> {
+        match self.take(key) {
+            Some((Value::Str(text), _)) => Ok(text),
+            Some((_, line)) => Err(invalid(line, &format!("`{key}` must be a string"))),
+            None => Err(invalid(self.line, &format!("missing key `{key}`"))),
+        }
+    }
+
+    fn bool(&mut self, key: &str) -> Result<Option<bool>, TopologyError> {
+        match self.take(key) {
+            Some((Value::Bool(flag), _)) => Ok(Some(flag)),
+            Some((_, line)) => Err(invalid(line, &format!("`{key}` must be a boolean"))),
+            None => Ok(None),
+        }
+    }
+
+    fn int<T: TryFrom<u64>>(&mut self, key: &str) -> Result<Option<T>, TopologyError> {
+        match self.take(key) {
+            Some((Value::Int(value), line)) => T::try_from(value)
+                .map(Some)
+                .map_err(|_| invalid(line, &format!("`{key}` is out of range"))),
+            Some((_, line)) => Err(invalid(line, &format!("`{key}` must be an integer"))),
+            None => Ok(None),
+        }
+    }
+
+    /// A CAN ID: standard up to `0x7FF`, extended above.
+    fn id(&mut self, key: &str) -> Result<Id, TopologyError> {
+        let line = self.line;
+        let raw = self
+            .int::<u32>(key)?
+            .ok_or_else(|| invalid(line, &format!("missing key `{key}`")))?;
+        u16::try_from(raw)
+            .ok()
+            .and_then(StandardId::new)
+            .map(Id::Standard)
+            .or_else(|| ExtendedId::new(raw).map(Id::Extended))
+            .ok_or_else(|| invalid(line, &format!("`{key}` is not a CAN ID")))
+    }
+}
+
+fn invalid(line: usize, reason: &str) -> TopologyError {
+    TopologyError::Parse {
+        line,
+        reason: reason.to_string(),
+    }
+}
+
+enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn parse(text: &str) -> Option<Self> {
+        if let Some(items) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            return items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| quoted(item).map(str::to_string))
+                .collect::<Option<_>>()
+                .map(Value::List);
+        }
+        if let Some(text) = quoted(text) {
+            return Some(Value::Str(text.to_string()));
+        }
+        match text {
+            "true" => return Some(Value::Bool(true)),
+            "false" => return Some(Value::Bool(false)),
+            _ => {}
+        }
+        let digits = text.replace('_', "");
+        let value = match digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        };
+        value.ok().map(Value::Int)
+    }
+}
+
+/// The contents of a `"..."` or `'...'` string.
+fn quoted(text: &str) -> Option<&str> {
+    ['"', '\''].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+    })
+}
+
+fn unquote(text: &str) -> &str {
+    quoted(text).unwrap_or(text)
+}
+
+/// `line` up to a `#` outside of a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (at, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) => return &line[..at],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockFrame, isotp::IsoTpEndpoint};
+    use embedded_can::Frame as _;
+
+    #[test]
+    fn builds_ten_node_network_with_gateway_and_responders() {
+        let mut builder = TopologyBuilder::new()
+            .bus("body")
+            .timed_bus("chassis", 500_000)
+            .gateway("gw", "body", "chassis")
+            .responder("engine", "chassis", ResponderKind::Obd { ecu: 0 })
+            .responder(
+                "bms",
+                "chassis",
+                ResponderKind::Uds {
+                    tx_id: StandardId::new(0x7E9).unwrap().into(),
+                    rx_id: StandardId::new(0x7E1).unwrap().into(),
+                },
+            );
+        for n in 0..10 {
+            let bus = if n % 2 == 0 { "body" } else { "chassis" };
+            builder = builder.node(format!("node{n}"), bus);
+        }
+        let mut net = builder.build().unwrap();
+        assert_eq!(net.bus("body").interface_count(), 6);
+        assert_eq!(net.bus("chassis").interface_count(), 8);
+        assert_eq!(net.node("node3").name().as_deref(), Some("node3"));
+        assert_eq!(net.node_names().count(), 10);
+
+        let Responder::Obd(engine) = net.responder_mut("engine") else {
+            panic!("engine is an OBD responder");
+        };
+        engine.set_pid(0x0D, alloc::vec![88]);
+        let mut scan_tool = IsoTpEndpoint::new(
+            net.bus("chassis").add_interface(Vec::new()).unwrap(),
+            StandardId::new(0x7DF).unwrap(),
+            StandardId::new(0x7E8).unwrap(),
+        );
+        scan_tool.send(&[0x01, 0x0D]).unwrap();
+        net.bus("chassis")
+            .advance(core::time::Duration::from_millis(1));
+        // The request reaches the body bus through the gateway and the engine answers it.
+        assert_eq!(net.pump().unwrap(), 2);
+        assert!(net.node("node0").has_frames());
+        net.bus("chassis")
+            .advance(core::time::Duration::from_millis(1));
+        assert_eq!(
+            scan_tool.poll().unwrap(),
+            alloc::vec![alloc::vec![0x41, 0x0D, 88]]
+        );
+
+        let frame = MockFrame::new(StandardId::new(0x10).unwrap(), &[]).unwrap();
+        net.node("node0").transmit(frame.clone()).unwrap();
+        net.pump().unwrap();
+        net.bus("chassis")
+            .advance(core::time::Duration::from_millis(1));
+        assert!(net.node("node1").received_frames().contains(&frame));
+    }
+
+    #[test]
+    fn description_errors_name_the_line() {
+        let parse = |text: &str| TopologyBuilder::parse(text).err().map(|e| e.to_string());
+        assert_eq!(
+            parse("[bus.a]\n[[node]]\nname = \"n\"\n"),
+            Some("topology line 2: missing key `bus`".to_string())
+        );
+        assert_eq!(
+            parse("[[node]]\nname = \"n\" # the node\nbus = \"a\"\ncolour = \"red\"\n"),
+            Some("topology line 4: unknown key `colour`".to_string())
+        );
+        assert_eq!(
+            parse("[[node]]\nname = \"n\"\nbus = \"a\"\nfilters = [\"0x123/0xFFF\"]\n"),
+            Some("topology line 4: invalid filter `0x123/0xFFF`".to_string())
+        );
+        assert_eq!(
+            parse("[[responder]]\nname = \"r\"\nbus = \"a\"\nkind = \"sdo\"\n"),
+            Some("topology line 1: `node_id` must be 1..=127".to_string())
+        );
+        let duplicate = TopologyBuilder::new()
+            .bus("a")
+            .node("x", "a")
+            .gateway("x", "a", "a")
+            .build();
+        assert!(matches!(duplicate, Err(TopologyError::DuplicateName(name)) if name == "x"));
+    }
+}
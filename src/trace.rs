@@ -0,0 +1,165 @@
+//! Structured activity hooks.
+//!
+//! A [`Tracer`] installed with [`BusHandle::set_tracer`](crate::BusHandle::set_tracer) is called
+//! for every transmit, delivery, filter rejection, drop and queue overflow on the bus. Forward the
+//! events to `log` or plain `eprintln!` to see mock bus activity interleaved with application
+//! logs:
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::{BusHandle, MockFrame};
+//! use embedded_can_mock::trace::TraceEvent;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let bus = BusHandle::new();
+//! let log = Arc::new(Mutex::new(Vec::new()));
+//! let sink = log.clone();
+//! bus.set_tracer(Some(Arc::new(move |event: &TraceEvent<'_>| {
+//!     sink.lock().unwrap().push(format!("{event:?}"));
+//! })));
+//!
+//! let node = bus.add_interface(vec![]).unwrap();
+//! node.transmit(MockFrame::new(StandardId::new(0x1).unwrap(), &[]).unwrap()).unwrap();
+//! assert_eq!(log.lock().unwrap().len(), 2); // transmit + delivery
+//! ```
+//!
+//! Tracers run while the bus is locked, so they must not call back into the bus.
+//!
+//! With the `tracing` feature, the same events are also emitted as `tracing` events (target
+//! `embedded_can_mock::trace`), and each frame's deliveries, rejections, drops and overflows are
+//! grouped in a `deliver` span, so a `tracing` subscriber shows bus activity without a tracer.
+
+use alloc::sync::Arc;
+
+use embedded_can::Frame;
+
+use crate::{
+    bus::{InterfaceId, TxToken},
+    frame::MockFrame,
+};
+
+/// Bus activity reported to a [`Tracer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TraceEvent<'a, F = MockFrame> {
+    /// A frame was offered to the bus.
+    Transmit {
+        /// Transmission identifier.
+        token: TxToken,
+        /// The frame.
+        frame: &'a F,
+    },
+    /// A frame was queued on a receiver.
+    Deliver {
+        /// Transmission identifier.
+        token: TxToken,
+        /// Receiving interface.
+        receiver: InterfaceId,
+        /// The frame.
+        frame: &'a F,
+    },
+    /// A receiver’s acceptance filters rejected a frame.
+    FilterReject {
+        /// Transmission identifier.
+        token: TxToken,
+        /// Rejecting interface.
+        receiver: InterfaceId,
+    },
+    /// A frame accepted by a receiver was dropped by fault injection.
+    Drop {
+        /// Transmission identifier.
+        token: TxToken,
+        /// Interface that lost the frame.
+        receiver: InterfaceId,
+    },
+    /// A frame was lost because the receiver’s queue was full.
+    QueueOverflow {
+        /// Transmission identifier.
+        token: TxToken,
+        /// Interface whose queue overflowed.
+        receiver: InterfaceId,
+    },
+    /// An interface left the bus (see [`BusEvent::NodeLeft`](crate::BusEvent::NodeLeft)).
+    NodeLeft {
+        /// Interface that left.
+        node: InterfaceId,
+    },
+}
+
+/// Callback receiving [`TraceEvent`]s.
+pub type Tracer<F = MockFrame> = Arc<dyn Fn(&TraceEvent<'_, F>) + Send + Sync>;
+
+/// Pass `event` to `tracer`, if one is installed, and with the `tracing` feature to `tracing`.
+pub(crate) fn dispatch<F: Frame>(tracer: Option<&Tracer<F>>, event: TraceEvent<'_, F>) {
+    if let Some(tracer) = tracer {
+        tracer(&event);
+    }
+    #[cfg(feature = "tracing")]
+    emit(&event);
+}
+
+/// [`dispatch`] for [`TraceEvent::NodeLeft`], which needs no frame.
+pub(crate) fn dispatch_node_left<F>(tracer: Option<&Tracer<F>>, node: InterfaceId) {
+    if let Some(tracer) = tracer {
+        tracer(&TraceEvent::NodeLeft { node });
+    }
+    #[cfg(feature = "tracing")]
+    emit_node_left(node);
+}
+
+/// Emit `event` as a `tracing` event.
+#[cfg(feature = "tracing")]
+fn emit<F: Frame>(event: &TraceEvent<'_, F>) {
+    match *event {
+        TraceEvent::Transmit { token, frame } => tracing::debug!(
+            source = token.source().as_raw(),
+            seq = token.seq(),
+            id = %format_args!("{:X}", raw_id(frame.id())),
+            data = ?frame.data(),
+            "transmit"
+        ),
+        TraceEvent::Deliver {
+            token,
+            receiver,
+            frame,
+        } => tracing::trace!(
+            source = token.source().as_raw(),
+            seq = token.seq(),
+            receiver = receiver.as_raw(),
+            id = %format_args!("{:X}", raw_id(frame.id())),
+            "deliver"
+        ),
+        TraceEvent::FilterReject { token, receiver } => tracing::trace!(
+            source = token.source().as_raw(),
+            seq = token.seq(),
+            receiver = receiver.as_raw(),
+            "filter reject"
+        ),
+        TraceEvent::Drop { token, receiver } => tracing::debug!(
+            source = token.source().as_raw(),
+            seq = token.seq(),
+            receiver = receiver.as_raw(),
+            "drop"
+        ),
+        TraceEvent::QueueOverflow { token, receiver } => tracing::warn!(
+            source = token.source().as_raw(),
+            seq = token.seq(),
+            receiver = receiver.as_raw(),
+            "queue overflow"
+        ),
+        TraceEvent::NodeLeft { node } => emit_node_left(node),
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn emit_node_left(node: InterfaceId) {
+    tracing::debug!(node = node.as_raw(), "node left");
+}
+
+#[cfg(feature = "tracing")]
+fn raw_id(id: embedded_can::Id) -> u32 {
+    match id {
+        embedded_can::Id::Standard(id) => u32::from(id.as_raw()),
+        embedded_can::Id::Extended(id) => id.as_raw(),
+    }
+}
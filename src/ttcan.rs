@@ -0,0 +1,273 @@
+//! Time-triggered CAN (TTCAN, ISO 11898-4) schedules.
+//!
+//! A [`TtSchedule`] divides virtual time into basic cycles of equal length. Each basic cycle is a
+//! list of [`TimeWindow`]s: exclusive windows reserved for one interface, and arbitration windows
+//! in which any node may compete for the bus. Several basic cycles form the system matrix and
+//! repeat in turn. Installed with [`BusHandle::set_schedule`](crate::BusHandle::set_schedule),
+//! the schedule checks every transmit against the virtual clock: a frame requested outside a
+//! window its sender may use is either rejected or delayed to the start of the next such window,
+//! depending on [`OutOfWindow`]. The bus also logs a [`WindowEvent`] whenever the clock reaches
+//! the start of a window.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_mock::ttcan::{OutOfWindow, TimeWindow, TtSchedule, WindowKind};
+//! use embedded_can_mock::{BusHandle, MockFrame, TransmitError};
+//!
+//! let ms = Duration::from_millis;
+//! let bus = BusHandle::new();
+//! let master = bus.add_interface(vec![]).unwrap();
+//! let node = bus.add_interface(vec![]).unwrap();
+//! bus.set_schedule(Some(
+//!     TtSchedule::new(ms(10), OutOfWindow::Reject).basic_cycle(vec![
+//!         TimeWindow::new(ms(0), ms(2), WindowKind::Exclusive(master.id())),
+//!         TimeWindow::new(ms(5), ms(5), WindowKind::Arbitration),
+//!     ]),
+//! ));
+//!
+//! let frame = MockFrame::new(StandardId::new(0x10).unwrap(), &[]).unwrap();
+//! master.transmit(frame.clone()).unwrap();
+//! assert!(matches!(node.transmit(frame.clone()), Err(TransmitError::OutsideWindow)));
+//! bus.advance(ms(6));
+//! node.transmit(frame).unwrap();
+//! assert_eq!(bus.take_window_events().len(), 2);
+//! ```
+//!
+//! The reference message that synchronises real TTCAN nodes is not modelled: every node shares
+//! the bus's virtual clock.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::bus::InterfaceId;
+
+/// Who may transmit in a [`TimeWindow`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowKind {
+    /// Reserved for one interface.
+    Exclusive(InterfaceId),
+    /// Open to every interface, which arbitrate as usual.
+    Arbitration,
+}
+
+/// A window within a basic cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// Start, relative to the beginning of the basic cycle.
+    pub offset: Duration,
+    /// Length of the window.
+    pub length: Duration,
+    /// Who may transmit in it.
+    pub kind: WindowKind,
+}
+
+impl TimeWindow {
+    /// A window starting `offset` into its basic cycle and lasting `length`.
+    pub fn new(offset: Duration, length: Duration, kind: WindowKind) -> Self {
+        Self {
+            offset,
+            length,
+            kind,
+        }
+    }
+
+    fn admits(&self, source: InterfaceId) -> bool {
+        match self.kind {
+            WindowKind::Exclusive(owner) => owner == source,
+            WindowKind::Arbitration => true,
+        }
+    }
+}
+
+/// What happens to a frame requested outside every window its sender may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutOfWindow {
+    /// Fail the transmit with [`TransmitError::OutsideWindow`](crate::TransmitError::OutsideWindow).
+    Reject,
+    /// Accept the transmit and request the bus at the start of the sender's next window.
+    Delay,
+}
+
+/// The start of a window, as returned by
+/// [`BusHandle::take_window_events`](crate::BusHandle::take_window_events).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowEvent {
+    /// Virtual time at which the window starts.
+    pub at: Duration,
+    /// Number of basic cycles completed before this one, counted from time zero.
+    pub cycle: u64,
+    /// Index of the window within its basic cycle.
+    pub window: usize,
+    /// Who may transmit in the window.
+    pub kind: WindowKind,
+}
+
+/// A TTCAN system matrix: basic cycles of equal length, repeated in turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtSchedule {
+    cycle: Duration,
+    cycles: Vec<Vec<TimeWindow>>,
+    out_of_window: OutOfWindow,
+}
+
+impl TtSchedule {
+    /// An empty schedule with basic cycles of length `cycle`; add cycles with
+    /// [`basic_cycle`](Self::basic_cycle).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cycle` is zero.
+    pub fn new(cycle: Duration, out_of_window: OutOfWindow) -> Self {
+        assert!(!cycle.is_zero(), "a basic cycle must have a length");
+        Self {
+            cycle,
+            cycles: Vec::new(),
+            out_of_window,
+        }
+    }
+
+    /// Append a basic cycle; windows should be ordered by offset and fit within the cycle.
+    pub fn basic_cycle(mut self, windows: Vec<TimeWindow>) -> Self {
+        self.cycles.push(windows);
+        self
+    }
+
+    /// Length of one basic cycle.
+    pub fn cycle_length(&self) -> Duration {
+        self.cycle
+    }
+
+    /// Handling of out-of-window transmits.
+    pub fn out_of_window(&self) -> OutOfWindow {
+        self.out_of_window
+    }
+
+    /// Number of the basic cycle running at `at`, and the time elapsed within it.
+    fn position(&self, at: Duration) -> (u64, Duration) {
+        let cycle = self.cycle.as_nanos();
+        let now = at.as_nanos();
+        (
+            (now / cycle) as u64,
+            Duration::from_nanos((now % cycle) as u64),
+        )
+    }
+
+    fn windows(&self, cycle: u64) -> &[TimeWindow] {
+        if self.cycles.is_empty() {
+            return &[];
+        }
+        &self.cycles[(cycle % self.cycles.len() as u64) as usize]
+    }
+
+    fn cycle_start(&self, cycle: u64) -> Duration {
+        Duration::from_nanos((self.cycle.as_nanos() * u128::from(cycle)) as u64)
+    }
+
+    /// When `source` may transmit a frame requested at `at`: `at` itself if a window admitting
+    /// it is open, otherwise the start of its next window, or `None` if it has none.
+    pub(crate) fn next_slot(&self, source: InterfaceId, at: Duration) -> Option<Duration> {
+        let (cycle, offset) = self.position(at);
+        let open = self.windows(cycle).iter().any(|window| {
+            window.admits(source)
+                && (window.offset..window.offset + window.length).contains(&offset)
+        });
+        if open {
+            return Some(at);
+        }
+        // Every window pattern repeats once per system matrix.
+        (cycle..=cycle + self.cycles.len() as u64)
+            .flat_map(|cycle| {
+                let start = self.cycle_start(cycle);
+                self.windows(cycle)
+                    .iter()
+                    .filter(|window| window.admits(source))
+                    .map(move |window| start + window.offset)
+            })
+            .find(|start| *start > at)
+    }
+
+    /// Window starts in `from..=to`, in time order.
+    pub(crate) fn events(&self, from: Duration, to: Duration) -> Vec<WindowEvent> {
+        let mut events = Vec::new();
+        if from > to {
+            return events;
+        }
+        let (first, _) = self.position(from);
+        let (last, _) = self.position(to);
+        for cycle in first..=last {
+            let start = self.cycle_start(cycle);
+            for (index, window) in self.windows(cycle).iter().enumerate() {
+                let at = start + window.offset;
+                if (from..=to).contains(&at) {
+                    events.push(WindowEvent {
+                        at,
+                        cycle,
+                        window: index,
+                        kind: window.kind,
+                    });
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusHandle, MockFrame};
+    use embedded_can::{Frame as _, StandardId};
+
+    #[test]
+    fn delayed_frames_wait_for_their_window_across_the_matrix() {
+        let ms = Duration::from_millis;
+        let bus = BusHandle::new();
+        let a = bus.add_interface(alloc::vec![]).unwrap();
+        let b = bus.add_interface(alloc::vec![]).unwrap();
+        let rx = bus.tap();
+        bus.advance(ms(3));
+        // Cycle 0 is a's; cycle 1 gives b an exclusive window and opens arbitration.
+        bus.set_schedule(Some(
+            TtSchedule::new(ms(10), OutOfWindow::Delay)
+                .basic_cycle(alloc::vec![TimeWindow::new(
+                    ms(0),
+                    ms(4),
+                    WindowKind::Exclusive(a.id()),
+                )])
+                .basic_cycle(alloc::vec![
+                    TimeWindow::new(ms(2), ms(2), WindowKind::Exclusive(b.id())),
+                    TimeWindow::new(ms(6), ms(2), WindowKind::Arbitration),
+                ]),
+        ));
+        let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+
+        a.transmit(frame(0x1)).unwrap();
+        b.transmit(frame(0x2)).unwrap();
+        assert_eq!(rx.pop_delivery().unwrap().timestamp, ms(3));
+        assert!(!rx.has_frames());
+        bus.advance(ms(10));
+        let delivery = rx.pop_delivery().unwrap();
+        assert_eq!((delivery.frame, delivery.timestamp), (frame(0x2), ms(12)));
+        // a's next slot is the arbitration window at 16 ms.
+        a.transmit(frame(0x3)).unwrap();
+        bus.advance(ms(5));
+        assert_eq!(rx.pop_delivery().unwrap().timestamp, ms(16));
+
+        let events = bus.take_window_events();
+        let starts: Vec<_> = events.iter().map(|e| (e.at, e.cycle, e.window)).collect();
+        assert_eq!(starts, [(ms(12), 1, 0), (ms(16), 1, 1)]);
+        // Without any window a node can never transmit, even in delay mode.
+        let c = bus.add_interface(alloc::vec![]).unwrap();
+        bus.set_schedule(Some(
+            TtSchedule::new(ms(10), OutOfWindow::Delay).basic_cycle(alloc::vec![]),
+        ));
+        assert!(matches!(
+            c.transmit(frame(0x4)),
+            Err(crate::TransmitError::OutsideWindow)
+        ));
+        bus.set_schedule(None);
+        c.transmit(frame(0x4)).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+//! Typed publish/subscribe over an interface.
+//!
+//! Application-level tests usually care about messages, not frames. Register how each message
+//! type maps onto a CAN ID in a [`Codecs`] table (any [`CanMessage`] with
+//! [`register`](Codecs::register), or a pair of closures with
+//! [`register_with`](Codecs::register_with)), wrap an interface in a [`TypedInterface`], and
+//! then [`publish`](TypedInterface::publish) values and [`subscribe`](TypedInterface::subscribe)
+//! to them. A frame that carries a registered ID but does not decode is reported to the
+//! subscriber as a [`DecodeError`] instead of being dropped, so tests see malformed traffic.
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::typed::{Codecs, TypedInterface};
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Heartbeat(u8);
+//!
+//! let mut codecs = Codecs::new();
+//! codecs.register_with(
+//!     StandardId::new(0x700).unwrap(),
+//!     |beat: &Heartbeat| vec![beat.0],
+//!     |data| match data {
+//!         [state] => Ok(Heartbeat(*state)),
+//!         _ => Err("heartbeat is one byte".into()),
+//!     },
+//! );
+//!
+//! let bus = BusHandle::new();
+//! let node = TypedInterface::new(bus.add_interface(vec![]).unwrap(), codecs.clone());
+//! let monitor = TypedInterface::new(bus.add_interface(vec![]).unwrap(), codecs);
+//! let beats = monitor.subscribe::<Heartbeat>().unwrap();
+//!
+//! node.publish(&Heartbeat(5)).unwrap();
+//! assert_eq!(beats.try_recv().unwrap().unwrap(), Heartbeat(5));
+//! assert!(beats.try_recv().is_none());
+//! ```
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::{
+    any::{Any, TypeId, type_name},
+    fmt,
+    marker::PhantomData,
+    time::Duration,
+};
+
+use embedded_can::{Frame as _, Id};
+
+use crate::{
+    bus::{InterfaceHandle, TransmitError, TxToken},
+    frame::MockFrame,
+    message::CanMessage,
+};
+
+type Encode = Box<dyn Fn(&dyn Any) -> Vec<u8> + Send + Sync>;
+type Decode = Box<dyn Fn(&[u8]) -> Result<Box<dyn Any + Send>, String> + Send + Sync>;
+
+struct Codec {
+    id: Id,
+    type_id: TypeId,
+    type_name: &'static str,
+    fd: bool,
+    encode: Encode,
+    decode: Decode,
+}
+
+/// Encoders and decoders for message types, one per CAN ID.
+///
+/// Cloning is cheap: the table is shared, so every [`TypedInterface`] of a test can use the
+/// same registrations.
+#[derive(Clone, Default)]
+pub struct Codecs {
+    codecs: Vec<Arc<Codec>>,
+}
+
+impl Codecs {
+    /// An empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a [`CanMessage`] type under its own ID.
+    pub fn register<M: CanMessage + Send + 'static>(&mut self) -> &mut Self {
+        self.insert(Codec {
+            id: M::id(),
+            type_id: TypeId::of::<M>(),
+            type_name: type_name::<M>(),
+            fd: M::DLC > 8,
+            encode: Box::new(|msg| {
+                let mut data = alloc::vec![0; M::DLC];
+                downcast::<M>(msg).encode_payload(&mut data);
+                data
+            }),
+            decode: Box::new(|data| {
+                if data.len() < M::DLC {
+                    return Err(alloc::format!(
+                        "payload is {} bytes, expected {}",
+                        data.len(),
+                        M::DLC
+                    ));
+                }
+                Ok(Box::new(M::decode_payload(data)))
+            }),
+        })
+    }
+
+    /// Register `M` under `id` with an encoder to payload bytes and a decoder that explains
+    /// why it rejects a payload.
+    pub fn register_with<M: Send + 'static>(
+        &mut self,
+        id: impl Into<Id>,
+        encode: impl Fn(&M) -> Vec<u8> + Send + Sync + 'static,
+        decode: impl Fn(&[u8]) -> Result<M, String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.insert(Codec {
+            id: id.into(),
+            type_id: TypeId::of::<M>(),
+            type_name: type_name::<M>(),
+            fd: false,
+            encode: Box::new(move |msg| encode(downcast::<M>(msg))),
+            decode: Box::new(move |data| {
+                decode(data).map(|msg| Box::new(msg) as Box<dyn Any + Send>)
+            }),
+        })
+    }
+
+    /// The ID `M` is registered under, if any.
+    pub fn id_of<M: 'static>(&self) -> Option<Id> {
+        self.lookup::<M>().ok().map(|codec| codec.id)
+    }
+
+    /// Replaces any registration for the same type or the same ID.
+    fn insert(&mut self, codec: Codec) -> &mut Self {
+        self.codecs
+            .retain(|old| old.type_id != codec.type_id && old.id != codec.id);
+        self.codecs.push(Arc::new(codec));
+        self
+    }
+
+    fn lookup<M: 'static>(&self) -> Result<&Arc<Codec>, TypedError> {
+        self.codecs
+            .iter()
+            .find(|codec| codec.type_id == TypeId::of::<M>())
+            .ok_or(TypedError::Unregistered(type_name::<M>()))
+    }
+}
+
+impl fmt::Debug for Codecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.codecs.iter().map(|codec| (codec.id, codec.type_name)))
+            .finish()
+    }
+}
+
+fn downcast<M: 'static>(msg: &dyn Any) -> &M {
+    msg.downcast_ref()
+        .expect("codecs are looked up by the message's type")
+}
+
+/// A frame with a registered ID whose payload the decoder rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    /// The frame that failed to decode.
+    pub frame: MockFrame,
+    /// The message type registered for the frame's ID.
+    pub message: &'static str,
+    /// The decoder's explanation.
+    pub reason: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot decode {} from {}: {}",
+            self.message, self.frame, self.reason
+        )
+    }
+}
+
+/// Errors returned by [`TypedInterface`].
+#[derive(Debug)]
+pub enum TypedError {
+    /// No codec is registered for the named message type.
+    Unregistered(&'static str),
+    /// The interface could not transmit the encoded frame.
+    Transmit(TransmitError),
+}
+
+impl fmt::Display for TypedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unregistered(message) => write!(f, "no codec registered for {message}"),
+            Self::Transmit(err) => write!(f, "transmit failed: {err:?}"),
+        }
+    }
+}
+
+/// An interface that sends and receives registered message types.
+pub struct TypedInterface {
+    iface: InterfaceHandle,
+    codecs: Codecs,
+}
+
+impl TypedInterface {
+    /// Wrap `iface`, encoding and decoding with `codecs`.
+    pub fn new(iface: InterfaceHandle, codecs: Codecs) -> Self {
+        Self { iface, codecs }
+    }
+
+    /// The wrapped interface, for frame-level access.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.iface
+    }
+
+    /// The codec table.
+    pub fn codecs(&self) -> &Codecs {
+        &self.codecs
+    }
+
+    /// Encode `msg` and transmit it.
+    pub fn publish<M: 'static>(&self, msg: &M) -> Result<TxToken, TypedError> {
+        let codec = self.codecs.lookup::<M>()?;
+        let mut frame =
+            MockFrame::new(codec.id, &(codec.encode)(msg)).expect("mock frames take any payload");
+        frame.meta_mut().fd = codec.fd;
+        self.iface.transmit(frame).map_err(TypedError::Transmit)
+    }
+
+    /// Receive every `M` this interface receives from now on.
+    ///
+    /// The subscription has its own queue (see [`InterfaceHandle::subscribe`]), so several
+    /// subscriptions and frame-level reads of the interface do not take messages from each
+    /// other.
+    pub fn subscribe<M: 'static>(&self) -> Result<Subscription<M>, TypedError> {
+        Ok(Subscription {
+            codec: self.codecs.lookup::<M>()?.clone(),
+            rx: self.iface.subscribe(),
+            _message: PhantomData,
+        })
+    }
+}
+
+/// Messages of type `M` received by a [`TypedInterface`].
+pub struct Subscription<M> {
+    codec: Arc<Codec>,
+    rx: InterfaceHandle,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M: 'static> Subscription<M> {
+    /// Take the oldest received `M`, skipping frames with other IDs, or `None` if there is none.
+    pub fn try_recv(&self) -> Option<Result<M, DecodeError>> {
+        while let Some(frame) = self.rx.pop_frame() {
+            if frame.id() == self.codec.id && !frame.is_remote_frame() {
+                return Some(self.decode(frame));
+            }
+        }
+        None
+    }
+
+    /// Wait up to `timeout` (forever for `None`) for the next `M`.
+    ///
+    /// Without the `std` feature, waiting behaves as for [`InterfaceHandle::wait_for_frame`].
+    pub fn recv(&self, timeout: Option<Duration>) -> Option<Result<M, DecodeError>> {
+        loop {
+            if let Some(msg) = self.try_recv() {
+                return Some(msg);
+            }
+            if !self.rx.wait_for_frame(timeout) {
+                return None;
+            }
+        }
+    }
+
+    /// Take every `M` received so far, in arrival order.
+    pub fn drain(&self) -> Vec<Result<M, DecodeError>> {
+        core::iter::from_fn(|| self.try_recv()).collect()
+    }
+
+    /// The subscriber interface the messages are read from.
+    pub fn interface(&self) -> &InterfaceHandle {
+        &self.rx
+    }
+
+    fn decode(&self, frame: MockFrame) -> Result<M, DecodeError> {
+        match (self.codec.decode)(frame.data()) {
+            Ok(msg) => Ok(*msg.downcast().expect("decoders return their own type")),
+            Err(reason) => Err(DecodeError {
+                frame,
+                message: self.codec.type_name,
+                reason,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::{ExtendedId, StandardId};
+
+    use super::*;
+    use crate::{BusHandle, message::Layout, message::read_signal, message::write_signal};
+
+    #[derive(Debug, PartialEq)]
+    struct Speed(u16);
+
+    impl CanMessage for Speed {
+        const ID: u32 = 0x18FE_F100;
+        const EXTENDED: bool = true;
+        const DLC: usize = 2;
+
+        fn encode_payload(&self, data: &mut [u8]) {
+            write_signal(data, &LAYOUT, &self.0);
+        }
+
+        fn decode_payload(data: &[u8]) -> Self {
+            Self(read_signal(data, &LAYOUT))
+        }
+    }
+
+    const LAYOUT: Layout = Layout {
+        start: 0,
+        len: 16,
+        signed: false,
+        scale: 1.0,
+        offset: 0.0,
+    };
+
+    #[test]
+    fn subscriptions_decode_their_type_and_surface_bad_payloads() {
+        let mut codecs = Codecs::new();
+        codecs.register::<Speed>();
+        assert_eq!(
+            codecs.id_of::<Speed>(),
+            Some(ExtendedId::new(0x18FE_F100).unwrap().into())
+        );
+
+        let bus = BusHandle::new();
+        let ecu = TypedInterface::new(bus.add_interface(vec![]).unwrap(), codecs.clone());
+        let tester = TypedInterface::new(bus.add_interface(vec![]).unwrap(), codecs);
+        let speeds = tester.subscribe::<Speed>().unwrap();
+        assert!(matches!(
+            tester.subscribe::<u8>(),
+            Err(TypedError::Unregistered("u8"))
+        ));
+
+        ecu.publish(&Speed(1234)).unwrap();
+        // Unrelated traffic is skipped; a short payload with the right ID is reported.
+        let other = MockFrame::new(StandardId::new(0x10).unwrap(), &[1]).unwrap();
+        ecu.interface().transmit(other).unwrap();
+        let short = MockFrame::new(ExtendedId::new(0x18FE_F100).unwrap(), &[1]).unwrap();
+        ecu.interface().transmit(short.clone()).unwrap();
+        ecu.publish(&Speed(7)).unwrap();
+
+        let got = speeds.drain();
+        assert_eq!(got.len(), 3);
+        assert_eq!(got[0], Ok(Speed(1234)));
+        let err = got[1].as_ref().unwrap_err();
+        assert_eq!(
+            (&err.frame, err.reason.as_str()),
+            (&short, "payload is 1 bytes, expected 2")
+        );
+        assert_eq!(got[2], Ok(Speed(7)));
+        // The interface's own queue is untouched by the subscription.
+        assert_eq!(tester.interface().received_frames().len(), 4);
+        assert!(speeds.recv(Some(Duration::ZERO)).is_none());
+    }
+}
@@ -0,0 +1,379 @@
+//! Scriptable UDS (ISO 14229) diagnostic server.
+//!
+//! A [`UdsServer`] sits on an [`IsoTpEndpoint`] and answers diagnostic requests the way a
+//! minimal ECU would: fixed positive or negative responses per service, data identifiers for
+//! `ReadDataByIdentifier`/`WriteDataByIdentifier`, seed/key `SecurityAccess`, and "response
+//! pending" (NRC `0x78`) delays measured on the virtual clock. Diagnostic tester tools can be
+//! tested against it end-to-end:
+//!
+//! ```
+//! use core::time::Duration;
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::isotp::IsoTpEndpoint;
+//! use embedded_can_mock::uds::{UdsResponse, UdsServer};
+//!
+//! let bus = BusHandle::new();
+//! let (request, response) = (StandardId::new(0x7E0).unwrap(), StandardId::new(0x7E8).unwrap());
+//! let mut ecu = UdsServer::new(IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), response, request));
+//! let mut tester = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), request, response);
+//! ecu.on_did(0xF190, UdsResponse::Positive(b"WVW0000000".to_vec()));
+//! ecu.on_service(0x11, UdsResponse::Negative(0x22));
+//!
+//! tester.send(&[0x11, 0x01]).unwrap();
+//! ecu.poll(bus.now()).unwrap();
+//! assert_eq!(tester.poll().unwrap(), vec![vec![0x7F, 0x11, 0x22]]);
+//!
+//! tester.send(&[0x22, 0xF1, 0x90]).unwrap();
+//! ecu.poll(bus.now()).unwrap(); // 13-byte response: first frame only
+//! tester.poll().unwrap(); // flow control
+//! ecu.poll(bus.now()).unwrap(); // consecutive frame
+//! let mut expected = vec![0x62, 0xF1, 0x90];
+//! expected.extend_from_slice(b"WVW0000000");
+//! assert_eq!(tester.poll().unwrap(), vec![expected]);
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{bus::TransmitError, isotp::IsoTpEndpoint};
+
+/// Negative response code: service not supported.
+pub const NRC_SERVICE_NOT_SUPPORTED: u8 = 0x11;
+/// Negative response code: sub-function not supported.
+pub const NRC_SUBFUNCTION_NOT_SUPPORTED: u8 = 0x12;
+/// Negative response code: incorrect message length or invalid format.
+pub const NRC_INCORRECT_LENGTH: u8 = 0x13;
+/// Negative response code: request sequence error.
+pub const NRC_REQUEST_SEQUENCE_ERROR: u8 = 0x24;
+/// Negative response code: request out of range.
+pub const NRC_REQUEST_OUT_OF_RANGE: u8 = 0x31;
+/// Negative response code: security access denied.
+pub const NRC_SECURITY_ACCESS_DENIED: u8 = 0x33;
+/// Negative response code: invalid key.
+pub const NRC_INVALID_KEY: u8 = 0x35;
+/// Negative response code: request correctly received, response pending.
+pub const NRC_RESPONSE_PENDING: u8 = 0x78;
+
+/// Scripted answer to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UdsResponse {
+    /// Positive response; the payload follows the response service ID (or the DID, for data
+    /// identifiers).
+    Positive(Vec<u8>),
+    /// Negative response with this response code.
+    Negative(u8),
+}
+
+/// Key function mapping a seed to the key the server expects.
+pub type KeyFn = Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
+struct SecurityLevel {
+    seed: Vec<u8>,
+    key: KeyFn,
+    /// Whether data identifier writes require this level.
+    protected: bool,
+}
+
+/// Minimal scriptable UDS server.
+///
+/// Without scripting, `DiagnosticSessionControl` (`0x10`) and `TesterPresent` (`0x3E`) are
+/// answered positively, `ReadDataByIdentifier` (`0x22`) and `WriteDataByIdentifier` (`0x2E`)
+/// serve the configured data identifiers, `SecurityAccess` (`0x27`) follows the configured
+/// levels, and everything else gets [`NRC_SERVICE_NOT_SUPPORTED`].
+pub struct UdsServer {
+    endpoint: IsoTpEndpoint,
+    services: BTreeMap<u8, UdsResponse>,
+    dids: BTreeMap<u16, UdsResponse>,
+    security: BTreeMap<u8, SecurityLevel>,
+    pending: BTreeMap<u8, Duration>,
+    deferred: Vec<(Duration, Vec<u8>)>,
+    seed_sent: Option<u8>,
+    unlocked: Option<u8>,
+}
+
+impl UdsServer {
+    /// Create a server answering requests received on `endpoint`.
+    pub fn new(endpoint: IsoTpEndpoint) -> Self {
+        Self {
+            endpoint,
+            services: BTreeMap::new(),
+            dids: BTreeMap::new(),
+            security: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            deferred: Vec::new(),
+            seed_sent: None,
+            unlocked: None,
+        }
+    }
+
+    /// The ISO-TP endpoint the server answers on.
+    pub fn endpoint(&self) -> &IsoTpEndpoint {
+        &self.endpoint
+    }
+
+    /// Answer every request for service `sid` with `response`, overriding built-in handling.
+    pub fn on_service(&mut self, sid: u8, response: UdsResponse) {
+        self.services.insert(sid, response);
+    }
+
+    /// Serve data identifier `did`: a positive response carries its value, a negative one is
+    /// returned for both reads and writes.
+    pub fn on_did(&mut self, did: u16, response: UdsResponse) {
+        self.dids.insert(did, response);
+    }
+
+    /// Current value of data identifier `did`, including values written by the tester.
+    pub fn did(&self, did: u16) -> Option<&[u8]> {
+        match self.dids.get(&did) {
+            Some(UdsResponse::Positive(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Configure `SecurityAccess` level `level` (an odd request-seed sub-function): the server
+    /// sends `seed` and expects `key(seed)` in the matching send-key request. When `protected`
+    /// is set, data identifier writes are denied until this or another protected level is
+    /// unlocked.
+    pub fn set_security_level(
+        &mut self,
+        level: u8,
+        seed: Vec<u8>,
+        key: impl Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static,
+        protected: bool,
+    ) {
+        self.security.insert(
+            level,
+            SecurityLevel {
+                seed,
+                key: Box::new(key),
+                protected,
+            },
+        );
+    }
+
+    /// The security level currently unlocked, if any.
+    pub fn unlocked_level(&self) -> Option<u8> {
+        self.unlocked
+    }
+
+    /// Answer requests for service `sid` with "response pending" (NRC `0x78`) first and the
+    /// final response once `delay` of virtual time has passed.
+    pub fn set_response_pending(&mut self, sid: u8, delay: Duration) {
+        self.pending.insert(sid, delay);
+    }
+
+    /// Handle the requests received so far and send delayed responses due by `now`.
+    ///
+    /// Returns the number of requests handled.
+    pub fn poll(&mut self, now: Duration) -> Result<usize, TransmitError> {
+        let requests = self.endpoint.poll()?;
+        for request in &requests {
+            let Some(response) = self.handle(request) else {
+                continue;
+            };
+            match self.pending.get(&request[0]) {
+                Some(delay) => {
+                    self.endpoint
+                        .send(&[0x7F, request[0], NRC_RESPONSE_PENDING])?;
+                    self.deferred.push((now + *delay, response));
+                }
+                None => self.endpoint.send(&response)?,
+            }
+        }
+        while let Some(index) = self.deferred.iter().position(|(due, _)| *due <= now) {
+            let (_, response) = self.deferred.remove(index);
+            self.endpoint.send(&response)?;
+        }
+        Ok(requests.len())
+    }
+
+    /// Build the response to `request`, or `None` if no response is to be sent.
+    fn handle(&mut self, request: &[u8]) -> Option<Vec<u8>> {
+        let sid = request[0];
+        let negative = |nrc| Some(vec![0x7F, sid, nrc]);
+        let positive = |payload: &[u8]| {
+            let mut response = vec![sid.wrapping_add(0x40)];
+            response.extend_from_slice(payload);
+            Some(response)
+        };
+        if let Some(scripted) = self.services.get(&sid) {
+            return match scripted {
+                UdsResponse::Positive(payload) => positive(payload),
+                UdsResponse::Negative(nrc) => negative(*nrc),
+            };
+        }
+        match (sid, &request[1..]) {
+            (0x10, [session]) => positive(&[*session, 0x00, 0x32, 0x01, 0xF4]),
+            (0x3E, [sub]) if sub & 0x80 != 0 => None,
+            (0x3E, [sub]) => positive(&[*sub]),
+            (0x22, dids) if !dids.is_empty() && dids.len() % 2 == 0 => {
+                let mut payload = Vec::new();
+                for did in dids.chunks(2) {
+                    match self.dids.get(&u16::from_be_bytes([did[0], did[1]])) {
+                        Some(UdsResponse::Positive(data)) => {
+                            payload.extend_from_slice(did);
+                            payload.extend_from_slice(data);
+                        }
+                        Some(UdsResponse::Negative(nrc)) => return negative(*nrc),
+                        None => return negative(NRC_REQUEST_OUT_OF_RANGE),
+                    }
+                }
+                positive(&payload)
+            }
+            (0x2E, [hi, lo, data @ ..]) if !data.is_empty() => {
+                let protected = |level: &u8| self.security.get(level).is_some_and(|s| s.protected);
+                let locked = self.security.values().any(|s| s.protected)
+                    && !self.unlocked.as_ref().is_some_and(protected);
+                match self.dids.get_mut(&u16::from_be_bytes([*hi, *lo])) {
+                    Some(UdsResponse::Negative(nrc)) => negative(*nrc),
+                    None => negative(NRC_REQUEST_OUT_OF_RANGE),
+                    Some(_) if locked => negative(NRC_SECURITY_ACCESS_DENIED),
+                    Some(UdsResponse::Positive(value)) => {
+                        *value = data.to_vec();
+                        positive(&[*hi, *lo])
+                    }
+                }
+            }
+            (0x27, [0x00, ..]) => negative(NRC_SUBFUNCTION_NOT_SUPPORTED),
+            (0x27, [level]) if level % 2 == 1 => {
+                let Some(security) = self.security.get(level) else {
+                    return negative(NRC_SUBFUNCTION_NOT_SUPPORTED);
+                };
+                let mut payload = vec![*level];
+                if self.unlocked == Some(*level) {
+                    // Already unlocked: ISO 14229 answers with an all-zero seed.
+                    payload.resize(1 + security.seed.len(), 0);
+                } else {
+                    payload.extend_from_slice(&security.seed);
+                    self.seed_sent = Some(*level);
+                }
+                positive(&payload)
+            }
+            (0x27, [level, key @ ..]) if level % 2 == 0 && !key.is_empty() => {
+                let seed_level = level - 1;
+                let Some(security) = self.security.get(&seed_level) else {
+                    return negative(NRC_SUBFUNCTION_NOT_SUPPORTED);
+                };
+                if self.seed_sent.take() != Some(seed_level) {
+                    return negative(NRC_REQUEST_SEQUENCE_ERROR);
+                }
+                if (security.key)(&security.seed) != key {
+                    return negative(NRC_INVALID_KEY);
+                }
+                self.unlocked = Some(seed_level);
+                positive(&[*level])
+            }
+            (0x10 | 0x3E | 0x22 | 0x2E | 0x27, _) => negative(NRC_INCORRECT_LENGTH),
+            _ => negative(NRC_SERVICE_NOT_SUPPORTED),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BusHandle;
+    use embedded_can::StandardId;
+
+    fn exchange(ecu: &mut UdsServer, tester: &mut IsoTpEndpoint, request: &[u8]) -> Vec<Vec<u8>> {
+        tester.send(request).unwrap();
+        ecu.poll(Duration::ZERO).unwrap();
+        tester.poll().unwrap()
+    }
+
+    #[test]
+    fn security_access_and_response_pending_follow_the_script() {
+        let bus = BusHandle::new();
+        let request = StandardId::new(0x7E1).unwrap();
+        let response = StandardId::new(0x7E9).unwrap();
+        let mut ecu = UdsServer::new(IsoTpEndpoint::new(
+            bus.add_interface(vec![]).unwrap(),
+            response,
+            request,
+        ));
+        let mut tester = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), request, response);
+        ecu.on_did(0x0101, UdsResponse::Positive(vec![0x00]));
+        ecu.set_security_level(
+            0x01,
+            vec![0x12, 0x34],
+            |seed| seed.iter().map(|b| b ^ 0xFF).collect(),
+            true,
+        );
+        ecu.set_response_pending(0x31, Duration::from_millis(100));
+        ecu.on_service(0x31, UdsResponse::Positive(vec![0x01, 0xFF, 0x00]));
+
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x2E, 0x01, 0x01, 0x07]),
+            vec![vec![0x7F, 0x2E, 0x33]]
+        );
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x02, 0xED, 0xCB]),
+            vec![vec![0x7F, 0x27, 0x24]]
+        );
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x01]),
+            vec![vec![0x67, 0x01, 0x12, 0x34]]
+        );
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x02, 0x00, 0x00]),
+            vec![vec![0x7F, 0x27, 0x35]]
+        );
+        exchange(&mut ecu, &mut tester, &[0x27, 0x01]);
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x02, 0xED, 0xCB]),
+            vec![vec![0x67, 0x02]]
+        );
+        assert_eq!(ecu.unlocked_level(), Some(0x01));
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x2E, 0x01, 0x01, 0x07]),
+            vec![vec![0x6E, 0x01, 0x01]]
+        );
+        assert_eq!(ecu.did(0x0101), Some(&[0x07][..]));
+
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x31, 0x01, 0xFF, 0x00]),
+            vec![vec![0x7F, 0x31, NRC_RESPONSE_PENDING]]
+        );
+        ecu.poll(Duration::from_millis(99)).unwrap();
+        assert!(tester.poll().unwrap().is_empty());
+        ecu.poll(Duration::from_millis(100)).unwrap();
+        assert_eq!(tester.poll().unwrap(), vec![vec![0x71, 0x01, 0xFF, 0x00]]);
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x85, 0x01]),
+            vec![vec![0x7F, 0x85, 0x11]]
+        );
+    }
+
+    #[test]
+    fn any_protected_level_unlocks_writes_and_bad_subfunctions_are_rejected() {
+        let bus = BusHandle::new();
+        let request = StandardId::new(0x7E2).unwrap();
+        let response = StandardId::new(0x7EA).unwrap();
+        let mut ecu = UdsServer::new(IsoTpEndpoint::new(
+            bus.add_interface(vec![]).unwrap(),
+            response,
+            request,
+        ));
+        let mut tester = IsoTpEndpoint::new(bus.add_interface(vec![]).unwrap(), request, response);
+        ecu.on_did(0x0101, UdsResponse::Positive(vec![0x00]));
+        ecu.set_security_level(0x01, vec![0x11], |seed| seed.to_vec(), true);
+        ecu.set_security_level(0x03, vec![0x33], |seed| seed.to_vec(), true);
+        ecu.on_service(0xC5, UdsResponse::Positive(vec![]));
+
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x00, 0x11]),
+            vec![vec![0x7F, 0x27, NRC_SUBFUNCTION_NOT_SUPPORTED]]
+        );
+        exchange(&mut ecu, &mut tester, &[0x27, 0x03]);
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x27, 0x04, 0x33]),
+            vec![vec![0x67, 0x04]]
+        );
+        assert_eq!(
+            exchange(&mut ecu, &mut tester, &[0x2E, 0x01, 0x01, 0x07]),
+            vec![vec![0x6E, 0x01, 0x01]]
+        );
+        assert_eq!(exchange(&mut ecu, &mut tester, &[0xC5]), vec![vec![0x05]]);
+    }
+}
@@ -0,0 +1,290 @@
+//! URI-style configuration strings for [`MockCan::open`](crate::MockCan).
+//!
+//! Applications that pick their CAN backend from a configuration file can select and configure
+//! the mock with a string such as
+//!
+//! ```text
+//! mock://bus1?filters=0x123/0x7FF,0x18DAF100/0x1FFFFF00&listen_only=true
+//! ```
+//!
+//! The host part names a bus: every interface opened with the same name while any of them is
+//! still alive shares one bus, which tests can reach with
+//! [`BusHandle::named`](crate::BusHandle::named). An empty name (`mock://`) opens a private bus.
+//! The query accepts these keys, each at most once:
+//!
+//! | key | value | builder equivalent |
+//! |-----|-------|--------------------|
+//! | `filters` | comma-separated `id/mask` pairs in hex | [`with_filters`](crate::MockBuilder::with_filters) |
+//! | `listen_only` | `true` / `false` | [`listen_only`](crate::MockBuilder::listen_only) |
+//! | `self_reception` | `true` / `false` | [`self_reception`](crate::MockBuilder::self_reception) |
+//! | `nonblocking` | `true` / `false` | [`nonblocking`](crate::MockBuilder::nonblocking) |
+//! | `rx_capacity` | decimal frame count | [`rx_capacity`](crate::MockBuilder::rx_capacity) |
+//! | `name` | any text | [`name`](crate::MockBuilder::name) |
+//!
+//! As in `candump` notation, a filter whose ID is written with up to 3 hex digits is a standard
+//! filter and one with more digits is an extended filter; the `0x` prefix is optional.
+//!
+//! ```
+//! use embedded_can::{Frame as _, StandardId};
+//! use embedded_can_interface::{BuilderBinding, RxFrameIo, TxFrameIo};
+//! use embedded_can_mock::{BusHandle, MockCan, MockFrame};
+//!
+//! let mut rx = MockCan::open("mock://uri-demo?filters=0x123/0x7FF&self_reception=false").unwrap();
+//! let mut tx = MockCan::open("mock://uri-demo").unwrap();
+//! let frame = |id| MockFrame::new(StandardId::new(id).unwrap(), &[]).unwrap();
+//!
+//! tx.send(&frame(0x122)).unwrap();
+//! tx.send(&frame(0x123)).unwrap();
+//! assert_eq!(rx.recv().unwrap(), frame(0x123));
+//! assert_eq!(BusHandle::named("uri-demo").interface_count(), 2);
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use embedded_can::{ExtendedId, StandardId};
+use embedded_can_interface::{BuilderBinding, IdMaskFilter};
+
+use crate::{BusHandle, MockBuilder, MockCan, filter};
+
+/// Scheme prefix of mock configuration strings.
+pub const SCHEME: &str = "mock://";
+
+/// Errors returned by [`MockUri::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UriError {
+    /// The string does not start with `mock://`.
+    UnsupportedScheme,
+    /// The query has a key this backend does not know.
+    UnknownKey(String),
+    /// A key appears more than once.
+    DuplicateKey(String),
+    /// A value cannot be parsed for its key.
+    InvalidValue {
+        /// The key.
+        key: String,
+        /// The offending value (for `filters`, the offending pair).
+        value: String,
+    },
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UriError::UnsupportedScheme => write!(f, "configuration must start with `{SCHEME}`"),
+            UriError::UnknownKey(key) => write!(f, "unknown configuration key `{key}`"),
+            UriError::DuplicateKey(key) => write!(f, "configuration key `{key}` given twice"),
+            UriError::InvalidValue { key, value } => {
+                write!(f, "invalid value `{value}` for configuration key `{key}`")
+            }
+        }
+    }
+}
+
+/// A parsed `mock://` configuration string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockUri {
+    /// Name of the shared bus, or empty for a private bus.
+    pub bus: String,
+    /// Acceptance filters.
+    pub filters: Vec<IdMaskFilter>,
+    /// Start in listen-only mode.
+    pub listen_only: bool,
+    /// Receive the interface's own frames (on unless set to `false`).
+    pub self_reception: bool,
+    /// Start in non-blocking mode.
+    pub nonblocking: bool,
+    /// Receive queue bound.
+    pub rx_capacity: Option<usize>,
+    /// Interface name.
+    pub name: Option<String>,
+}
+
+impl MockUri {
+    /// Parse a configuration string (see the [module documentation](self)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_can_mock::uri::{MockUri, UriError};
+    ///
+    /// let uri = MockUri::parse("mock://bus1?filters=0x123/0x7FF&rx_capacity=16").unwrap();
+    /// assert_eq!((uri.bus.as_str(), uri.filters.len(), uri.rx_capacity), ("bus1", 1, Some(16)));
+    ///
+    /// let err = MockUri::parse("mock://bus1?filters=0x123/0xFFF").unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid value `0x123/0xFFF` for configuration key `filters`");
+    /// assert_eq!(MockUri::parse("socketcan://can0"), Err(UriError::UnsupportedScheme));
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, UriError> {
+        let rest = text
+            .strip_prefix(SCHEME)
+            .ok_or(UriError::UnsupportedScheme)?;
+        let (bus, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut uri = MockUri {
+            bus: bus.trim_end_matches('/').to_string(),
+            filters: Vec::new(),
+            listen_only: false,
+            self_reception: true,
+            nonblocking: false,
+            rx_capacity: None,
+            name: None,
+        };
+        let mut seen: Vec<&str> = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if seen.contains(&key) {
+                return Err(UriError::DuplicateKey(key.to_string()));
+            }
+            seen.push(key);
+            let invalid = |value: &str| UriError::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+            let flag = || match value {
+                "true" | "1" => Ok(true),
+                "false" | "0" => Ok(false),
+                _ => Err(invalid(value)),
+            };
+            match key {
+                "filters" => {
+                    uri.filters = value
+                        .split(',')
+                        .filter(|pair| !pair.is_empty())
+                        .map(|pair| parse_filter(pair).ok_or_else(|| invalid(pair)))
+                        .collect::<Result<_, _>>()?;
+                }
+                "listen_only" => uri.listen_only = flag()?,
+                "self_reception" => uri.self_reception = flag()?,
+                "nonblocking" => uri.nonblocking = flag()?,
+                "rx_capacity" => {
+                    uri.rx_capacity = Some(value.parse().map_err(|_| invalid(value))?);
+                }
+                "name" => uri.name = Some(value.to_string()),
+                _ => return Err(UriError::UnknownKey(key.to_string())),
+            }
+        }
+        Ok(uri)
+    }
+
+    /// A [`MockBuilder`] configured as this string describes, on the named bus (or a new private
+    /// bus if the name is empty).
+    pub fn builder(&self) -> MockBuilder {
+        let mut builder = MockCan::builder()
+            .with_filters(self.filters.clone())
+            .expect("setting filters does not fail")
+            .listen_only(self.listen_only)
+            .self_reception(self.self_reception)
+            .nonblocking(self.nonblocking);
+        if !self.bus.is_empty() {
+            builder = builder.on_bus(&BusHandle::named(&self.bus));
+        }
+        if let Some(capacity) = self.rx_capacity {
+            builder = builder.rx_capacity(capacity);
+        }
+        if let Some(name) = &self.name {
+            builder = builder.name(name.clone());
+        }
+        builder
+    }
+}
+
+/// Parse one `id/mask` filter; the ID's digit count selects standard or extended.
+pub(crate) fn parse_filter(pair: &str) -> Option<IdMaskFilter> {
+    let (id, mask) = pair.split_once('/')?;
+    let hex = |text: &str| {
+        let digits = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        Some((value, digits.len()))
+    };
+    let ((id, digits), (mask, _)) = (hex(id)?, hex(mask)?);
+    let extended = digits > 3;
+    let (id_max, mask_max) = if extended {
+        (ExtendedId::MAX.as_raw(), filter::EXTENDED_MASK)
+    } else {
+        (u32::from(StandardId::MAX.as_raw()), filter::STANDARD_MASK)
+    };
+    (id <= id_max && mask <= mask_max).then(|| filter::raw_filter(id, mask, extended))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockError;
+    use embedded_can_interface::{Id as IfaceId, IdMask};
+
+    #[test]
+    fn parses_filters_flags_and_errors() {
+        let uri = MockUri::parse(
+            "mock://bus/?filters=123/7FF,0x18DAF100/0x1FFFFF00&nonblocking=1&name=ecu&listen_only=false",
+        )
+        .unwrap();
+        assert_eq!(uri.bus, "bus");
+        assert_eq!(
+            uri.filters,
+            vec![
+                IdMaskFilter {
+                    id: IfaceId::Standard(StandardId::new(0x123).unwrap()),
+                    mask: IdMask::Standard(0x7FF),
+                },
+                IdMaskFilter {
+                    id: IfaceId::Extended(ExtendedId::new(0x18DA_F100).unwrap()),
+                    mask: IdMask::Extended(0x1FFF_FF00),
+                },
+            ]
+        );
+        assert!(uri.nonblocking && !uri.listen_only && uri.self_reception);
+        assert_eq!(uri.name.as_deref(), Some("ecu"));
+        // Leading zeros make an extended filter.
+        let uri = MockUri::parse("mock://?filters=0x00000123/0x7FF").unwrap();
+        assert!(matches!(uri.filters[0].id, IfaceId::Extended(_)));
+
+        let invalid = |key: &str, value: &str| UriError::InvalidValue {
+            key: key.into(),
+            value: value.into(),
+        };
+        assert_eq!(
+            MockUri::parse("mock://?filters=800/7FF"),
+            Err(invalid("filters", "800/7FF"))
+        );
+        assert_eq!(
+            MockUri::parse("mock://?listen_only=yes"),
+            Err(invalid("listen_only", "yes"))
+        );
+        assert_eq!(
+            MockUri::parse("mock://?bitrate=500000"),
+            Err(UriError::UnknownKey("bitrate".into()))
+        );
+        assert_eq!(
+            MockUri::parse("mock://?nonblocking=1&nonblocking=0"),
+            Err(UriError::DuplicateKey("nonblocking".into()))
+        );
+        assert!(matches!(
+            MockCan::open("mock://?rx_capacity=-1"),
+            Err(MockError::InvalidUri(_))
+        ));
+    }
+
+    #[test]
+    fn named_buses_live_as_long_as_their_interfaces() {
+        let a = MockCan::open("mock://uri-test?listen_only=true&rx_capacity=4").unwrap();
+        let b = MockCan::open("mock://uri-test").unwrap();
+        let bus = BusHandle::named("uri-test");
+        assert_eq!(bus.interface_count(), 2);
+        assert!(a.iface.is_listen_only());
+        assert_eq!(a.iface.rx_capacity(), Some(4));
+        drop((a, b, bus));
+        assert_eq!(BusHandle::named("uri-test").interface_count(), 0);
+
+        // Unnamed and non-URI opens each get a private bus.
+        let private = MockCan::open("mock://").unwrap();
+        let legacy = MockCan::open("can0").unwrap();
+        assert!(!private.bus.ptr_eq(&legacy.bus));
+    }
+}
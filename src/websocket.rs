@@ -0,0 +1,338 @@
+//! Live traffic over WebSocket.
+//!
+//! A [`FrameStream`] records a bus and serves the traffic on a WebSocket endpoint, one JSON text
+//! message per completed frame, so a browser dashboard can follow a long-running simulation:
+//!
+//! ```js
+//! new WebSocket("ws://127.0.0.1:9000").onmessage = (msg) => console.log(JSON.parse(msg.data));
+//! ```
+//!
+//! Each message has the shape written by [`to_json`]:
+//!
+//! ```json
+//! {"at_us":1500,"source":3,"id":291,"extended":false,"remote":false,"fd":false,
+//!  "data":"AABB","candump":"123#AABB","receivers":[3,4],"error":null}
+//! ```
+//!
+//! `at_us` is the virtual completion time in microseconds, `source` and `receivers` are
+//! [`InterfaceId`](crate::bus::InterfaceId)s, and `error` names the injected
+//! [`BusEvent`](crate::BusEvent) that replaced delivery, if any. Clients only receive; anything
+//! they send is ignored. The server needs no dependencies and speaks just enough of RFC 6455
+//! for browsers and common client libraries.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write as _;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use embedded_can::{Frame, Id};
+
+use crate::{bus::BusHandle, frame::MockFrame, recorder::Recorder, recorder::TraceRecord};
+
+/// How often the server thread forwards new records and accepts new clients.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// GUID appended to the client's key in the opening handshake (RFC 6455, section 1.3).
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// A WebSocket endpoint streaming one bus's traffic.
+///
+/// Serving stops, and every client is disconnected, when the stream is dropped.
+pub struct FrameStream {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FrameStream {
+    /// Start recording `bus` and serve its traffic on `addr` (bind port 0 to let the OS pick
+    /// one). Clients receive the frames completing after they connect.
+    pub fn serve(bus: &BusHandle, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let recorder = Recorder::attach(bus);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name(format!("can-websocket {addr}"))
+            .spawn({
+                let stop = stop.clone();
+                move || serve(&listener, &recorder, &stop)
+            })?;
+        Ok(Self {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the endpoint listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(listener: &TcpListener, recorder: &Recorder, stop: &AtomicBool) {
+    let mut clients: Vec<TcpStream> = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        while let Ok((stream, _)) = listener.accept() {
+            // Records completing before the handshake finishes are not sent to the new client.
+            let records = recorder.take_records();
+            broadcast(&mut clients, &records);
+            if let Ok(stream) = handshake(stream) {
+                clients.push(stream);
+            }
+        }
+        broadcast(&mut clients, &recorder.take_records());
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Send every record to every client, dropping clients that have gone away.
+fn broadcast(clients: &mut Vec<TcpStream>, records: &[TraceRecord]) {
+    for record in records {
+        let message = text_message(&to_json(record));
+        clients.retain_mut(|client| client.write_all(&message).is_ok());
+    }
+}
+
+/// Answer a client's opening handshake, or reject a request that is not a WebSocket upgrade.
+fn handshake(stream: TcpStream) -> io::Result<TcpStream> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("sec-websocket-key")
+        {
+            key = Some(String::from(value.trim()));
+        }
+    }
+    let mut stream = stream;
+    let Some(key) = key else {
+        stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Err(io::ErrorKind::InvalidData.into());
+    };
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    )?;
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    Ok(stream)
+}
+
+/// `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{HANDSHAKE_GUID}").as_bytes()))
+}
+
+/// An unmasked, unfragmented text frame.
+fn text_message(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = alloc::vec![0x81];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Render one record as the JSON object streamed to clients.
+pub fn to_json(record: &TraceRecord) -> String {
+    let frame = &record.frame;
+    let (id, extended) = match frame.id() {
+        Id::Standard(id) => (u32::from(id.as_raw()), false),
+        Id::Extended(id) => (id.as_raw(), true),
+    };
+    let data: String = frame.data().iter().map(|b| format!("{b:02X}")).collect();
+    let receivers: Vec<String> = record
+        .receivers
+        .iter()
+        .map(|id| format!("{}", id.as_raw()))
+        .collect();
+    let mut json = format!(
+        "{{\"at_us\":{},\"source\":{},\"id\":{id},\"extended\":{extended},\"remote\":{},\
+         \"fd\":{},\"data\":\"{data}\",\"candump\":\"{}\",\"receivers\":[{}],\"error\":",
+        record.at.as_micros(),
+        record.token.source().as_raw(),
+        frame.is_remote_frame(),
+        frame.meta().fd,
+        MockFrame::fmt_candump(frame),
+        receivers.join(","),
+    );
+    match &record.error {
+        Some(error) => {
+            let _ = write!(json, "\"{error:?}\"}}");
+        }
+        None => json.push_str("null}"),
+    }
+    json
+}
+
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+    for block in padded.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..40 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, temp);
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+    let mut digest = [0; 20];
+    for (out, h) in digest.chunks_exact_mut(4).zip(h) {
+        out.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use embedded_can::StandardId;
+
+    use super::*;
+
+    #[test]
+    fn clients_receive_frames_as_json_after_the_handshake() {
+        // The example handshake of RFC 6455, section 1.3.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+
+        let bus = BusHandle::new();
+        let node = bus.add_interface(Vec::new()).unwrap();
+        let stream = FrameStream::serve(&bus, "127.0.0.1:0").unwrap();
+
+        let mut client = TcpStream::connect(stream.local_addr()).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .unwrap();
+        let mut reader = BufReader::new(client);
+        let mut response = String::new();
+        while !response.ends_with("\r\n\r\n") {
+            reader.read_line(&mut response).unwrap();
+        }
+        assert!(response.starts_with("HTTP/1.1 101"), "{response}");
+        assert!(response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        let frame = MockFrame::new(StandardId::new(0x123).unwrap(), &[0xAA, 0xBB]).unwrap();
+        node.transmit(frame).unwrap();
+        let mut header = [0; 2];
+        reader.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x81);
+        // Messages of 126 bytes or more carry a 16-bit length.
+        assert_eq!(header[1], 126);
+        let mut len = [0; 2];
+        reader.read_exact(&mut len).unwrap();
+        let mut payload = alloc::vec![0; usize::from(u16::from_be_bytes(len))];
+        reader.read_exact(&mut payload).unwrap();
+        let json = String::from_utf8(payload).unwrap();
+        let raw = node.id().as_raw();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"at_us\":0,\"source\":{raw},\"id\":291,\"extended\":false,\"remote\":false,\
+                 \"fd\":false,\"data\":\"AABB\",\"candump\":\"123#AABB\",\"receivers\":[{raw}],\
+                 \"error\":null}}"
+            )
+        );
+        drop(stream);
+    }
+}
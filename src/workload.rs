@@ -0,0 +1,241 @@
+//! Synthetic workloads for benchmarking the bus and sizing test rigs.
+//!
+//! A [`Workload`] describes a bus shape: how many nodes, how much traffic per second of virtual
+//! time, how selective each node's acceptance filters are and how large payloads are. Building
+//! it yields a [`WorkloadRig`] whose [`run`](WorkloadRig::run) pushes the traffic through the
+//! transmit, deliver and receive paths and reports how long that took in wall-clock time. All
+//! choices come from a seed, so two runs of the same workload do identical work.
+//!
+//! ```
+//! use embedded_can_mock::workload::Workload;
+//! use std::time::Duration;
+//!
+//! let workload = Workload {
+//!     nodes: 8,
+//!     frames_per_second: 2_000,
+//!     filter_density: 0.25,
+//!     ..Default::default()
+//! };
+//! let mut rig = workload.build();
+//! let stats = rig.run(Duration::from_millis(100));
+//! assert_eq!(stats.frames, 200);
+//! assert!(stats.deliveries < stats.frames * 8);
+//! ```
+//!
+//! Available with the `bench` feature.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use embedded_can::{Frame, StandardId};
+use embedded_can_interface::IdMaskFilter;
+
+use crate::{
+    arbitrary,
+    bus::{BusHandle, InterfaceHandle},
+    frame::MockFrame,
+    rng::Rng,
+};
+
+/// Number of distinct message IDs workload traffic uses.
+pub const MESSAGE_IDS: u16 = 64;
+
+/// Shape of a synthetic bus workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workload {
+    /// Number of attached nodes; each frame is sent by a randomly chosen one.
+    pub nodes: usize,
+    /// Frames transmitted per second of virtual time, across the whole bus.
+    pub frames_per_second: u32,
+    /// Fraction (`0.0..=1.0`) of the [`MESSAGE_IDS`] each node's filters accept; `1.0` installs
+    /// no filters at all.
+    pub filter_density: f64,
+    /// Payload length of every frame.
+    pub payload_len: usize,
+    /// Seed for node, ID and filter choices.
+    pub seed: u64,
+}
+
+impl Default for Workload {
+    /// Four unfiltered nodes exchanging 1000 8-byte frames per second.
+    fn default() -> Self {
+        Self {
+            nodes: 4,
+            frames_per_second: 1_000,
+            filter_density: 1.0,
+            payload_len: 8,
+            seed: 0,
+        }
+    }
+}
+
+/// Result of [`WorkloadRig::run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunStats {
+    /// Frames transmitted.
+    pub frames: u64,
+    /// Frames received, summed over all nodes.
+    pub deliveries: u64,
+    /// Wall-clock time spent transmitting and draining (zero without `std`).
+    pub elapsed: Duration,
+}
+
+/// A bus populated according to a [`Workload`].
+pub struct WorkloadRig {
+    bus: BusHandle,
+    nodes: Vec<InterfaceHandle>,
+    workload: Workload,
+    rng: Rng,
+    /// Fractional frames carried over between runs so rates stay exact.
+    carry: u128,
+}
+
+impl Workload {
+    /// Create the bus and nodes, with filters according to
+    /// [`filter_density`](Self::filter_density).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nodes` is zero.
+    pub fn build(&self) -> WorkloadRig {
+        assert!(self.nodes > 0, "a workload needs at least one node");
+        let mut rng = Rng::new(self.seed);
+        let bus = BusHandle::new();
+        let accepted = (f64::from(MESSAGE_IDS) * self.filter_density.clamp(0.0, 1.0)) as u16;
+        let nodes = (0..self.nodes)
+            .map(|_| {
+                let filters = if accepted >= MESSAGE_IDS {
+                    Vec::new()
+                } else {
+                    node_filters(&mut rng, accepted)
+                };
+                let node = bus.add_interface(filters).unwrap();
+                node.set_self_reception(false);
+                node
+            })
+            .collect();
+        WorkloadRig {
+            bus,
+            nodes,
+            workload: self.clone(),
+            rng,
+            carry: 0,
+        }
+    }
+}
+
+/// Exact filters for `count` distinct message IDs, or one never-matching filter for none.
+fn node_filters(rng: &mut Rng, count: u16) -> Vec<IdMaskFilter> {
+    let mut ids: Vec<u16> = (0..MESSAGE_IDS).collect();
+    // Partial Fisher-Yates shuffle: the first `count` entries become a random subset.
+    for i in 0..usize::from(count) {
+        let j = rng.range(i as u64, ids.len() as u64 - 1) as usize;
+        ids.swap(i, j);
+    }
+    if count == 0 {
+        // An extended filter never matches the standard IDs workloads use.
+        return alloc::vec![arbitrary::filter(arbitrary::id(u32::MAX, true), u32::MAX)];
+    }
+    ids[..usize::from(count)]
+        .iter()
+        .map(|&id| arbitrary::filter(message_id(id).into(), u32::MAX))
+        .collect()
+}
+
+fn message_id(index: u16) -> StandardId {
+    StandardId::new(0x100 + index).unwrap()
+}
+
+impl WorkloadRig {
+    /// The bus the nodes are attached to.
+    pub fn bus(&self) -> &BusHandle {
+        &self.bus
+    }
+
+    /// The workload's nodes, in attach order.
+    pub fn nodes(&self) -> &[InterfaceHandle] {
+        &self.nodes
+    }
+
+    /// Generate the next `count` frames with their sending node, without transmitting them.
+    pub fn frames(&mut self, count: usize) -> Vec<(usize, MockFrame)> {
+        let payload = alloc::vec![0xA5; self.workload.payload_len];
+        (0..count)
+            .map(|_| {
+                let node = self.rng.range(0, self.nodes.len() as u64 - 1) as usize;
+                let id = self.rng.range(0, u64::from(MESSAGE_IDS) - 1) as u16;
+                (node, MockFrame::new(message_id(id), &payload).unwrap())
+            })
+            .collect()
+    }
+
+    /// Transmit `duration` worth of traffic, draining every node after each frame, and advance
+    /// the virtual clock by `duration`.
+    pub fn run(&mut self, duration: Duration) -> RunStats {
+        let total = u128::from(self.workload.frames_per_second) * duration.as_nanos() + self.carry;
+        let count = (total / 1_000_000_000) as usize;
+        self.carry = total % 1_000_000_000;
+        let frames = self.frames(count);
+
+        #[cfg(feature = "std")]
+        let start = std::time::Instant::now();
+        let mut deliveries = 0;
+        for (node, frame) in frames {
+            let _ = self.nodes[node].transmit(frame);
+            for receiver in &self.nodes {
+                deliveries += receiver.drain_frames().len() as u64;
+            }
+        }
+        #[cfg(feature = "std")]
+        let elapsed = start.elapsed();
+        #[cfg(not(feature = "std"))]
+        let elapsed = Duration::ZERO;
+
+        self.bus.advance(duration);
+        RunStats {
+            frames: count as u64,
+            deliveries,
+            elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_density_controls_fan_out_and_runs_are_reproducible() {
+        let run = |density| {
+            Workload {
+                nodes: 10,
+                frames_per_second: 3_000,
+                filter_density: density,
+                seed: 3,
+                ..Default::default()
+            }
+            .build()
+            .run(Duration::from_millis(100))
+        };
+
+        let everyone = run(1.0);
+        assert_eq!(everyone.frames, 300);
+        // Without self-reception every other node receives every frame.
+        assert_eq!(everyone.deliveries, 300 * 9);
+        assert_eq!(run(0.0).deliveries, 0);
+        let half = run(0.5);
+        assert_eq!(half.deliveries, run(0.5).deliveries);
+        assert!((900..1_800).contains(&half.deliveries));
+
+        // Rates carry fractional frames over between runs.
+        let mut rig = Workload {
+            frames_per_second: 3,
+            ..Default::default()
+        }
+        .build();
+        let frames: u64 = (0..10)
+            .map(|_| rig.run(Duration::from_millis(100)).frames)
+            .sum();
+        assert_eq!(frames, 3);
+    }
+}
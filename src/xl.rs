@@ -0,0 +1,214 @@
+//! Experimental CAN XL frames.
+//!
+//! [`XlFrame`] models the CAN XL data frame: an 11-bit priority identifier used for arbitration,
+//! an SDU type describing the payload’s protocol, a virtual CAN network ID (VCID), a 32-bit
+//! acceptance field and a payload of 1 to 2048 bytes. It implements [`embedded_can::Frame`], with
+//! the priority as the frame’s standard ID, so the bus arbitrates, filters and delivers XL frames
+//! like any other frame type (`BusHandle::<XlFrame>::default()`).
+//!
+//! ```
+//! use embedded_can::StandardId;
+//! use embedded_can_mock::BusHandle;
+//! use embedded_can_mock::xl::{SDU_IPV4, XlFrame};
+//!
+//! let bus = BusHandle::<XlFrame>::default();
+//! let node = bus.add_interface(vec![]).unwrap();
+//!
+//! let frame = XlFrame::new(StandardId::new(0x42).unwrap(), SDU_IPV4, &[0x45; 1500])
+//!     .unwrap()
+//!     .with_vcid(7);
+//! node.transmit(frame.clone()).unwrap();
+//! let received = node.pop_frame().unwrap();
+//! assert_eq!(received.vcid(), 7);
+//! assert_eq!(received.payload().len(), 1500);
+//! ```
+//!
+//! Available with the `xl` feature. The API may change as CAN XL support in the wider ecosystem
+//! settles.
+
+use alloc::vec::Vec;
+
+use embedded_can::{Frame, Id, StandardId};
+
+use crate::frame::{FrameMeta, MockFrame};
+
+/// Largest CAN XL payload, in bytes.
+pub const MAX_XL_PAYLOAD: usize = 2048;
+
+/// SDU type: content-based addressing (CiA 611-1).
+pub const SDU_CONTENT_BASED: u8 = 0x01;
+/// SDU type: classic CAN or CAN FD frame tunnelled in the payload.
+pub const SDU_CLASSIC_FD_TUNNEL: u8 = 0x03;
+/// SDU type: IEEE 802.3 (Ethernet) frame.
+pub const SDU_ETHERNET: u8 = 0x04;
+/// SDU type: IPv4 packet.
+pub const SDU_IPV4: u8 = 0x08;
+
+/// A CAN XL data frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XlFrame {
+    priority: StandardId,
+    sdu_type: u8,
+    vcid: u8,
+    acceptance: u32,
+    /// Simple extended content (SEC): the payload is protected by CADsec.
+    sec: bool,
+    payload: Vec<u8>,
+}
+
+impl XlFrame {
+    /// Create a frame with VCID 0 and acceptance field 0.
+    ///
+    /// Returns `None` if `payload` is empty or longer than [`MAX_XL_PAYLOAD`].
+    pub fn new(priority: StandardId, sdu_type: u8, payload: &[u8]) -> Option<Self> {
+        (1..=MAX_XL_PAYLOAD).contains(&payload.len()).then(|| Self {
+            priority,
+            sdu_type,
+            vcid: 0,
+            acceptance: 0,
+            sec: false,
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Set the virtual CAN network ID.
+    pub fn with_vcid(mut self, vcid: u8) -> Self {
+        self.vcid = vcid;
+        self
+    }
+
+    /// Set the acceptance field.
+    pub fn with_acceptance(mut self, acceptance: u32) -> Self {
+        self.acceptance = acceptance;
+        self
+    }
+
+    /// Set the simple extended content (SEC) bit.
+    pub fn with_sec(mut self, sec: bool) -> Self {
+        self.sec = sec;
+        self
+    }
+
+    /// 11-bit priority identifier, used for arbitration.
+    pub fn priority(&self) -> StandardId {
+        self.priority
+    }
+
+    /// SDU type of the payload.
+    pub fn sdu_type(&self) -> u8 {
+        self.sdu_type
+    }
+
+    /// Virtual CAN network ID.
+    pub fn vcid(&self) -> u8 {
+        self.vcid
+    }
+
+    /// Acceptance field.
+    pub fn acceptance(&self) -> u32 {
+        self.acceptance
+    }
+
+    /// Simple extended content (SEC) bit.
+    pub fn sec(&self) -> bool {
+        self.sec
+    }
+
+    /// The payload (1 to [`MAX_XL_PAYLOAD`] bytes).
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+impl Frame for XlFrame {
+    /// An XL frame with SDU type 0; `None` unless `id` is a standard ID and the payload is 1 to
+    /// [`MAX_XL_PAYLOAD`] bytes long.
+    fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+        match id.into() {
+            Id::Standard(priority) => XlFrame::new(priority, 0, data),
+            Id::Extended(_) => None,
+        }
+    }
+
+    /// CAN XL has no remote frames; always `None`.
+    fn new_remote(_id: impl Into<Id>, _dlc: usize) -> Option<Self> {
+        None
+    }
+
+    fn is_extended(&self) -> bool {
+        false
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        false
+    }
+
+    fn id(&self) -> Id {
+        Id::Standard(self.priority)
+    }
+
+    fn dlc(&self) -> usize {
+        self.payload.len()
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+impl From<&XlFrame> for MockFrame {
+    /// A [`MockFrame`] with the same ID and payload, recording the SDU type in its
+    /// [metadata](FrameMeta::xl_sdu_type).
+    fn from(frame: &XlFrame) -> Self {
+        MockFrame::from_frame(frame).with_meta(FrameMeta {
+            xl_sdu_type: Some(frame.sdu_type),
+            ..FrameMeta::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BusHandle, timing::BusTiming};
+    use core::time::Duration;
+
+    #[test]
+    fn xl_frames_arbitrate_by_priority_and_keep_their_fields() {
+        let id = |raw| StandardId::new(raw).unwrap();
+        assert!(XlFrame::new(id(0x1), SDU_ETHERNET, &[]).is_none());
+        assert!(XlFrame::new(id(0x1), SDU_ETHERNET, &[0; MAX_XL_PAYLOAD + 1]).is_none());
+        assert!(<XlFrame as Frame>::new(embedded_can::ExtendedId::ZERO, &[1]).is_none());
+        assert!(<XlFrame as Frame>::new_remote(id(0x1), 1).is_none());
+
+        let bus = BusHandle::<XlFrame>::default();
+        bus.set_timing(Some(BusTiming::new(500_000).with_data_bitrate(10_000_000)));
+        let a = bus.add_interface(alloc::vec![]).unwrap();
+        let b = bus.add_interface(alloc::vec![]).unwrap();
+        let rx = bus.add_interface(alloc::vec![]).unwrap();
+        let low = XlFrame::new(id(0x300), SDU_CONTENT_BASED, &[1; 64])
+            .unwrap()
+            .with_acceptance(0xDEAD_BEEF)
+            .with_sec(true);
+        let high = XlFrame::new(id(0x100), SDU_CLASSIC_FD_TUNNEL, &[2; MAX_XL_PAYLOAD])
+            .unwrap()
+            .with_vcid(3);
+        // Occupy the wire so both frames arbitrate together.
+        a.transmit(XlFrame::new(id(0x7FF), 0, &[0]).unwrap())
+            .unwrap();
+        a.transmit(low.clone()).unwrap();
+        b.transmit(high.clone()).unwrap();
+        bus.advance(Duration::from_millis(10));
+
+        let received: Vec<_> = core::iter::from_fn(|| rx.pop_frame()).collect();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[1], high);
+        assert_eq!(received[2], low);
+        assert_eq!(received[2].acceptance(), 0xDEAD_BEEF);
+        assert!(received[2].sec());
+
+        let mock = MockFrame::from(&received[1]);
+        assert_eq!(mock.data().len(), MAX_XL_PAYLOAD);
+        assert_eq!(mock.meta().xl_sdu_type, Some(SDU_CLASSIC_FD_TUNNEL));
+    }
+}